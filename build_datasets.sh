@@ -80,8 +80,30 @@ else
 fi
 
 # Auto-generate hf/parquet_null_audit.md + update hf/README.md at the end of a normal run.
-# Skip if the user explicitly requested --null-check (one-off mode) or asked for help/version output.
-if [[ " ${RUST_ARGS[*]:-} " != *" --null-check "* && " ${RUST_ARGS[*]:-} " != *" --help "* && " ${RUST_ARGS[*]:-} " != *" -h "* && " ${RUST_ARGS[*]:-} " != *" --version "* ]]; then
+# Skip if the user explicitly requested a one-off mode or asked for help/version output.
+RUN_NULL_CHECK=1
+PREV_ARG=""
+for arg in ${RUST_ARGS[@]+"${RUST_ARGS[@]}"}; do
+  # `--limit-bulk-to-dataset-npis` takes its value either inline or as the next argument.
+  if [[ "${PREV_ARG}" == "--limit-bulk-to-dataset-npis" && "${arg}" == "false" ]]; then
+    RUN_NULL_CHECK=0
+  fi
+  PREV_ARG="${arg}"
+  case "${arg}" in
+    --null-check | --null-check=*) RUN_NULL_CHECK=0 ;;
+    --cache-vacuum | --cache-vacuum=* | --vacuum-cache | --vacuum-cache=*) RUN_NULL_CHECK=0 ;;
+    --recompute-hcpcs-selection | --recompute-hcpcs-selection=*) RUN_NULL_CHECK=0 ;;
+    --hcpcs-export-only | --hcpcs-export-only=*) RUN_NULL_CHECK=0 ;;
+    --join-health-report | --join-health-report=*) RUN_NULL_CHECK=0 ;;
+    --export-hcpcs-cache | --export-hcpcs-cache=* | --hcpcs-cache-export | --hcpcs-cache-export=*) RUN_NULL_CHECK=0 ;;
+    --selftest | --selftest=*) RUN_NULL_CHECK=0 ;;
+    --dry-run | --dry-run=*) RUN_NULL_CHECK=0 ;;
+    --limit-bulk-to-dataset-npis=false) RUN_NULL_CHECK=0 ;;
+    --help | -h | --version | -V) RUN_NULL_CHECK=0 ;;
+  esac
+done
+
+if [[ "${RUN_NULL_CHECK}" -eq 1 ]]; then
   NULL_CMD=(cargo run --release --manifest-path "${MANIFEST_PATH}" --)
   if [[ ${#RUST_ARGS[@]} -gt 0 ]]; then
     NULL_CMD+=("${RUST_ARGS[@]}")
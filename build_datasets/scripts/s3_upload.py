@@ -0,0 +1,49 @@
+"""Uploads one build_datasets output to S3; run by `--upload-s3` (src/upload.rs).
+
+Needs boto3 (`pip install boto3`). Inputs come from S3_* environment variables. The file's
+SHA-256 and destination are kept in `<file>.s3.sha256`, so an unchanged file is not re-uploaded.
+"""
+
+import hashlib
+import os
+import sys
+
+try:
+    import boto3
+    from botocore.config import Config
+except ImportError:
+    print("boto3 is required. Install with: pip install boto3", file=sys.stderr)
+    sys.exit(1)
+
+local_file = os.environ["S3_LOCAL_FILE"]
+bucket = os.environ["S3_BUCKET"]
+key = os.environ["S3_KEY"]
+endpoint = os.environ["S3_ENDPOINT"] or None
+sidecar = local_file + ".s3.sha256"
+
+digest = hashlib.sha256()
+with open(local_file, "rb") as f:
+    for chunk in iter(lambda: f.read(1 << 20), b""):
+        digest.update(chunk)
+record = f"{digest.hexdigest()}  s3://{bucket}/{key}  {endpoint or ''}\n"
+try:
+    with open(sidecar) as f:
+        if f.read() == record:
+            print("Unchanged since the last upload; skipped.")
+            sys.exit(0)
+except FileNotFoundError:
+    pass
+
+# MinIO and most other S3-compatible servers expect path-style addressing.
+config = Config(s3={"addressing_style": "path"}) if endpoint else None
+client = boto3.client("s3", endpoint_url=endpoint, config=config)
+client.upload_file(
+    local_file,
+    bucket,
+    key,
+    ExtraArgs={"ContentType": os.environ["S3_CONTENT_TYPE"]},
+)
+with open(sidecar + ".tmp", "w") as f:
+    f.write(record)
+os.replace(sidecar + ".tmp", sidecar)
+print("Upload complete.")
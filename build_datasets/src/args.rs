@@ -1,8 +1,10 @@
-use clap::Parser;
+use clap::{Parser, ValueEnum};
+use serde::Serialize;
 
 use crate::constants::{DEFAULT_DATASET_URL, DEFAULT_HCPCS_API_BASE_URL, DEFAULT_NPI_API_BASE_URL};
 
-#[derive(Debug, Parser)]
+/// Also serialized (secrets redacted) to `run_config.json`; see `run_config.rs`.
+#[derive(Debug, Parser, Serialize)]
 #[command(name = "build_datasets")]
 #[command(about = "Build resumable NPI/HCPCS mappings for Medicaid provider spending data")]
 pub struct Args {
@@ -12,12 +14,84 @@ pub struct Args {
 
     /// Source URL used when input_path does not exist locally.
     #[arg(long, default_value = DEFAULT_DATASET_URL)]
+    #[serde(serialize_with = "crate::run_config::serialize_url")]
     pub input_url: String,
 
+    /// Resolve NPIs from this list instead of the spending file: one NPI per line, or a CSV
+    /// when `--npi-list-column` is set. Requires `--npi-only`.
+    #[arg(long, requires = "npi_only")]
+    pub npi_list: Option<std::path::PathBuf>,
+
+    /// Header name of the NPI column when `--npi-list` is a CSV.
+    #[arg(long, requires = "npi_list")]
+    pub npi_list_column: Option<String>,
+
+    /// Build only the NPI side (mapping, cache, parquet); skip all HCPCS work.
+    #[arg(long, alias = "only-npi", default_value_t = false)]
+    pub npi_only: bool,
+
+    /// Build only the HCPCS side (mapping, cache, parquet); skip all NPI work, including the
+    /// NPPES bulk preload.
+    #[arg(
+        long,
+        alias = "only-hcpcs",
+        default_value_t = false,
+        conflicts_with_all = ["npi_only", "npi_list"]
+    )]
+    pub hcpcs_only: bool,
+
+    /// Warn instead of failing when the input yields zero NPIs or HCPCS codes (e.g. an empty
+    /// or header-only file), and go on to write empty outputs.
+    #[arg(long, default_value_t = false)]
+    pub allow_empty_input: bool,
+
+    /// Abort before any resolution when the input yields more unique NPIs (or HCPCS codes) than
+    /// this, which usually means a wrong column or schema mismatch rather than real data. The
+    /// default sits above the ~9M NPIs NPPES has ever issued.
+    #[arg(long, default_value_t = 15_000_000, value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..))]
+    pub max_identifiers: usize,
+
+    /// Before any processing, fail with an expected-vs-actual diff if the spending parquet's
+    /// columns or types differ from the expected layout. Without it, drift surfaces mid-run.
+    #[arg(long, default_value_t = false)]
+    pub strict_schema: bool,
+
+    /// Write default-path outputs (mapping CSVs, parquets, triage, unresolved report) under a
+    /// `year=YYYY/` subdirectory so several dataset periods coexist. The year comes from
+    /// `--dataset-period`, else the first 4-digit year in the input file name (or input URL).
+    /// Caches stay shared across periods.
+    #[arg(long, default_value_t = false)]
+    pub partition_by_year: bool,
+
+    /// Dataset year for `--partition-by-year` (implies it), e.g. `2024`.
+    #[arg(long, value_parser = parse_dataset_year)]
+    pub dataset_period: Option<String>,
+
     /// Output CSV path for unresolved identifiers report (NPI + HCPCS).
     #[arg(long)]
     pub unresolved_report_csv: Option<std::path::PathBuf>,
 
+    /// Also write the unresolved identifiers report as parquet next to the CSV
+    /// (same columns, `fetched_at_unix` typed as BIGINT).
+    #[arg(long, default_value_t = false)]
+    pub emit_unresolved_parquet: bool,
+
+    /// Output path for the run summary JSON (plan counts, lookup totals, timestamps).
+    /// Defaults to `run_summary.json` in the output directory.
+    #[arg(long)]
+    pub run_summary_json: Option<std::path::PathBuf>,
+
+    /// Add a `luhn_valid` column to the unresolved report: NPI check-digit validity, so
+    /// malformed source NPIs stand out from genuinely missing ones (blank for HCPCS rows).
+    #[arg(long, default_value_t = false)]
+    pub verify_luhn_in_report: bool,
+
+    /// While the NPI and HCPCS lookups run, rewrite the unresolved report every this many
+    /// seconds, so a run killed without a graceful shutdown (OOM, SIGKILL) still leaves a triage
+    /// artifact. 0 keeps the default: the report is written only at the end (or on Ctrl-C).
+    #[arg(long, default_value_t = 0)]
+    pub report_interval_secs: u64,
+
     /// NPI -> provider mapping CSV output path.
     #[arg(long, alias = "npi-mapping-csv")]
     pub mapping_csv: Option<std::path::PathBuf>,
@@ -48,12 +122,49 @@ pub struct Args {
     #[arg(long)]
     pub hcpcs_api_responses_parquet: Option<std::path::PathBuf>,
 
+    /// Comma-separated subset of columns to write to the resolved NPI parquet
+    /// (e.g. `npi,basic,taxonomies`). Defaults to all columns.
+    #[arg(long, value_delimiter = ',')]
+    pub npi_output_columns: Option<Vec<String>>,
+
+    /// Comma-separated subset of columns to write to the resolved HCPCS parquet
+    /// (e.g. `hcpcs_code,ef_short_desc`). Defaults to all columns.
+    #[arg(long, value_delimiter = ',')]
+    pub hcpcs_output_columns: Option<Vec<String>>,
+
+    /// Append a `content_hash` column to both resolved parquets: a stable hash of each row's
+    /// resolved content (not its request metadata), for diffing builds.
+    #[arg(long, default_value_t = false)]
+    pub content_hash: bool,
+
+    /// Rewrite both resolved parquets ordered by their key (`npi` / `hcpcs_code`) once written.
+    /// Rows are otherwise in emit order (bulk NPPES first, then cache). The extra DuckDB pass
+    /// reads the whole file back and briefly needs disk for a second copy.
+    #[arg(long, default_value_t = false)]
+    pub sort_output: bool,
+
+    /// Also write `npi.ndjson` / `hcpcs.ndjson` beside the resolved parquets: one JSON object
+    /// per row with the identifier, `api_run_id`, and `response_json`, for jq and streaming
+    /// loaders that cannot read parquet.
+    #[arg(long, default_value_t = false)]
+    pub emit_ndjson: bool,
+
     /// Optional local CPT/HCPCS fallback CSV used when HCPCS API is missing codes.
     ///
     /// Expected columns: hcpcs_code, short_desc, long_desc (date/flag columns optional).
     #[arg(long)]
     pub hcpcs_fallback_csv: Option<std::path::PathBuf>,
 
+    /// NUCC taxonomy CSV used to fill taxonomy `desc` in bulk-sourced NPI rows (e.g. a newer
+    /// `nucc_taxonomy_<release>.csv`). Defaults to `data/manually_cleaned/nucc/nucc_taxonomy.csv`.
+    #[arg(long)]
+    pub taxonomy_reference_csv: Option<std::path::PathBuf>,
+
+    /// Minimum share (0-1) of non-blank values in the fallback CSV's code column that must be
+    /// valid HCPCS/CPT codes; below it the file is rejected as likely mislabeled.
+    #[arg(long, default_value_t = 0.9, value_parser = parse_fraction)]
+    pub fallback_validation_threshold: f64,
+
     /// Rebuild mapping files from cache+API even if mapping CSV already exists.
     #[arg(long, default_value_t = false)]
     pub rebuild_map: bool,
@@ -77,10 +188,26 @@ pub struct Args {
     #[arg(long, default_value_t = 2)]
     pub requests_per_second: u32,
 
+    /// After this many HTTP 429 (rate limited) responses from the NPI API, halve the NPI
+    /// request rate, and again after each further this many. `Retry-After` still sets the wait
+    /// before retrying the rate-limited request itself. 0 (the default) keeps
+    /// `--requests-per-second` fixed.
+    #[arg(long, default_value_t = 0)]
+    pub npi_rate_limit_threshold: usize,
+
     /// Max retry attempts for transient API failures.
     #[arg(long, default_value_t = 5)]
     pub max_retries: u32,
 
+    /// Initial delay (ms) before the first per-request retry; doubles on each further retry.
+    /// Must be at least 1: a zero delay would stay zero and retry without waiting.
+    #[arg(long, default_value_t = 1_000, value_parser = clap::builder::RangedU64ValueParser::<u64>::new().range(1..))]
+    pub retry_backoff_base_ms: u64,
+
+    /// Ceiling (ms) for the per-request exponential retry backoff.
+    #[arg(long, default_value_t = 60_000)]
+    pub retry_backoff_max_ms: u64,
+
     /// Additional retry rounds for identifiers that still fail after per-request retries.
     ///
     /// Example: with 2 rounds, the pipeline does initial pass + up to 2 follow-up passes
@@ -92,16 +219,103 @@ pub struct Args {
     #[arg(long, default_value_t = 30)]
     pub failure_retry_delay_seconds: u64,
 
-    /// Optional cap for new uncached lookups in this run.
+    /// Stop looking up identifiers once they have failed this many times across runs.
+    ///
+    /// Failures are counted per identifier in the SQLite cache (`attempt_count`); identifiers at
+    /// or above the limit are marked `permanently_failed` and skipped. Unset = always retry.
+    #[arg(long)]
+    pub max_lifetime_attempts: Option<u32>,
+
+    /// Treat `ok`/`not_found` NPI cache rows fetched more than this many days ago as missing,
+    /// so they are looked up again (NPPES bulk preloads refresh them too). Unset = never expire.
+    #[arg(long)]
+    pub npi_cache_ttl_days: Option<u64>,
+
+    /// Skip API lookups for NPIs that fail the check digit (Luhn over the `80840` prefix).
+    ///
+    /// Failing NPIs are cached with status `invalid` and reported as such in the unresolved
+    /// report. Without this flag they are still sent to the API like any other NPI.
+    #[arg(long)]
+    pub strict_npi_validation: bool,
+
+    /// Print a plain status line every N seconds during API lookups and NPPES bulk scans.
+    ///
+    /// Lines are printed even when progress bars are hidden (non-TTY logs). Unset keeps the
+    /// default: no periodic lookup lines, and a bulk-scan line every 1M rows.
+    #[arg(long)]
+    pub progress_interval_seconds: Option<u64>,
+
+    /// Optional cap for new uncached lookups in this run, applied to NPI and HCPCS separately.
     #[arg(long)]
     pub max_new_lookups: Option<usize>,
 
+    /// Optional cap for new uncached lookups across NPI and HCPCS combined. Both builds draw
+    /// from one budget as they dispatch lookups; identifiers left over stay unresolved. Applied
+    /// after `--max-new-lookups` when both are set.
+    #[arg(long)]
+    pub max_new_lookups_total: Option<usize>,
+
+    /// Use this exact `api_run_id` for rows written in this run instead of a generated
+    /// `api-run-<unix_millis>` id (e.g. for byte-stable golden-output comparisons).
+    #[arg(long, value_parser = parse_api_run_id)]
+    pub api_run_id: Option<String>,
+
     /// Skip API requests and only use existing cache entries.
     #[arg(long, default_value_t = false)]
     pub skip_api: bool,
 
+    /// Strict offline build: resolve only from the caches, NPPES bulk files, and the local HCPCS
+    /// fallback (implies `--skip-api`; a missing spending input is an error, not a download).
+    /// The run fails at the end if any identifier has no cached answer (`error` or never
+    /// looked up), after writing the unresolved report and before any upload.
+    #[arg(long, default_value_t = false)]
+    pub offline: bool,
+
+    /// With `--offline`, accept an intentionally partial build: report unresolved identifiers
+    /// but exit successfully.
+    #[arg(long, default_value_t = false, requires = "offline")]
+    pub allow_unresolved: bool,
+
+    /// Extra PEM root certificate(s) to trust for HTTPS (e.g. a private CA for internal mirrors).
+    #[arg(long)]
+    pub ca_cert: Option<std::path::PathBuf>,
+
+    /// Disable TLS certificate verification. Development only: this makes every request
+    /// vulnerable to interception.
+    #[arg(long, default_value_t = false)]
+    pub danger_accept_invalid_certs: bool,
+
+    /// Max idle pooled connections kept per API host (reqwest default: unlimited).
+    #[arg(long)]
+    pub pool_max_idle_per_host: Option<usize>,
+
+    /// Speak HTTP/2 without ALPN/upgrade negotiation. Only for hosts known to support it
+    /// (e.g. an internal mirror); the public NPI/HCPCS APIs negotiate on their own.
+    #[arg(long, default_value_t = false)]
+    pub http2_prior_knowledge: bool,
+
+    /// TCP keepalive interval in seconds for API connections (default: OS setting).
+    #[arg(long)]
+    pub tcp_keepalive_secs: Option<u64>,
+
+    /// Seconds to wait for a TCP/TLS connection to an API or download host.
+    #[arg(long, default_value_t = 30, value_parser = clap::builder::RangedU64ValueParser::<u64>::new().range(1..))]
+    pub connect_timeout_secs: u64,
+
+    /// Seconds one NPI/HCPCS API request may take end to end, including the body, before it
+    /// fails and goes through the usual retry/backoff. Does not apply to the input download.
+    #[arg(long, default_value_t = 120, value_parser = clap::builder::RangedU64ValueParser::<u64>::new().range(1..))]
+    pub request_timeout_secs: u64,
+
+    /// Minimum TLS version for API and download connections: `1.2` or `1.3` (the rustls
+    /// backend cannot negotiate anything older).
+    #[arg(long, default_value = "1.2", value_parser = parse_min_tls_version)]
+    #[serde(serialize_with = "crate::run_config::serialize_tls_version")]
+    pub min_tls_version: reqwest::tls::Version,
+
     /// NPI API base URL.
     #[arg(long, default_value = DEFAULT_NPI_API_BASE_URL)]
+    #[serde(serialize_with = "crate::run_config::serialize_url")]
     pub api_base_url: String,
 
     /// NPI API version query parameter.
@@ -110,15 +324,64 @@ pub struct Args {
 
     /// HCPCS API base URL.
     #[arg(long, default_value = DEFAULT_HCPCS_API_BASE_URL)]
+    #[serde(serialize_with = "crate::run_config::serialize_url")]
     pub hcpcs_api_base_url: String,
 
+    /// Response shape served by `--hcpcs-api-base-url`.
+    ///
+    /// `clinicaltables` (default) is the NLM ClinicalTables search API. `cms` expects a
+    /// CMS-style endpoint answering `?hcpcs_code=<code>` with a JSON array of flat HCPCS
+    /// records (or `{"data": [...]}`); it has no OR queries, so every code is its own request.
+    #[arg(long, value_enum, default_value_t = HcpcsSource::Clinicaltables)]
+    pub hcpcs_source: HcpcsSource,
+
+    /// HTTP statuses (e.g. `404,422`) that mean "unknown code" on a single-code HCPCS lookup.
+    ///
+    /// Such responses are cached as `not_found` (reason `http_status_<code>`) instead of
+    /// `error`, so they are not retried. A failed batch still falls back to single lookups,
+    /// where this applies. Default: only an empty result is `not_found`.
+    #[arg(long, value_delimiter = ',', value_parser = parse_error_status)]
+    pub hcpcs_not_found_statuses: Vec<u16>,
+
     /// Number of HCPCS codes to query per batched HCPCS API request.
     ///
-    /// The HCPCS API allows count up to 500 per request; batch size controls
-    /// how many explicit code terms are combined in a single OR query.
+    /// Batch size controls how many explicit code terms are combined in a single OR query;
+    /// the rows returned per request are capped by `--hcpcs-batch-count`.
     #[arg(long, default_value_t = 100)]
     pub hcpcs_batch_size: usize,
 
+    /// ClinicalTables `count` (result row limit) for single-code HCPCS lookups.
+    ///
+    /// Raise it for historical codes with many description revisions, which a small limit
+    /// silently truncates. At most 500, the API's documented maximum.
+    #[arg(long, default_value_t = 20, value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..=500))]
+    pub hcpcs_single_count: usize,
+
+    /// ClinicalTables `count` (result row limit) for batched HCPCS lookups.
+    ///
+    /// Must be at least `--hcpcs-batch-size`, so every code in a batch can return a row, and at
+    /// most 500, the API's documented maximum.
+    #[arg(long, default_value_t = 500, value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..=500))]
+    pub hcpcs_batch_count: usize,
+
+    /// Debugging aid: bypass batching and request every HCPCS code on its own (same as a
+    /// batch size of 1), still honoring concurrency and rate limits.
+    #[arg(long, default_value_t = false)]
+    pub hcpcs_single_mode: bool,
+
+    /// Casing applied to emitted `hcpcs_code` values (mapping CSV, resolved parquet, cache rows).
+    ///
+    /// `upper` canonicalizes codes to uppercase; `source` preserves whatever casing the
+    /// HCPCS API (or the spending dataset) used.
+    #[arg(long, value_enum, default_value_t = HcpcsCaseMode::Upper)]
+    pub normalize_hcpcs_case: HcpcsCaseMode,
+
+    /// Write only the preferred `ok` record per code to the HCPCS mapping CSV (non-NOC first,
+    /// then earliest effective/add/term date), giving a plain code-to-description mapping.
+    /// By default every `ok` record is written, so a code can span several rows.
+    #[arg(long, default_value_t = false)]
+    pub hcpcs_mapping_one_row_per_code: bool,
+
     /// Directory containing extracted monthly NPPES CSV bundles.
     ///
     /// Expected files are produced by `download.sh` under:
@@ -133,12 +396,61 @@ pub struct Args {
     #[arg(long)]
     pub nppes_weekly_dir: Option<std::path::PathBuf>,
 
+    /// NPPES entity types to leave out of the NPI build (`1` = individuals, `2` = organizations).
+    ///
+    /// Bulk-matched NPIs are filtered before any API call and marked `skipped` in the cache.
+    /// NPIs only resolvable via the API are filtered once the response reveals their type.
+    #[arg(long, value_delimiter = ',', value_parser = ["1", "2"])]
+    pub exclude_npi_types: Vec<String>,
+
+    /// Print how many source NPIs appear only as billing, only as servicing, or in both roles
+    /// (one extra DuckDB aggregate over the input).
+    #[arg(long, default_value_t = false)]
+    pub npi_role_stats: bool,
+
+    /// Write the full raw body of every failed NPI/HCPCS API response (error status, invalid
+    /// JSON, or unparseable payload) to this directory as `<kind>_<id>_<run id>.body`, with a
+    /// `.json` sidecar holding the request URL (credentials and key/token-like query values
+    /// redacted), status, and error. Off by default.
+    #[arg(long)]
+    pub debug_dump_failed_bodies: Option<std::path::PathBuf>,
+
+    /// If writing the NPI or HCPCS parquet fails (e.g. disk full), log it and still run the
+    /// other dataset's export and the unresolved report/triage writes. Uploads are skipped and
+    /// the run exits non-zero at the end to signal the partial failure.
+    #[arg(long, alias = "continue-on-parquet-error", default_value_t = false)]
+    pub continue_on_export_error: bool,
+
     /// Skip local NPPES bulk-file loading and use cache/API only.
     #[arg(long, default_value_t = false)]
     pub skip_nppes_bulk: bool,
 
+    /// Preload every NPPES primary file under the monthly/weekly directories (e.g. split state
+    /// extracts) instead of only the newest one in each. Newer files win for NPIs in several.
+    #[arg(long, default_value_t = false, conflicts_with = "skip_nppes_bulk")]
+    pub nppes_merge_all: bool,
+
+    /// Threads scanning each NPPES primary CSV. Above 1, the file is split into byte ranges at
+    /// line boundaries and scanned in parallel; cache and parquet writes stay on one thread, so
+    /// bulk rows land in completion order rather than file order.
+    #[arg(long, default_value_t = 1, value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..))]
+    pub bulk_scan_threads: usize,
+
+    /// `false` exports every NPI in the local NPPES primary file(s), not just the spending
+    /// dataset's, to `--npi-api-responses-parquet` (default `output/nppes_full.parquet`), then
+    /// exits without reading the spending input, caches, or APIs. The output covers the whole
+    /// registry (roughly 9M rows, several GB), so this is opt-in.
+    #[arg(
+        long,
+        default_value_t = true,
+        action = clap::ArgAction::Set,
+        conflicts_with = "skip_nppes_bulk"
+    )]
+    pub limit_bulk_to_dataset_npis: bool,
+
     /// Optional Hugging Face token. Upload only happens if upload flags are set.
     #[arg(long)]
+    #[serde(serialize_with = "crate::run_config::serialize_secret")]
     pub hf_token: Option<String>,
 
     /// Optional Hugging Face repo id. Upload only happens if upload flags are set.
@@ -181,8 +493,199 @@ pub struct Args {
     #[arg(long)]
     pub hf_hcpcs_path_in_repo: Option<String>,
 
+    /// Also upload the NPI/HCPCS mapping CSVs and `npi.parquet`/`hcpcs.parquet` to
+    /// `s3://<bucket>/<prefix>`, via `python3` + boto3 with the usual `AWS_*` credentials.
+    #[arg(long, value_parser = parse_s3_url)]
+    pub upload_s3: Option<String>,
+
+    /// Custom S3 endpoint for `--upload-s3` (e.g. `http://localhost:9000` for MinIO).
+    #[arg(long, requires = "upload_s3")]
+    pub s3_endpoint: Option<String>,
+
     /// Generate `hf/parquet_null_audit.md` + update `hf/README.md` with null/empty-list stats
     /// for the resolved identifier Parquet outputs, then exit.
     #[arg(long, default_value_t = false)]
     pub null_check: bool,
+
+    /// Run `VACUUM` + `PRAGMA wal_checkpoint(TRUNCATE)` on the NPI and HCPCS cache DBs, print
+    /// before/after file sizes, then exit without building anything.
+    #[arg(long, alias = "vacuum-cache", default_value_t = false)]
+    pub cache_vacuum: bool,
+
+    /// Write row-level and paid-weighted match rates of the spending input against the
+    /// existing NPI/HCPCS mapping CSVs to this JSON file, then exit without building anything.
+    #[arg(long)]
+    pub join_health_report: Option<std::path::PathBuf>,
+
+    /// Dump every `hcpcs_cache` row (all records and statuses, with `fetched_at_unix` and
+    /// `attempt_count`) to this parquet for auditing, then exit without building anything.
+    #[arg(long, alias = "hcpcs-cache-export")]
+    pub export_hcpcs_cache: Option<std::path::PathBuf>,
+
+    /// Run the NPI and HCPCS pipelines end to end on a small bundled fixture against a
+    /// local mock API in a temp dir, check the output row counts, then exit (no network).
+    #[arg(long, default_value_t = false)]
+    pub selftest: bool,
+
+    /// Rewrite the HCPCS mapping CSV from the existing cache with the current record
+    /// selection logic (no API calls, no input scan), then exit.
+    #[arg(long, alias = "hcpcs-export-only", default_value_t = false)]
+    pub recompute_hcpcs_selection: bool,
+
+    /// With `--recompute-hcpcs-selection`, also rewrite the resolved HCPCS parquet.
+    #[arg(long, default_value_t = false, requires = "recompute_hcpcs_selection")]
+    pub recompute_hcpcs_parquet: bool,
+
+    /// Regenerate `npi.parquet`/`hcpcs.parquet` purely from the caches and NPPES bulk files,
+    /// skipping classification and API calls, then exit. Use after a crash during export;
+    /// fails if the caches do not fully cover the input.
+    #[arg(long, default_value_t = false, conflicts_with_all = ["rebuild_map", "reset_map"])]
+    pub resume_parquet: bool,
+
+    /// Print the NPI and HCPCS download plan tables, then exit. Classification and the NPPES
+    /// bulk preload run against throwaway copies of the caches; no API calls, and caches,
+    /// mappings, and parquets are left untouched.
+    #[arg(
+        long,
+        default_value_t = false,
+        conflicts_with_all = ["reset_map", "resume_parquet", "selftest", "null_check"]
+    )]
+    pub dry_run: bool,
+
+    /// Suppress informational output (plan tables, status lines, "Wrote ..." messages).
+    /// Warnings and errors still go to stderr, and the exit code is unchanged.
+    #[arg(long, default_value_t = false)]
+    pub quiet: bool,
+
+    /// Compression codec for the written parquet files: `zstd` (or `zstd:<level>`, level
+    /// 1-22), `snappy`, `gzip`, or `uncompressed`.
+    ///
+    /// The outputs are write-once, read-many and dominated by JSON strings, which ZSTD shrinks
+    /// far more than Snappy.
+    #[arg(long, default_value = "zstd:3", value_parser = parse_parquet_compression)]
+    pub parquet_compression: ParquetCompression,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum HcpcsCaseMode {
+    Upper,
+    Source,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum HcpcsSource {
+    Clinicaltables,
+    Cms,
+}
+
+/// `--parquet-compression` codec.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ParquetCompression {
+    Uncompressed,
+    Snappy,
+    Zstd { level: i32 },
+    Gzip,
+}
+
+impl Default for ParquetCompression {
+    fn default() -> Self {
+        Self::Zstd { level: 3 }
+    }
+}
+
+fn parse_parquet_compression(value: &str) -> Result<ParquetCompression, String> {
+    let value = value.trim().to_ascii_lowercase();
+    match value.split_once(':') {
+        None => match value.as_str() {
+            "uncompressed" | "none" => Ok(ParquetCompression::Uncompressed),
+            "snappy" => Ok(ParquetCompression::Snappy),
+            "gzip" => Ok(ParquetCompression::Gzip),
+            "zstd" => Ok(ParquetCompression::default()),
+            _ => Err(format!(
+                "expected zstd, zstd:<level>, snappy, gzip, or uncompressed (got {value:?})"
+            )),
+        },
+        Some(("zstd", level)) => match level.parse::<i32>() {
+            Ok(level) if (1..=22).contains(&level) => Ok(ParquetCompression::Zstd { level }),
+            _ => Err(format!(
+                "expected a zstd level from 1 to 22 (got {level:?})"
+            )),
+        },
+        Some(_) => Err(format!(
+            "only zstd takes a level, as zstd:<level> (got {value:?})"
+        )),
+    }
+}
+
+fn parse_error_status(value: &str) -> Result<u16, String> {
+    let status: u16 = value
+        .trim()
+        .parse()
+        .map_err(|_| format!("expected an HTTP status code (got {value:?})"))?;
+    if !(400..=599).contains(&status) {
+        return Err(format!("expected a 4xx or 5xx status (got {status})"));
+    }
+    Ok(status)
+}
+
+fn parse_fraction(value: &str) -> Result<f64, String> {
+    let parsed: f64 = value
+        .trim()
+        .parse()
+        .map_err(|_| format!("expected a number between 0 and 1 (got {value:?})"))?;
+    if !(0.0..=1.0).contains(&parsed) {
+        return Err(format!("expected a number between 0 and 1 (got {parsed})"));
+    }
+    Ok(parsed)
+}
+
+fn parse_min_tls_version(value: &str) -> Result<reqwest::tls::Version, String> {
+    match value.trim() {
+        "1.2" => Ok(reqwest::tls::Version::TLS_1_2),
+        "1.3" => Ok(reqwest::tls::Version::TLS_1_3),
+        "1.0" | "1.1" => Err(format!(
+            "TLS {value} cannot be enforced: the rustls backend only speaks TLS 1.2 and 1.3"
+        )),
+        _ => Err(format!("expected 1.2 or 1.3 (got {value:?})")),
+    }
+}
+
+fn parse_dataset_year(value: &str) -> Result<String, String> {
+    let trimmed = value.trim();
+    if trimmed.len() != 4 || !trimmed.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(format!("expected a 4-digit year (got {value:?})"));
+    }
+    Ok(trimmed.to_string())
+}
+
+fn parse_s3_url(value: &str) -> Result<String, String> {
+    let trimmed = value.trim().trim_end_matches('/');
+    let bucket = trimmed
+        .strip_prefix("s3://")
+        .map(|rest| rest.split('/').next().unwrap_or_default())
+        .ok_or_else(|| format!("expected s3://<bucket>[/<prefix>] (got {value:?})"))?;
+    if bucket.is_empty() {
+        return Err(format!("S3 URL is missing a bucket name (got {value:?})"));
+    }
+    Ok(trimmed.to_string())
+}
+
+fn parse_api_run_id(value: &str) -> Result<String, String> {
+    let trimmed = value.trim();
+    if trimmed.is_empty() {
+        return Err("api run id must not be empty".to_string());
+    }
+    if trimmed.len() > 128 {
+        return Err(format!(
+            "api run id must be at most 128 characters (got {})",
+            trimmed.len()
+        ));
+    }
+    if trimmed.chars().any(char::is_control) {
+        return Err("api run id must not contain control characters".to_string());
+    }
+    Ok(trimmed.to_string())
 }
@@ -0,0 +1,57 @@
+use anyhow::{Context, Result};
+use std::{
+    fs::{self, File},
+    io::{BufRead, BufReader, Read, Seek, SeekFrom, Take},
+    ops::Range,
+    path::Path,
+};
+
+/// Splits the data rows of a headered CSV into at most `parts` byte ranges for
+/// `--bulk-scan-threads`. Every range starts at a line boundary, so this assumes no quoted
+/// field spans a newline (true of the NPPES dissemination files).
+pub fn split_csv_ranges(path: &Path, parts: usize) -> Result<Vec<Range<u64>>> {
+    let len = fs::metadata(path)
+        .with_context(|| format!("Failed reading metadata of {}", path.display()))?
+        .len();
+    let mut reader = BufReader::new(
+        File::open(path).with_context(|| format!("Failed opening {}", path.display()))?,
+    );
+    let data_start = reader
+        .skip_until(b'\n')
+        .with_context(|| format!("Failed reading header of {}", path.display()))?
+        as u64;
+    let data_len = len.saturating_sub(data_start);
+    let parts = parts.max(1) as u64;
+
+    let mut bounds = vec![data_start];
+    for part in 1..parts {
+        let target = data_start + data_len * part / parts;
+        // Back up one byte so a target that already sits at a line start stays there.
+        reader
+            .seek(SeekFrom::Start(target.saturating_sub(1)))
+            .with_context(|| format!("Failed seeking in {}", path.display()))?;
+        let skipped = reader
+            .skip_until(b'\n')
+            .with_context(|| format!("Failed reading {}", path.display()))?
+            as u64;
+        let bound = (target.saturating_sub(1) + skipped).min(len);
+        if bounds.last().is_some_and(|last| bound > *last) {
+            bounds.push(bound);
+        }
+    }
+    if bounds.last().is_some_and(|last| len > *last) {
+        bounds.push(len);
+    }
+    Ok(bounds.windows(2).map(|w| w[0]..w[1]).collect())
+}
+
+/// Headerless CSV reader over one range from [`split_csv_ranges`].
+pub fn range_reader(path: &Path, range: &Range<u64>) -> Result<csv::Reader<BufReader<Take<File>>>> {
+    let mut file =
+        File::open(path).with_context(|| format!("Failed opening {}", path.display()))?;
+    file.seek(SeekFrom::Start(range.start))
+        .with_context(|| format!("Failed seeking in {}", path.display()))?;
+    Ok(csv::ReaderBuilder::new()
+        .has_headers(false)
+        .from_reader(BufReader::new(file.take(range.end - range.start))))
+}
@@ -0,0 +1,83 @@
+use anyhow::{Context, Result};
+use rusqlite::{Connection as SqliteConnection, OpenFlags};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::output::info;
+
+/// On-disk footprint of a SQLite DB in WAL mode: main file plus `-wal` and `-shm` sidecars.
+fn sqlite_footprint_bytes(path: &Path) -> u64 {
+    let mut total = fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    for suffix in ["-wal", "-shm"] {
+        let mut sidecar = path.as_os_str().to_owned();
+        sidecar.push(suffix);
+        total += fs::metadata(&sidecar).map(|m| m.len()).unwrap_or(0);
+    }
+    total
+}
+
+fn format_mib(bytes: u64) -> String {
+    format!("{:.2} MiB", bytes as f64 / (1024.0 * 1024.0))
+}
+
+fn vacuum_sqlite_cache(label: &str, path: &Path) -> Result<()> {
+    if !path.exists() {
+        info!(
+            "{label} cache DB not found at {}; skipping.",
+            path.display()
+        );
+        return Ok(());
+    }
+
+    let before = sqlite_footprint_bytes(path);
+    let conn = SqliteConnection::open(path)
+        .with_context(|| format!("Failed opening cache DB {}", path.display()))?;
+    // Fold the WAL into the main file first so VACUUM rewrites everything, then truncate the
+    // WAL that VACUUM itself produced.
+    conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()))
+        .with_context(|| format!("Failed checkpointing WAL for {}", path.display()))?;
+    conn.execute_batch("VACUUM")
+        .with_context(|| format!("Failed vacuuming {}", path.display()))?;
+    conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()))
+        .with_context(|| format!("Failed checkpointing WAL for {}", path.display()))?;
+    drop(conn);
+    let after = sqlite_footprint_bytes(path);
+
+    info!(
+        "Vacuumed {label} cache DB {}: {} -> {} (reclaimed {})",
+        path.display(),
+        format_mib(before),
+        format_mib(after),
+        format_mib(before.saturating_sub(after))
+    );
+    Ok(())
+}
+
+/// Snapshot of a cache DB (WAL included) under `scratch_dir` for `--dry-run`, which then
+/// classifies and preloads against the copy. The source is only read. A missing source yields
+/// a path that does not exist yet, so the dry run starts from an empty cache.
+pub fn scratch_cache_copy(cache_db: &Path, scratch_dir: &Path) -> Result<PathBuf> {
+    let file_name = cache_db
+        .file_name()
+        .with_context(|| format!("Cache DB path {} has no file name", cache_db.display()))?;
+    fs::create_dir_all(scratch_dir)
+        .with_context(|| format!("Failed creating {}", scratch_dir.display()))?;
+    let copy = scratch_dir.join(file_name);
+    if !cache_db.exists() {
+        return Ok(copy);
+    }
+    let conn = SqliteConnection::open_with_flags(cache_db, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .with_context(|| format!("Failed opening cache DB {}", cache_db.display()))?;
+    conn.execute("VACUUM INTO ?1", [copy.to_string_lossy()])
+        .with_context(|| format!("Failed copying {} for --dry-run", cache_db.display()))?;
+    Ok(copy)
+}
+
+/// Compact both cache DBs in place. Touches nothing but the SQLite files themselves.
+pub fn vacuum_cache_dbs(npi_cache_db: &Path, hcpcs_cache_db: &Path) -> Result<()> {
+    vacuum_sqlite_cache("NPI", npi_cache_db)?;
+    vacuum_sqlite_cache("HCPCS", hcpcs_cache_db)?;
+    Ok(())
+}
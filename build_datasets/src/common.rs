@@ -1,12 +1,16 @@
 use anyhow::{Context, Result, bail};
-use reqwest::{Client, StatusCode};
+use flate2::read::MultiGzDecoder;
+use indicatif::{ProgressBar, ProgressStyle};
+use reqwest::{Certificate, Client, StatusCode};
+use rusqlite::Connection as SqliteConnection;
 use std::{
+    collections::{HashMap, hash_map::Entry},
     fs::{self, File},
-    io::Write,
+    io::{BufReader, Read, Write},
     path::{Path, PathBuf},
     sync::{
         Arc,
-        atomic::{AtomicBool, Ordering},
+        atomic::{AtomicBool, AtomicUsize, Ordering},
     },
     time::Duration,
     time::{SystemTime, UNIX_EPOCH},
@@ -14,6 +18,9 @@ use std::{
 use tokio::sync::Mutex;
 use tokio::time::{Instant, sleep};
 
+use crate::args::Args;
+use crate::output::info;
+
 pub fn delete_if_exists(path: &Path) -> Result<()> {
     if path.exists() {
         fs::remove_file(path).with_context(|| format!("Failed deleting {}", path.display()))?;
@@ -21,6 +28,22 @@ pub fn delete_if_exists(path: &Path) -> Result<()> {
     Ok(())
 }
 
+/// Opens `path` for reading, gunzipping it on the fly when the name ends in `.gz`.
+pub fn open_maybe_gzip(path: &Path) -> Result<Box<dyn Read>> {
+    let file = File::open(path).with_context(|| format!("Failed opening {}", path.display()))?;
+    if is_gzip_path(path) {
+        Ok(Box::new(MultiGzDecoder::new(BufReader::new(file))))
+    } else {
+        Ok(Box::new(file))
+    }
+}
+
+pub fn is_gzip_path(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("gz"))
+}
+
 pub fn project_root() -> PathBuf {
     let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
     manifest_dir
@@ -29,6 +52,42 @@ pub fn project_root() -> PathBuf {
         .unwrap_or(manifest_dir)
 }
 
+/// Fails (or warns under `--allow-empty-input`) when extraction found no identifiers, which
+/// usually means an empty/header-only input or renamed columns rather than a real no-op.
+pub fn ensure_identifiers_extracted(
+    count: usize,
+    kind: &str,
+    source: &Path,
+    hint: &str,
+    allow_empty_input: bool,
+    max_identifiers: usize,
+) -> Result<()> {
+    if count > max_identifiers {
+        bail!(
+            "Extracted {count} unique {kind} from {}, above --max-identifiers {max_identifiers}. \
+This usually means they were read from the wrong column (schema mismatch). Raise --max-identifiers if the count is expected.",
+            source.display()
+        );
+    }
+    if count > 0 {
+        return Ok(());
+    }
+    let message = format!("No {kind} extracted from {}; {hint}", source.display());
+    if allow_empty_input {
+        eprintln!("Warning: {message}. Continuing because --allow-empty-input is set.");
+        return Ok(());
+    }
+    bail!("{message}. Pass --allow-empty-input to build empty outputs anyway.")
+}
+
+/// First standalone 4-digit run in 1900..=2099 (e.g. `2026` in `.../2026-02-09/file.parquet`).
+pub fn dataset_year_from_text(text: &str) -> Option<String> {
+    text.split(|c: char| !c.is_ascii_digit())
+        .filter(|run| run.len() == 4)
+        .find(|run| matches!(run.parse::<u32>(), Ok(1900..=2099)))
+        .map(str::to_string)
+}
+
 pub fn file_name_from_url(url: &str) -> Result<String> {
     let trimmed = url.trim().trim_end_matches('/');
     let file_name = trimmed
@@ -56,6 +115,31 @@ pub async fn download_file(client: &Client, url: &str, output_path: &Path) -> Re
     let mut file = File::create(output_path)
         .with_context(|| format!("Failed creating {}", output_path.display()))?;
 
+    let progress = match response.content_length() {
+        Some(total) => {
+            let progress = ProgressBar::new(total);
+            if let Ok(style) = ProgressStyle::with_template(
+                "{spinner:.green} {prefix:.bold} [{elapsed_precise}] [{bar:32.cyan/blue}] \
+{bytes}/{total_bytes} ({percent}%) {bytes_per_sec} eta {eta_precise}",
+            ) {
+                progress.set_style(style.progress_chars("=> "));
+            }
+            progress
+        }
+        None => {
+            let progress = ProgressBar::new_spinner();
+            if let Ok(style) = ProgressStyle::with_template(
+                "{spinner:.green} {prefix:.bold} [{elapsed_precise}] {bytes} {bytes_per_sec}",
+            ) {
+                progress.set_style(style);
+            }
+            progress
+        }
+    };
+    progress.set_prefix("DOWNLOAD");
+    progress.enable_steady_tick(Duration::from_millis(250));
+
+    let started = std::time::Instant::now();
     let mut downloaded: u64 = 0;
     while let Some(chunk) = response
         .chunk()
@@ -65,15 +149,61 @@ pub async fn download_file(client: &Client, url: &str, output_path: &Path) -> Re
         file.write_all(&chunk)
             .with_context(|| format!("Failed writing {}", output_path.display()))?;
         downloaded += chunk.len() as u64;
-        if downloaded % (512 * 1024 * 1024) < chunk.len() as u64 {
-            println!("Downloaded ~{} MiB", downloaded / (1024 * 1024));
-        }
+        progress.set_position(downloaded);
     }
+    progress.finish_and_clear();
+
+    let elapsed_secs = started.elapsed().as_secs_f64();
+    let mib = downloaded as f64 / (1024.0 * 1024.0);
+    let mib_per_sec = if elapsed_secs > 0.0 {
+        mib / elapsed_secs
+    } else {
+        mib
+    };
+    info!(
+        "Download complete: {} ({mib:.1} MiB in {elapsed_secs:.1}s, {mib_per_sec:.1} MiB/s)",
+        output_path.display()
+    );
+    Ok(())
+}
 
-    println!("Download complete: {}", output_path.display());
+/// Add `column` to an existing SQLite cache table when it was created by an older build.
+pub fn ensure_sqlite_column(
+    conn: &SqliteConnection,
+    table: &str,
+    column: &str,
+    decl: &str,
+) -> Result<()> {
+    let mut stmt = conn
+        .prepare(&format!("PRAGMA table_info({table})"))
+        .with_context(|| format!("Failed reading schema for {table}"))?;
+    let mut rows = stmt
+        .query([])
+        .with_context(|| format!("Failed querying schema for {table}"))?;
+    while let Some(row) = rows.next()? {
+        let name: String = row.get(1)?;
+        if name == column {
+            return Ok(());
+        }
+    }
+    drop(rows);
+    drop(stmt);
+    conn.execute_batch(&format!("ALTER TABLE {table} ADD COLUMN {column} {decl};"))
+        .with_context(|| format!("Failed adding {table}.{column}"))?;
     Ok(())
 }
 
+/// Reads a PEM file of one or more root certificates; fails if none parse.
+pub fn load_ca_certificates(path: &Path) -> Result<Vec<Certificate>> {
+    let pem = fs::read(path).with_context(|| format!("Failed reading {}", path.display()))?;
+    let certs = Certificate::from_pem_bundle(&pem)
+        .with_context(|| format!("Failed parsing PEM certificates from {}", path.display()))?;
+    if certs.is_empty() {
+        bail!("No PEM certificates found in {}", path.display());
+    }
+    Ok(certs)
+}
+
 pub fn sql_escape_path(path: &Path) -> String {
     path.to_string_lossy().replace('\'', "''")
 }
@@ -106,12 +236,189 @@ pub fn is_retryable_status(status: StatusCode) -> bool {
     )
 }
 
+/// Per-request retry settings shared by the NPI and HCPCS resolvers.
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub backoff_base: Duration,
+    pub backoff_max: Duration,
+    /// `--request-timeout-secs`, applied to each attempt.
+    pub request_timeout: Duration,
+}
+
+impl RetryPolicy {
+    pub fn from_args(args: &Args) -> Self {
+        Self {
+            max_retries: args.max_retries,
+            backoff_base: Duration::from_millis(args.retry_backoff_base_ms),
+            backoff_max: Duration::from_millis(
+                args.retry_backoff_max_ms.max(args.retry_backoff_base_ms),
+            ),
+            request_timeout: Duration::from_secs(args.request_timeout_secs),
+        }
+    }
+
+    pub fn attempts(&self) -> u32 {
+        self.max_retries.max(1)
+    }
+
+    pub fn initial_backoff(&self) -> Duration {
+        self.backoff_base.min(self.backoff_max)
+    }
+
+    /// Exponential backoff: double the previous delay, capped at `backoff_max`.
+    pub fn next_backoff(&self, current: Duration) -> Duration {
+        current.saturating_mul(2).min(self.backoff_max)
+    }
+}
+
+/// Remaining `--max-new-lookups-total` budget, shared by the NPI and HCPCS builds. `None` means
+/// no combined cap.
+pub struct LookupBudget {
+    remaining: Option<AtomicUsize>,
+}
+
+impl LookupBudget {
+    pub fn new(limit: Option<usize>) -> Self {
+        Self {
+            remaining: limit.map(AtomicUsize::new),
+        }
+    }
+
+    pub fn remaining(&self) -> Option<usize> {
+        self.remaining
+            .as_ref()
+            .map(|remaining| remaining.load(Ordering::SeqCst))
+    }
+
+    /// Takes up to `wanted` lookups from the budget and returns how many were granted (`wanted`
+    /// when there is no cap). Each build takes its lookups as it dispatches them, so a parallel
+    /// run never exceeds the combined cap and neither build reserves lookups it never makes.
+    pub fn take(&self, wanted: usize) -> usize {
+        let Some(remaining) = &self.remaining else {
+            return wanted;
+        };
+        let mut granted = 0;
+        let _ = remaining.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |left| {
+            granted = wanted.min(left);
+            Some(left - granted)
+        });
+        granted
+    }
+
+    /// Keeps the part of `ids` that fits the budget and returns the rest.
+    pub fn admit(&self, ids: &mut Vec<String>) -> Vec<String> {
+        let granted = self.take(ids.len());
+        ids.split_off(granted)
+    }
+}
+
+/// Wall-clock cadence for plain status lines (`--progress-interval-seconds`).
+pub struct StatusTicker {
+    interval: Option<Duration>,
+    last: Instant,
+}
+
+impl StatusTicker {
+    pub fn new(interval: Option<Duration>) -> Self {
+        Self {
+            interval,
+            last: Instant::now(),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.interval.is_some()
+    }
+
+    /// True at most once per interval; always false when no interval is configured.
+    pub fn due(&mut self) -> bool {
+        match self.interval {
+            Some(interval) if self.last.elapsed() >= interval => {
+                self.last = Instant::now();
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Identifiers with a lookup currently in flight.
+///
+/// A duplicate enqueue of an identifier that is already being fetched does not issue a second
+/// request. It waits on the first lookup instead: [`InFlightIds::release`] hands it back so the
+/// caller settles it with that lookup's result.
+pub struct InFlightIds {
+    key: fn(&str) -> String,
+    /// In-flight key -> duplicates waiting on its result.
+    ids: HashMap<String, Vec<String>>,
+    coalesced: usize,
+}
+
+impl InFlightIds {
+    pub fn new(key: fn(&str) -> String) -> Self {
+        Self {
+            key,
+            ids: HashMap::new(),
+            coalesced: 0,
+        }
+    }
+
+    /// Returns false if `id` is already in flight; it is then queued behind that lookup (and
+    /// counted as a coalesced duplicate).
+    pub fn claim(&mut self, id: &str) -> bool {
+        match self.ids.entry((self.key)(id)) {
+            Entry::Vacant(entry) => {
+                entry.insert(Vec::new());
+                true
+            }
+            Entry::Occupied(mut entry) => {
+                entry.get_mut().push(id.to_string());
+                self.coalesced += 1;
+                false
+            }
+        }
+    }
+
+    /// Marks the lookup for `id` done and returns the duplicates that were waiting on it.
+    pub fn release(&mut self, id: &str) -> Vec<String> {
+        self.ids.remove(&(self.key)(id)).unwrap_or_default()
+    }
+
+    /// Next queued identifier that isn't already in flight.
+    pub fn next_claimed(&mut self, queue: &mut impl Iterator<Item = String>) -> Option<String> {
+        queue.find(|id| self.claim(id))
+    }
+
+    /// Next queued batch with in-flight identifiers removed (skipping batches left empty).
+    pub fn next_claimed_batch(
+        &mut self,
+        queue: &mut impl Iterator<Item = Vec<String>>,
+    ) -> Option<Vec<String>> {
+        for mut batch in queue.by_ref() {
+            batch.retain(|id| self.claim(id));
+            if !batch.is_empty() {
+                return Some(batch);
+            }
+        }
+        None
+    }
+
+    pub fn coalesced(&self) -> usize {
+        self.coalesced
+    }
+}
+
 pub fn parse_retry_after(value: Option<&reqwest::header::HeaderValue>) -> Option<Duration> {
     let value = value?.to_str().ok()?.trim();
     let secs = value.parse::<u64>().ok()?;
     Some(Duration::from_secs(secs))
 }
 
+pub fn elapsed_millis(started: Instant) -> i64 {
+    i64::try_from(started.elapsed().as_millis()).unwrap_or(i64::MAX)
+}
+
 pub fn truncate_for_log(text: &str) -> String {
     let trimmed = text.trim();
     let max_len = 300usize;
@@ -164,3 +471,49 @@ pub fn now_unix_millis() -> i128 {
 pub fn new_api_run_id() -> String {
     format!("api-run-{}", now_unix_millis())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trimmed(id: &str) -> String {
+        id.trim().to_string()
+    }
+
+    #[test]
+    fn duplicate_enqueue_waits_on_the_lookup_in_flight() {
+        let mut in_flight = InFlightIds::new(trimmed);
+        let mut queue = ["A1", " A1", "B2", "A1 "].map(String::from).into_iter();
+
+        assert_eq!(in_flight.next_claimed(&mut queue).as_deref(), Some("A1"));
+        // " A1" is already in flight, so it is queued behind it rather than dispatched.
+        assert_eq!(in_flight.next_claimed(&mut queue).as_deref(), Some("B2"));
+        assert_eq!(in_flight.next_claimed(&mut queue), None);
+        assert_eq!(in_flight.coalesced(), 2);
+
+        assert_eq!(in_flight.release("A1"), [" A1", "A1 "]);
+        assert!(in_flight.release("B2").is_empty());
+        // Once settled, the identifier can be looked up again.
+        assert!(in_flight.claim(" A1"));
+    }
+
+    #[test]
+    fn duplicate_in_a_batch_waits_on_the_lookup_in_flight() {
+        let mut in_flight = InFlightIds::new(trimmed);
+        let mut queue = vec![
+            vec!["X".to_string(), "Y".to_string()],
+            vec![" X".to_string()],
+            vec!["Y ".to_string(), "Z".to_string()],
+        ]
+        .into_iter();
+
+        assert_eq!(
+            in_flight.next_claimed_batch(&mut queue).unwrap(),
+            ["X", "Y"]
+        );
+        // The all-duplicate batch is skipped; the mixed one keeps only the new code.
+        assert_eq!(in_flight.next_claimed_batch(&mut queue).unwrap(), ["Z"]);
+        assert_eq!(in_flight.release("X"), [" X"]);
+        assert_eq!(in_flight.release("Y"), ["Y "]);
+    }
+}
@@ -0,0 +1,56 @@
+use anyhow::{Result, bail};
+use std::sync::Mutex;
+
+/// Export failures swallowed under `--continue-on-export-error`, reported once the run ends.
+pub struct ExportFailures {
+    continue_on_error: bool,
+    failures: Mutex<Vec<String>>,
+}
+
+impl ExportFailures {
+    pub fn new(continue_on_error: bool) -> Self {
+        Self {
+            continue_on_error,
+            failures: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Passes `result` through unless `--continue-on-export-error` is set, in which case a
+    /// failure is logged and recorded so the remaining exports and reports still run.
+    pub fn tolerate(&self, dataset: &str, result: Result<()>) -> Result<()> {
+        let Err(err) = result else {
+            return Ok(());
+        };
+        if !self.continue_on_error {
+            return Err(err);
+        }
+        eprintln!(
+            "Error: {dataset} export failed; continuing (--continue-on-export-error): {err:#}"
+        );
+        self.lock().push(format!("{dataset}: {err:#}"));
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    /// Fails the run (non-zero exit) if any export was skipped past by [`Self::tolerate`].
+    pub fn ensure_none(&self) -> Result<()> {
+        let failures = self.lock();
+        if failures.is_empty() {
+            return Ok(());
+        }
+        bail!(
+            "{} dataset export(s) failed; other outputs were written:\n  {}",
+            failures.len(),
+            failures.join("\n  ")
+        )
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<String>> {
+        self.failures
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
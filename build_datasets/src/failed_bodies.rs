@@ -0,0 +1,111 @@
+use anyhow::{Context, Result};
+use serde_json::json;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+use crate::output::info;
+
+/// Set once from `--debug-dump-failed-bodies`; dumping is a no-op while unset.
+static DUMP_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// Query parameter names (lowercased substrings) whose values never reach a dump file.
+const SENSITIVE_QUERY_KEYS: &[&str] = &["key", "token", "secret", "password", "auth", "signature"];
+
+/// One failed API response, as handed to [`dump_failed_body`].
+pub struct FailedBody<'a> {
+    /// `npi`, `hcpcs`, or `hcpcs_batch`.
+    pub kind: &'a str,
+    pub identifier: &'a str,
+    pub api_run_id: &'a str,
+    pub request_url: &'a str,
+    pub http_status: Option<u16>,
+    pub error_message: &'a str,
+    pub body: &'a str,
+}
+
+pub fn enable_failed_body_dumps(dir: &Path) -> Result<()> {
+    fs::create_dir_all(dir).with_context(|| format!("Failed creating {}", dir.display()))?;
+    let _ = DUMP_DIR.set(dir.to_path_buf());
+    info!("Dumping failed API response bodies to {}", dir.display());
+    Ok(())
+}
+
+/// Writes `<kind>_<identifier>_<api_run_id>.body` (the raw body, untruncated) and a `.json`
+/// sidecar with the redacted request URL, status, and error. Failures only warn: a debugging
+/// aid must never fail the run.
+pub fn dump_failed_body(failed: &FailedBody<'_>) {
+    let Some(dir) = DUMP_DIR.get() else {
+        return;
+    };
+    let stem = format!(
+        "{}_{}_{}",
+        file_safe(failed.kind),
+        file_safe(failed.identifier),
+        file_safe(failed.api_run_id)
+    );
+    let body_path = dir.join(format!("{stem}.body"));
+    let meta_path = dir.join(format!("{stem}.json"));
+    let meta = json!({
+        "kind": failed.kind,
+        "identifier": failed.identifier,
+        "api_run_id": failed.api_run_id,
+        "request_url": redact_url(failed.request_url),
+        "http_status": failed.http_status,
+        "error_message": failed.error_message,
+        "body_bytes": failed.body.len(),
+        "body_file": body_path.file_name().map(|n| n.to_string_lossy().into_owned()),
+    });
+    let written = fs::write(&body_path, failed.body).and_then(|_| {
+        fs::write(
+            &meta_path,
+            serde_json::to_string_pretty(&meta).unwrap_or_default(),
+        )
+    });
+    if let Err(err) = written {
+        eprintln!(
+            "Warning: failed dumping response body to {}: {err}",
+            body_path.display()
+        );
+    }
+}
+
+/// Drops URL credentials and masks sensitive query parameter values.
+pub fn redact_url(url: &str) -> String {
+    let Ok(mut parsed) = reqwest::Url::parse(url) else {
+        return url.to_string();
+    };
+    let _ = parsed.set_username("");
+    let _ = parsed.set_password(None);
+    let pairs: Vec<(String, String)> = parsed
+        .query_pairs()
+        .map(|(key, value)| {
+            let lowered = key.to_ascii_lowercase();
+            let value = if SENSITIVE_QUERY_KEYS.iter().any(|s| lowered.contains(s)) {
+                "REDACTED".to_string()
+            } else {
+                value.into_owned()
+            };
+            (key.into_owned(), value)
+        })
+        .collect();
+    if !pairs.is_empty() {
+        parsed.query_pairs_mut().clear().extend_pairs(pairs);
+    }
+    parsed.to_string()
+}
+
+fn file_safe(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
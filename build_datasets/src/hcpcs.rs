@@ -1,13 +1,14 @@
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, bail};
 use csv::{ReaderBuilder, StringRecord, Writer};
 use duckdb::Connection;
 use futures::{StreamExt, stream::FuturesUnordered};
 use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
 use reqwest::{Client, header::RETRY_AFTER};
-use rusqlite::{Connection as SqliteConnection, OptionalExtension, params};
+use rusqlite::{Connection as SqliteConnection, OpenFlags, OptionalExtension, params};
 use serde_json::{Value, json};
 use std::{
-    collections::{HashMap, HashSet},
+    cell::Cell,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
     fs,
     io::IsTerminal,
     path::Path,
@@ -21,12 +22,18 @@ use tokio::sync::Mutex;
 use tokio::time::{Instant, sleep};
 
 use crate::{
-    args::Args,
+    args::{Args, HcpcsCaseMode, HcpcsSource, ParquetCompression},
     common::{
-        is_retryable_status, now_unix_seconds, parse_retry_after, source_expr, sql_escape_path,
-        truncate_for_log, wait_for_rate_slot,
+        InFlightIds, LookupBudget, RetryPolicy, StatusTicker, elapsed_millis,
+        ensure_identifiers_extracted, ensure_sqlite_column, is_retryable_status, now_unix_seconds,
+        parse_retry_after, source_expr, sql_escape_path, truncate_for_log, wait_for_rate_slot,
     },
-    parquet_writer::StringParquetWriter,
+    export_errors::ExportFailures,
+    failed_bodies::{FailedBody, dump_failed_body},
+    normalize::{normalize_code_key, normalize_hcpcs_code, split_hcpcs_modifier},
+    output::info,
+    parquet_writer::{ParquetOutputOptions, StringParquetWriter},
+    run_summary::{HcpcsPlanCounts, LookupTotals, record_hcpcs_lookups, record_hcpcs_plan},
 };
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -39,6 +46,8 @@ struct HcpcsApiRecord {
     term_dt: String,
     obsolete: bool,
     is_noc: bool,
+    /// Set from the fallback CSV's `preferred` column; exported ahead of the NOC ordering.
+    preferred: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -60,9 +69,11 @@ struct HcpcsApiReferenceRow {
     api_run_id: String,
     requested_at_utc: String,
     request_params_json: String,
+    latency_ms: Option<i64>,
     response_json_raw: Option<String>,
 }
 
+#[derive(Clone)]
 enum HcpcsResolveResult {
     Found {
         records: Vec<HcpcsApiRecord>,
@@ -78,8 +89,71 @@ enum HcpcsResolveResult {
     },
 }
 
+/// Where single-code lookups go and which response shape they return (`--hcpcs-source`).
+#[derive(Debug, Clone)]
+struct HcpcsEndpoint {
+    base_url: String,
+    source: HcpcsSource,
+    /// `--hcpcs-not-found-statuses`, honored by single-code lookups.
+    not_found_statuses: Vec<u16>,
+    /// ClinicalTables `count` for single-code and batched lookups (`--hcpcs-single-count`,
+    /// `--hcpcs-batch-count`).
+    single_count: usize,
+    batch_count: usize,
+}
+
+impl HcpcsEndpoint {
+    fn from_args(args: &Args) -> Self {
+        Self {
+            base_url: args.hcpcs_api_base_url.clone(),
+            source: args.hcpcs_source,
+            not_found_statuses: args.hcpcs_not_found_statuses.clone(),
+            single_count: args.hcpcs_single_count,
+            batch_count: args.hcpcs_batch_count,
+        }
+    }
+}
+
+/// Column layout of `hcpcs.parquet` (select a subset with `--hcpcs-output-columns`).
+pub const HCPCS_PARQUET_COLUMNS: &[&str] = &[
+    "hcpcs_code",
+    "modifier",
+    "ef_short_desc",
+    "ef_long_desc",
+    "ef_add_dt",
+    "ef_act_eff_dt",
+    "ef_term_dt",
+    "ef_obsolete",
+    "ef_is_noc",
+    "response_codes",
+    "response_display",
+    "response_extra_fields",
+    "url",
+    "error_message",
+    "api_run_id",
+    "requested_at_utc",
+    "request_params",
+    "latency_ms",
+    "response_json",
+];
+
+/// Columns fed to `content_hash` (`--content-hash`): the code and its `ef_*` descriptor fields.
+/// The raw `response_*` columns (which depend on how codes were batched), request metadata,
+/// and `error_message` are excluded.
+pub const HCPCS_CONTENT_HASH_COLUMNS: &[&str] = &[
+    "hcpcs_code",
+    "ef_short_desc",
+    "ef_long_desc",
+    "ef_add_dt",
+    "ef_act_eff_dt",
+    "ef_term_dt",
+    "ef_obsolete",
+    "ef_is_noc",
+];
+
 struct HcpcsCache {
     conn: SqliteConnection,
+    case_mode: HcpcsCaseMode,
 }
 
 #[derive(Debug, Clone)]
@@ -113,6 +187,10 @@ impl HcpcsCache {
                 status TEXT NOT NULL,
                 error_message TEXT NOT NULL DEFAULT '',
                 fetched_at_unix INTEGER NOT NULL,
+                attempt_count INTEGER NOT NULL DEFAULT 0,
+                modifier TEXT NOT NULL DEFAULT '',
+                code_key TEXT NOT NULL DEFAULT '',
+                preferred TEXT NOT NULL DEFAULT '',
                 PRIMARY KEY (
                     hcpcs_code,
                     short_desc,
@@ -144,6 +222,7 @@ impl HcpcsCache {
                 api_run_id TEXT,
                 requested_at_utc TEXT,
                 request_params_json TEXT,
+                latency_ms INTEGER,
                 response_json_raw TEXT
             );
             CREATE INDEX IF NOT EXISTS idx_hcpcs_api_responses_requested_at
@@ -151,7 +230,51 @@ impl HcpcsCache {
             ",
         )
         .context("Failed initializing HCPCS cache schema")?;
-        Ok(Self { conn })
+        ensure_sqlite_column(&conn, "hcpcs_api_responses", "latency_ms", "INTEGER")?;
+        ensure_sqlite_column(
+            &conn,
+            "hcpcs_cache",
+            "attempt_count",
+            "INTEGER NOT NULL DEFAULT 0",
+        )?;
+        ensure_sqlite_column(&conn, "hcpcs_cache", "modifier", "TEXT NOT NULL DEFAULT ''")?;
+        // `code_key` (normalized `hcpcs_code`) is the exact lookup key; `hcpcs_code` keeps the
+        // emitted casing. Caches from before the column get it backfilled here.
+        ensure_sqlite_column(&conn, "hcpcs_cache", "code_key", "TEXT NOT NULL DEFAULT ''")?;
+        ensure_sqlite_column(
+            &conn,
+            "hcpcs_cache",
+            "preferred",
+            "TEXT NOT NULL DEFAULT ''",
+        )?;
+        conn.execute_batch(
+            "
+            UPDATE hcpcs_cache SET code_key = UPPER(TRIM(hcpcs_code)) WHERE code_key = '';
+            CREATE INDEX IF NOT EXISTS idx_hcpcs_cache_key_status
+                ON hcpcs_cache(code_key, status);
+            ",
+        )
+        .context("Failed migrating HCPCS cache code_key column")?;
+        Ok(Self {
+            conn,
+            case_mode: HcpcsCaseMode::Upper,
+        })
+    }
+
+    /// Read-only counterpart of `open` for readers that run alongside the lookup workers; see
+    /// `NpiCache::open_read_only`.
+    fn open_read_only(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Self::open(path);
+        }
+        let conn = SqliteConnection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .with_context(|| format!("Failed opening cache DB {}", path.display()))?;
+        conn.busy_timeout(Duration::from_secs(5))
+            .context("Failed setting HCPCS cache busy timeout")?;
+        Ok(Self {
+            conn,
+            case_mode: HcpcsCaseMode::Upper,
+        })
     }
 
     fn upsert_api_responses(&mut self, rows: &[HcpcsApiReferenceRow]) -> Result<()> {
@@ -159,6 +282,7 @@ impl HcpcsCache {
             return Ok(());
         }
 
+        let case_mode = self.case_mode;
         let tx = self
             .conn
             .transaction()
@@ -183,9 +307,10 @@ impl HcpcsCache {
                     api_run_id,
                     requested_at_utc,
                     request_params_json,
-                    response_json_raw
+                    response_json_raw,
+                    latency_ms
                 ) VALUES (
-                    ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17
+                    ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18
                 )
                 ON CONFLICT(hcpcs_code) DO UPDATE SET
                     ef_short_desc_json = excluded.ef_short_desc_json,
@@ -203,7 +328,8 @@ impl HcpcsCache {
                     api_run_id = excluded.api_run_id,
                     requested_at_utc = excluded.requested_at_utc,
                     request_params_json = excluded.request_params_json,
-                    response_json_raw = excluded.response_json_raw
+                    response_json_raw = excluded.response_json_raw,
+                    latency_ms = excluded.latency_ms
                 WHERE excluded.requested_at_utc > hcpcs_api_responses.requested_at_utc
                    OR hcpcs_api_responses.requested_at_utc IS NULL
                 ",
@@ -211,7 +337,7 @@ impl HcpcsCache {
             .context("Failed preparing HCPCS API responses upsert statement")?;
         for row in rows {
             stmt.execute(params![
-                row.hcpcs_code.as_str(),
+                apply_hcpcs_case(&row.hcpcs_code, case_mode),
                 row.ef_short_desc_json.as_deref(),
                 row.ef_long_desc_json.as_deref(),
                 row.ef_add_dt_json.as_deref(),
@@ -228,6 +354,7 @@ impl HcpcsCache {
                 row.requested_at_utc.as_str(),
                 row.request_params_json.as_str(),
                 row.response_json_raw.as_deref(),
+                row.latency_ms,
             ])
             .with_context(|| {
                 format!(
@@ -242,37 +369,69 @@ impl HcpcsCache {
         Ok(())
     }
 
+    /// Stores the modifiers seen with each code (comma-separated, sorted) on all of its cache
+    /// rows. Runs after lookups, since replacing a code's rows resets the column.
+    fn record_modifiers(&mut self, modifiers: &BTreeMap<String, BTreeSet<String>>) -> Result<()> {
+        let tx = self
+            .conn
+            .transaction()
+            .context("Failed starting HCPCS modifier transaction")?;
+        let mut stmt = tx
+            .prepare("UPDATE hcpcs_cache SET modifier = ?2 WHERE code_key = ?1")
+            .context("Failed preparing HCPCS modifier update statement")?;
+        for (code, code_modifiers) in modifiers {
+            let joined = code_modifiers
+                .iter()
+                .map(String::as_str)
+                .collect::<Vec<_>>()
+                .join(",");
+            stmt.execute(params![normalize_code_key(code), joined])
+                .with_context(|| format!("Failed recording HCPCS modifiers for {code}"))?;
+        }
+        drop(stmt);
+        tx.commit()
+            .context("Failed committing HCPCS modifier transaction")?;
+        Ok(())
+    }
+
+    fn emit_code(&self, code: &str) -> String {
+        apply_hcpcs_case(code, self.case_mode)
+    }
+
     fn export_api_responses_parquet(
         &self,
         output_path: &Path,
         hcpcs_fallback_csv: &Path,
         api_run_id: &str,
+        output: ParquetOutputOptions<'_>,
     ) -> Result<()> {
         // This is intentionally a *unified* dataset export: one row per unique code in the
         // spending dataset, populated from hcpcs_cache (API-derived and/or local fallback).
         //
         // We still incorporate request metadata from `hcpcs_api_responses` when available.
-        let columns = [
-            "hcpcs_code",
-            "ef_short_desc",
-            "ef_long_desc",
-            "ef_add_dt",
-            "ef_act_eff_dt",
-            "ef_term_dt",
-            "ef_obsolete",
-            "ef_is_noc",
-            "response_codes",
-            "response_display",
-            "response_extra_fields",
-            "url",
-            "error_message",
-            "api_run_id",
-            "requested_at_utc",
-            "request_params",
-            "response_json",
-        ];
-        let mut writer = StringParquetWriter::try_new(output_path, &columns, 10_000)?;
+        let mut writer = StringParquetWriter::try_new(
+            output_path,
+            HCPCS_PARQUET_COLUMNS,
+            output,
+            HCPCS_CONTENT_HASH_COLUMNS,
+            10_000,
+        )?;
+
+        match self.write_api_response_rows(&mut writer, hcpcs_fallback_csv, api_run_id) {
+            Ok(()) => writer.finish(),
+            Err(err) => {
+                let _ = writer.abort();
+                Err(err)
+            }
+        }
+    }
 
+    fn write_api_response_rows(
+        &self,
+        writer: &mut StringParquetWriter,
+        hcpcs_fallback_csv: &Path,
+        api_run_id: &str,
+    ) -> Result<()> {
         let synthetic_requested_at = now_unix_seconds().to_string();
         let synthetic_url = format!("hcpcs_cache:{}", hcpcs_fallback_csv.display());
         let synthetic_request_params = json!({
@@ -285,9 +444,10 @@ impl HcpcsCache {
             .conn
             .prepare(
                 "
-                SELECT DISTINCT hcpcs_code
+                SELECT code_key, MIN(hcpcs_code)
                 FROM hcpcs_cache
-                ORDER BY UPPER(hcpcs_code)
+                GROUP BY code_key
+                ORDER BY code_key
                 ",
             )
             .context("Failed preparing distinct HCPCS code query")?;
@@ -305,8 +465,9 @@ impl HcpcsCache {
                     obsolete,
                     is_noc
                 FROM hcpcs_cache
-                WHERE hcpcs_code = ?1 COLLATE NOCASE AND status = 'ok'
+                WHERE code_key = ?1 AND status = 'ok'
                 ORDER BY
+                    CASE WHEN preferred = 'true' THEN 0 ELSE 1 END,
                     CASE WHEN LOWER(COALESCE(is_noc, 'false')) = 'false' THEN 0 ELSE 1 END,
                     act_eff_dt,
                     add_dt,
@@ -322,12 +483,13 @@ impl HcpcsCache {
                 "
                 SELECT status, error_message
                 FROM hcpcs_cache
-                WHERE hcpcs_code = ?1 COLLATE NOCASE AND status IN ('not_found', 'error')
+                WHERE code_key = ?1 AND status IN ('not_found', 'error', 'permanently_failed')
                 ORDER BY
                     CASE status
                         WHEN 'not_found' THEN 0
                         WHEN 'error' THEN 1
-                        ELSE 2
+                        WHEN 'permanently_failed' THEN 2
+                        ELSE 3
                     END,
                     fetched_at_unix DESC
                 LIMIT 1
@@ -335,11 +497,22 @@ impl HcpcsCache {
             )
             .context("Failed preparing HCPCS status query")?;
 
+        let mut modifier_row = self
+            .conn
+            .prepare("SELECT NULLIF(MAX(modifier), '') FROM hcpcs_cache WHERE code_key = ?1")
+            .context("Failed preparing HCPCS modifier query")?;
+
         let mut api_meta = self
             .conn
             .prepare(
                 "
-                SELECT url, api_run_id, requested_at_utc, request_params_json, response_json_raw
+                SELECT
+                    url,
+                    api_run_id,
+                    requested_at_utc,
+                    request_params_json,
+                    response_json_raw,
+                    CAST(latency_ms AS TEXT)
                 FROM hcpcs_api_responses
                 WHERE hcpcs_code = ?1 COLLATE NOCASE
                 LIMIT 1
@@ -352,15 +525,16 @@ impl HcpcsCache {
             .context("Failed querying distinct HCPCS codes")?;
 
         while let Some(row) = rows.next().context("Failed iterating HCPCS codes")? {
-            let raw_code: String = row.get(0).context("Failed reading hcpcs_code")?;
-            let hcpcs_code = normalize_code_key(&raw_code);
-            if hcpcs_code.is_empty() {
+            let code_key: String = row.get(0).context("Failed reading code_key")?;
+            let raw_code: String = row.get(1).context("Failed reading hcpcs_code")?;
+            let hcpcs_code = self.emit_code(&raw_code);
+            if code_key.is_empty() {
                 continue;
             }
 
             let mut ok_records = Vec::new();
             let mut ok_iter = ok_rows
-                .query([&hcpcs_code])
+                .query([&code_key])
                 .with_context(|| format!("Failed querying ok HCPCS rows for {hcpcs_code}"))?;
             while let Some(orow) = ok_iter
                 .next()
@@ -381,7 +555,7 @@ impl HcpcsCache {
                 ("ok".to_string(), None)
             } else {
                 let status_pair: Option<(String, String)> = status_row
-                    .query_row([&hcpcs_code], |r| Ok((r.get(0)?, r.get(1)?)))
+                    .query_row([&code_key], |r| Ok((r.get(0)?, r.get(1)?)))
                     .optional()
                     .with_context(|| format!("Failed querying HCPCS status for {hcpcs_code}"))?;
                 match status_pair {
@@ -499,22 +673,31 @@ impl HcpcsCache {
                 )
             };
 
-            let (url, meta_api_run_id, requested_at_utc, request_params, response_json_raw) =
-                api_meta
-                    .query_row([&hcpcs_code], |r| {
-                        Ok((
-                            r.get::<usize, Option<String>>(0)?,
-                            r.get::<usize, Option<String>>(1)?,
-                            r.get::<usize, Option<String>>(2)?,
-                            r.get::<usize, Option<String>>(3)?,
-                            r.get::<usize, Option<String>>(4)?,
-                        ))
-                    })
-                    .optional()
-                    .with_context(|| {
-                        format!("Failed querying HCPCS API metadata for {hcpcs_code}")
-                    })?
-                    .unwrap_or((None, None, None, None, None));
+            let (
+                url,
+                meta_api_run_id,
+                requested_at_utc,
+                request_params,
+                response_json_raw,
+                latency_ms,
+            ) = api_meta
+                .query_row([&hcpcs_code], |r| {
+                    Ok((
+                        r.get::<usize, Option<String>>(0)?,
+                        r.get::<usize, Option<String>>(1)?,
+                        r.get::<usize, Option<String>>(2)?,
+                        r.get::<usize, Option<String>>(3)?,
+                        r.get::<usize, Option<String>>(4)?,
+                        r.get::<usize, Option<String>>(5)?,
+                    ))
+                })
+                .optional()
+                .with_context(|| format!("Failed querying HCPCS API metadata for {hcpcs_code}"))?
+                .unwrap_or((None, None, None, None, None, None));
+
+            let modifier: Option<String> = modifier_row
+                .query_row([&code_key], |r| r.get(0))
+                .with_context(|| format!("Failed querying HCPCS modifiers for {hcpcs_code}"))?;
 
             let url = url.unwrap_or_else(|| synthetic_url.clone());
             let api_run_id = meta_api_run_id.unwrap_or_else(|| api_run_id.to_string());
@@ -532,6 +715,7 @@ impl HcpcsCache {
 
             writer.push_row(&[
                 Some(hcpcs_code.as_str()),
+                modifier.as_deref(),
                 Some(ef_short_desc.as_str()),
                 Some(ef_long_desc.as_str()),
                 Some(ef_add_dt.as_str()),
@@ -547,19 +731,31 @@ impl HcpcsCache {
                 Some(api_run_id.as_str()),
                 Some(requested_at_utc.as_str()),
                 Some(request_params.as_str()),
+                latency_ms.as_deref(),
                 Some(response_json.as_str()),
             ])?;
         }
 
-        writer.finish()
+        Ok(())
     }
 
-    fn classify_for_lookup(&self, codes: &[String]) -> Result<(usize, Vec<String>)> {
+    /// `permanently_failed` codes are neither resolved nor looked up while
+    /// `max_lifetime_attempts` still covers them.
+    fn classify_for_lookup(
+        &self,
+        codes: &[String],
+        max_lifetime_attempts: Option<u32>,
+    ) -> Result<(usize, Vec<String>)> {
         let mut stmt = self
             .conn
             .prepare(
-                "SELECT 1 FROM hcpcs_cache
-                 WHERE hcpcs_code = ?1 COLLATE NOCASE AND status IN ('ok', 'not_found')
+                "SELECT status IN ('ok', 'not_found') FROM hcpcs_cache
+                 WHERE code_key = ?1
+                   AND (
+                       status IN ('ok', 'not_found')
+                       OR (status = 'permanently_failed' AND attempt_count >= ?2)
+                   )
+                 ORDER BY 1 DESC
                  LIMIT 1",
             )
             .context("Failed preparing HCPCS cache lookup statement")?;
@@ -568,31 +764,45 @@ impl HcpcsCache {
         let mut missing = Vec::new();
 
         for code in codes {
-            let exists: Option<i64> = stmt
-                .query_row([code], |row| row.get(0))
+            let is_resolved: Option<bool> = stmt
+                .query_row(
+                    params![normalize_code_key(code), max_lifetime_attempts],
+                    |row| row.get(0),
+                )
                 .optional()
                 .with_context(|| format!("Failed HCPCS cache lookup for {code}"))?;
-            if exists.is_some() {
-                resolved += 1;
-            } else {
-                missing.push(code.clone());
+            match is_resolved {
+                Some(true) => resolved += 1,
+                Some(false) => {}
+                None => missing.push(code.clone()),
             }
         }
 
         Ok((resolved, missing))
     }
 
+    /// Flip `error` rows with at least `max_attempts` lifetime failures to `permanently_failed`.
+    fn mark_permanently_failed(&self, max_attempts: u32) -> Result<usize> {
+        self.conn
+            .execute(
+                "UPDATE hcpcs_cache SET status = 'permanently_failed'
+                 WHERE status = 'error' AND attempt_count >= ?1",
+                [max_attempts],
+            )
+            .context("Failed marking permanently failed HCPCS codes")
+    }
+
     fn has_ok_record(&self, code: &str) -> Result<bool> {
         let mut stmt = self
             .conn
             .prepare(
                 "SELECT 1 FROM hcpcs_cache
-                 WHERE hcpcs_code = ?1 COLLATE NOCASE AND status = 'ok'
+                 WHERE code_key = ?1 AND status = 'ok'
                  LIMIT 1",
             )
             .context("Failed preparing HCPCS ok-status lookup statement")?;
         let exists: Option<i64> = stmt
-            .query_row([code], |row| row.get(0))
+            .query_row([normalize_code_key(code)], |row| row.get(0))
             .optional()
             .with_context(|| format!("Failed HCPCS ok-status lookup for {code}"))?;
         Ok(exists.is_some())
@@ -601,16 +811,47 @@ impl HcpcsCache {
     fn replace_with_ok_records(&self, code: &str, records: &[HcpcsApiRecord]) -> Result<()> {
         self.conn
             .execute(
-                "DELETE FROM hcpcs_cache WHERE hcpcs_code = ?1 COLLATE NOCASE",
-                [code],
+                "DELETE FROM hcpcs_cache WHERE code_key = ?1",
+                [normalize_code_key(code)],
             )
             .with_context(|| format!("Failed clearing HCPCS cache rows for {code}"))?;
+        self.insert_ok_records(code, records)
+    }
 
+    /// Adds the `preferred` fallback records for a code that already has `ok` rows (e.g. from
+    /// the API) but none pinned yet, so an edited fallback CSV takes effect on existing caches.
+    /// Returns whether anything was pinned.
+    fn pin_preferred_records(&self, code: &str, records: &[HcpcsApiRecord]) -> Result<bool> {
+        let preferred: Vec<HcpcsApiRecord> =
+            records.iter().filter(|r| r.preferred).cloned().collect();
+        if preferred.is_empty() {
+            return Ok(false);
+        }
+        let already_pinned: Option<i64> = self
+            .conn
+            .query_row(
+                "SELECT 1 FROM hcpcs_cache
+                 WHERE code_key = ?1 AND status = 'ok' AND preferred = 'true'
+                 LIMIT 1",
+                [normalize_code_key(code)],
+                |row| row.get(0),
+            )
+            .optional()
+            .with_context(|| format!("Failed HCPCS preferred-record lookup for {code}"))?;
+        if already_pinned.is_some() {
+            return Ok(false);
+        }
+        self.insert_ok_records(code, &preferred)?;
+        Ok(true)
+    }
+
+    fn insert_ok_records(&self, code: &str, records: &[HcpcsApiRecord]) -> Result<()> {
+        let code_key = normalize_code_key(code);
         for record in records {
             self.conn
                 .execute(
                     "
-                    INSERT INTO hcpcs_cache (
+                    INSERT OR REPLACE INTO hcpcs_cache (
                         hcpcs_code,
                         short_desc,
                         long_desc,
@@ -621,12 +862,14 @@ impl HcpcsCache {
                         is_noc,
                         status,
                         error_message,
-                        fetched_at_unix
+                        fetched_at_unix,
+                        code_key,
+                        preferred
                     )
-                    VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, 'ok', '', strftime('%s', 'now'))
+                    VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, 'ok', '', strftime('%s', 'now'), ?9, ?10)
                     ",
                     params![
-                        record.hcpcs_code,
+                        self.emit_code(&record.hcpcs_code),
                         record.short_desc,
                         record.long_desc,
                         record.add_dt,
@@ -634,6 +877,8 @@ impl HcpcsCache {
                         record.term_dt,
                         if record.obsolete { "true" } else { "false" },
                         if record.is_noc { "true" } else { "false" },
+                        code_key,
+                        if record.preferred { "true" } else { "false" },
                     ],
                 )
                 .with_context(|| format!("Failed inserting HCPCS cache row for {code}"))?;
@@ -642,11 +887,9 @@ impl HcpcsCache {
     }
 
     fn set_not_found(&self, code: &str, reason: &str) -> Result<()> {
+        let code_key = normalize_code_key(code);
         self.conn
-            .execute(
-                "DELETE FROM hcpcs_cache WHERE hcpcs_code = ?1 COLLATE NOCASE",
-                [code],
-            )
+            .execute("DELETE FROM hcpcs_cache WHERE code_key = ?1", [&code_key])
             .with_context(|| format!("Failed clearing HCPCS cache rows for {code}"))?;
         self.conn
             .execute(
@@ -662,22 +905,31 @@ impl HcpcsCache {
                     is_noc,
                     status,
                     error_message,
-                    fetched_at_unix
+                    fetched_at_unix,
+                    code_key
                 )
-                VALUES (?1, '', '', '', '', '', '', '', 'not_found', ?2, strftime('%s', 'now'))
+                VALUES (?1, '', '', '', '', '', '', '', 'not_found', ?2, strftime('%s', 'now'), ?3)
                 ",
-                params![code, reason],
+                params![self.emit_code(code), reason, code_key],
             )
             .with_context(|| format!("Failed inserting HCPCS not_found sentinel for {code}"))?;
         Ok(())
     }
 
     fn set_error(&self, code: &str, message: &str) -> Result<()> {
-        self.conn
-            .execute(
-                "DELETE FROM hcpcs_cache WHERE hcpcs_code = ?1 COLLATE NOCASE",
-                [code],
+        let code_key = normalize_code_key(code);
+        // Rows are replaced wholesale, so carry the lifetime failure count across the delete.
+        let prior_attempts: i64 = self
+            .conn
+            .query_row(
+                "SELECT COALESCE(MAX(attempt_count), 0) FROM hcpcs_cache
+                 WHERE code_key = ?1",
+                [&code_key],
+                |row| row.get(0),
             )
+            .with_context(|| format!("Failed reading HCPCS attempt count for {code}"))?;
+        self.conn
+            .execute("DELETE FROM hcpcs_cache WHERE code_key = ?1", [&code_key])
             .with_context(|| format!("Failed clearing HCPCS cache rows for {code}"))?;
         self.conn
             .execute(
@@ -693,17 +945,25 @@ impl HcpcsCache {
                     is_noc,
                     status,
                     error_message,
-                    fetched_at_unix
+                    fetched_at_unix,
+                    attempt_count,
+                    code_key
                 )
-                VALUES (?1, '', '', '', '', '', '', '', 'error', ?2, strftime('%s', 'now'))
+                VALUES (?1, '', '', '', '', '', '', '', 'error', ?2, strftime('%s', 'now'), ?3, ?4)
                 ",
-                params![code, truncate_for_log(message)],
+                params![
+                    self.emit_code(code),
+                    truncate_for_log(message),
+                    prior_attempts + 1,
+                    code_key
+                ],
             )
             .with_context(|| format!("Failed inserting HCPCS error sentinel for {code}"))?;
         Ok(())
     }
 
-    fn export_mapping_csv(&self, output_path: &Path) -> Result<()> {
+    /// With `one_row_per_code`, only the first (preferred) record per code is written.
+    fn export_mapping_csv(&self, output_path: &Path, one_row_per_code: bool) -> Result<()> {
         if let Some(parent) = output_path.parent() {
             fs::create_dir_all(parent).with_context(|| {
                 format!(
@@ -728,6 +988,7 @@ impl HcpcsCache {
         writer
             .write_record([
                 "hcpcs_code",
+                "modifier",
                 "short_desc",
                 "long_desc",
                 "add_dt",
@@ -742,7 +1003,8 @@ impl HcpcsCache {
 
         // Export all successful HCPCS records (including NOC). This file is intended for
         // downstream joins; when multiple rows match for a code/date, prefer non-NOC rows
-        // and fall back to NOC only when that's all that's available.
+        // and fall back to NOC only when that's all that's available. Fallback rows marked
+        // `preferred` come before either.
         // `is_noc` field definition:
         // https://clinicaltables.nlm.nih.gov/apidoc/hcpcs/v3/doc.html
         let mut stmt = self
@@ -759,11 +1021,14 @@ impl HcpcsCache {
                     obsolete,
                     is_noc,
                     status,
-                    fetched_at_unix
+                    fetched_at_unix,
+                    modifier,
+                    code_key
                 FROM hcpcs_cache
                 WHERE status = 'ok'
                 ORDER BY
-                    hcpcs_code,
+                    code_key,
+                    CASE WHEN preferred = 'true' THEN 0 ELSE 1 END,
                     CASE WHEN LOWER(COALESCE(is_noc, 'false')) = 'false' THEN 0 ELSE 1 END,
                     act_eff_dt,
                     add_dt,
@@ -776,8 +1041,16 @@ impl HcpcsCache {
             .query([])
             .context("Failed querying HCPCS mapping rows")?;
 
+        let mut last_code: Option<String> = None;
         while let Some(row) = rows.next().context("Failed iterating HCPCS mapping rows")? {
             let hcpcs_code: String = row.get(0).context("Failed reading hcpcs_code")?;
+            if one_row_per_code {
+                let code_key: String = row.get(11).context("Failed reading code_key")?;
+                if last_code.as_deref() == Some(code_key.as_str()) {
+                    continue;
+                }
+                last_code = Some(code_key);
+            }
             let short_desc: String = row.get(1).context("Failed reading short_desc")?;
             let long_desc: String = row.get(2).context("Failed reading long_desc")?;
             let add_dt: String = row.get(3).context("Failed reading add_dt")?;
@@ -787,10 +1060,12 @@ impl HcpcsCache {
             let is_noc: String = row.get(7).context("Failed reading is_noc")?;
             let status: String = row.get(8).context("Failed reading status")?;
             let fetched_at_unix: i64 = row.get(9).context("Failed reading fetched_at_unix")?;
+            let modifier: String = row.get(10).context("Failed reading modifier")?;
 
             writer
                 .write_record([
-                    hcpcs_code,
+                    self.emit_code(&hcpcs_code),
+                    modifier,
                     short_desc,
                     long_desc,
                     add_dt,
@@ -851,22 +1126,10 @@ fn parse_boolish(value: &str) -> bool {
     )
 }
 
-fn normalize_hcpcs_code(raw: &str) -> Option<String> {
-    let mut compact: String = raw.trim().chars().filter(|c| !c.is_whitespace()).collect();
-    if compact.is_empty() {
-        return None;
-    }
-    if compact.ends_with(".0") {
-        compact.truncate(compact.len().saturating_sub(2));
-    }
-    let normalized = compact.to_ascii_uppercase();
-    let valid = normalized.len() == 5 && normalized.chars().all(|c| c.is_ascii_alphanumeric());
-    if valid { Some(normalized) } else { None }
-}
-
 fn load_local_hcpcs_fallback_records(
     fallback_csv: &Path,
     verbose: bool,
+    min_valid_code_share: f64,
 ) -> Result<HashMap<String, Vec<HcpcsApiRecord>>> {
     if !fallback_csv.exists() {
         return Ok(HashMap::new());
@@ -939,9 +1202,12 @@ fn load_local_hcpcs_fallback_records(
     let term_dt_idx = find_header_index(&headers, &["term_dt", "term_date", "end_date"]);
     let obsolete_idx = find_header_index(&headers, &["obsolete", "is_obsolete"]);
     let is_noc_idx = find_header_index(&headers, &["is_noc", "noc"]);
+    let preferred_idx = find_header_index(&headers, &["preferred", "is_preferred"]);
 
     let mut fallback_records: HashMap<String, Vec<HcpcsApiRecord>> = HashMap::new();
     let mut loaded_rows = 0usize;
+    let mut nonblank_codes = 0usize;
+    let mut valid_codes = 0usize;
 
     for row in reader.records() {
         let row = row.with_context(|| {
@@ -950,9 +1216,14 @@ fn load_local_hcpcs_fallback_records(
                 fallback_csv.display()
             )
         })?;
-        let Some(code) = normalize_hcpcs_code(row.get(code_idx).unwrap_or_default()) else {
+        let raw_code = row.get(code_idx).unwrap_or_default();
+        if !raw_code.trim().is_empty() {
+            nonblank_codes += 1;
+        }
+        let Some(code) = normalize_hcpcs_code(raw_code) else {
             continue;
         };
+        valid_codes += 1;
 
         let mut short_desc = field_at(&row, short_desc_idx);
         let mut long_desc = field_at(&row, long_desc_idx);
@@ -975,19 +1246,36 @@ fn load_local_hcpcs_fallback_records(
             term_dt: field_at(&row, term_dt_idx),
             obsolete: parse_boolish(&field_at(&row, obsolete_idx)),
             is_noc: parse_boolish(&field_at(&row, is_noc_idx)),
+            preferred: parse_boolish(&field_at(&row, preferred_idx)),
         };
 
         fallback_records.entry(code).or_default().push(record);
         loaded_rows += 1;
     }
 
+    // A mislabeled column (NPIs, ZIPs, row ids, ...) would otherwise seed junk as HCPCS `ok`.
+    if nonblank_codes > 0 {
+        let valid_share = valid_codes as f64 / nonblank_codes as f64;
+        if valid_share < min_valid_code_share {
+            bail!(
+                "Local HCPCS fallback CSV {}: only {}/{} ({:.1}%) values in code column '{}' look like HCPCS/CPT codes (need {:.1}%); the selected column likely isn't HCPCS. Fix the file or lower --fallback-validation-threshold.",
+                fallback_csv.display(),
+                valid_codes,
+                nonblank_codes,
+                valid_share * 100.0,
+                headers.get(code_idx).unwrap_or_default(),
+                min_valid_code_share * 100.0
+            );
+        }
+    }
+
     for records in fallback_records.values_mut() {
         let mut dedup = HashSet::new();
         records.retain(|record| dedup.insert(record.clone()));
     }
 
     if verbose && !fallback_records.is_empty() {
-        println!(
+        info!(
             "Loaded local HCPCS/CPT fallback records from {} (rows={}, unique_codes={}).",
             fallback_csv.display(),
             loaded_rows,
@@ -1001,25 +1289,28 @@ fn seed_hcpcs_cache_from_local_fallback(
     cache: &HcpcsCache,
     target_codes: &[String],
     local_fallback: &HashMap<String, Vec<HcpcsApiRecord>>,
-) -> Result<usize> {
+) -> Result<(usize, usize)> {
     if local_fallback.is_empty() {
-        return Ok(0);
+        return Ok((0, 0));
     }
 
     let mut seeded = 0usize;
+    let mut pinned = 0usize;
     for code in target_codes {
-        let normalized =
-            normalize_hcpcs_code(code).unwrap_or_else(|| code.trim().to_ascii_uppercase());
+        let normalized = normalize_hcpcs_code(code).unwrap_or_else(|| normalize_code_key(code));
         let Some(records) = local_fallback.get(&normalized) else {
             continue;
         };
         if cache.has_ok_record(code)? {
+            if cache.pin_preferred_records(code, records)? {
+                pinned += 1;
+            }
             continue;
         }
         cache.replace_with_ok_records(code, records)?;
         seeded += 1;
     }
-    Ok(seeded)
+    Ok((seeded, pinned))
 }
 
 fn local_fallback_dataset_stats(
@@ -1034,8 +1325,7 @@ fn local_fallback_dataset_stats(
     let mut overlap = 0usize;
     let mut already_ok = 0usize;
     for code in target_codes {
-        let normalized =
-            normalize_hcpcs_code(code).unwrap_or_else(|| code.trim().to_ascii_uppercase());
+        let normalized = normalize_hcpcs_code(code).unwrap_or_else(|| normalize_code_key(code));
         if !local_fallback.contains_key(&normalized) {
             continue;
         }
@@ -1051,14 +1341,14 @@ fn fallback_records_for_code(
     local_fallback: &HashMap<String, Vec<HcpcsApiRecord>>,
     code: &str,
 ) -> Option<Vec<HcpcsApiRecord>> {
-    let normalized = normalize_hcpcs_code(code).unwrap_or_else(|| code.trim().to_ascii_uppercase());
+    let normalized = normalize_hcpcs_code(code).unwrap_or_else(|| normalize_code_key(code));
     local_fallback.get(&normalized).cloned()
 }
 
 fn cached_not_found_code_keys(cache: &HcpcsCache) -> Result<HashSet<String>> {
     let mut stmt = cache
         .conn
-        .prepare("SELECT DISTINCT hcpcs_code FROM hcpcs_cache WHERE status = 'not_found'")
+        .prepare("SELECT DISTINCT code_key FROM hcpcs_cache WHERE status = 'not_found'")
         .context("Failed preparing cached HCPCS not_found query")?;
     let rows = stmt
         .query_map([], |row| row.get::<usize, String>(0))
@@ -1066,8 +1356,7 @@ fn cached_not_found_code_keys(cache: &HcpcsCache) -> Result<HashSet<String>> {
 
     let mut keys = HashSet::new();
     for row in rows {
-        let code: String = row.context("Failed reading cached HCPCS not_found code")?;
-        let key = normalize_code_key(&code);
+        let key: String = row.context("Failed reading cached HCPCS not_found code")?;
         if !key.is_empty() {
             keys.insert(key);
         }
@@ -1118,18 +1407,50 @@ pub async fn build_hcpcs_mapping(
     api_responses_parquet: &Path,
     hcpcs_fallback_csv: &Path,
     api_run_id: &str,
+    export_failures: &ExportFailures,
+    lookup_budget: &LookupBudget,
     progress_hub: Option<Arc<MultiProgress>>,
     shutdown_requested: Arc<AtomicBool>,
 ) -> Result<bool> {
-    println!("Extracting unique HCPCS codes...");
-    let unique_codes = extract_unique_hcpcs_codes(input_path)?;
-    println!(
+    if args.hcpcs_source == HcpcsSource::Cms
+        && args.hcpcs_api_base_url == crate::constants::DEFAULT_HCPCS_API_BASE_URL
+    {
+        bail!(
+            "--hcpcs-source cms needs --hcpcs-api-base-url pointing at a CMS-style HCPCS endpoint \
+(the default URL serves the ClinicalTables shape)"
+        );
+    }
+    let batch_size = effective_hcpcs_batch_size(args);
+    if batch_size > 1 && args.hcpcs_batch_count < batch_size {
+        bail!(
+            "--hcpcs-batch-count {} is below the HCPCS batch size {batch_size}; a batch could lose codes",
+            args.hcpcs_batch_count
+        );
+    }
+
+    info!("Extracting unique HCPCS codes...");
+    let code_modifiers = extract_hcpcs_code_modifiers(input_path)?;
+    let unique_codes: Vec<String> = code_modifiers.keys().cloned().collect();
+    info!(
         "Discovered {} unique HCPCS codes in source data.",
         unique_codes.len()
     );
+    ensure_identifiers_extracted(
+        unique_codes.len(),
+        "HCPCS codes",
+        input_path,
+        "check that the file has rows and a HCPCS_CODE column",
+        args.allow_empty_input,
+        args.max_identifiers,
+    )?;
 
     let mut cache = HcpcsCache::open(cache_db)?;
-    let local_fallback_records = load_local_hcpcs_fallback_records(hcpcs_fallback_csv, true)?;
+    cache.case_mode = args.normalize_hcpcs_case;
+    let local_fallback_records = load_local_hcpcs_fallback_records(
+        hcpcs_fallback_csv,
+        true,
+        args.fallback_validation_threshold,
+    )?;
     let local_fallback_code_count = local_fallback_records.len();
     let (dataset_codes_in_fallback, fallback_ok_before_seed) =
         local_fallback_dataset_stats(&cache, &unique_codes, &local_fallback_records)?;
@@ -1138,12 +1459,12 @@ pub async fn build_hcpcs_mapping(
         &unique_codes,
         &local_fallback_records,
     )?;
-    let seeded_from_local_fallback =
+    let (seeded_from_local_fallback, pinned_from_local_fallback) =
         seed_hcpcs_cache_from_local_fallback(&cache, &unique_codes, &local_fallback_records)?;
     let total_recovered_from_local_fallback = not_found_recovered + seeded_from_local_fallback;
     let fallback_ok_after_seed = fallback_ok_before_seed + total_recovered_from_local_fallback;
     if local_fallback_code_count > 0 {
-        println!(
+        info!(
             "Local fallback matched {} dataset HCPCS/CPT codes: already_ok_before={} newly_seeded={} total_ok_after_seed={} (fallback affects HCPCS only; NPI uses NPPES/API). Source={}",
             dataset_codes_in_fallback,
             fallback_ok_before_seed,
@@ -1152,19 +1473,33 @@ pub async fn build_hcpcs_mapping(
             hcpcs_fallback_csv.display()
         );
     }
+    if pinned_from_local_fallback > 0 {
+        info!(
+            "Pinned preferred local fallback records for {pinned_from_local_fallback} already-resolved HCPCS codes."
+        );
+    }
     if not_found_checked > 0 {
-        println!(
+        info!(
             "Rechecked cached HCPCS not_found codes against local fallback: checked={} recovered={}.",
             not_found_checked, not_found_recovered
         );
     }
 
-    let (resolved_count, mut missing_codes) = cache.classify_for_lookup(&unique_codes)?;
+    if let Some(max_attempts) = args.max_lifetime_attempts {
+        let marked = cache.mark_permanently_failed(max_attempts)?;
+        if marked > 0 {
+            info!(
+                "Marked {marked} HCPCS codes permanently_failed after {max_attempts}+ failed lookups (--max-lifetime-attempts)."
+            );
+        }
+    }
+    let (resolved_count, mut missing_codes) =
+        cache.classify_for_lookup(&unique_codes, args.max_lifetime_attempts)?;
     let unresolved_before_limit = missing_codes.len();
 
     if let Some(limit) = args.max_new_lookups {
         if missing_codes.len() > limit {
-            println!(
+            info!(
                 "Applying --max-new-lookups={} to HCPCS lookups (from {}).",
                 limit,
                 missing_codes.len()
@@ -1172,31 +1507,46 @@ pub async fn build_hcpcs_mapping(
             missing_codes.truncate(limit);
         }
     }
+    // The combined budget is only taken as lookups are dispatched; this is an upper bound.
     let planned_api_lookups = if args.skip_api {
         0
     } else {
-        missing_codes.len()
+        lookup_budget
+            .remaining()
+            .map_or(missing_codes.len(), |left| left.min(missing_codes.len()))
     };
     print_hcpcs_download_plan_table(
         unique_codes.len(),
         resolved_count,
         unresolved_before_limit,
         planned_api_lookups,
-        args.hcpcs_batch_size.max(1),
+        effective_hcpcs_batch_size(args),
         local_fallback_code_count,
         dataset_codes_in_fallback,
         fallback_ok_before_seed,
         total_recovered_from_local_fallback,
         fallback_ok_after_seed,
     );
+    record_hcpcs_plan(HcpcsPlanCounts {
+        unique_identifiers: unique_codes.len(),
+        resolved_before_api: resolved_count,
+        planned_api_lookups,
+        fallback_seeded: total_recovered_from_local_fallback,
+    });
+    if args.dry_run {
+        return Ok(false);
+    }
 
     let mut interrupted = shutdown_requested.load(Ordering::SeqCst);
     let mut api_reference_rows: Vec<HcpcsApiReferenceRow> = Vec::new();
     if interrupted {
-        println!("Shutdown requested; skipping new HCPCS API lookups.");
+        info!("Shutdown requested; skipping new HCPCS API lookups.");
     } else if args.skip_api {
-        println!("--skip-api set; unresolved HCPCS codes remain unresolved.");
+        info!("--skip-api set; unresolved HCPCS codes remain unresolved.");
     } else if !missing_codes.is_empty() {
+        if args.hcpcs_single_mode {
+            info!("--hcpcs-single-mode set; HCPCS lookups use one request per code.");
+        }
         let (api_interrupted, rows) = resolve_missing_hcpcs(
             &cache,
             missing_codes,
@@ -1204,6 +1554,7 @@ pub async fn build_hcpcs_mapping(
             client,
             args,
             api_run_id,
+            lookup_budget,
             progress_hub.clone(),
             Arc::clone(&shutdown_requested),
         )
@@ -1213,13 +1564,29 @@ pub async fn build_hcpcs_mapping(
     }
 
     cache.upsert_api_responses(&api_reference_rows)?;
-    cache.export_mapping_csv(mapping_csv)?;
-    println!("Wrote HCPCS mapping CSV {}", mapping_csv.display());
-    cache.export_api_responses_parquet(api_responses_parquet, hcpcs_fallback_csv, api_run_id)?;
-    println!(
-        "Wrote HCPCS resolved identifier dataset {}",
-        api_responses_parquet.display()
-    );
+    cache.record_modifiers(&code_modifiers)?;
+    cache.export_mapping_csv(mapping_csv, args.hcpcs_mapping_one_row_per_code)?;
+    info!("Wrote HCPCS mapping CSV {}", mapping_csv.display());
+    let export = cache
+        .export_api_responses_parquet(
+            api_responses_parquet,
+            hcpcs_fallback_csv,
+            api_run_id,
+            ParquetOutputOptions {
+                columns: args.hcpcs_output_columns.as_deref(),
+                content_hash: args.content_hash,
+                sort_output: args.sort_output,
+                ndjson: args.emit_ndjson,
+                compression: args.parquet_compression,
+            },
+        )
+        .map(|()| {
+            info!(
+                "Wrote HCPCS resolved identifier dataset {}",
+                api_responses_parquet.display()
+            );
+        });
+    export_failures.tolerate("HCPCS", export)?;
     Ok(interrupted || shutdown_requested.load(Ordering::SeqCst))
 }
 
@@ -1267,57 +1634,57 @@ fn print_hcpcs_download_plan_table(
         )
     };
 
-    println!();
-    println!("{bold}{cyan}{border}{reset}");
-    println!("{bold}{cyan}{section}{reset}");
-    println!("{bold}{cyan}{border}{reset}");
-    println!(
+    info!();
+    info!("{bold}{cyan}{border}{reset}");
+    info!("{bold}{cyan}{section}{reset}");
+    info!("{bold}{cyan}{border}{reset}");
+    info!(
         "| {:<42} | {:<24} |",
         "Unique HCPCS codes in dataset",
         format_count(unique_hcpcs)
     );
-    println!(
+    info!(
         "| {:<42} | {}{:<24}{} |",
         "Already saved in cache",
         green,
         format_count(resolved_in_cache),
         reset
     );
-    println!(
+    info!(
         "| {:<42} | {}{:<24}{} |",
         "Still unresolved",
         yellow,
         format_count(unresolved_before_limit),
         reset
     );
-    println!(
+    info!(
         "| {:<42} | {}{:<24}{} |",
         "Planned API downloads now",
         magenta,
         format_count(planned_api_lookups),
         reset
     );
-    println!(
+    info!(
         "| {:<42} | {:<24} |",
         "Lookup mode",
         format!("batched OR (size={batch_size})")
     );
-    println!(
+    info!(
         "| {:<42} | {:<24} |",
         "Local fallback codes loaded",
         format_count(local_fallback_loaded)
     );
-    println!(
+    info!(
         "| {:<42} | {:<24} |",
         "Dataset codes in fallback",
         format_count(dataset_codes_in_fallback)
     );
-    println!(
+    info!(
         "| {:<42} | {:<24} |",
         "Fallback already ok (before run)",
         format_count(fallback_ok_before_seed)
     );
-    println!(
+    info!(
         "| {:<42} | {}{:<24}{} |",
         "Fallback newly seeded (this run)",
         green,
@@ -1328,19 +1695,19 @@ fn print_hcpcs_download_plan_table(
         ),
         reset
     );
-    println!(
+    info!(
         "| {:<42} | {}{:<24}{} |",
         "Fallback total ok (after seed)",
         green,
         format_count(fallback_ok_after_seed),
         reset
     );
-    println!(
+    info!(
         "| {:<42} | {}{:<24}{} |",
         "Fallback applies to", white, "HCPCS/CPT only (not NPI)", reset
     );
-    println!("{bold}{cyan}{border}{reset}");
-    println!();
+    info!("{bold}{cyan}{border}{reset}");
+    info!();
 }
 
 pub fn is_hcpcs_dataset_complete(
@@ -1348,24 +1715,29 @@ pub fn is_hcpcs_dataset_complete(
     cache_db: &Path,
     mapping_csv: &Path,
     hcpcs_fallback_csv: &Path,
+    fallback_validation_threshold: f64,
 ) -> Result<bool> {
     if !cache_db.exists() || !mapping_csv.exists() {
         return Ok(false);
     }
 
     let unique_codes = extract_unique_hcpcs_codes(input_path)?;
+    if unique_codes.is_empty() {
+        // Nothing extracted is never "complete"; let the build report it.
+        return Ok(false);
+    }
     let cache = HcpcsCache::open(cache_db)?;
     let mut stmt = cache
         .conn
         .prepare(
             "SELECT 1 FROM hcpcs_cache
-             WHERE hcpcs_code = ?1 COLLATE NOCASE AND status IN ('ok', 'not_found', 'error')
+             WHERE code_key = ?1 AND status IN ('ok', 'not_found', 'error', 'permanently_failed')
              LIMIT 1",
         )
         .context("Failed preparing HCPCS completeness query")?;
     for code in &unique_codes {
         let exists: Option<i64> = stmt
-            .query_row([code], |row| row.get(0))
+            .query_row([normalize_code_key(code)], |row| row.get(0))
             .optional()
             .with_context(|| format!("Failed checking HCPCS cache coverage for {code}"))?;
         if exists.is_none() {
@@ -1373,14 +1745,17 @@ pub fn is_hcpcs_dataset_complete(
         }
     }
 
-    let local_fallback_records = load_local_hcpcs_fallback_records(hcpcs_fallback_csv, false)?;
+    let local_fallback_records = load_local_hcpcs_fallback_records(
+        hcpcs_fallback_csv,
+        false,
+        fallback_validation_threshold,
+    )?;
     if local_fallback_records.is_empty() {
         return Ok(true);
     }
 
     for code in unique_codes {
-        let normalized =
-            normalize_hcpcs_code(&code).unwrap_or_else(|| code.trim().to_ascii_uppercase());
+        let normalized = normalize_hcpcs_code(&code).unwrap_or_else(|| normalize_code_key(&code));
         if local_fallback_records.contains_key(&normalized) && !cache.has_ok_record(&code)? {
             return Ok(false);
         }
@@ -1394,9 +1769,107 @@ pub fn export_hcpcs_api_responses_parquet(
     output_path: &Path,
     hcpcs_fallback_csv: &Path,
     api_run_id: &str,
+    case_mode: HcpcsCaseMode,
+    output: ParquetOutputOptions<'_>,
+) -> Result<()> {
+    let mut cache = HcpcsCache::open(cache_db)?;
+    cache.case_mode = case_mode;
+    cache.export_api_responses_parquet(output_path, hcpcs_fallback_csv, api_run_id, output)
+}
+
+/// Re-runs only the mapping CSV export against an existing cache, so record selection changes
+/// can be applied without re-fetching.
+pub fn export_hcpcs_mapping_csv(
+    cache_db: &Path,
+    mapping_csv: &Path,
+    case_mode: HcpcsCaseMode,
+    one_row_per_code: bool,
 ) -> Result<()> {
+    if !cache_db.exists() {
+        bail!(
+            "HCPCS cache DB {} does not exist; run a normal build first",
+            cache_db.display()
+        );
+    }
+    let mut cache = HcpcsCache::open(cache_db)?;
+    cache.case_mode = case_mode;
+    cache.export_mapping_csv(mapping_csv, one_row_per_code)
+}
+
+/// Every `hcpcs_cache` row, in table order.
+const HCPCS_CACHE_EXPORT_COLUMNS: &[&str] = &[
+    "hcpcs_code",
+    "short_desc",
+    "long_desc",
+    "add_dt",
+    "act_eff_dt",
+    "term_dt",
+    "obsolete",
+    "is_noc",
+    "status",
+    "error_message",
+    "fetched_at_unix",
+    "attempt_count",
+    "modifier",
+    "code_key",
+    "preferred",
+];
+
+/// Dumps the whole `hcpcs_cache` table (all records and statuses, not just the preferred
+/// record per code) to an all-string parquet for auditing. Returns the row count.
+pub fn export_hcpcs_cache_parquet(
+    cache_db: &Path,
+    output_path: &Path,
+    compression: ParquetCompression,
+) -> Result<usize> {
+    if !cache_db.exists() {
+        bail!(
+            "HCPCS cache DB {} does not exist; run a normal build first",
+            cache_db.display()
+        );
+    }
     let cache = HcpcsCache::open(cache_db)?;
-    cache.export_api_responses_parquet(output_path, hcpcs_fallback_csv, api_run_id)
+    let query = format!(
+        "SELECT {} FROM hcpcs_cache ORDER BY hcpcs_code, fetched_at_unix, status",
+        HCPCS_CACHE_EXPORT_COLUMNS
+            .iter()
+            .map(|c| format!("CAST({c} AS TEXT)"))
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    let mut stmt = cache
+        .conn
+        .prepare(&query)
+        .context("Failed preparing HCPCS cache export query")?;
+    let mut rows = stmt
+        .query([])
+        .context("Failed running HCPCS cache export query")?;
+
+    let mut writer = StringParquetWriter::try_new(
+        output_path,
+        HCPCS_CACHE_EXPORT_COLUMNS,
+        ParquetOutputOptions {
+            compression,
+            ..ParquetOutputOptions::default()
+        },
+        &[],
+        10_000,
+    )?;
+    let mut written = 0usize;
+    while let Some(row) = rows.next().context("Failed reading HCPCS cache row")? {
+        let values = (0..HCPCS_CACHE_EXPORT_COLUMNS.len())
+            .map(|idx| row.get::<usize, Option<String>>(idx))
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .context("Failed decoding HCPCS cache row")?;
+        let refs: Vec<Option<&str>> = values.iter().map(Option::as_deref).collect();
+        if let Err(err) = writer.push_row(&refs) {
+            writer.abort()?;
+            return Err(err);
+        }
+        written += 1;
+    }
+    writer.finish()?;
+    Ok(written)
 }
 
 pub fn backfill_hcpcs_api_responses_from_legacy_parquet(
@@ -1508,6 +1981,7 @@ pub fn backfill_hcpcs_api_responses_from_legacy_parquet(
             api_run_id: api_run_id.unwrap_or_default(),
             requested_at_utc: requested_at_utc.unwrap_or_default(),
             request_params_json: request_params_json.unwrap_or_default(),
+            latency_ms: None,
             response_json_raw,
         });
     }
@@ -1521,14 +1995,14 @@ pub fn collect_unresolved_hcpcs(
     cache_db: &Path,
 ) -> Result<Vec<UnresolvedHcpcsEntry>> {
     let unique_codes = extract_unique_hcpcs_codes(input_path)?;
-    let cache = HcpcsCache::open(cache_db)?;
+    let cache = HcpcsCache::open_read_only(cache_db)?;
     let mut stmt = cache
         .conn
         .prepare(
             "
             SELECT status, error_message, fetched_at_unix
             FROM hcpcs_cache
-            WHERE hcpcs_code = ?1 COLLATE NOCASE
+            WHERE code_key = ?1
             ORDER BY
                 CASE status
                     WHEN 'ok' THEN 0
@@ -1545,7 +2019,9 @@ pub fn collect_unresolved_hcpcs(
     let mut unresolved = Vec::new();
     for code in unique_codes {
         let row: Option<(String, String, i64)> = stmt
-            .query_row([&code], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .query_row([normalize_code_key(&code)], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+            })
             .optional()
             .with_context(|| format!("Failed unresolved HCPCS lookup for {code}"))?;
 
@@ -1632,6 +2108,7 @@ fn build_hcpcs_reference_row_from_value(
         api_run_id: api_run_id.to_string(),
         requested_at_utc: requested_at_utc.to_string(),
         request_params_json: request_params_json.to_string(),
+        latency_ms: None,
         response_json_raw: serde_json::to_string(response_value).ok(),
     }
 }
@@ -1641,14 +2118,38 @@ fn build_hcpcs_reference_row_from_value(
 // from `hcpcs_api_responses` included when available.
 
 fn extract_unique_hcpcs_codes(input_path: &Path) -> Result<Vec<String>> {
+    Ok(extract_hcpcs_code_modifiers(input_path)?
+        .into_keys()
+        .collect())
+}
+
+/// Unique base HCPCS codes with the modifiers billed alongside them. Modifiers come from an
+/// optional `HCPCS_MODIFIER` column or a combined `HCPCS_CODE` value like `J1885-JW`; lookups
+/// only ever use the base code.
+fn extract_hcpcs_code_modifiers(input_path: &Path) -> Result<BTreeMap<String, BTreeSet<String>>> {
     let conn = Connection::open_in_memory().context("Failed opening DuckDB")?;
     let source = source_expr(input_path)?;
+    let has_modifier_column: i64 = conn
+        .query_row(
+            &format!(
+                "SELECT COUNT(*) FROM (DESCRIBE SELECT * FROM {source}) \
+                 WHERE UPPER(column_name) = 'HCPCS_MODIFIER'"
+            ),
+            [],
+            |row| row.get(0),
+        )
+        .context("Failed describing source columns for HCPCS_MODIFIER")?;
+    let modifier_expr = if has_modifier_column > 0 {
+        "NULLIF(TRIM(CAST(HCPCS_MODIFIER AS VARCHAR)), '')"
+    } else {
+        "CAST(NULL AS VARCHAR)"
+    };
     let query = format!(
         "
         WITH src AS (
             SELECT * FROM {source}
         )
-        SELECT DISTINCT TRIM(CAST(HCPCS_CODE AS VARCHAR)) AS hcpcs_code
+        SELECT DISTINCT TRIM(CAST(HCPCS_CODE AS VARCHAR)) AS hcpcs_code, {modifier_expr}
         FROM src
         WHERE HCPCS_CODE IS NOT NULL
           AND TRIM(CAST(HCPCS_CODE AS VARCHAR)) <> ''
@@ -1659,16 +2160,39 @@ fn extract_unique_hcpcs_codes(input_path: &Path) -> Result<Vec<String>> {
         .prepare(&query)
         .context("Failed preparing unique HCPCS query")?;
     let rows = stmt
-        .query_map([], |row| row.get::<usize, String>(0))
+        .query_map([], |row| {
+            Ok((
+                row.get::<usize, String>(0)?,
+                row.get::<usize, Option<String>>(1)?,
+            ))
+        })
         .context("Failed running unique HCPCS query")?;
 
-    let mut codes = Vec::new();
+    let mut codes: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
     for row in rows {
-        codes.push(row.context("Failed reading HCPCS row")?);
+        let (raw_code, column_modifier) = row.context("Failed reading HCPCS row")?;
+        let (base, combined_modifier) = split_hcpcs_modifier(&raw_code);
+        let modifiers = codes.entry(base.to_string()).or_default();
+        if let Some(modifier) = column_modifier
+            .map(|m| m.to_ascii_uppercase())
+            .or(combined_modifier)
+        {
+            modifiers.insert(modifier);
+        }
     }
     Ok(codes)
 }
 
+/// `--hcpcs-single-mode` forces one code per request (the plain `resolve_hcpcs` path), as does
+/// `--hcpcs-source cms`, which has no batched OR query.
+fn effective_hcpcs_batch_size(args: &Args) -> usize {
+    if args.hcpcs_single_mode || args.hcpcs_source == HcpcsSource::Cms {
+        1
+    } else {
+        args.hcpcs_batch_size.max(1)
+    }
+}
+
 fn chunk_hcpcs_codes(codes: Vec<String>, batch_size: usize) -> Vec<Vec<String>> {
     let size = batch_size.max(1);
     if codes.is_empty() {
@@ -1737,6 +2261,7 @@ async fn resolve_missing_hcpcs(
     client: &Client,
     args: &Args,
     api_run_id: &str,
+    lookup_budget: &LookupBudget,
     progress_hub: Option<Arc<MultiProgress>>,
     shutdown_requested: Arc<AtomicBool>,
 ) -> Result<(bool, Vec<HcpcsApiReferenceRow>)> {
@@ -1765,7 +2290,7 @@ async fn resolve_missing_hcpcs(
 
     let mut interrupted = shutdown_requested.load(Ordering::SeqCst);
     let mut reference_rows = Vec::new();
-    let batch_size = args.hcpcs_batch_size.max(1);
+    let batch_size = effective_hcpcs_batch_size(args);
     let mut round_codes = missing_codes;
     let mut retry_round = 0u32;
     let max_retry_rounds = args.failure_retry_rounds;
@@ -1776,6 +2301,10 @@ async fn resolve_missing_hcpcs(
     let mut not_found = 0usize;
     let mut failed = 0usize;
     let mut fallback_hits = 0usize;
+    let mut in_flight_ids = InFlightIds::new(normalize_code_key);
+    let mut budget_exhausted = false;
+    let mut status_ticker =
+        StatusTicker::new(args.progress_interval_seconds.map(Duration::from_secs));
 
     while !round_codes.is_empty() {
         if shutdown_requested.load(Ordering::SeqCst) {
@@ -1833,13 +2362,27 @@ async fn resolve_missing_hcpcs(
                 interrupted = true;
                 break;
             }
-            if let Some(batch_codes) = queue.next() {
+            if budget_exhausted {
+                break;
+            }
+            if let Some(mut batch_codes) = in_flight_ids.next_claimed_batch(&mut queue) {
+                let over_budget = lookup_budget.admit(&mut batch_codes);
+                if !over_budget.is_empty() {
+                    for code in &over_budget {
+                        in_flight_ids.release(code);
+                    }
+                    next_round_codes.extend(over_budget);
+                    budget_exhausted = true;
+                }
+                if batch_codes.is_empty() {
+                    break;
+                }
                 in_flight.push(resolve_hcpcs_batch(
                     batch_codes,
                     client.clone(),
-                    args.hcpcs_api_base_url.clone(),
+                    HcpcsEndpoint::from_args(args),
                     api_run_id.to_string(),
-                    args.max_retries.max(1),
+                    RetryPolicy::from_args(args),
                     Arc::clone(&next_slot),
                     min_interval,
                 ));
@@ -1848,16 +2391,29 @@ async fn resolve_missing_hcpcs(
 
         let mut pending_current_round = current_round_total;
         while let Some(batch_results) = in_flight.next().await {
+            // Duplicates that were enqueued while a code was in flight settle with its result;
+            // only the code that was actually looked up keeps the reference row.
+            let mut settled = Vec::with_capacity(batch_results.len());
             for (code, result) in batch_results {
-                attempts += 1;
+                let waiters = in_flight_ids.release(&code);
+                settled.extend(waiters.into_iter().map(|id| (id, result.clone(), false)));
+                settled.push((code, result, true));
+            }
+            for (code, result, looked_up) in settled {
+                attempts += usize::from(looked_up);
                 pending_current_round = pending_current_round.saturating_sub(1);
+                let mut push_reference_row = |row| {
+                    if looked_up {
+                        reference_rows.push(row);
+                    }
+                };
 
                 match result {
                     HcpcsResolveResult::Found {
                         mut records,
                         reference_row,
                     } => {
-                        reference_rows.push(reference_row);
+                        push_reference_row(reference_row);
                         records.retain(|record| record.hcpcs_code.eq_ignore_ascii_case(&code));
                         let mut dedup = HashSet::new();
                         records.retain(|record| dedup.insert(record.clone()));
@@ -1870,7 +2426,9 @@ async fn resolve_missing_hcpcs(
                                 found += 1;
                                 fallback_hits += 1;
                             } else {
-                                cache.set_not_found(&code, "not_found")?;
+                                // The API matched the code but every record was dropped by
+                                // the exact-code filter above.
+                                cache.set_not_found(&code, "filtered_out")?;
                                 not_found += 1;
                             }
                         } else {
@@ -1895,7 +2453,7 @@ async fn resolve_missing_hcpcs(
                             cache.set_not_found(&code, &reason)?;
                             not_found += 1;
                         }
-                        reference_rows.push(reference_row);
+                        push_reference_row(reference_row);
                         progress.inc(1);
                     }
                     HcpcsResolveResult::Error {
@@ -1903,7 +2461,7 @@ async fn resolve_missing_hcpcs(
                         reference_row,
                     } => {
                         cache.set_error(&code, &error_message)?;
-                        reference_rows.push(reference_row);
+                        push_reference_row(reference_row);
                         if can_retry_errors_again && !shutdown_requested.load(Ordering::SeqCst) {
                             next_round_codes.push(code);
                             retry_failover_triggered = true;
@@ -1934,21 +2492,41 @@ async fn resolve_missing_hcpcs(
             progress.set_message(format!(
                 "mode={mode} ok={found} not_found={not_found} failed={failed} fallback={fallback_hits} remaining={remaining_in_round} retry_queued={retry_queued}"
             ));
+            if status_ticker.due() {
+                let settled = found + not_found + failed;
+                progress.suspend(|| {
+                    info!(
+                        "HCPCS status: settled={settled}/{total} mode={mode} ok={found} not_found={not_found} failed={failed} fallback={fallback_hits} remaining={remaining_in_round} retry_queued={retry_queued}"
+                    )
+                });
+            }
 
             if shutdown_requested.load(Ordering::SeqCst) {
                 interrupted = true;
-            } else if !retry_failover_triggered {
-                if let Some(next_batch) = queue.next() {
-                    in_flight.push(resolve_hcpcs_batch(
-                        next_batch,
-                        client.clone(),
-                        args.hcpcs_api_base_url.clone(),
-                        api_run_id.to_string(),
-                        args.max_retries.max(1),
-                        Arc::clone(&next_slot),
-                        min_interval,
-                    ));
+            } else if !retry_failover_triggered
+                && !budget_exhausted
+                && let Some(mut next_batch) = in_flight_ids.next_claimed_batch(&mut queue)
+            {
+                let over_budget = lookup_budget.admit(&mut next_batch);
+                if !over_budget.is_empty() {
+                    for code in &over_budget {
+                        in_flight_ids.release(code);
+                    }
+                    next_round_codes.extend(over_budget);
+                    budget_exhausted = true;
+                }
+                if next_batch.is_empty() {
+                    continue;
                 }
+                in_flight.push(resolve_hcpcs_batch(
+                    next_batch,
+                    client.clone(),
+                    HcpcsEndpoint::from_args(args),
+                    api_run_id.to_string(),
+                    RetryPolicy::from_args(args),
+                    Arc::clone(&next_slot),
+                    min_interval,
+                ));
             }
         }
 
@@ -1957,12 +2535,20 @@ async fn resolve_missing_hcpcs(
         }
         round_codes = next_round_codes;
 
-        if round_codes.is_empty() || interrupted {
+        if round_codes.is_empty() || interrupted || budget_exhausted {
             break;
         }
         retry_round = retry_round.saturating_add(1);
     }
 
+    if budget_exhausted {
+        progress.suspend(|| {
+            info!(
+                "--max-new-lookups-total budget used up; {} HCPCS codes left unresolved for a later run.",
+                round_codes.len()
+            )
+        });
+    }
     let settled = found + not_found + failed;
     if interrupted {
         progress.abandon_with_message(format!(
@@ -1974,15 +2560,26 @@ async fn resolve_missing_hcpcs(
             "done: settled={settled}/{total} ok={found} not_found={not_found} failed={failed} fallback={fallback_hits} attempts={attempts}"
         ));
     }
+    record_hcpcs_lookups(LookupTotals {
+        ok: found,
+        not_found,
+        error: failed,
+    });
+    if in_flight_ids.coalesced() > 0 {
+        info!(
+            "Coalesced {} duplicate HCPCS lookups that were already in flight.",
+            in_flight_ids.coalesced()
+        );
+    }
     Ok((interrupted, reference_rows))
 }
 
 async fn resolve_hcpcs_batch(
     codes: Vec<String>,
     client: Client,
-    api_base_url: String,
+    endpoint: HcpcsEndpoint,
     api_run_id: String,
-    max_retries: u32,
+    retry: RetryPolicy,
     next_slot: Arc<Mutex<Instant>>,
     min_interval: Duration,
 ) -> Vec<(String, HcpcsResolveResult)> {
@@ -1993,9 +2590,9 @@ async fn resolve_hcpcs_batch(
         let (code, result) = resolve_hcpcs(
             codes[0].clone(),
             client,
-            api_base_url,
+            endpoint,
             api_run_id,
-            max_retries,
+            retry,
             next_slot,
             min_interval,
         )
@@ -2004,16 +2601,14 @@ async fn resolve_hcpcs_batch(
     }
 
     wait_for_rate_slot(&next_slot, min_interval).await;
-    match fetch_hcpcs_batch_records(&client, &api_base_url, &codes, &api_run_id, max_retries).await
-    {
+    match fetch_hcpcs_batch_records(&client, &endpoint, &codes, &api_run_id, retry).await {
         Ok(results) => results,
         Err(batch_error) => {
             let mut fallback_results = Vec::with_capacity(codes.len());
             for code in codes {
                 wait_for_rate_slot(&next_slot, min_interval).await;
                 let single_result =
-                    fetch_hcpcs_records(&client, &api_base_url, &code, &api_run_id, max_retries)
-                        .await;
+                    fetch_hcpcs_records(&client, &endpoint, &code, &api_run_id, retry).await;
                 match single_result {
                     HcpcsResolveResult::Error {
                         error_message,
@@ -2042,51 +2637,69 @@ async fn resolve_hcpcs_batch(
 async fn resolve_hcpcs(
     code: String,
     client: Client,
-    api_base_url: String,
+    endpoint: HcpcsEndpoint,
     api_run_id: String,
-    max_retries: u32,
+    retry: RetryPolicy,
     next_slot: Arc<Mutex<Instant>>,
     min_interval: Duration,
 ) -> (String, HcpcsResolveResult) {
     wait_for_rate_slot(&next_slot, min_interval).await;
-    let result = fetch_hcpcs_records(&client, &api_base_url, &code, &api_run_id, max_retries).await;
+    let result = fetch_hcpcs_records(&client, &endpoint, &code, &api_run_id, retry).await;
     (code, result)
 }
 
+/// Query parameters (and their `request_params_json` form) for a single-code lookup.
+fn hcpcs_single_query(
+    endpoint: &HcpcsEndpoint,
+    hcpcs_code: &str,
+) -> (Vec<(&'static str, String)>, String) {
+    match endpoint.source {
+        HcpcsSource::Clinicaltables => {
+            let code_filter = format!("code:{hcpcs_code}");
+            let request_params_json = json!({
+                "terms": hcpcs_code,
+                "sf": "code",
+                "q": code_filter,
+                "count": endpoint.single_count,
+                "df": "code,display",
+                "ef": "short_desc,long_desc,add_dt,term_dt,act_eff_dt,obsolete,is_noc"
+            })
+            .to_string();
+            let query = vec![
+                ("terms", hcpcs_code.to_string()),
+                ("sf", "code".to_string()),
+                ("q", code_filter),
+                ("count", endpoint.single_count.to_string()),
+                ("df", "code,display".to_string()),
+                (
+                    "ef",
+                    "short_desc,long_desc,add_dt,term_dt,act_eff_dt,obsolete,is_noc".to_string(),
+                ),
+            ];
+            (query, request_params_json)
+        }
+        HcpcsSource::Cms => (
+            vec![("hcpcs_code", hcpcs_code.to_string())],
+            json!({ "hcpcs_code": hcpcs_code }).to_string(),
+        ),
+    }
+}
+
 async fn fetch_hcpcs_records(
     client: &Client,
-    api_base_url: &str,
+    endpoint: &HcpcsEndpoint,
     hcpcs_code: &str,
     api_run_id: &str,
-    max_retries: u32,
+    retry: RetryPolicy,
 ) -> HcpcsResolveResult {
-    let code_filter = format!("code:{hcpcs_code}");
-    let request_params_json = json!({
-        "terms": hcpcs_code,
-        "sf": "code",
-        "q": code_filter,
-        "count": 20,
-        "df": "code,display",
-        "ef": "short_desc,long_desc,add_dt,term_dt,act_eff_dt,obsolete,is_noc"
-    })
-    .to_string();
-    let request_url = reqwest::Url::parse_with_params(
-        api_base_url,
-        &[
-            ("terms", hcpcs_code),
-            ("sf", "code"),
-            ("q", code_filter.as_str()),
-            ("count", "20"),
-            ("df", "code,display"),
-            (
-                "ef",
-                "short_desc,long_desc,add_dt,term_dt,act_eff_dt,obsolete,is_noc",
-            ),
-        ],
-    )
-    .map(|url| url.to_string())
-    .unwrap_or_else(|_| format!("{api_base_url}?terms={hcpcs_code}"));
+    let api_base_url = endpoint.base_url.as_str();
+    let (query, request_params_json) = hcpcs_single_query(endpoint, hcpcs_code);
+    let request_url = reqwest::Url::parse_with_params(api_base_url, &query)
+        .map(|url| url.to_string())
+        .unwrap_or_else(|_| format!("{api_base_url}?terms={hcpcs_code}"));
     let requested_at_utc = now_unix_seconds().to_string();
+    // Round-trip time of the most recent attempt (send + body read), recorded on every row.
+    let attempt_latency_ms: Cell<Option<i64>> = Cell::new(None);
 
     let make_base_row = || HcpcsApiReferenceRow {
         hcpcs_code: hcpcs_code.to_string(),
@@ -2106,26 +2719,30 @@ async fn fetch_hcpcs_records(
         api_run_id: api_run_id.to_string(),
         requested_at_utc: requested_at_utc.clone(),
         request_params_json: request_params_json.clone(),
+        latency_ms: attempt_latency_ms.get(),
         response_json_raw: None,
     };
+    let dump = |http_status: Option<u16>, error_message: &str, body: &str| {
+        dump_failed_body(&FailedBody {
+            kind: "hcpcs",
+            identifier: hcpcs_code,
+            api_run_id,
+            request_url: &request_url,
+            http_status,
+            error_message,
+            body,
+        })
+    };
 
-    let attempts = max_retries.max(1);
-    let mut backoff = Duration::from_secs(1);
+    let attempts = retry.attempts();
+    let mut backoff = retry.initial_backoff();
 
     for attempt in 1..=attempts {
+        let started = Instant::now();
         let response = client
             .get(api_base_url)
-            .query(&[
-                ("terms", hcpcs_code),
-                ("sf", "code"),
-                ("q", code_filter.as_str()),
-                ("count", "20"),
-                ("df", "code,display"),
-                (
-                    "ef",
-                    "short_desc,long_desc,add_dt,term_dt,act_eff_dt,obsolete,is_noc",
-                ),
-            ])
+            .query(&query)
+            .timeout(retry.request_timeout)
             .send()
             .await;
 
@@ -2133,7 +2750,9 @@ async fn fetch_hcpcs_records(
             Ok(resp) => {
                 let status = resp.status();
                 if status.is_success() {
-                    let body_text = match resp.text().await {
+                    let body_text = resp.text().await;
+                    attempt_latency_ms.set(Some(elapsed_millis(started)));
+                    let body_text = match body_text {
                         Ok(text) => text,
                         Err(err) => {
                             let mut row = make_base_row();
@@ -2147,13 +2766,24 @@ async fn fetch_hcpcs_records(
                             };
                         }
                     };
-                    let body: Value = match serde_json::from_str(&body_text) {
+                    let body = serde_json::from_str::<Value>(&body_text)
+                        .map_err(anyhow::Error::from)
+                        .and_then(|value| match endpoint.source {
+                            HcpcsSource::Clinicaltables => Ok(value),
+                            HcpcsSource::Cms => cms_payload_to_clinicaltables(&value),
+                        });
+                    let body = match body {
                         Ok(value) => value,
                         Err(err) => {
                             let mut row = make_base_row();
                             row.http_status = Some(status.as_u16() as i64);
                             row.error_message =
                                 Some(format!("Invalid HCPCS API JSON for {hcpcs_code}: {err}"));
+                            dump(
+                                Some(status.as_u16()),
+                                row.error_message.as_deref().unwrap_or_default(),
+                                &body_text,
+                            );
                             return HcpcsResolveResult::Error {
                                 error_message: row.error_message.clone().unwrap_or_default(),
                                 reference_row: row,
@@ -2161,7 +2791,7 @@ async fn fetch_hcpcs_records(
                         }
                     };
 
-                    let row = build_hcpcs_reference_row_from_value(
+                    let mut row = build_hcpcs_reference_row_from_value(
                         &body,
                         hcpcs_code,
                         &request_url,
@@ -2170,10 +2800,11 @@ async fn fetch_hcpcs_records(
                         &requested_at_utc,
                         &request_params_json,
                     );
+                    row.latency_ms = attempt_latency_ms.get();
 
                     return match parse_hcpcs_payload(hcpcs_code, &body) {
                         Ok(records) if records.is_empty() => HcpcsResolveResult::NotFound {
-                            reason: "not_found".to_string(),
+                            reason: hcpcs_not_found_reason(&body).to_string(),
                             reference_row: row,
                         },
                         Ok(records) => HcpcsResolveResult::Found {
@@ -2184,6 +2815,7 @@ async fn fetch_hcpcs_records(
                             let mut error_row = row;
                             let message =
                                 format!("Failed parsing HCPCS payload for {hcpcs_code}: {err}");
+                            dump(Some(status.as_u16()), &message, &body_text);
                             error_row.error_message = Some(message.clone());
                             HcpcsResolveResult::Error {
                                 error_message: message,
@@ -2193,8 +2825,19 @@ async fn fetch_hcpcs_records(
                     };
                 }
 
+                if endpoint.not_found_statuses.contains(&status.as_u16()) {
+                    attempt_latency_ms.set(Some(elapsed_millis(started)));
+                    let mut row = make_base_row();
+                    row.http_status = Some(status.as_u16() as i64);
+                    return HcpcsResolveResult::NotFound {
+                        reason: format!("http_status_{}", status.as_u16()),
+                        reference_row: row,
+                    };
+                }
+
                 let retry_after = parse_retry_after(resp.headers().get(RETRY_AFTER));
                 let body = resp.text().await.unwrap_or_default();
+                attempt_latency_ms.set(Some(elapsed_millis(started)));
                 if is_retryable_status(status) {
                     if attempt == attempts {
                         let mut row = make_base_row();
@@ -2206,6 +2849,7 @@ async fn fetch_hcpcs_records(
                             attempts,
                             truncate_for_log(&body)
                         );
+                        dump(Some(status.as_u16()), &message, &body);
                         row.error_message = Some(message.clone());
                         return HcpcsResolveResult::Error {
                             error_message: message,
@@ -2213,7 +2857,7 @@ async fn fetch_hcpcs_records(
                         };
                     }
                     tokio::time::sleep(retry_after.unwrap_or(backoff)).await;
-                    backoff = (backoff + backoff).min(Duration::from_secs(60));
+                    backoff = retry.next_backoff(backoff);
                     continue;
                 }
 
@@ -2225,6 +2869,7 @@ async fn fetch_hcpcs_records(
                     hcpcs_code,
                     truncate_for_log(&body)
                 );
+                dump(Some(status.as_u16()), &message, &body);
                 row.error_message = Some(message.clone());
                 return HcpcsResolveResult::Error {
                     error_message: message,
@@ -2232,6 +2877,7 @@ async fn fetch_hcpcs_records(
                 };
             }
             Err(err) => {
+                attempt_latency_ms.set(Some(elapsed_millis(started)));
                 if attempt == attempts {
                     let mut row = make_base_row();
                     let message = format!("HCPCS API request failed for {hcpcs_code}: {err}");
@@ -2242,7 +2888,7 @@ async fn fetch_hcpcs_records(
                     };
                 }
                 tokio::time::sleep(backoff).await;
-                backoff = (backoff + backoff).min(Duration::from_secs(60));
+                backoff = retry.next_backoff(backoff);
             }
         }
     }
@@ -2256,16 +2902,19 @@ async fn fetch_hcpcs_records(
     }
 }
 
-fn normalize_code_key(code: &str) -> String {
-    code.trim().to_ascii_uppercase()
+fn apply_hcpcs_case(code: &str, case_mode: HcpcsCaseMode) -> String {
+    match case_mode {
+        HcpcsCaseMode::Upper => normalize_code_key(code),
+        HcpcsCaseMode::Source => code.trim().to_string(),
+    }
 }
 
 async fn fetch_hcpcs_batch_records(
     client: &Client,
-    api_base_url: &str,
+    endpoint: &HcpcsEndpoint,
     hcpcs_codes: &[String],
     api_run_id: &str,
-    max_retries: u32,
+    retry: RetryPolicy,
 ) -> std::result::Result<Vec<(String, HcpcsResolveResult)>, String> {
     if hcpcs_codes.is_empty() {
         return Ok(Vec::new());
@@ -2280,12 +2929,14 @@ async fn fetch_hcpcs_batch_records(
         return Ok(Vec::new());
     }
 
+    let api_base_url = endpoint.base_url.as_str();
+    let count = endpoint.batch_count.to_string();
     let code_filter = format!("code:({})", cleaned_codes.join(" OR "));
     let request_params_json = json!({
         "terms": "",
         "sf": "code",
         "q": code_filter,
-        "count": 500,
+        "count": endpoint.batch_count,
         "df": "code,display",
         "ef": "short_desc,long_desc,add_dt,term_dt,act_eff_dt,obsolete,is_noc"
     })
@@ -2296,7 +2947,7 @@ async fn fetch_hcpcs_batch_records(
             ("terms", ""),
             ("sf", "code"),
             ("q", code_filter.as_str()),
-            ("count", "500"),
+            ("count", count.as_str()),
             ("df", "code,display"),
             (
                 "ef",
@@ -2307,24 +2958,146 @@ async fn fetch_hcpcs_batch_records(
     .map(|url| url.to_string())
     .unwrap_or_else(|_| format!("{api_base_url}?q={code_filter}"));
     let requested_at_utc = now_unix_seconds().to_string();
+    let batch_identifier = format!("{}_and_{}_more", cleaned_codes[0], cleaned_codes.len() - 1);
+    let dump = |http_status: Option<u16>, error_message: &str, body: &str| {
+        dump_failed_body(&FailedBody {
+            kind: "hcpcs_batch",
+            identifier: &batch_identifier,
+            api_run_id,
+            request_url: &request_url,
+            http_status,
+            error_message,
+            body,
+        })
+    };
 
-    let attempts = max_retries.max(1);
-    let mut backoff = Duration::from_secs(1);
+    // A batch can match more rows than `count` (codes with many revisions or NOC variants).
+    // The leading total tells how many exist; page through the rest with `offset` and merge
+    // the pages into one payload so every downstream parser sees the full result.
+    let base_query = [
+        ("terms", ""),
+        ("sf", "code"),
+        ("q", code_filter.as_str()),
+        ("count", count.as_str()),
+        ("df", "code,display"),
+        (
+            "ef",
+            "short_desc,long_desc,add_dt,term_dt,act_eff_dt,obsolete,is_noc",
+        ),
+    ];
+    let mut body: Option<Value> = None;
+    let mut status: u16;
+    let mut latency_ms = 0i64;
+    let mut offset = 0usize;
+    loop {
+        let offset_text = offset.to_string();
+        let mut query = base_query.to_vec();
+        if offset > 0 {
+            query.push(("offset", offset_text.as_str()));
+        }
+        let (page_status, page_latency_ms, page) =
+            fetch_hcpcs_batch_page(client, api_base_url, &query, retry, &dump).await?;
+        status = page_status;
+        latency_ms += page_latency_ms;
+        let total = page.get(0).and_then(Value::as_u64).unwrap_or(0) as usize;
+        let page_rows = page.get(1).and_then(Value::as_array).map_or(0, Vec::len);
+        offset += page_rows;
+        match body.as_mut() {
+            Some(merged) => merge_hcpcs_page(merged, page),
+            None => body = Some(page),
+        }
+        if page_rows == 0 || offset >= total {
+            break;
+        }
+    }
+    let body = body.unwrap_or(Value::Null);
+
+    let records_by_code = parse_hcpcs_payload_by_code(&body).map_err(|err| {
+        let message = format!("Failed parsing HCPCS batch payload for requested codes: {err}");
+        dump(Some(status), &message, &body.to_string());
+        message
+    })?;
+
+    let mut outcomes = Vec::with_capacity(hcpcs_codes.len());
+    for code in hcpcs_codes {
+        let lookup_key = normalize_code_key(code);
+        let records = records_by_code
+            .get(&lookup_key)
+            .cloned()
+            .unwrap_or_default();
+        let mut reference_row = build_hcpcs_reference_row_for_code(
+            &body,
+            code,
+            &request_url,
+            status as i64,
+            api_run_id,
+            &requested_at_utc,
+            &request_params_json,
+        );
+        reference_row.latency_ms = Some(latency_ms);
+
+        if records.is_empty() {
+            outcomes.push((
+                code.clone(),
+                HcpcsResolveResult::NotFound {
+                    reason: hcpcs_not_found_reason(&body).to_string(),
+                    reference_row,
+                },
+            ));
+        } else {
+            outcomes.push((
+                code.clone(),
+                HcpcsResolveResult::Found {
+                    records,
+                    reference_row,
+                },
+            ));
+        }
+    }
+    Ok(outcomes)
+}
+
+/// Appends one page's codes, `extra_fields` columns, and display rows onto `merged`.
+fn merge_hcpcs_page(merged: &mut Value, page: Value) {
+    let (Some(merged), Value::Array(page)) = (merged.as_array_mut(), page) else {
+        return;
+    };
+    for (idx, part) in page.into_iter().enumerate().skip(1) {
+        match (merged.get_mut(idx), part) {
+            (Some(Value::Array(rows)), Value::Array(more)) => rows.extend(more),
+            (Some(Value::Object(columns)), Value::Object(more)) => {
+                for (key, value) in more {
+                    match (columns.get_mut(&key), value) {
+                        (Some(Value::Array(rows)), Value::Array(more)) => rows.extend(more),
+                        (None, value) => {
+                            columns.insert(key, value);
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// One page of a batched lookup, retried per `retry`: `(http_status, latency_ms, body)`.
+async fn fetch_hcpcs_batch_page(
+    client: &Client,
+    api_base_url: &str,
+    query: &[(&str, &str)],
+    retry: RetryPolicy,
+    dump: &impl Fn(Option<u16>, &str, &str),
+) -> std::result::Result<(u16, i64, Value), String> {
+    let attempts = retry.attempts();
+    let mut backoff = retry.initial_backoff();
 
     for attempt in 1..=attempts {
+        let started = Instant::now();
         let response = client
             .get(api_base_url)
-            .query(&[
-                ("terms", ""),
-                ("sf", "code"),
-                ("q", code_filter.as_str()),
-                ("count", "500"),
-                ("df", "code,display"),
-                (
-                    "ef",
-                    "short_desc,long_desc,add_dt,term_dt,act_eff_dt,obsolete,is_noc",
-                ),
-            ])
+            .query(query)
+            .timeout(retry.request_timeout)
             .send()
             .await;
 
@@ -2335,78 +3108,46 @@ async fn fetch_hcpcs_batch_records(
                     let body_text = resp.text().await.map_err(|err| {
                         format!("Failed reading HCPCS batch response body: {err}")
                     })?;
-                    let body: Value = serde_json::from_str(&body_text)
-                        .map_err(|err| format!("Invalid HCPCS batch JSON: {err}"))?;
-                    let records_by_code = parse_hcpcs_payload_by_code(&body).map_err(|err| {
-                        format!("Failed parsing HCPCS batch payload for requested codes: {err}")
+                    let latency_ms = elapsed_millis(started);
+                    let body: Value = serde_json::from_str(&body_text).map_err(|err| {
+                        let message = format!("Invalid HCPCS batch JSON: {err}");
+                        dump(Some(status.as_u16()), &message, &body_text);
+                        message
                     })?;
-
-                    let mut outcomes = Vec::with_capacity(hcpcs_codes.len());
-                    for code in hcpcs_codes {
-                        let lookup_key = normalize_code_key(code);
-                        let records = records_by_code
-                            .get(&lookup_key)
-                            .cloned()
-                            .unwrap_or_default();
-                        let reference_row = build_hcpcs_reference_row_for_code(
-                            &body,
-                            code,
-                            &request_url,
-                            status.as_u16() as i64,
-                            api_run_id,
-                            &requested_at_utc,
-                            &request_params_json,
-                        );
-
-                        if records.is_empty() {
-                            outcomes.push((
-                                code.clone(),
-                                HcpcsResolveResult::NotFound {
-                                    reason: "not_found".to_string(),
-                                    reference_row,
-                                },
-                            ));
-                        } else {
-                            outcomes.push((
-                                code.clone(),
-                                HcpcsResolveResult::Found {
-                                    records,
-                                    reference_row,
-                                },
-                            ));
-                        }
-                    }
-                    return Ok(outcomes);
+                    return Ok((status.as_u16(), latency_ms, body));
                 }
-
                 let retry_after = parse_retry_after(resp.headers().get(RETRY_AFTER));
                 let body = resp.text().await.unwrap_or_default();
                 if is_retryable_status(status) {
                     if attempt == attempts {
-                        return Err(format!(
+                        let message = format!(
                             "HCPCS batch retryable status {} after {} attempts. Body: {}",
                             status,
                             attempts,
                             truncate_for_log(&body)
-                        ));
+                        );
+                        dump(Some(status.as_u16()), &message, &body);
+                        return Err(message);
                     }
                     tokio::time::sleep(retry_after.unwrap_or(backoff)).await;
-                    backoff = (backoff + backoff).min(Duration::from_secs(60));
+                    backoff = retry.next_backoff(backoff);
                     continue;
                 }
 
-                return Err(format!(
+                let message = format!(
                     "HCPCS batch non-retryable status {}. Body: {}",
                     status,
                     truncate_for_log(&body)
-                ));
+                );
+                dump(Some(status.as_u16()), &message, &body);
+                return Err(message);
             }
             Err(err) => {
                 if attempt == attempts {
                     return Err(format!("HCPCS batch request failed: {err}"));
                 }
                 tokio::time::sleep(backoff).await;
-                backoff = (backoff + backoff).min(Duration::from_secs(60));
+                backoff = retry.next_backoff(backoff);
             }
         }
     }
@@ -2452,6 +3193,7 @@ fn parse_hcpcs_payload_by_code(payload: &Value) -> Result<HashMap<String, Vec<Hc
                 term_dt,
                 obsolete,
                 is_noc,
+                preferred: false,
             });
     }
     Ok(by_code)
@@ -2564,6 +3306,7 @@ fn build_hcpcs_reference_row_for_code(
         api_run_id: api_run_id.to_string(),
         requested_at_utc: requested_at_utc.to_string(),
         request_params_json: request_params_json.to_string(),
+        latency_ms: None,
         response_json_raw: serde_json::to_string(&filtered_response_value).ok(),
     }
 }
@@ -2603,12 +3346,103 @@ fn parse_hcpcs_payload(requested_code: &str, payload: &Value) -> Result<Vec<Hcpc
             term_dt,
             obsolete,
             is_noc,
+            preferred: false,
         });
     }
 
     Ok(records)
 }
 
+/// Rewrites a `--hcpcs-source cms` payload (a JSON array of flat records, or an object wrapping
+/// one under `data`/`results`) into the ClinicalTables `[hit_count, codes, extra_fields,
+/// display]` shape, so parsing, the cache, and `response_json_raw` consumers see one layout.
+/// Keys match case-insensitively with punctuation ignored, so both `short_description` and
+/// the HCPCS release file's `SHORT DESCRIPTION` work.
+fn cms_payload_to_clinicaltables(payload: &Value) -> Result<Value> {
+    const FIELDS: &[(&str, &[&str])] = &[
+        ("short_desc", &["shortdesc", "shortdescription"]),
+        ("long_desc", &["longdesc", "longdescription"]),
+        ("add_dt", &["adddt", "adddate"]),
+        (
+            "act_eff_dt",
+            &["acteffdt", "acteffdate", "actioneffectivedate"],
+        ),
+        ("term_dt", &["termdt", "termdate", "terminationdate"]),
+        ("obsolete", &["obsolete"]),
+        ("is_noc", &["isnoc", "noc"]),
+    ];
+    const CODE_KEYS: &[&str] = &["hcpcscode", "hcpcs", "hcpc", "code"];
+
+    let items = match payload {
+        Value::Array(items) => items,
+        Value::Object(obj) => obj
+            .get("data")
+            .or_else(|| obj.get("results"))
+            .and_then(Value::as_array)
+            .context("CMS HCPCS payload object has no `data` or `results` array")?,
+        _ => bail!("CMS HCPCS payload is neither an array nor an object"),
+    };
+
+    let mut codes = Vec::new();
+    let mut display = Vec::new();
+    let mut extra: Vec<Vec<Value>> = vec![Vec::new(); FIELDS.len()];
+    for item in items {
+        let obj = item
+            .as_object()
+            .context("CMS HCPCS payload record is not an object")?;
+        let lookup = |keys: &[&str]| {
+            obj.iter()
+                .find(|(key, _)| {
+                    let key: String = key
+                        .chars()
+                        .filter(char::is_ascii_alphanumeric)
+                        .map(|c| c.to_ascii_lowercase())
+                        .collect();
+                    keys.contains(&key.as_str())
+                })
+                .map(|(_, value)| value_to_string(value).trim().to_string())
+                .unwrap_or_default()
+        };
+        let code = lookup(CODE_KEYS);
+        if code.is_empty() {
+            continue;
+        }
+        for (values, (_, keys)) in extra.iter_mut().zip(FIELDS) {
+            values.push(Value::String(lookup(keys)));
+        }
+        display.push(json!([code, lookup(FIELDS[0].1)]));
+        codes.push(Value::String(code));
+    }
+
+    let extra_fields: serde_json::Map<String, Value> = FIELDS
+        .iter()
+        .zip(extra)
+        .map(|((name, _), values)| (name.to_string(), Value::Array(values)))
+        .collect();
+    Ok(json!([
+        codes.len(),
+        codes,
+        Value::Object(extra_fields),
+        display
+    ]))
+}
+
+/// Why a payload produced no records for a requested code, stored as the `not_found` cache
+/// row's `error_message`: `empty_results` when the API returned no codes at all,
+/// `no_code_match` when it returned codes but none equal to the requested one.
+fn hcpcs_not_found_reason(payload: &Value) -> &'static str {
+    let has_codes = payload
+        .as_array()
+        .and_then(|arr| arr.get(1))
+        .and_then(Value::as_array)
+        .is_some_and(|codes| !codes.is_empty());
+    if has_codes {
+        "no_code_match"
+    } else {
+        "empty_results"
+    }
+}
+
 fn field_value(extra: &Value, field: &str, idx: usize) -> String {
     let Some(obj) = extra.as_object() else {
         return String::new();
@@ -2669,3 +3503,538 @@ fn parse_bool_flag(value: &str) -> Option<bool> {
         _ => None,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_api::{MALFORMED_JSON, MockApi, MockResponse, fast_retry, hcpcs_rows};
+    use clap::Parser;
+    use std::path::PathBuf;
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "build_datasets_hcpcs_{name}_{}",
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn test_args(mock: &MockApi, extra: &[&str]) -> Args {
+        let url = mock.url("/hcpcs");
+        let mut argv = vec![
+            "build_datasets",
+            "--hcpcs-api-base-url",
+            url.as_str(),
+            "--max-retries",
+            "1",
+            "--failure-retry-rounds",
+            "0",
+            "--requests-per-second",
+            "0",
+        ];
+        argv.extend_from_slice(extra);
+        Args::try_parse_from(argv).unwrap()
+    }
+
+    async fn resolve(cache: &HcpcsCache, args: &Args, codes: &[&str]) {
+        resolve_missing_hcpcs(
+            cache,
+            codes.iter().map(|code| code.to_string()).collect(),
+            &HashMap::new(),
+            &Client::new(),
+            args,
+            "test-run",
+            &LookupBudget::new(None),
+            None,
+            Arc::new(AtomicBool::new(false)),
+        )
+        .await
+        .unwrap();
+    }
+
+    /// `(hcpcs_code, status, error_message)` rows cached under `code`'s key.
+    fn cached_rows(cache: &HcpcsCache, code: &str) -> Vec<(String, String, String)> {
+        let mut stmt = cache
+            .conn
+            .prepare(
+                "SELECT hcpcs_code, status, error_message FROM hcpcs_cache
+                 WHERE code_key = ?1 ORDER BY hcpcs_code, short_desc",
+            )
+            .unwrap();
+        stmt.query_map([normalize_code_key(code)], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+        })
+        .unwrap()
+        .collect::<rusqlite::Result<_>>()
+        .unwrap()
+    }
+
+    fn endpoint(mock: &MockApi) -> HcpcsEndpoint {
+        HcpcsEndpoint {
+            base_url: mock.url("/hcpcs"),
+            source: HcpcsSource::Clinicaltables,
+            not_found_statuses: Vec::new(),
+            single_count: 500,
+            batch_count: 500,
+        }
+    }
+
+    async fn lookup(mock: &MockApi, code: &str) -> HcpcsResolveResult {
+        fetch_hcpcs_records(
+            &Client::new(),
+            &endpoint(mock),
+            code,
+            "test-run",
+            fast_retry(3),
+        )
+        .await
+    }
+
+    async fn batch_lookup(
+        mock: &MockApi,
+        codes: &[&str],
+    ) -> std::result::Result<Vec<(String, HcpcsResolveResult)>, String> {
+        let codes: Vec<String> = codes.iter().map(|code| code.to_string()).collect();
+        fetch_hcpcs_batch_records(
+            &Client::new(),
+            &endpoint(mock),
+            &codes,
+            "test-run",
+            fast_retry(3),
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn found_code_returns_its_records() {
+        let mock = MockApi::scripted(vec![MockResponse::json(hcpcs_rows(
+            1,
+            &[("J1234", "Injection")],
+        ))])
+        .await;
+        let HcpcsResolveResult::Found { records, .. } = lookup(&mock, "J1234").await else {
+            panic!("expected Found");
+        };
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].hcpcs_code, "J1234");
+        assert_eq!(records[0].short_desc, "Injection");
+        let requests = mock.requests();
+        assert_eq!(requests[0].param("q"), "code:J1234");
+        assert_eq!(requests[0].param("count"), "500");
+    }
+
+    #[tokio::test]
+    async fn empty_result_is_not_found() {
+        let mock = MockApi::scripted(vec![MockResponse::json(hcpcs_rows(0, &[]))]).await;
+        let HcpcsResolveResult::NotFound { reason, .. } = lookup(&mock, "99999").await else {
+            panic!("expected NotFound");
+        };
+        assert_eq!(reason, "empty_results");
+    }
+
+    #[tokio::test]
+    async fn server_error_is_retried_until_it_succeeds() {
+        let mock = MockApi::scripted(vec![
+            MockResponse::status(500),
+            MockResponse::json(hcpcs_rows(1, &[("J1234", "Injection")])),
+        ])
+        .await;
+        assert!(matches!(
+            lookup(&mock, "J1234").await,
+            HcpcsResolveResult::Found { .. }
+        ));
+        assert_eq!(mock.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn rate_limit_waits_for_retry_after() {
+        let mock = MockApi::scripted(vec![
+            MockResponse::raw(429, "[]").retry_after(1),
+            MockResponse::json(hcpcs_rows(1, &[("J1234", "Injection")])),
+        ])
+        .await;
+        assert!(matches!(
+            lookup(&mock, "J1234").await,
+            HcpcsResolveResult::Found { .. }
+        ));
+        assert!(mock.first_retry_gap().unwrap() >= Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn malformed_json_is_an_error_without_retrying() {
+        let mock = MockApi::scripted(vec![MockResponse::raw(200, MALFORMED_JSON)]).await;
+        let HcpcsResolveResult::Error { error_message, .. } = lookup(&mock, "J1234").await else {
+            panic!("expected Error");
+        };
+        assert!(
+            error_message.contains("Invalid HCPCS API JSON"),
+            "{error_message}"
+        );
+        assert_eq!(mock.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn batch_settles_each_code_from_one_request() {
+        let mock = MockApi::scripted(vec![MockResponse::json(hcpcs_rows(
+            1,
+            &[("J1234", "Injection")],
+        ))])
+        .await;
+        let outcomes = batch_lookup(&mock, &["J1234", "99999"]).await.unwrap();
+        assert!(
+            matches!(outcomes[0], (ref code, HcpcsResolveResult::Found { .. }) if code == "J1234")
+        );
+        assert!(
+            matches!(outcomes[1], (ref code, HcpcsResolveResult::NotFound { .. }) if code == "99999")
+        );
+        let requests = mock.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].param("q"), "code:(J1234 OR 99999)");
+    }
+
+    #[tokio::test]
+    async fn batch_rate_limit_waits_for_retry_after() {
+        let mock = MockApi::scripted(vec![
+            MockResponse::raw(429, "[]").retry_after(1),
+            MockResponse::json(hcpcs_rows(1, &[("J1234", "Injection")])),
+        ])
+        .await;
+        let outcomes = batch_lookup(&mock, &["J1234", "A0428"]).await.unwrap();
+        assert!(matches!(outcomes[0].1, HcpcsResolveResult::Found { .. }));
+        assert!(mock.first_retry_gap().unwrap() >= Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn batch_malformed_json_fails_the_batch() {
+        let mock = MockApi::scripted(vec![MockResponse::raw(200, MALFORMED_JSON)]).await;
+        let Err(err) = batch_lookup(&mock, &["J1234", "A0428"]).await else {
+            panic!("expected the batch to fail");
+        };
+        assert!(err.contains("Invalid HCPCS batch JSON"), "{err}");
+    }
+
+    #[test]
+    fn not_found_reason_tells_empty_results_from_no_code_match() {
+        assert_eq!(hcpcs_not_found_reason(&hcpcs_rows(0, &[])), "empty_results");
+        assert_eq!(
+            hcpcs_not_found_reason(&hcpcs_rows(1, &[("J12345", "Other")])),
+            "no_code_match"
+        );
+    }
+
+    #[tokio::test]
+    async fn unmatched_single_lookup_is_not_found_with_no_code_match() {
+        let mock = MockApi::scripted(vec![MockResponse::json(hcpcs_rows(
+            1,
+            &[("J12345", "Other")],
+        ))])
+        .await;
+        let HcpcsResolveResult::NotFound { reason, .. } = lookup(&mock, "J1234").await else {
+            panic!("expected NotFound");
+        };
+        assert_eq!(reason, "no_code_match");
+    }
+
+    #[tokio::test]
+    async fn records_dropped_by_the_exact_code_filter_are_cached_as_filtered_out() {
+        let mock = MockApi::scripted(vec![MockResponse::json(hcpcs_rows(
+            1,
+            &[("J1234", "Injection")],
+        ))])
+        .await;
+        let dir = scratch_dir("filtered_out");
+        let cache = HcpcsCache::open(&dir.join("cache.sqlite")).unwrap();
+        // The batch matches by normalized key, but the exact-code filter keeps the padded
+        // code from claiming J1234's records.
+        resolve(&cache, &test_args(&mock, &[]), &["J1234 ", "99999"]).await;
+        assert_eq!(cached_rows(&cache, "J1234")[0].1, "not_found");
+        assert_eq!(cached_rows(&cache, "J1234")[0].2, "filtered_out");
+        assert_eq!(cached_rows(&cache, "99999")[0].2, "no_code_match");
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn cms_array_payload_becomes_clinicaltables_rows() {
+        let payload = json!([
+            {
+                "HCPCS_CODE": "J1234",
+                "Short Description": "Injection",
+                "Long_Description": "Injection, long",
+                "ADD_DT": "20200101",
+                "Action Effective Date": "20200201",
+                "TERM_DT": "",
+                "Obsolete": false,
+                "NOC": "N"
+            },
+            { "HCPCS_CODE": " ", "Short Description": "skipped: no code" },
+            { "hcpc": "A0428", "shortDesc": "Ambulance" }
+        ]);
+        let converted = cms_payload_to_clinicaltables(&payload).unwrap();
+        assert_eq!(converted[0], 2);
+        assert_eq!(converted[1], json!(["J1234", "A0428"]));
+        assert_eq!(
+            converted[2]["short_desc"],
+            json!(["Injection", "Ambulance"])
+        );
+        assert_eq!(converted[2]["long_desc"], json!(["Injection, long", ""]));
+        assert_eq!(converted[2]["act_eff_dt"], json!(["20200201", ""]));
+        assert_eq!(converted[2]["obsolete"], json!(["false", ""]));
+        assert_eq!(converted[2]["is_noc"], json!(["N", ""]));
+        assert_eq!(converted[3][0], json!(["J1234", "Injection"]));
+
+        let records = parse_hcpcs_payload("J1234", &converted).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].short_desc, "Injection");
+    }
+
+    #[test]
+    fn cms_object_payload_reads_data_or_results() {
+        let record = json!({ "hcpcs_code": "J1234", "short_desc": "Injection" });
+        for key in ["data", "results"] {
+            let mut payload = json!({ "meta": {} });
+            payload[key] = json!([record.clone()]);
+            let converted = cms_payload_to_clinicaltables(&payload).unwrap();
+            assert_eq!(converted[1], json!(["J1234"]), "{key}");
+            assert_eq!(converted[2]["short_desc"], json!(["Injection"]), "{key}");
+        }
+
+        let err = cms_payload_to_clinicaltables(&json!({ "rows": [] })).unwrap_err();
+        assert!(
+            err.to_string().contains("no `data` or `results` array"),
+            "{err}"
+        );
+        assert!(cms_payload_to_clinicaltables(&json!("J1234")).is_err());
+    }
+
+    #[tokio::test]
+    async fn configured_not_found_status_settles_as_not_found() {
+        let mock = MockApi::scripted(vec![MockResponse::status(404)]).await;
+        let endpoint = HcpcsEndpoint {
+            not_found_statuses: vec![404],
+            ..endpoint(&mock)
+        };
+        let result = fetch_hcpcs_records(
+            &Client::new(),
+            &endpoint,
+            "J1234",
+            "test-run",
+            fast_retry(3),
+        )
+        .await;
+        let HcpcsResolveResult::NotFound {
+            reason,
+            reference_row,
+        } = result
+        else {
+            panic!("expected NotFound");
+        };
+        assert_eq!(reason, "http_status_404");
+        assert_eq!(reference_row.http_status, Some(404));
+        assert_eq!(mock.requests().len(), 1);
+
+        // Without the status configured, a 404 stays an error.
+        assert!(matches!(
+            lookup(&mock, "J1234").await,
+            HcpcsResolveResult::Error { .. }
+        ));
+    }
+
+    fn api_record(code: &str, short_desc: &str, is_noc: bool) -> HcpcsApiRecord {
+        HcpcsApiRecord {
+            hcpcs_code: code.to_string(),
+            short_desc: short_desc.to_string(),
+            long_desc: String::new(),
+            add_dt: "2020-01-01".to_string(),
+            act_eff_dt: "2020-01-01".to_string(),
+            term_dt: String::new(),
+            obsolete: false,
+            is_noc,
+            preferred: false,
+        }
+    }
+
+    /// `(hcpcs_code, short_desc)` of every exported mapping row.
+    fn exported_mapping(
+        cache: &HcpcsCache,
+        path: &Path,
+        one_row_per_code: bool,
+    ) -> Vec<(String, String)> {
+        cache.export_mapping_csv(path, one_row_per_code).unwrap();
+        ReaderBuilder::new()
+            .from_path(path)
+            .unwrap()
+            .records()
+            .map(|record| {
+                let record = record.unwrap();
+                (record[0].to_string(), record[2].to_string())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn one_row_per_code_keeps_the_preferred_record_for_each_code() {
+        let dir = scratch_dir("one_row_per_code");
+        let cache = HcpcsCache::open(&dir.join("cache.sqlite")).unwrap();
+        cache
+            .insert_ok_records(
+                "J1234",
+                &[
+                    api_record("J1234", "Unlisted drug", true),
+                    api_record("J1234", "Injection", false),
+                ],
+            )
+            .unwrap();
+        cache
+            .insert_ok_records("A0428", &[api_record("A0428", "Ambulance", false)])
+            .unwrap();
+        let path = dir.join("hcpcs_code_mapping.csv");
+
+        assert_eq!(
+            exported_mapping(&cache, &path, true),
+            [
+                ("A0428".to_string(), "Ambulance".to_string()),
+                ("J1234".to_string(), "Injection".to_string()),
+            ]
+        );
+        assert_eq!(exported_mapping(&cache, &path, false).len(), 3);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn mixed_case_codes_share_one_code_key() {
+        let dir = scratch_dir("mixed_case");
+        let path = dir.join("cache.sqlite");
+        // A cache from before `code_key`, with a stale lowercase error row.
+        rusqlite::Connection::open(&path)
+            .unwrap()
+            .execute_batch(
+                "
+                CREATE TABLE hcpcs_cache (
+                    hcpcs_code TEXT NOT NULL,
+                    short_desc TEXT NOT NULL DEFAULT '',
+                    long_desc TEXT NOT NULL DEFAULT '',
+                    add_dt TEXT NOT NULL DEFAULT '',
+                    act_eff_dt TEXT NOT NULL DEFAULT '',
+                    term_dt TEXT NOT NULL DEFAULT '',
+                    obsolete TEXT NOT NULL DEFAULT '',
+                    is_noc TEXT NOT NULL DEFAULT '',
+                    status TEXT NOT NULL,
+                    error_message TEXT NOT NULL DEFAULT '',
+                    fetched_at_unix INTEGER NOT NULL,
+                    PRIMARY KEY (
+                        hcpcs_code, short_desc, long_desc, add_dt, act_eff_dt, term_dt, obsolete,
+                        is_noc, status
+                    )
+                );
+                INSERT INTO hcpcs_cache (hcpcs_code, short_desc, status, error_message, fetched_at_unix)
+                VALUES ('j1885', '', 'error', 'http_status_503', 0),
+                       ('J1886', 'Other', 'ok', '', 0);
+                ",
+            )
+            .unwrap();
+        let mut cache = HcpcsCache::open(&path).unwrap();
+
+        let codes = ["J1885", "j1886"].map(String::from);
+        let (resolved, missing) = cache.classify_for_lookup(&codes, None).unwrap();
+        assert_eq!((resolved, missing), (1, vec!["J1885".to_string()]));
+
+        // Source casing lets both spellings land as separate rows under one key...
+        cache.case_mode = HcpcsCaseMode::Source;
+        cache
+            .insert_ok_records("j1885", &[api_record("j1885", "Lower", false)])
+            .unwrap();
+        cache
+            .insert_ok_records("J1885", &[api_record("J1885", "Upper", false)])
+            .unwrap();
+        assert!(cache.has_ok_record("J1885").unwrap());
+        assert_eq!(cached_rows(&cache, "j1885").len(), 3);
+
+        // ...and a replace clears exactly that key, whatever the casing, and nothing else.
+        cache
+            .replace_with_ok_records("j1885", &[api_record("J1885", "Injection", false)])
+            .unwrap();
+        assert_eq!(
+            cached_rows(&cache, "J1885"),
+            [("J1885".to_string(), "ok".to_string(), String::new())]
+        );
+        assert_eq!(cached_rows(&cache, "J1886").len(), 1);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn batch_pages_until_the_reported_total_is_read() {
+        let mock = MockApi::start(|request, _| {
+            let page: &[(&str, &str)] = match request.param("offset") {
+                "" => &[("J1234", "Injection"), ("J1234", "Injection, NOC")],
+                "2" => &[("A0428", "Ambulance")],
+                _ => &[],
+            };
+            MockResponse::json(hcpcs_rows(3, page))
+        })
+        .await;
+        let outcomes = batch_lookup(&mock, &["J1234", "A0428"]).await.unwrap();
+        let HcpcsResolveResult::Found { records, .. } = &outcomes[0].1 else {
+            panic!("expected J1234 Found");
+        };
+        assert_eq!(records.len(), 2);
+        let HcpcsResolveResult::Found { records, .. } = &outcomes[1].1 else {
+            panic!("expected A0428 from the second page");
+        };
+        assert_eq!(records[0].short_desc, "Ambulance");
+
+        let offsets: Vec<String> = mock
+            .requests()
+            .iter()
+            .map(|request| request.param("offset").to_string())
+            .collect();
+        assert_eq!(offsets, ["", "2"]);
+    }
+
+    #[tokio::test]
+    async fn lowercased_api_code_is_emitted_in_the_configured_case() {
+        let mock = MockApi::scripted(vec![MockResponse::json(hcpcs_rows(
+            1,
+            &[("j1234", "Injection")],
+        ))])
+        .await;
+        let dir = scratch_dir("case_mode");
+        for (mode, expected) in [("upper", "J1234"), ("source", "j1234")] {
+            let args = test_args(&mock, &["--normalize-hcpcs-case", mode]);
+            let mut cache = HcpcsCache::open(&dir.join(format!("{mode}.sqlite"))).unwrap();
+            cache.case_mode = args.normalize_hcpcs_case;
+            resolve(&cache, &args, &["J1234"]).await;
+
+            assert_eq!(cached_rows(&cache, "J1234")[0].0, expected, "{mode}");
+            let mapping = dir.join(format!("{mode}.csv"));
+            assert_eq!(
+                exported_mapping(&cache, &mapping, true)[0].0,
+                expected,
+                "{mode}"
+            );
+        }
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn fallback_code_column_holding_npis_is_rejected() {
+        let dir = scratch_dir("fallback_npis");
+        let csv = dir.join("fallback.csv");
+        fs::write(
+            &csv,
+            "code,short_desc\n1234567893,Dr A\n1245319599,Dr B\n1003000126,Dr C\nJ1234,Injection\n",
+        )
+        .unwrap();
+
+        let err = load_local_hcpcs_fallback_records(&csv, false, 0.9).unwrap_err();
+        let message = err.to_string();
+        assert!(message.contains("1/4"), "{message}");
+        assert!(message.contains("likely isn't HCPCS"), "{message}");
+
+        // A threshold the file meets loads the valid rows.
+        let records = load_local_hcpcs_fallback_records(&csv, false, 0.25).unwrap();
+        assert_eq!(records.keys().collect::<Vec<_>>(), ["J1234"]);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,124 @@
+use anyhow::Result;
+use std::{future::Future, path::PathBuf, sync::Arc, time::Duration};
+use tokio::{
+    task::{JoinError, JoinHandle},
+    time::{Instant, MissedTickBehavior},
+};
+
+use crate::{
+    UnresolvedReportFlags, args::Args, npi::NpiInput, output::info,
+    write_unresolved_identifiers_report,
+};
+
+/// Owned copy of what `write_unresolved_identifiers_report` needs, so `--report-interval-secs`
+/// writes can run on a blocking thread while the builds borrow `args`.
+pub struct UnresolvedReportJob {
+    spending_input: PathBuf,
+    npi_list: Option<PathBuf>,
+    npi_list_column: Option<String>,
+    include_npi: bool,
+    include_hcpcs: bool,
+    npi_cache_db: PathBuf,
+    hcpcs_cache_db: PathBuf,
+    output_csv: PathBuf,
+    output_parquet: Option<PathBuf>,
+    flags: UnresolvedReportFlags,
+}
+
+impl UnresolvedReportJob {
+    pub fn new(
+        args: &Args,
+        spending_input: PathBuf,
+        npi_cache_db: PathBuf,
+        hcpcs_cache_db: PathBuf,
+        output_csv: PathBuf,
+        output_parquet: Option<PathBuf>,
+    ) -> Self {
+        Self {
+            spending_input,
+            npi_list: args.npi_list.clone(),
+            npi_list_column: args.npi_list_column.clone(),
+            include_npi: !args.hcpcs_only,
+            include_hcpcs: !args.npi_only,
+            npi_cache_db,
+            hcpcs_cache_db,
+            output_csv,
+            output_parquet,
+            flags: UnresolvedReportFlags::from_args(args),
+        }
+    }
+
+    fn write(&self) -> Result<()> {
+        let npi_input = match &self.npi_list {
+            Some(path) => NpiInput::List {
+                path,
+                column: self.npi_list_column.as_deref(),
+            },
+            None => NpiInput::Spending(&self.spending_input),
+        };
+        write_unresolved_identifiers_report(
+            self.include_npi.then_some(npi_input),
+            self.include_hcpcs.then_some(self.spending_input.as_path()),
+            &self.npi_cache_db,
+            &self.hcpcs_cache_db,
+            &self.output_csv,
+            self.output_parquet.as_deref(),
+            self.flags,
+        )
+    }
+}
+
+/// Drives `builds` to completion, rewriting the unresolved report every `interval` meanwhile.
+/// Each write runs on a blocking thread, so the lookup workers keep being polled, and reads
+/// the caches read-only. A tick that lands while the previous write is still running is
+/// skipped. A failed write only warns: the report is a convenience, the build is not.
+pub async fn with_interim_unresolved_reports<F>(
+    builds: F,
+    interval: Option<Duration>,
+    job: Arc<UnresolvedReportJob>,
+) -> Result<bool>
+where
+    F: Future<Output = Result<bool>>,
+{
+    let Some(interval) = interval else {
+        return builds.await;
+    };
+    tokio::pin!(builds);
+    let mut ticker = tokio::time::interval_at(Instant::now() + interval, interval);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Skip);
+    let mut in_flight: Option<JoinHandle<Result<()>>> = None;
+    let result = loop {
+        tokio::select! {
+            result = &mut builds => break result,
+            _ = ticker.tick() => {
+                if in_flight.as_ref().is_some_and(|handle| !handle.is_finished()) {
+                    continue;
+                }
+                if let Some(handle) = in_flight.take() {
+                    log_interim_outcome(&job, handle.await);
+                }
+                let job = Arc::clone(&job);
+                in_flight = Some(tokio::task::spawn_blocking(move || job.write()));
+            }
+        }
+    };
+    // The final report goes through the same temp file, so let a running write finish first.
+    if let Some(handle) = in_flight {
+        log_interim_outcome(&job, handle.await);
+    }
+    result
+}
+
+fn log_interim_outcome(job: &UnresolvedReportJob, outcome: Result<Result<()>, JoinError>) {
+    match outcome {
+        Ok(Ok(())) => info!(
+            "Wrote interim unresolved identifiers report {}",
+            job.output_csv.display()
+        ),
+        Ok(Err(err)) => eprintln!(
+            "Warning: failed writing interim unresolved identifiers report {}: {err:#}",
+            job.output_csv.display()
+        ),
+        Err(err) => eprintln!("Warning: interim unresolved identifiers report task failed: {err}"),
+    }
+}
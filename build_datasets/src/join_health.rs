@@ -0,0 +1,168 @@
+use anyhow::{Context, Result, bail};
+use duckdb::Connection;
+use serde::Serialize;
+use std::{fs, path::Path};
+
+use crate::common::{source_expr, sql_escape_path};
+use crate::output::info;
+
+/// Match counts for one join key (billing NPI, servicing NPI, or HCPCS code).
+#[derive(Debug, Serialize)]
+struct KeyCoverage {
+    /// Spending rows with a non-blank key.
+    rows_with_key: i64,
+    /// Of those, rows whose key has an `ok` row in the mapping CSV.
+    rows_matched: i64,
+    row_match_rate: Option<f64>,
+    /// `TOTAL_PAID` summed over `rows_with_key` / `rows_matched`.
+    paid_with_key: f64,
+    paid_matched: f64,
+    paid_match_rate: Option<f64>,
+}
+
+impl KeyCoverage {
+    fn new(rows_with_key: i64, rows_matched: i64, paid_with_key: f64, paid_matched: f64) -> Self {
+        Self {
+            rows_with_key,
+            rows_matched,
+            row_match_rate: ratio(rows_matched as f64, rows_with_key as f64),
+            paid_with_key,
+            paid_matched,
+            paid_match_rate: ratio(paid_matched, paid_with_key),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct JoinHealthReport {
+    input_path: String,
+    npi_mapping_csv: String,
+    hcpcs_mapping_csv: String,
+    rows: i64,
+    total_paid: f64,
+    billing_npi: KeyCoverage,
+    servicing_npi: KeyCoverage,
+    hcpcs: KeyCoverage,
+}
+
+fn ratio(numerator: f64, denominator: f64) -> Option<f64> {
+    (denominator != 0.0).then(|| numerator / denominator)
+}
+
+/// Row-level and paid-weighted match rates of the spending input against the NPI/HCPCS
+/// mapping CSVs, written as JSON to `output_path`.
+pub fn write_join_health_report(
+    input_path: &Path,
+    npi_mapping_csv: &Path,
+    hcpcs_mapping_csv: &Path,
+    output_path: &Path,
+) -> Result<()> {
+    for (label, path) in [
+        ("NPI mapping CSV", npi_mapping_csv),
+        ("HCPCS mapping CSV", hcpcs_mapping_csv),
+    ] {
+        if !path.exists() {
+            bail!(
+                "{label} not found at {}; run a normal build first",
+                path.display()
+            );
+        }
+    }
+
+    let conn = Connection::open_in_memory().context("Failed opening DuckDB")?;
+    let source = source_expr(input_path)?;
+    let npi_csv = sql_escape_path(npi_mapping_csv);
+    let hcpcs_csv = sql_escape_path(hcpcs_mapping_csv);
+    // HCPCS keys follow `normalize_hcpcs_code` closely enough for a join check: trimmed,
+    // uppercased, numeric-export `.0` suffix and combined `-XX` modifier dropped.
+    let query = format!(
+        r"
+        WITH src AS (
+            SELECT
+                NULLIF(TRIM(CAST(BILLING_PROVIDER_NPI_NUM AS VARCHAR)), '') AS billing_npi,
+                NULLIF(TRIM(CAST(SERVICING_PROVIDER_NPI_NUM AS VARCHAR)), '') AS servicing_npi,
+                NULLIF(
+                    regexp_replace(
+                        regexp_replace(UPPER(TRIM(CAST(HCPCS_CODE AS VARCHAR))), '\.0$', ''),
+                        '^([A-Z0-9]{{5}})\s*-\s*[A-Z0-9]{{2}}$',
+                        '\1'
+                    ),
+                    ''
+                ) AS hcpcs_code,
+                COALESCE(CAST(TOTAL_PAID AS DOUBLE), 0) AS paid
+            FROM {source}
+        ),
+        npi_ok AS (
+            SELECT DISTINCT TRIM(npi) AS npi
+            FROM read_csv('{npi_csv}', header = true, all_varchar = true)
+            WHERE status = 'ok'
+        ),
+        hcpcs_ok AS (
+            SELECT DISTINCT UPPER(TRIM(hcpcs_code)) AS hcpcs_code
+            FROM read_csv('{hcpcs_csv}', header = true, all_varchar = true)
+            WHERE status = 'ok'
+        )
+        SELECT
+            COUNT(*),
+            COALESCE(SUM(paid), 0),
+            COUNT(src.billing_npi),
+            COUNT(b.npi),
+            COALESCE(SUM(paid) FILTER (WHERE src.billing_npi IS NOT NULL), 0),
+            COALESCE(SUM(paid) FILTER (WHERE b.npi IS NOT NULL), 0),
+            COUNT(src.servicing_npi),
+            COUNT(s.npi),
+            COALESCE(SUM(paid) FILTER (WHERE src.servicing_npi IS NOT NULL), 0),
+            COALESCE(SUM(paid) FILTER (WHERE s.npi IS NOT NULL), 0),
+            COUNT(src.hcpcs_code),
+            COUNT(h.hcpcs_code),
+            COALESCE(SUM(paid) FILTER (WHERE src.hcpcs_code IS NOT NULL), 0),
+            COALESCE(SUM(paid) FILTER (WHERE h.hcpcs_code IS NOT NULL), 0)
+        FROM src
+        LEFT JOIN npi_ok b ON b.npi = src.billing_npi
+        LEFT JOIN npi_ok s ON s.npi = src.servicing_npi
+        LEFT JOIN hcpcs_ok h ON h.hcpcs_code = src.hcpcs_code
+        "
+    );
+
+    let report = conn
+        .query_row(&query, [], |row| {
+            Ok(JoinHealthReport {
+                input_path: input_path.display().to_string(),
+                npi_mapping_csv: npi_mapping_csv.display().to_string(),
+                hcpcs_mapping_csv: hcpcs_mapping_csv.display().to_string(),
+                rows: row.get(0)?,
+                total_paid: row.get(1)?,
+                billing_npi: KeyCoverage::new(row.get(2)?, row.get(3)?, row.get(4)?, row.get(5)?),
+                servicing_npi: KeyCoverage::new(row.get(6)?, row.get(7)?, row.get(8)?, row.get(9)?),
+                hcpcs: KeyCoverage::new(row.get(10)?, row.get(11)?, row.get(12)?, row.get(13)?),
+            })
+        })
+        .context("Failed computing join health")?;
+
+    if let Some(parent) = output_path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed creating {}", parent.display()))?;
+    }
+    let json =
+        serde_json::to_string_pretty(&report).context("Failed serializing join health report")?;
+    fs::write(output_path, json + "\n")
+        .with_context(|| format!("Failed writing {}", output_path.display()))?;
+
+    let pct = |rate: Option<f64>| {
+        rate.map(|r| format!("{:.2}%", r * 100.0))
+            .unwrap_or_else(|| "n/a".to_string())
+    };
+    for (label, coverage) in [
+        ("billing NPI", &report.billing_npi),
+        ("servicing NPI", &report.servicing_npi),
+        ("HCPCS", &report.hcpcs),
+    ] {
+        info!(
+            "Join health {label}: rows {} paid {}",
+            pct(coverage.row_match_rate),
+            pct(coverage.paid_match_rate)
+        );
+    }
+    info!("Wrote join health report {}", output_path.display());
+    Ok(())
+}
@@ -1,16 +1,32 @@
 mod args;
+mod bulk_scan;
+mod cache_maintenance;
 mod common;
 mod constants;
+mod export_errors;
+mod failed_bodies;
 mod hcpcs;
+mod interim_report;
+mod join_health;
+mod mock_api;
+mod normalize;
 mod npi;
 mod null_audit;
+mod output;
 mod parquet_writer;
+mod run_config;
+mod run_summary;
+mod schema_check;
+mod selftest;
+mod spending_schema;
+mod taxonomy;
 mod triage;
 mod upload;
 
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, bail};
 use clap::Parser;
 use csv::Writer;
+use duckdb::Connection;
 use indicatif::MultiProgress;
 use reqwest::Client;
 use std::{
@@ -20,38 +36,85 @@ use std::{
         Arc,
         atomic::{AtomicBool, Ordering},
     },
+    time::Duration,
 };
 
 use args::Args;
+use cache_maintenance::{scratch_cache_copy, vacuum_cache_dbs};
 use common::{
-    delete_if_exists, download_file, file_name_from_url, install_ctrlc_handler, new_api_run_id,
-    project_root,
+    LookupBudget, dataset_year_from_text, delete_if_exists, download_file, file_name_from_url,
+    install_ctrlc_handler, load_ca_certificates, new_api_run_id, now_unix_seconds, project_root,
+    sql_escape_path,
 };
+use export_errors::ExportFailures;
 use hcpcs::{
-    backfill_hcpcs_api_responses_from_legacy_parquet, build_hcpcs_mapping,
-    collect_unresolved_hcpcs, export_hcpcs_api_responses_parquet, is_hcpcs_dataset_complete,
+    HCPCS_PARQUET_COLUMNS, backfill_hcpcs_api_responses_from_legacy_parquet, build_hcpcs_mapping,
+    collect_unresolved_hcpcs, export_hcpcs_api_responses_parquet, export_hcpcs_cache_parquet,
+    export_hcpcs_mapping_csv, is_hcpcs_dataset_complete,
 };
+use interim_report::{UnresolvedReportJob, with_interim_unresolved_reports};
+use join_health::write_join_health_report;
+use normalize::npi_luhn_valid;
 use npi::{
+    BulkScanOptions, NPI_PARQUET_COLUMNS, NpiInput, NppesBulkMode,
     backfill_npi_api_responses_from_legacy_parquet, build_npi_mapping, collect_unresolved_npis,
-    export_npi_api_responses_parquet, is_npi_dataset_complete,
+    export_full_nppes_parquet, export_npi_api_responses_parquet, is_npi_dataset_complete,
 };
 use null_audit::generate_and_update_hf_docs;
+use output::info;
+use parquet_writer::{ParquetOutputOptions, resolve_column_projection};
+use run_config::write_run_config;
+use run_summary::write_run_summary;
+use schema_check::check_spending_schema;
+use taxonomy::load_taxonomy_reference;
 use triage::write_unresolved_identifier_triage;
 use upload::maybe_upload_outputs;
 
+/// Run flags that change what the unresolved identifiers report lists.
+#[derive(Debug, Clone, Copy)]
+struct UnresolvedReportFlags {
+    /// `--verify-luhn-in-report`: add the NPI check-digit columns.
+    include_luhn: bool,
+    /// `--exclude-npi-types` is active, so `skipped` NPIs are settled rather than pending.
+    skipped_is_resolved: bool,
+}
+
+impl UnresolvedReportFlags {
+    fn from_args(args: &Args) -> Self {
+        Self {
+            include_luhn: args.verify_luhn_in_report,
+            skipped_is_resolved: !args.exclude_npi_types.is_empty(),
+        }
+    }
+}
+
+/// `hcpcs_input_path` is `None` under `--npi-only` and `npi_input` is `None` under
+/// `--hcpcs-only`, leaving that dataset out of the report.
 fn write_unresolved_identifiers_report(
-    input_path: &Path,
+    npi_input: Option<NpiInput<'_>>,
+    hcpcs_input_path: Option<&Path>,
     npi_cache_db: &Path,
     hcpcs_cache_db: &Path,
     output_csv: &Path,
+    output_parquet: Option<&Path>,
+    flags: UnresolvedReportFlags,
 ) -> Result<()> {
+    let include_luhn = flags.include_luhn;
     if let Some(parent) = output_csv.parent() {
         fs::create_dir_all(parent)
             .with_context(|| format!("Failed creating {}", parent.display()))?;
     }
 
-    let npis = collect_unresolved_npis(input_path, npi_cache_db)?;
-    let hcpcs = collect_unresolved_hcpcs(input_path, hcpcs_cache_db)?;
+    let npis = match npi_input {
+        Some(npi_input) => {
+            collect_unresolved_npis(npi_input, npi_cache_db, flags.skipped_is_resolved)?
+        }
+        None => Vec::new(),
+    };
+    let hcpcs = match hcpcs_input_path {
+        Some(input_path) => collect_unresolved_hcpcs(input_path, hcpcs_cache_db)?,
+        None => Vec::new(),
+    };
 
     let file_name = output_csv
         .file_name()
@@ -61,14 +124,18 @@ fn write_unresolved_identifiers_report(
 
     let mut writer = Writer::from_path(&tmp_path)
         .with_context(|| format!("Failed creating unresolved report {}", tmp_path.display()))?;
+    let mut header = vec![
+        "identifier_type",
+        "identifier",
+        "status",
+        "error_message",
+        "fetched_at_unix",
+    ];
+    if include_luhn {
+        header.push("luhn_valid");
+    }
     writer
-        .write_record([
-            "identifier_type",
-            "identifier",
-            "status",
-            "error_message",
-            "fetched_at_unix",
-        ])
+        .write_record(&header)
         .context("Failed writing unresolved report header")?;
 
     for item in npis {
@@ -76,14 +143,22 @@ fn write_unresolved_identifiers_report(
             .fetched_at_unix
             .map(|v| v.to_string())
             .unwrap_or_default();
+        let mut record = vec![
+            "npi",
+            item.npi.as_str(),
+            item.status.as_str(),
+            item.error_message.as_deref().unwrap_or(""),
+            fetched_at.as_str(),
+        ];
+        if include_luhn {
+            record.push(if npi_luhn_valid(item.npi.trim()) {
+                "true"
+            } else {
+                "false"
+            });
+        }
         writer
-            .write_record([
-                "npi",
-                item.npi.as_str(),
-                item.status.as_str(),
-                item.error_message.as_deref().unwrap_or(""),
-                fetched_at.as_str(),
-            ])
+            .write_record(&record)
             .context("Failed writing unresolved NPI record")?;
     }
 
@@ -92,14 +167,18 @@ fn write_unresolved_identifiers_report(
             .fetched_at_unix
             .map(|v| v.to_string())
             .unwrap_or_default();
+        let mut record = vec![
+            "hcpcs",
+            item.hcpcs_code.as_str(),
+            item.status.as_str(),
+            item.error_message.as_deref().unwrap_or(""),
+            fetched_at.as_str(),
+        ];
+        if include_luhn {
+            record.push("");
+        }
         writer
-            .write_record([
-                "hcpcs",
-                item.hcpcs_code.as_str(),
-                item.status.as_str(),
-                item.error_message.as_deref().unwrap_or(""),
-                fetched_at.as_str(),
-            ])
+            .write_record(&record)
             .context("Failed writing unresolved HCPCS record")?;
     }
 
@@ -113,12 +192,224 @@ fn write_unresolved_identifiers_report(
             output_csv.display()
         )
     })?;
+
+    if let Some(output_parquet) = output_parquet {
+        write_unresolved_report_parquet(output_csv, output_parquet, include_luhn)?;
+    }
+    Ok(())
+}
+
+/// Identifiers that an offline run could not answer from cache, bulk, or fallback: `error`
+/// rows and identifiers never looked up. `not_found` and `permanently_failed` are settled
+/// outcomes and do not count. Returns an error message when there are any.
+fn offline_unresolved_summary(
+    npi_input: Option<NpiInput<'_>>,
+    hcpcs_input_path: Option<&Path>,
+    npi_cache_db: &Path,
+    hcpcs_cache_db: &Path,
+    skipped_is_resolved: bool,
+) -> Result<Option<String>> {
+    const SAMPLE: usize = 10;
+    let unanswered = |status: &str| status == "error" || status == "missing_cache";
+    let npis: Vec<String> = match npi_input {
+        Some(npi_input) => collect_unresolved_npis(npi_input, npi_cache_db, skipped_is_resolved)?
+            .into_iter()
+            .filter(|item| unanswered(&item.status))
+            .map(|item| item.npi)
+            .collect(),
+        None => Vec::new(),
+    };
+    let hcpcs: Vec<String> = match hcpcs_input_path {
+        Some(input_path) => collect_unresolved_hcpcs(input_path, hcpcs_cache_db)?
+            .into_iter()
+            .filter(|item| unanswered(&item.status))
+            .map(|item| item.hcpcs_code)
+            .collect(),
+        None => Vec::new(),
+    };
+    if npis.is_empty() && hcpcs.is_empty() {
+        return Ok(None);
+    }
+    let sample = |ids: &[String]| {
+        let mut shown = ids
+            .iter()
+            .take(SAMPLE)
+            .cloned()
+            .collect::<Vec<_>>()
+            .join(", ");
+        if ids.len() > SAMPLE {
+            shown.push_str(", ...");
+        }
+        shown
+    };
+    Ok(Some(format!(
+        "--offline build is incomplete: {} NPI(s) [{}] and {} HCPCS code(s) [{}] have no cached answer. \
+Run with API access to resolve them, or pass --allow-unresolved for an intentionally partial build.",
+        npis.len(),
+        sample(&npis),
+        hcpcs.len(),
+        sample(&hcpcs)
+    )))
+}
+
+/// Output and cache locations used by [`resume_parquet_exports`].
+struct ResumePaths<'a> {
+    npi_cache_db: &'a Path,
+    npi_parquet: &'a Path,
+    hcpcs_cache_db: &'a Path,
+    hcpcs_parquet: &'a Path,
+    hcpcs_fallback_csv: &'a Path,
+    nppes_monthly_dir: &'a Path,
+    nppes_weekly_dir: &'a Path,
+}
+
+/// `--resume-parquet`: rewrite both resolved parquets from the (already complete) caches and
+/// bulk files without classification or API calls. `npi_input` is `None` under `--hcpcs-only`.
+fn resume_parquet_exports(
+    args: &Args,
+    npi_input: Option<NpiInput<'_>>,
+    paths: &ResumePaths<'_>,
+    api_run_id: &str,
+    shutdown_requested: &Arc<AtomicBool>,
+) -> Result<()> {
+    info!(
+        "--resume-parquet: pure re-export from cache and NPPES bulk files (no classification, no API calls)."
+    );
+    if let Some(npi_input) = npi_input {
+        export_npi_api_responses_parquet(
+            npi_input,
+            paths.npi_cache_db,
+            paths.npi_parquet,
+            api_run_id,
+            shutdown_requested,
+            paths.nppes_monthly_dir,
+            paths.nppes_weekly_dir,
+            NppesBulkMode::from_args(args),
+            ParquetOutputOptions {
+                columns: args.npi_output_columns.as_deref(),
+                content_hash: args.content_hash,
+                sort_output: args.sort_output,
+                ndjson: args.emit_ndjson,
+                compression: args.parquet_compression,
+            },
+            &args.exclude_npi_types,
+            BulkScanOptions::from_args(args),
+        )?;
+    }
+    if args.npi_only || shutdown_requested.load(Ordering::SeqCst) {
+        return Ok(());
+    }
+    export_hcpcs_api_responses_parquet(
+        paths.hcpcs_cache_db,
+        paths.hcpcs_parquet,
+        paths.hcpcs_fallback_csv,
+        api_run_id,
+        args.normalize_hcpcs_case,
+        ParquetOutputOptions {
+            columns: args.hcpcs_output_columns.as_deref(),
+            content_hash: args.content_hash,
+            sort_output: args.sort_output,
+            ndjson: args.emit_ndjson,
+            compression: args.parquet_compression,
+        },
+    )?;
+    info!(
+        "Wrote HCPCS resolved identifier dataset {}",
+        paths.hcpcs_parquet.display()
+    );
+    Ok(())
+}
+
+fn print_npi_skip_reason(hcpcs_only: bool, mapping_csv: &Path, api_responses_parquet: &Path) {
+    if hcpcs_only {
+        info!("--hcpcs-only set; skipping NPI build.");
+    } else {
+        info!(
+            "NPI dataset already built (mapping: {}, resolved dataset: {}). Skipping NPI build (pass --rebuild-map or --reset-map to rebuild).",
+            mapping_csv.display(),
+            api_responses_parquet.display()
+        );
+    }
+}
+
+fn print_hcpcs_skip_reason(npi_only: bool, mapping_csv: &Path, api_responses_parquet: &Path) {
+    if npi_only {
+        info!("--npi-only set; skipping HCPCS build.");
+    } else {
+        info!(
+            "HCPCS dataset already built (mapping: {}, resolved dataset: {}). Skipping HCPCS build (cache coverage is complete, including local fallback where applicable; pass --rebuild-map or --reset-map to rebuild).",
+            mapping_csv.display(),
+            api_responses_parquet.display()
+        );
+    }
+}
+
+/// Converts the unresolved CSV with DuckDB so quoted multi-line error messages survive intact.
+fn write_unresolved_report_parquet(
+    report_csv: &Path,
+    output_parquet: &Path,
+    include_luhn: bool,
+) -> Result<()> {
+    let file_name = output_parquet
+        .file_name()
+        .and_then(|x| x.to_str())
+        .unwrap_or("unresolved_identifiers.parquet");
+    let tmp_path = output_parquet.with_file_name(format!("{file_name}.tmp"));
+    delete_if_exists(&tmp_path)?;
+
+    let conn = Connection::open_in_memory().context("Failed opening DuckDB")?;
+    let query = format!(
+        "
+        COPY (
+            SELECT * FROM read_csv(
+                '{}',
+                header = true,
+                columns = {{
+                    'identifier_type': 'VARCHAR',
+                    'identifier': 'VARCHAR',
+                    'status': 'VARCHAR',
+                    'error_message': 'VARCHAR',
+                    'fetched_at_unix': 'BIGINT'{}
+                }}
+            )
+        ) TO '{}' (FORMAT parquet)
+        ",
+        sql_escape_path(report_csv),
+        if include_luhn {
+            ",\n                    'luhn_valid': 'BOOLEAN'"
+        } else {
+            ""
+        },
+        sql_escape_path(&tmp_path)
+    );
+    conn.execute_batch(&query).with_context(|| {
+        format!(
+            "Failed converting unresolved report {} to parquet",
+            report_csv.display()
+        )
+    })?;
+    fs::rename(&tmp_path, output_parquet).with_context(|| {
+        format!(
+            "Failed moving unresolved report parquet {} to {}",
+            tmp_path.display(),
+            output_parquet.display()
+        )
+    })?;
+    info!(
+        "Wrote unresolved identifiers parquet {}",
+        output_parquet.display()
+    );
     Ok(())
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let args = Args::parse();
+    let mut args = Args::parse();
+    args.skip_api |= args.offline;
+    output::set_quiet(args.quiet);
+    if args.selftest {
+        return selftest::run_selftest().await;
+    }
 
     let project_dir = project_root();
     let data_dir = project_dir.join("data");
@@ -133,9 +424,33 @@ async fn main() -> Result<()> {
         .nppes_weekly_dir
         .clone()
         .unwrap_or_else(|| raw_nppes_dir.join("weekly"));
-    let mappings_dir = data_dir.join("mappings");
     let cache_dir = data_dir.join("cache");
-    let output_dir = data_dir.join("output");
+
+    let default_input_path = raw_medicaid_dir.join(file_name_from_url(&args.input_url)?);
+    let input_path = args.input_path.clone().unwrap_or(default_input_path);
+
+    // Per-period outputs live under `year=YYYY/`; raw inputs and caches stay shared.
+    let period_dir = if args.partition_by_year || args.dataset_period.is_some() {
+        let year = match &args.dataset_period {
+            Some(year) => Some(year.clone()),
+            None if args.input_path.is_some() => input_path
+                .file_name()
+                .and_then(|name| dataset_year_from_text(&name.to_string_lossy())),
+            None => dataset_year_from_text(&args.input_url),
+        };
+        let Some(year) = year else {
+            bail!(
+                "--partition-by-year could not find a year in {}; pass --dataset-period <YYYY>",
+                input_path.display()
+            );
+        };
+        info!("Writing outputs for dataset period year={year}");
+        data_dir.join(format!("year={year}"))
+    } else {
+        data_dir.clone()
+    };
+    let mappings_dir = period_dir.join("mappings");
+    let output_dir = period_dir.join("output");
 
     fs::create_dir_all(&raw_medicaid_dir)
         .with_context(|| format!("Failed creating {}", raw_medicaid_dir.display()))?;
@@ -154,9 +469,6 @@ async fn main() -> Result<()> {
     fs::create_dir_all(&output_dir)
         .with_context(|| format!("Failed creating {}", output_dir.display()))?;
 
-    let default_input_path = raw_medicaid_dir.join(file_name_from_url(&args.input_url)?);
-    let input_path = args.input_path.clone().unwrap_or(default_input_path);
-
     let npi_mapping_csv = args
         .mapping_csv
         .clone()
@@ -190,21 +502,154 @@ async fn main() -> Result<()> {
     let unresolved_report_csv = args
         .unresolved_report_csv
         .clone()
-        .unwrap_or_else(|| data_dir.join("unresolved_identifiers.csv"));
-    let api_run_id = new_api_run_id();
+        .unwrap_or_else(|| period_dir.join("unresolved_identifiers.csv"));
+    let unresolved_report_parquet = args
+        .emit_unresolved_parquet
+        .then(|| unresolved_report_csv.with_extension("parquet"));
+    let api_run_id = args.api_run_id.clone().unwrap_or_else(new_api_run_id);
+    let started_at_unix = now_unix_seconds();
+    let run_summary_json = args
+        .run_summary_json
+        .clone()
+        .unwrap_or_else(|| output_dir.join("run_summary.json"));
+    resolve_column_projection(NPI_PARQUET_COLUMNS, args.npi_output_columns.as_deref())
+        .context("Invalid --npi-output-columns")?;
+    resolve_column_projection(HCPCS_PARQUET_COLUMNS, args.hcpcs_output_columns.as_deref())
+        .context("Invalid --hcpcs-output-columns")?;
 
     if args.null_check {
         generate_and_update_hf_docs(&npi_api_responses_parquet, &hcpcs_api_responses_parquet)?;
         return Ok(());
     }
 
-    let client = Client::builder()
+    if args.cache_vacuum {
+        vacuum_cache_dbs(&npi_cache_db, &hcpcs_cache_db)?;
+        return Ok(());
+    }
+
+    if let Some(report_path) = &args.join_health_report {
+        write_join_health_report(
+            &input_path,
+            &npi_mapping_csv,
+            &hcpcs_mapping_csv,
+            report_path,
+        )?;
+        return Ok(());
+    }
+
+    if let Some(export_path) = &args.export_hcpcs_cache {
+        let rows =
+            export_hcpcs_cache_parquet(&hcpcs_cache_db, export_path, args.parquet_compression)?;
+        info!("Wrote {rows} HCPCS cache rows to {}", export_path.display());
+        return Ok(());
+    }
+
+    if args.recompute_hcpcs_selection {
+        export_hcpcs_mapping_csv(
+            &hcpcs_cache_db,
+            &hcpcs_mapping_csv,
+            args.normalize_hcpcs_case,
+            args.hcpcs_mapping_one_row_per_code,
+        )?;
+        info!("Wrote HCPCS mapping CSV {}", hcpcs_mapping_csv.display());
+        if args.recompute_hcpcs_parquet {
+            export_hcpcs_api_responses_parquet(
+                &hcpcs_cache_db,
+                &hcpcs_api_responses_parquet,
+                &hcpcs_fallback_csv,
+                &api_run_id,
+                args.normalize_hcpcs_case,
+                ParquetOutputOptions {
+                    columns: args.hcpcs_output_columns.as_deref(),
+                    content_hash: args.content_hash,
+                    sort_output: args.sort_output,
+                    ndjson: args.emit_ndjson,
+                    compression: args.parquet_compression,
+                },
+            )?;
+            info!(
+                "Wrote HCPCS resolved identifier dataset {}",
+                hcpcs_api_responses_parquet.display()
+            );
+        }
+        return Ok(());
+    }
+
+    if !args.dry_run {
+        write_run_config(&args, &api_run_id, &output_dir.join("run_config.json"))?;
+    }
+    let taxonomy_reference_csv = args.taxonomy_reference_csv.clone().unwrap_or_else(|| {
+        data_dir
+            .join("manually_cleaned")
+            .join("nucc")
+            .join("nucc_taxonomy.csv")
+    });
+    load_taxonomy_reference(&taxonomy_reference_csv)?;
+
+    let mut client_builder = Client::builder()
         .user_agent("medicaid-provider-spending-mappings/0.4")
+        .tls_version_min(args.min_tls_version)
+        .gzip(true)
+        .brotli(true)
+        .connect_timeout(Duration::from_secs(args.connect_timeout_secs));
+    if let Some(ca_cert) = &args.ca_cert {
+        for cert in load_ca_certificates(ca_cert).context("Invalid --ca-cert")? {
+            client_builder = client_builder.add_root_certificate(cert);
+        }
+        info!(
+            "Trusting additional root certificate(s) from {}",
+            ca_cert.display()
+        );
+    }
+    if let Some(max_idle) = args.pool_max_idle_per_host {
+        client_builder = client_builder.pool_max_idle_per_host(max_idle);
+    }
+    if args.http2_prior_knowledge {
+        client_builder = client_builder.http2_prior_knowledge();
+    }
+    if let Some(secs) = args.tcp_keepalive_secs {
+        client_builder = client_builder.tcp_keepalive(Duration::from_secs(secs));
+    }
+    if args.danger_accept_invalid_certs {
+        eprintln!(
+            "WARNING: --danger-accept-invalid-certs disables TLS certificate verification; do not use outside development."
+        );
+        client_builder = client_builder.tls_danger_accept_invalid_certs(true);
+    }
+    let client = client_builder
         .build()
         .context("Failed creating HTTP client")?;
 
     let shutdown_requested = Arc::new(AtomicBool::new(false));
+    let export_failures = ExportFailures::new(args.continue_on_export_error);
+    let lookup_budget = LookupBudget::new(args.max_new_lookups_total);
     install_ctrlc_handler(Arc::clone(&shutdown_requested));
+    if let Some(dir) = &args.debug_dump_failed_bodies {
+        failed_bodies::enable_failed_body_dumps(dir)?;
+    }
+
+    if !args.limit_bulk_to_dataset_npis {
+        if args.dry_run {
+            bail!("--dry-run has no plan to print with --limit-bulk-to-dataset-npis=false");
+        }
+        if args.hcpcs_only {
+            bail!(
+                "--hcpcs-only skips the NPI export that --limit-bulk-to-dataset-npis=false builds"
+            );
+        }
+        let output_path = args
+            .npi_api_responses_parquet
+            .clone()
+            .unwrap_or_else(|| output_dir.join("nppes_full.parquet"));
+        return export_full_nppes_parquet(
+            &args,
+            &output_path,
+            &api_run_id,
+            &shutdown_requested,
+            &nppes_monthly_dir,
+            &nppes_weekly_dir,
+        );
+    }
 
     if args.reset_map {
         delete_if_exists(&npi_mapping_csv)?;
@@ -226,12 +671,13 @@ async fn main() -> Result<()> {
                 .join("hcpcs_api_reference.parquet"),
         )?;
         delete_if_exists(&unresolved_report_csv)?;
-        println!(
+        delete_if_exists(&unresolved_report_csv.with_extension("parquet"))?;
+        info!(
             "Reset mapping state (deleted NPI + HCPCS mappings, cache DBs, and API response datasets)."
         );
     }
 
-    if !args.reset_map {
+    if !args.reset_map && !args.dry_run {
         // Migration path: older runs wrote append-only API request logs under data/reference/**.
         // If present, import them into the new cache-backed, deduped API response tables so that
         // `data/output/{npi,hcpcs}.parquet` can be generated without re-querying the APIs.
@@ -242,13 +688,13 @@ async fn main() -> Result<()> {
         if legacy_npi_parquet.exists() {
             match backfill_npi_api_responses_from_legacy_parquet(&npi_cache_db, &legacy_npi_parquet)
             {
-                Ok(imported) if imported > 0 => println!(
+                Ok(imported) if imported > 0 => info!(
                     "Imported {} NPI API response rows from legacy parquet {}",
                     imported,
                     legacy_npi_parquet.display()
                 ),
                 Ok(_) => {}
-                Err(err) => println!(
+                Err(err) => eprintln!(
                     "Warning: failed importing legacy NPI API response parquet {}: {err}",
                     legacy_npi_parquet.display()
                 ),
@@ -263,13 +709,13 @@ async fn main() -> Result<()> {
                 &hcpcs_cache_db,
                 &legacy_hcpcs_parquet,
             ) {
-                Ok(imported) if imported > 0 => println!(
+                Ok(imported) if imported > 0 => info!(
                     "Imported {} HCPCS API response rows from legacy parquet {}",
                     imported,
                     legacy_hcpcs_parquet.display()
                 ),
                 Ok(_) => {}
-                Err(err) => println!(
+                Err(err) => eprintln!(
                     "Warning: failed importing legacy HCPCS API response parquet {}: {err}",
                     legacy_hcpcs_parquet.display()
                 ),
@@ -277,23 +723,104 @@ async fn main() -> Result<()> {
         }
     }
 
-    if !input_path.exists() {
-        println!(
+    let npi_input = NpiInput::from_args(&args, &input_path);
+    let hcpcs_input_path = (!args.npi_only).then_some(input_path.as_path());
+    let report_npi_input = (!args.hcpcs_only).then_some(npi_input);
+    if let Some(npi_list) = &args.npi_list {
+        if !npi_list.exists() {
+            bail!("NPI list not found at {}", npi_list.display());
+        }
+        info!(
+            "Using NPI list {} (spending input not read)",
+            npi_list.display()
+        );
+    } else if !input_path.exists() && args.offline {
+        bail!(
+            "Input file missing at {} and --offline forbids downloading it from {}",
+            input_path.display(),
+            args.input_url
+        );
+    } else if !input_path.exists() {
+        info!(
             "Input file missing at {}. Downloading from {}",
             input_path.display(),
             args.input_url
         );
         download_file(&client, &args.input_url, &input_path).await?;
     } else {
-        println!("Using input file {}", input_path.display());
+        info!("Using input file {}", input_path.display());
+    }
+    if args.strict_schema && args.npi_list.is_none() {
+        check_spending_schema(&input_path)?;
     }
 
-    let npi_dataset_done = if args.reset_map || args.rebuild_map {
+    if args.dry_run {
+        // Plans are computed against throwaway cache copies so nothing under data/ changes.
+        let scratch_dir =
+            std::env::temp_dir().join(format!("build_datasets_dry_run_{}", std::process::id()));
+        let plans = async {
+            if !args.hcpcs_only {
+                let scratch_npi_dir = scratch_dir.join("npi");
+                build_npi_mapping(
+                    &args,
+                    &client,
+                    npi_input,
+                    &scratch_cache_copy(&npi_cache_db, &scratch_npi_dir)?,
+                    &scratch_npi_dir.join("npi_provider_mapping.csv"),
+                    &scratch_npi_dir.join("npi.parquet"),
+                    &api_run_id,
+                    &export_failures,
+                    &lookup_budget,
+                    None,
+                    Arc::clone(&shutdown_requested),
+                    &nppes_monthly_dir,
+                    &nppes_weekly_dir,
+                )
+                .await?;
+            }
+            if !args.npi_only {
+                let scratch_hcpcs_dir = scratch_dir.join("hcpcs");
+                build_hcpcs_mapping(
+                    &args,
+                    &client,
+                    &input_path,
+                    &scratch_cache_copy(&hcpcs_cache_db, &scratch_hcpcs_dir)?,
+                    &scratch_hcpcs_dir.join("hcpcs_code_mapping.csv"),
+                    &scratch_hcpcs_dir.join("hcpcs.parquet"),
+                    &hcpcs_fallback_csv,
+                    &api_run_id,
+                    &export_failures,
+                    &lookup_budget,
+                    None,
+                    Arc::clone(&shutdown_requested),
+                )
+                .await?;
+            }
+            anyhow::Ok(())
+        }
+        .await;
+        let _ = fs::remove_dir_all(&scratch_dir);
+        plans?;
+        info!("Dry run complete: no API calls made; caches, mappings, and parquets untouched.");
+        return Ok(());
+    }
+
+    let npi_dataset_done = if args.hcpcs_only {
+        true
+    } else if args.reset_map || args.rebuild_map {
         false
     } else {
-        is_npi_dataset_complete(&input_path, &npi_cache_db, &npi_mapping_csv)?
+        is_npi_dataset_complete(
+            npi_input,
+            &npi_cache_db,
+            &npi_mapping_csv,
+            args.npi_cache_ttl_days,
+            !args.exclude_npi_types.is_empty(),
+        )?
     };
-    let hcpcs_dataset_done = if args.reset_map || args.rebuild_map {
+    let hcpcs_dataset_done = if args.npi_only {
+        true
+    } else if args.reset_map || args.rebuild_map {
         false
     } else {
         is_hcpcs_dataset_complete(
@@ -301,31 +828,107 @@ async fn main() -> Result<()> {
             &hcpcs_cache_db,
             &hcpcs_mapping_csv,
             &hcpcs_fallback_csv,
+            args.fallback_validation_threshold,
         )?
     };
-    let should_build_npi_map = args.reset_map || args.rebuild_map || !npi_dataset_done;
-    let should_build_hcpcs_map = args.reset_map || args.rebuild_map || !hcpcs_dataset_done;
-
-    let mut interrupted = false;
-    match (should_build_npi_map, should_build_hcpcs_map) {
-        (true, true) => {
-            println!("Building NPI and HCPCS mappings in parallel...");
-            let progress_hub = Arc::new(MultiProgress::new());
-            let (npi_interrupted, hcpcs_interrupted) = tokio::try_join!(
-                build_npi_mapping(
+    if args.resume_parquet {
+        if !npi_dataset_done || !hcpcs_dataset_done {
+            bail!(
+                "--resume-parquet needs complete cache coverage, but the {} cache does not cover the input; rerun without --resume-parquet",
+                if npi_dataset_done { "HCPCS" } else { "NPI" }
+            );
+        }
+        return resume_parquet_exports(
+            &args,
+            report_npi_input,
+            &ResumePaths {
+                npi_cache_db: &npi_cache_db,
+                npi_parquet: &npi_api_responses_parquet,
+                hcpcs_cache_db: &hcpcs_cache_db,
+                hcpcs_parquet: &hcpcs_api_responses_parquet,
+                hcpcs_fallback_csv: &hcpcs_fallback_csv,
+                nppes_monthly_dir: &nppes_monthly_dir,
+                nppes_weekly_dir: &nppes_weekly_dir,
+            },
+            &api_run_id,
+            &shutdown_requested,
+        );
+    }
+
+    let should_build_npi_map =
+        !args.hcpcs_only && (args.reset_map || args.rebuild_map || !npi_dataset_done);
+    let should_build_hcpcs_map =
+        !args.npi_only && (args.reset_map || args.rebuild_map || !hcpcs_dataset_done);
+
+    let builds = async {
+        let mut interrupted = false;
+        match (should_build_npi_map, should_build_hcpcs_map) {
+            (true, true) => {
+                info!("Building NPI and HCPCS mappings in parallel...");
+                let progress_hub = Arc::new(MultiProgress::new());
+                let (npi_interrupted, hcpcs_interrupted) = tokio::try_join!(
+                    build_npi_mapping(
+                        &args,
+                        &client,
+                        npi_input,
+                        &npi_cache_db,
+                        &npi_mapping_csv,
+                        &npi_api_responses_parquet,
+                        &api_run_id,
+                        &export_failures,
+                        &lookup_budget,
+                        Some(Arc::clone(&progress_hub)),
+                        Arc::clone(&shutdown_requested),
+                        &nppes_monthly_dir,
+                        &nppes_weekly_dir,
+                    ),
+                    build_hcpcs_mapping(
+                        &args,
+                        &client,
+                        &input_path,
+                        &hcpcs_cache_db,
+                        &hcpcs_mapping_csv,
+                        &hcpcs_api_responses_parquet,
+                        &hcpcs_fallback_csv,
+                        &api_run_id,
+                        &export_failures,
+                        &lookup_budget,
+                        Some(Arc::clone(&progress_hub)),
+                        Arc::clone(&shutdown_requested),
+                    ),
+                )?;
+                interrupted = npi_interrupted || hcpcs_interrupted;
+            }
+            (true, false) => {
+                interrupted = build_npi_mapping(
                     &args,
                     &client,
-                    &input_path,
+                    npi_input,
                     &npi_cache_db,
                     &npi_mapping_csv,
                     &npi_api_responses_parquet,
                     &api_run_id,
-                    Some(Arc::clone(&progress_hub)),
+                    &export_failures,
+                    &lookup_budget,
+                    None,
                     Arc::clone(&shutdown_requested),
                     &nppes_monthly_dir,
                     &nppes_weekly_dir,
-                ),
-                build_hcpcs_mapping(
+                )
+                .await?;
+                print_hcpcs_skip_reason(
+                    args.npi_only,
+                    &hcpcs_mapping_csv,
+                    &hcpcs_api_responses_parquet,
+                );
+            }
+            (false, true) => {
+                print_npi_skip_reason(
+                    args.hcpcs_only,
+                    &npi_mapping_csv,
+                    &npi_api_responses_parquet,
+                );
+                interrupted = build_hcpcs_mapping(
                     &args,
                     &client,
                     &input_path,
@@ -334,150 +937,175 @@ async fn main() -> Result<()> {
                     &hcpcs_api_responses_parquet,
                     &hcpcs_fallback_csv,
                     &api_run_id,
-                    Some(Arc::clone(&progress_hub)),
+                    &export_failures,
+                    &lookup_budget,
+                    None,
                     Arc::clone(&shutdown_requested),
-                ),
-            )?;
-            interrupted = npi_interrupted || hcpcs_interrupted;
-        }
-        (true, false) => {
-            interrupted = build_npi_mapping(
-                &args,
-                &client,
-                &input_path,
-                &npi_cache_db,
-                &npi_mapping_csv,
-                &npi_api_responses_parquet,
-                &api_run_id,
-                None,
-                Arc::clone(&shutdown_requested),
-                &nppes_monthly_dir,
-                &nppes_weekly_dir,
-            )
-            .await?;
-            println!(
-                "HCPCS dataset already built (mapping: {}, resolved dataset: {}). Skipping HCPCS build (cache coverage is complete, including local fallback where applicable; pass --rebuild-map or --reset-map to rebuild).",
-                hcpcs_mapping_csv.display(),
-                hcpcs_api_responses_parquet.display()
-            );
-        }
-        (false, true) => {
-            println!(
-                "NPI dataset already built (mapping: {}, resolved dataset: {}). Skipping NPI build (pass --rebuild-map or --reset-map to rebuild).",
-                npi_mapping_csv.display(),
-                npi_api_responses_parquet.display()
-            );
-            interrupted = build_hcpcs_mapping(
-                &args,
-                &client,
-                &input_path,
-                &hcpcs_cache_db,
-                &hcpcs_mapping_csv,
-                &hcpcs_api_responses_parquet,
-                &hcpcs_fallback_csv,
-                &api_run_id,
-                None,
-                Arc::clone(&shutdown_requested),
-            )
-            .await?;
-        }
-        (false, false) => {
-            println!(
-                "NPI dataset already built (mapping: {}, resolved dataset: {}). Skipping NPI build (pass --rebuild-map or --reset-map to rebuild).",
-                npi_mapping_csv.display(),
-                npi_api_responses_parquet.display()
-            );
-            println!(
-                "HCPCS dataset already built (mapping: {}, resolved dataset: {}). Skipping HCPCS build (cache coverage is complete, including local fallback where applicable; pass --rebuild-map or --reset-map to rebuild).",
-                hcpcs_mapping_csv.display(),
-                hcpcs_api_responses_parquet.display()
-            );
+                )
+                .await?;
+            }
+            (false, false) => {
+                print_npi_skip_reason(
+                    args.hcpcs_only,
+                    &npi_mapping_csv,
+                    &npi_api_responses_parquet,
+                );
+                print_hcpcs_skip_reason(
+                    args.npi_only,
+                    &hcpcs_mapping_csv,
+                    &hcpcs_api_responses_parquet,
+                );
+            }
         }
-    }
+        anyhow::Ok(interrupted)
+    };
+    let report_job = Arc::new(UnresolvedReportJob::new(
+        &args,
+        input_path.clone(),
+        npi_cache_db.clone(),
+        hcpcs_cache_db.clone(),
+        unresolved_report_csv.clone(),
+        unresolved_report_parquet.clone(),
+    ));
+    let report_interval =
+        (args.report_interval_secs > 0).then(|| Duration::from_secs(args.report_interval_secs));
+    let interrupted = with_interim_unresolved_reports(builds, report_interval, report_job).await?;
 
-    if !should_build_npi_map && !npi_api_responses_parquet.exists() {
-        export_npi_api_responses_parquet(
-            &input_path,
+    if !args.hcpcs_only && !should_build_npi_map && !npi_api_responses_parquet.exists() {
+        let export = export_npi_api_responses_parquet(
+            npi_input,
             &npi_cache_db,
             &npi_api_responses_parquet,
             &api_run_id,
             &shutdown_requested,
             &nppes_monthly_dir,
             &nppes_weekly_dir,
-            args.skip_nppes_bulk,
-        )?;
+            NppesBulkMode::from_args(&args),
+            ParquetOutputOptions {
+                columns: args.npi_output_columns.as_deref(),
+                content_hash: args.content_hash,
+                sort_output: args.sort_output,
+                ndjson: args.emit_ndjson,
+                compression: args.parquet_compression,
+            },
+            &args.exclude_npi_types,
+            BulkScanOptions::from_args(&args),
+        );
+        export_failures.tolerate("NPI", export)?;
     }
-    if !should_build_hcpcs_map && !hcpcs_api_responses_parquet.exists() {
-        export_hcpcs_api_responses_parquet(
+    if !args.npi_only && !should_build_hcpcs_map && !hcpcs_api_responses_parquet.exists() {
+        let export = export_hcpcs_api_responses_parquet(
             &hcpcs_cache_db,
             &hcpcs_api_responses_parquet,
             &hcpcs_fallback_csv,
             &api_run_id,
-        )?;
+            args.normalize_hcpcs_case,
+            ParquetOutputOptions {
+                columns: args.hcpcs_output_columns.as_deref(),
+                content_hash: args.content_hash,
+                sort_output: args.sort_output,
+                ndjson: args.emit_ndjson,
+                compression: args.parquet_compression,
+            },
+        );
+        export_failures.tolerate("HCPCS", export)?;
     }
 
     if interrupted || shutdown_requested.load(Ordering::SeqCst) {
         write_unresolved_identifiers_report(
-            &input_path,
+            report_npi_input,
+            hcpcs_input_path,
             &npi_cache_db,
             &hcpcs_cache_db,
             &unresolved_report_csv,
+            unresolved_report_parquet.as_deref(),
+            UnresolvedReportFlags::from_args(&args),
         )?;
-        println!(
+        info!(
             "Wrote unresolved identifiers report {}",
             unresolved_report_csv.display()
         );
         let triage_dir = output_dir.join("triage");
         match write_unresolved_identifier_triage(&unresolved_report_csv, &triage_dir) {
-            Ok(summary) => println!(
-                "Wrote unresolved identifier triage outputs {} (hcpcs_rows={} hcpcs_needs_review={} npi_rows={} npi_needs_review={})",
+            Ok(summary) => info!(
+                "Wrote unresolved identifier triage outputs {} (hcpcs_rows={} hcpcs_needs_review={} npi_rows={} npi_needs_review={} npi_invalid={} permanently_failed={})",
                 triage_dir.display(),
                 summary.hcpcs_rows,
                 summary.hcpcs_needs_review_rows,
                 summary.npi_rows,
-                summary.npi_needs_review_rows
+                summary.npi_needs_review_rows,
+                summary.npi_invalid_rows,
+                summary.permanently_failed_rows
             ),
-            Err(err) => println!(
+            Err(err) => eprintln!(
                 "Warning: failed writing unresolved identifier triage outputs {}: {err}",
                 triage_dir.display()
             ),
         }
-        println!("Graceful shutdown complete. Progress saved; skipping uploads.");
-        return Ok(());
+        write_run_summary(&run_summary_json, &api_run_id, started_at_unix, true)?;
+        info!("Graceful shutdown complete. Progress saved; skipping uploads.");
+        return export_failures.ensure_none();
     }
 
-    maybe_upload_outputs(
-        &args,
-        &npi_mapping_csv,
-        &hcpcs_mapping_csv,
-        &npi_api_responses_parquet,
-        &hcpcs_api_responses_parquet,
-    )?;
+    let offline_incomplete = if args.offline && !args.allow_unresolved {
+        offline_unresolved_summary(
+            report_npi_input,
+            hcpcs_input_path,
+            &npi_cache_db,
+            &hcpcs_cache_db,
+            !args.exclude_npi_types.is_empty(),
+        )?
+    } else {
+        None
+    };
+    if offline_incomplete.is_some() {
+        info!("Skipping uploads: the offline build left identifiers unresolved.");
+    } else if export_failures.is_empty() {
+        maybe_upload_outputs(
+            &args,
+            &npi_mapping_csv,
+            &hcpcs_mapping_csv,
+            &npi_api_responses_parquet,
+            &hcpcs_api_responses_parquet,
+        )?;
+    } else {
+        info!("Skipping uploads: a dataset export failed.");
+    }
 
     write_unresolved_identifiers_report(
-        &input_path,
+        report_npi_input,
+        hcpcs_input_path,
         &npi_cache_db,
         &hcpcs_cache_db,
         &unresolved_report_csv,
+        unresolved_report_parquet.as_deref(),
+        UnresolvedReportFlags::from_args(&args),
     )?;
-    println!(
+    info!(
         "Wrote unresolved identifiers report {}",
         unresolved_report_csv.display()
     );
     let triage_dir = output_dir.join("triage");
     match write_unresolved_identifier_triage(&unresolved_report_csv, &triage_dir) {
-        Ok(summary) => println!(
-            "Wrote unresolved identifier triage outputs {} (hcpcs_rows={} hcpcs_needs_review={} npi_rows={} npi_needs_review={})",
+        Ok(summary) => info!(
+            "Wrote unresolved identifier triage outputs {} (hcpcs_rows={} hcpcs_needs_review={} npi_rows={} npi_needs_review={} npi_invalid={} permanently_failed={})",
             triage_dir.display(),
             summary.hcpcs_rows,
             summary.hcpcs_needs_review_rows,
             summary.npi_rows,
-            summary.npi_needs_review_rows
+            summary.npi_needs_review_rows,
+            summary.npi_invalid_rows,
+            summary.permanently_failed_rows
         ),
-        Err(err) => println!(
+        Err(err) => eprintln!(
             "Warning: failed writing unresolved identifier triage outputs {}: {err}",
             triage_dir.display()
         ),
     }
+    write_run_summary(&run_summary_json, &api_run_id, started_at_unix, false)?;
+    export_failures.ensure_none()?;
+    if let Some(summary) = offline_incomplete {
+        bail!("{summary}");
+    }
     Ok(())
 }
@@ -0,0 +1,232 @@
+//! Local mock of the NPPES and Clinical Tables APIs for the resolver tests and `--selftest`.
+//!
+//! Each [`MockApi`] listens on its own ephemeral port and answers from a scripted handler, so
+//! callers never share hit counts or fixtures.
+
+use reqwest::Url;
+use serde_json::Value;
+#[cfg(test)]
+use serde_json::json;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+#[cfg(test)]
+use crate::common::RetryPolicy;
+#[cfg(test)]
+use std::time::Duration;
+
+/// One request received by a [`MockApi`].
+#[derive(Debug, Clone)]
+pub struct MockRequest {
+    pub path: String,
+    pub params: HashMap<String, String>,
+    pub received_at: Instant,
+}
+
+impl MockRequest {
+    pub fn param(&self, name: &str) -> &str {
+        self.params.get(name).map_or("", String::as_str)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MockResponse {
+    status: u16,
+    retry_after_secs: Option<u64>,
+    body: String,
+}
+
+impl MockResponse {
+    pub fn json(body: Value) -> Self {
+        Self::raw(200, &body.to_string())
+    }
+
+    pub fn raw(status: u16, body: &str) -> Self {
+        Self {
+            status,
+            retry_after_secs: None,
+            body: body.to_string(),
+        }
+    }
+
+    pub fn status(status: u16) -> Self {
+        Self::raw(status, "{}")
+    }
+
+    pub fn retry_after(mut self, secs: u64) -> Self {
+        self.retry_after_secs = Some(secs);
+        self
+    }
+}
+
+type Handler = dyn Fn(&MockRequest, usize) -> MockResponse + Send + Sync;
+
+pub struct MockApi {
+    base_url: String,
+    requests: Arc<Mutex<Vec<MockRequest>>>,
+}
+
+impl MockApi {
+    /// Serves every request with `handler(request, earlier_requests_on_the_same_path)`.
+    pub async fn start(
+        handler: impl Fn(&MockRequest, usize) -> MockResponse + Send + Sync + 'static,
+    ) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("bind mock API listener");
+        let base_url = format!(
+            "http://{}",
+            listener.local_addr().expect("mock API address")
+        );
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let handler: Arc<Handler> = Arc::new(handler);
+        let log = Arc::clone(&requests);
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(handle_connection(
+                    stream,
+                    Arc::clone(&handler),
+                    Arc::clone(&log),
+                ));
+            }
+        });
+        Self { base_url, requests }
+    }
+
+    #[cfg(test)]
+    /// Answers the `n`th request with `responses[n]`, repeating the last one after that.
+    pub async fn scripted(responses: Vec<MockResponse>) -> Self {
+        assert!(!responses.is_empty(), "scripted mock needs a response");
+        Self::start(move |_, previous| responses[previous.min(responses.len() - 1)].clone()).await
+    }
+
+    pub fn url(&self, path: &str) -> String {
+        format!("{}{path}", self.base_url)
+    }
+
+    pub fn requests(&self) -> Vec<MockRequest> {
+        self.requests
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+
+    #[cfg(test)]
+    /// Time between the first two requests, if there were at least two.
+    pub fn first_retry_gap(&self) -> Option<Duration> {
+        match self.requests().as_slice() {
+            [first, second, ..] => Some(second.received_at.duration_since(first.received_at)),
+            _ => None,
+        }
+    }
+}
+
+async fn handle_connection(
+    mut stream: TcpStream,
+    handler: Arc<Handler>,
+    log: Arc<Mutex<Vec<MockRequest>>>,
+) -> std::io::Result<()> {
+    let mut buf = Vec::with_capacity(4096);
+    let mut chunk = [0u8; 4096];
+    while !buf.windows(4).any(|w| w == b"\r\n\r\n") {
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            return Ok(());
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    }
+    let head = String::from_utf8_lossy(&buf);
+    let target = head.split_whitespace().nth(1).unwrap_or("/");
+    let url = Url::parse(&format!("http://mock{target}")).expect("mock request target");
+    let request = MockRequest {
+        path: url.path().to_string(),
+        params: url.query_pairs().into_owned().collect(),
+        received_at: Instant::now(),
+    };
+    let previous = {
+        let mut log = log.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let previous = log.iter().filter(|r| r.path == request.path).count();
+        log.push(request.clone());
+        previous
+    };
+    let response = handler(&request, previous);
+    let retry_after = response
+        .retry_after_secs
+        .map(|secs| format!("Retry-After: {secs}\r\n"))
+        .unwrap_or_default();
+    let raw = format!(
+        "HTTP/1.1 {} MOCK\r\nContent-Type: application/json\r\n{retry_after}Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+        response.status,
+        response.body.len(),
+        response.body
+    );
+    stream.write_all(raw.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+#[cfg(test)]
+/// Short backoff so retry tests stay fast; `Retry-After` still overrides it.
+pub fn fast_retry(max_retries: u32) -> RetryPolicy {
+    RetryPolicy {
+        max_retries,
+        backoff_base: Duration::from_millis(10),
+        backoff_max: Duration::from_millis(20),
+        request_timeout: Duration::from_secs(10),
+    }
+}
+
+#[cfg(test)]
+/// NPPES v2 answer for a known individual provider.
+pub fn npi_found(npi: &str, first_name: &str, last_name: &str) -> Value {
+    json!({
+        "result_count": 1,
+        "results": [{
+            "number": npi,
+            "enumeration_type": "NPI-1",
+            "basic": { "first_name": first_name, "last_name": last_name, "status": "A" },
+            "addresses": [],
+            "practice_locations": [],
+            "taxonomies": [],
+            "identifiers": [],
+            "other_names": [],
+            "endpoints": []
+        }]
+    })
+}
+
+#[cfg(test)]
+/// NPPES v2 answer for an unknown NPI.
+pub fn npi_not_found() -> Value {
+    json!({ "result_count": 0, "results": [] })
+}
+
+#[cfg(test)]
+/// Clinical Tables answer `[total, codes, extra_fields, display]` with one row per
+/// `(code, short_desc)`. `total` may exceed the rows to simulate a paged result.
+pub fn hcpcs_rows(total: usize, rows: &[(&str, &str)]) -> Value {
+    let column = |value: &str| vec![value.to_string(); rows.len()];
+    json!([
+        total,
+        rows.iter().map(|(code, _)| *code).collect::<Vec<_>>(),
+        {
+            "short_desc": rows.iter().map(|(_, desc)| *desc).collect::<Vec<_>>(),
+            "long_desc": rows.iter().map(|(_, desc)| format!("{desc}, long")).collect::<Vec<_>>(),
+            "add_dt": column("20200101"),
+            "act_eff_dt": column("20200101"),
+            "term_dt": column(""),
+            "obsolete": column("false"),
+            "is_noc": column("false")
+        },
+        rows.iter().map(|(code, desc)| json!([code, desc])).collect::<Vec<_>>()
+    ])
+}
+
+/// Not JSON at all: the body is cut off mid-array.
+pub const MALFORMED_JSON: &str = "{\"result_count\": 1, \"results\": [";
@@ -0,0 +1,152 @@
+//! Identifier normalization shared by the NPI, HCPCS, and triage code paths.
+//!
+//! Keep these as the single definition of "what an identifier is" so the mapping builders,
+//! parquet exports, and unresolved-report triage never disagree on a code.
+
+/// Canonical NPI text: surrounding whitespace removed, otherwise verbatim.
+pub fn normalize_npi(raw: &str) -> &str {
+    raw.trim()
+}
+
+/// Strict HCPCS/CPT code normalization used for local fallback rows and dataset codes.
+///
+/// Strips all whitespace and a trailing `.0` (numeric CSV export artifact), uppercases, and
+/// only accepts 5-character alphanumeric codes.
+pub fn normalize_hcpcs_code(raw: &str) -> Option<String> {
+    let mut compact: String = raw.trim().chars().filter(|c| !c.is_whitespace()).collect();
+    if compact.is_empty() {
+        return None;
+    }
+    if compact.ends_with(".0") {
+        compact.truncate(compact.len().saturating_sub(2));
+    }
+    let normalized = compact.to_ascii_uppercase();
+    let valid = normalized.len() == 5 && normalized.chars().all(|c| c.is_ascii_alphanumeric());
+    if valid { Some(normalized) } else { None }
+}
+
+/// Splits a combined claim-line value such as `J1885-JW` into its base code and uppercased
+/// two-character modifier. Anything else (including plain codes) comes back whole, trimmed.
+pub fn split_hcpcs_modifier(raw: &str) -> (&str, Option<String>) {
+    let trimmed = raw.trim();
+    if let Some((base, modifier)) = trimmed.split_once('-') {
+        let (base, modifier) = (base.trim(), modifier.trim());
+        if normalize_hcpcs_code(base).is_some()
+            && modifier.len() == 2
+            && modifier.chars().all(|c| c.is_ascii_alphanumeric())
+        {
+            return (base, Some(modifier.to_ascii_uppercase()));
+        }
+    }
+    (trimmed, None)
+}
+
+fn luhn_mod10(digits: &[u32]) -> u32 {
+    let mut sum: u32 = 0;
+    let mut double = false;
+    for d in digits.iter().rev() {
+        let mut v = *d;
+        if double {
+            v *= 2;
+            if v > 9 {
+                v -= 9;
+            }
+        }
+        sum += v;
+        double = !double;
+    }
+    sum % 10
+}
+
+/// NPI check digit: Luhn over the fixed `80840` prefix plus all 10 digits. False for anything
+/// that is not exactly 10 ASCII digits.
+pub fn npi_luhn_valid(npi_digits: &str) -> bool {
+    if npi_digits.len() != 10 || !npi_digits.as_bytes().iter().all(|b| b.is_ascii_digit()) {
+        return false;
+    }
+
+    // NPI validation uses Luhn with the fixed "80840" prefix applied to the full 10 digits.
+    let mut digits: [u32; 15] = [0; 15];
+    digits[..5].copy_from_slice(&[8, 0, 8, 4, 0]);
+    for (idx, b) in npi_digits.as_bytes().iter().enumerate() {
+        digits[5 + idx] = (b - b'0') as u32;
+    }
+    luhn_mod10(&digits) == 0
+}
+
+/// Lenient lookup key for codes/identifiers: trimmed and uppercased.
+pub fn normalize_code_key(code: &str) -> String {
+    code.trim().to_ascii_uppercase()
+}
+
+/// Digits-only postal code (ZIP+4 dashes/spaces dropped).
+pub fn normalize_postal_code(raw: &str) -> String {
+    let mut out = String::with_capacity(raw.len());
+    for ch in raw.chars() {
+        if ch.is_ascii_digit() {
+            out.push(ch);
+        }
+    }
+    out
+}
+
+/// Country code with NPPES semantics: blank means `US`.
+pub fn normalize_country_code(raw: &str) -> String {
+    let trimmed = raw.trim();
+    if trimmed.is_empty() {
+        "US".to_string()
+    } else {
+        trimmed.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn npi_is_trimmed_but_otherwise_verbatim() {
+        assert_eq!(normalize_npi("  1234567893\t"), "1234567893");
+        assert_eq!(normalize_npi("12345 67893"), "12345 67893");
+        assert_eq!(normalize_npi(""), "");
+    }
+
+    #[test]
+    fn hcpcs_codes_are_compacted_uppercased_and_length_checked() {
+        assert_eq!(normalize_hcpcs_code(" j1885 ").as_deref(), Some("J1885"));
+        assert_eq!(normalize_hcpcs_code("J 18 85").as_deref(), Some("J1885"));
+        assert_eq!(normalize_hcpcs_code("99213.0").as_deref(), Some("99213"));
+        assert_eq!(normalize_hcpcs_code(""), None);
+        assert_eq!(normalize_hcpcs_code("   "), None);
+        assert_eq!(normalize_hcpcs_code("J188"), None);
+        assert_eq!(normalize_hcpcs_code("J18850"), None);
+        assert_eq!(normalize_hcpcs_code("J188-"), None);
+    }
+
+    #[test]
+    fn modifier_is_split_only_from_a_valid_base_code() {
+        assert_eq!(
+            split_hcpcs_modifier(" J1885-jw "),
+            ("J1885", Some("JW".to_string()))
+        );
+        assert_eq!(split_hcpcs_modifier("J1885"), ("J1885", None));
+        assert_eq!(split_hcpcs_modifier("J1885-JWX"), ("J1885-JWX", None));
+        assert_eq!(split_hcpcs_modifier("J18-JW"), ("J18-JW", None));
+    }
+
+    #[test]
+    fn npi_check_digit_uses_the_80840_prefix() {
+        assert!(npi_luhn_valid("1234567893"));
+        assert!(!npi_luhn_valid("1234567890"));
+        assert!(!npi_luhn_valid("123456789"));
+        assert!(!npi_luhn_valid("123456789a"));
+    }
+
+    #[test]
+    fn keys_postal_and_country_codes() {
+        assert_eq!(normalize_code_key(" j1885 "), "J1885");
+        assert_eq!(normalize_postal_code("12345-6789"), "123456789");
+        assert_eq!(normalize_country_code("  "), "US");
+        assert_eq!(normalize_country_code(" CA "), "CA");
+    }
+}
@@ -1,20 +1,22 @@
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, anyhow, bail};
 use csv::Writer;
 use duckdb::Connection;
 use futures::{StreamExt, stream::FuturesUnordered};
 use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
-use reqwest::{Client, header::RETRY_AFTER};
-use rusqlite::{Connection as SqliteConnection, OptionalExtension, params};
+use reqwest::{Client, StatusCode, header::RETRY_AFTER};
+use rusqlite::{Connection as SqliteConnection, OpenFlags, OptionalExtension, params};
 use serde::Deserialize;
 use serde_json::{Value, json};
 use std::{
+    cell::Cell,
     collections::{HashMap, HashSet},
     fs,
     io::IsTerminal,
     path::{Path, PathBuf},
     sync::{
         Arc,
-        atomic::{AtomicBool, Ordering},
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+        mpsc::{RecvTimeoutError, sync_channel},
     },
     time::{Duration, SystemTime},
 };
@@ -23,11 +25,20 @@ use tokio::time::{Instant, sleep};
 
 use crate::{
     args::Args,
+    bulk_scan,
     common::{
-        is_retryable_status, now_unix_seconds, parse_retry_after, source_expr, sql_escape_path,
+        InFlightIds, LookupBudget, RetryPolicy, StatusTicker, elapsed_millis,
+        ensure_identifiers_extracted, ensure_sqlite_column, is_gzip_path, is_retryable_status,
+        now_unix_seconds, open_maybe_gzip, parse_retry_after, source_expr, sql_escape_path,
         truncate_for_log, wait_for_rate_slot,
     },
-    parquet_writer::StringParquetWriter,
+    export_errors::ExportFailures,
+    failed_bodies::{FailedBody, dump_failed_body},
+    normalize::{normalize_country_code, normalize_npi, normalize_postal_code, npi_luhn_valid},
+    output::info,
+    parquet_writer::{ParquetOutputOptions, StringParquetWriter},
+    run_summary::{LookupTotals, NpiPlanCounts, record_npi_lookups, record_npi_plan},
+    taxonomy::taxonomy_description,
 };
 
 struct NpiCache {
@@ -37,6 +48,8 @@ struct NpiCache {
 #[derive(Debug, Clone)]
 pub struct UnresolvedNpiEntry {
     pub npi: String,
+    /// Cache status, or `missing_cache`. `invalid` (check digit failed, never sent to the API)
+    /// separates bad source identifiers from API failures.
     pub status: String,
     pub error_message: Option<String>,
     pub fetched_at_unix: Option<i64>,
@@ -58,7 +71,8 @@ impl NpiCache {
                 provider_name TEXT,
                 status TEXT NOT NULL,
                 error_message TEXT,
-                fetched_at_unix INTEGER NOT NULL
+                fetched_at_unix INTEGER NOT NULL,
+                attempt_count INTEGER NOT NULL DEFAULT 0
             );
             CREATE INDEX IF NOT EXISTS idx_npi_cache_status ON npi_cache(status);
             CREATE TABLE IF NOT EXISTS npi_api_responses (
@@ -75,6 +89,9 @@ impl NpiCache {
                 api_run_id TEXT,
                 requested_at_utc TEXT,
                 request_params_json TEXT,
+                latency_ms INTEGER,
+                http_status INTEGER,
+                duration_ms INTEGER,
                 results_json TEXT,
                 response_json_raw TEXT
             );
@@ -83,26 +100,74 @@ impl NpiCache {
             ",
         )
         .context("Failed initializing NPI cache schema")?;
+        ensure_sqlite_column(&conn, "npi_api_responses", "latency_ms", "INTEGER")?;
+        ensure_sqlite_column(&conn, "npi_api_responses", "http_status", "INTEGER")?;
+        ensure_sqlite_column(&conn, "npi_api_responses", "duration_ms", "INTEGER")?;
+        ensure_sqlite_column(
+            &conn,
+            "npi_cache",
+            "attempt_count",
+            "INTEGER NOT NULL DEFAULT 0",
+        )?;
+        ensure_sqlite_column(&conn, "npi_cache", "deactivated", "INTEGER")?;
         Ok(Self { conn })
     }
 
-    fn classify_for_lookup(&self, npis: &[String]) -> Result<(usize, Vec<String>)> {
+    /// For readers that run alongside the lookup workers (the incremental unresolved report):
+    /// a read-only WAL connection never takes the write lock, so it cannot make a worker's
+    /// cache write fail with SQLITE_BUSY. Falls back to `open` when the DB does not exist yet.
+    fn open_read_only(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Self::open(path);
+        }
+        let conn = SqliteConnection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .with_context(|| format!("Failed opening cache DB {}", path.display()))?;
+        conn.busy_timeout(Duration::from_secs(5))
+            .context("Failed setting NPI cache busy timeout")?;
+        Ok(Self { conn })
+    }
+
+    /// `skipped_is_resolved` is true while `--exclude-npi-types` is active; otherwise NPIs
+    /// previously skipped by an exclusion are looked up again. `permanently_failed` NPIs are
+    /// neither resolved nor looked up while `max_lifetime_attempts` still covers them.
+    /// `ok`/`not_found` rows fetched before `stale_before_unix` count as missing. `invalid` rows
+    /// count as resolved only under `--strict-npi-validation`.
+    fn classify_for_lookup(
+        &self,
+        npis: &[String],
+        skipped_is_resolved: bool,
+        invalid_is_resolved: bool,
+        max_lifetime_attempts: Option<u32>,
+        stale_before_unix: Option<i64>,
+    ) -> Result<(usize, Vec<String>)> {
         let mut stmt = self
             .conn
-            .prepare("SELECT status FROM npi_cache WHERE npi = ?1")
+            .prepare("SELECT status, attempt_count, fetched_at_unix FROM npi_cache WHERE npi = ?1")
             .context("Failed preparing NPI cache lookup statement")?;
 
         let mut resolved = 0usize;
         let mut missing = Vec::new();
 
         for npi in npis {
-            let status: Option<String> = stmt
-                .query_row([npi], |row| row.get(0))
+            let row: Option<(String, i64, i64)> = stmt
+                .query_row([npi], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
                 .optional()
                 .with_context(|| format!("Failed NPI cache lookup for {npi}"))?;
 
-            match status.as_deref() {
-                Some("ok") | Some("not_found") => resolved += 1,
+            match row
+                .as_ref()
+                .map(|(status, attempts, fetched_at)| (status.as_str(), *attempts, *fetched_at))
+            {
+                Some(("ok" | "not_found", _, fetched_at))
+                    if stale_before_unix.is_some_and(|cutoff| fetched_at < cutoff) =>
+                {
+                    missing.push(npi.clone())
+                }
+                Some(("ok", ..)) | Some(("not_found", ..)) => resolved += 1,
+                Some(("skipped", ..)) if skipped_is_resolved => resolved += 1,
+                Some(("invalid", ..)) if invalid_is_resolved => resolved += 1,
+                Some(("permanently_failed", attempts, _))
+                    if max_lifetime_attempts.is_some_and(|max| attempts >= i64::from(max)) => {}
                 Some(_) | None => missing.push(npi.clone()),
             }
         }
@@ -110,16 +175,64 @@ impl NpiCache {
         Ok((resolved, missing))
     }
 
-    fn upsert_ok(&self, npi: &str, provider_name: &str) -> Result<()> {
-        self.upsert(npi, Some(provider_name), "ok", None)
+    /// Flip `error` rows with at least `max_attempts` lifetime failures to `permanently_failed`.
+    fn mark_permanently_failed(&self, max_attempts: u32) -> Result<usize> {
+        self.conn
+            .execute(
+                "UPDATE npi_cache SET status = 'permanently_failed'
+                 WHERE status = 'error' AND attempt_count >= ?1",
+                [max_attempts],
+            )
+            .context("Failed marking permanently failed NPIs")
+    }
+
+    /// Cache every NPI in `npis` that fails the check digit as `invalid`; returns how many.
+    fn mark_invalid(&mut self, npis: &[String]) -> Result<usize> {
+        let tx = self
+            .conn
+            .transaction()
+            .context("Failed starting NPI validation transaction")?;
+        let mut flagged = 0usize;
+        for npi in npis.iter().filter(|npi| !npi_luhn_valid(npi)) {
+            tx.execute(
+                "
+                INSERT INTO npi_cache (
+                    npi, provider_name, status, error_message, fetched_at_unix, attempt_count
+                )
+                VALUES (?1, NULL, 'invalid', ?2, strftime('%s', 'now'), 0)
+                ON CONFLICT(npi) DO UPDATE SET
+                    provider_name = NULL,
+                    deactivated = NULL,
+                    status = 'invalid',
+                    error_message = excluded.error_message,
+                    fetched_at_unix = excluded.fetched_at_unix,
+                    attempt_count = 0
+                WHERE npi_cache.status <> 'invalid'
+                ",
+                params![npi, "NPI check digit failed (Luhn with 80840 prefix)"],
+            )
+            .with_context(|| format!("Failed marking NPI {npi} invalid"))?;
+            flagged += 1;
+        }
+        tx.commit()
+            .context("Failed committing NPI validation transaction")?;
+        Ok(flagged)
+    }
+
+    fn upsert_ok(&self, npi: &str, provider_name: &str, deactivated: bool) -> Result<()> {
+        self.upsert(npi, Some(provider_name), "ok", None, Some(deactivated))
     }
 
     fn upsert_not_found(&self, npi: &str) -> Result<()> {
-        self.upsert(npi, None, "not_found", None)
+        self.upsert(npi, None, "not_found", None, None)
+    }
+
+    fn upsert_skipped(&self, npi: &str) -> Result<()> {
+        self.upsert(npi, None, "skipped", None, None)
     }
 
     fn upsert_error(&self, npi: &str, message: &str) -> Result<()> {
-        self.upsert(npi, None, "error", Some(message))
+        self.upsert(npi, None, "error", Some(message), None)
     }
 
     fn upsert(
@@ -128,19 +241,31 @@ impl NpiCache {
         provider_name: Option<&str>,
         status: &str,
         error_message: Option<&str>,
+        deactivated: Option<bool>,
     ) -> Result<()> {
         self.conn
             .execute(
                 "
-                INSERT INTO npi_cache (npi, provider_name, status, error_message, fetched_at_unix)
-                VALUES (?1, ?2, ?3, ?4, strftime('%s', 'now'))
+                INSERT INTO npi_cache (
+                    npi, provider_name, status, error_message, fetched_at_unix, attempt_count,
+                    deactivated
+                )
+                VALUES (
+                    ?1, ?2, ?3, ?4, strftime('%s', 'now'), CASE WHEN ?3 = 'error' THEN 1 ELSE 0 END,
+                    ?5
+                )
                 ON CONFLICT(npi) DO UPDATE SET
                     provider_name = excluded.provider_name,
                     status = excluded.status,
                     error_message = excluded.error_message,
-                    fetched_at_unix = excluded.fetched_at_unix
+                    fetched_at_unix = excluded.fetched_at_unix,
+                    attempt_count = CASE
+                        WHEN excluded.status = 'error' THEN npi_cache.attempt_count + 1
+                        ELSE 0
+                    END,
+                    deactivated = excluded.deactivated
                 ",
-                params![npi, provider_name, status, error_message],
+                params![npi, provider_name, status, error_message, deactivated],
             )
             .with_context(|| format!("Failed updating NPI cache for {npi}"))?;
         Ok(())
@@ -173,9 +298,12 @@ impl NpiCache {
                     requested_at_utc,
                     request_params_json,
                     results_json,
-                    response_json_raw
+                    response_json_raw,
+                    latency_ms,
+                    http_status,
+                    duration_ms
                 ) VALUES (
-                    ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15
+                    ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18
                 )
                 ON CONFLICT(npi) DO UPDATE SET
                     basic_json = excluded.basic_json,
@@ -191,7 +319,10 @@ impl NpiCache {
                     requested_at_utc = excluded.requested_at_utc,
                     request_params_json = excluded.request_params_json,
                     results_json = excluded.results_json,
-                    response_json_raw = excluded.response_json_raw
+                    response_json_raw = excluded.response_json_raw,
+                    latency_ms = excluded.latency_ms,
+                    http_status = excluded.http_status,
+                    duration_ms = excluded.duration_ms
                 WHERE excluded.requested_at_utc > npi_api_responses.requested_at_utc
                    OR npi_api_responses.requested_at_utc IS NULL
                 ",
@@ -214,6 +345,9 @@ impl NpiCache {
                 row.request_params_json.as_str(),
                 row.results_json.as_deref(),
                 row.response_json_raw.as_deref(),
+                row.latency_ms,
+                row.http_status,
+                row.duration_ms,
             ])
             .with_context(|| format!("Failed upserting NPI API response row for {}", row.npi))?;
         }
@@ -223,6 +357,27 @@ impl NpiCache {
         Ok(())
     }
 
+    /// NPIs a bulk scan cannot improve on: a cached API response without an error (the
+    /// registry answered, found or not), or a failed check digit (never in NPPES).
+    fn npis_settled_without_bulk(&self) -> Result<HashSet<String>> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "
+                SELECT npi FROM npi_api_responses WHERE error_message IS NULL
+                UNION
+                SELECT npi FROM npi_cache WHERE status = 'invalid'
+                ",
+            )
+            .context("Failed preparing settled NPI query")?;
+        let npis = stmt
+            .query_map([], |row| row.get::<_, String>(0))
+            .context("Failed querying settled NPIs")?
+            .collect::<rusqlite::Result<HashSet<_>>>()
+            .context("Failed reading settled NPIs")?;
+        Ok(npis)
+    }
+
     fn export_mapping_csv(&self, output_path: &Path) -> Result<()> {
         if let Some(parent) = output_path.parent() {
             fs::create_dir_all(parent).with_context(|| {
@@ -246,17 +401,34 @@ impl NpiCache {
             )
         })?;
         writer
-            .write_record(["npi", "provider_name", "status", "fetched_at_unix"])
+            .write_record([
+                "npi",
+                "provider_name",
+                "status",
+                "fetched_at_unix",
+                "deactivated",
+            ])
             .context("Failed writing NPI mapping CSV header")?;
 
+        // Rows cached before `deactivated` existed fall back to the stored API `basic.status`.
         let mut stmt = self
             .conn
             .prepare(
                 "
-                SELECT npi, COALESCE(provider_name, ''), status, fetched_at_unix
-                FROM npi_cache
-                WHERE status IN ('ok', 'not_found')
-                ORDER BY npi
+                SELECT
+                    c.npi,
+                    COALESCE(c.provider_name, ''),
+                    c.status,
+                    c.fetched_at_unix,
+                    CASE WHEN c.status = 'ok' THEN COALESCE(
+                        c.deactivated,
+                        json_extract(r.basic_json, '$.status') = 'D',
+                        0
+                    ) END
+                FROM npi_cache c
+                LEFT JOIN npi_api_responses r ON r.npi = c.npi AND json_valid(r.basic_json)
+                WHERE c.status IN ('ok', 'not_found')
+                ORDER BY c.npi
                 ",
             )
             .context("Failed preparing NPI mapping export query")?;
@@ -267,8 +439,15 @@ impl NpiCache {
             let provider_name: String = row.get(1).context("Failed reading provider_name")?;
             let status: String = row.get(2).context("Failed reading status")?;
             let fetched_at_unix: i64 = row.get(3).context("Failed reading fetched_at_unix")?;
+            let deactivated: Option<bool> = row.get(4).context("Failed reading deactivated")?;
             writer
-                .write_record([npi, provider_name, status, fetched_at_unix.to_string()])
+                .write_record([
+                    npi,
+                    provider_name,
+                    status,
+                    fetched_at_unix.to_string(),
+                    deactivated.map(|d| d.to_string()).unwrap_or_default(),
+                ])
                 .context("Failed writing NPI mapping row")?;
         }
         writer
@@ -320,6 +499,8 @@ struct NpiApiReferenceRow {
     api_run_id: String,
     requested_at_utc: String,
     request_params_json: String,
+    latency_ms: Option<i64>,
+    duration_ms: Option<i64>,
     results_json: Option<String>,
     response_json_raw: Option<String>,
 }
@@ -338,94 +519,145 @@ enum NpiResolveResult {
     },
 }
 
+impl NpiResolveResult {
+    fn reference_row_mut(&mut self) -> &mut NpiApiReferenceRow {
+        match self {
+            Self::Found { reference_row, .. }
+            | Self::NotFound { reference_row }
+            | Self::Error { reference_row, .. } => reference_row,
+        }
+    }
+}
+
 pub async fn build_npi_mapping(
     args: &Args,
     client: &Client,
-    input_path: &Path,
+    npi_input: NpiInput<'_>,
     cache_db: &Path,
     mapping_csv: &Path,
     api_responses_parquet: &Path,
     api_run_id: &str,
+    export_failures: &ExportFailures,
+    lookup_budget: &LookupBudget,
     progress_hub: Option<Arc<MultiProgress>>,
     shutdown_requested: Arc<AtomicBool>,
     nppes_monthly_dir: &Path,
     nppes_weekly_dir: &Path,
 ) -> Result<bool> {
-    println!("Extracting unique NPIs...");
-    let unique_npis = extract_unique_npis(input_path)?;
-    println!(
+    info!("Extracting unique NPIs...");
+    let unique_npis = extract_unique_npis(npi_input)?;
+    info!(
         "Discovered {} unique NPIs in source data.",
         unique_npis.len()
     );
+    ensure_identifiers_extracted(
+        unique_npis.len(),
+        "NPIs",
+        npi_input.source_path(),
+        npi_input.empty_hint(),
+        args.allow_empty_input,
+        args.max_identifiers,
+    )?;
 
     let mut cache = NpiCache::open(cache_db)?;
-    let mut exporter =
-        NpiResolvedParquetExporter::try_new(api_responses_parquet, &unique_npis, api_run_id)?;
-    let (resolved_before_bulk, _) = cache.classify_for_lookup(&unique_npis)?;
+    let mut exporter = NpiResolvedParquetExporter::try_new(
+        api_responses_parquet,
+        &unique_npis,
+        api_run_id,
+        ParquetOutputOptions {
+            columns: args.npi_output_columns.as_deref(),
+            content_hash: args.content_hash,
+            sort_output: args.sort_output,
+            ndjson: args.emit_ndjson,
+            compression: args.parquet_compression,
+        },
+        &args.exclude_npi_types,
+    )?;
+    exporter.scan = BulkScanOptions::from_args(args);
+    let skip_excluded = !args.exclude_npi_types.is_empty();
+    if args.strict_npi_validation {
+        let flagged = cache.mark_invalid(&unique_npis)?;
+        if flagged > 0 {
+            info!(
+                "Skipping {flagged} NPIs that fail the check digit; cached as invalid (--strict-npi-validation)."
+            );
+        }
+    }
+    if let Some(max_attempts) = args.max_lifetime_attempts {
+        let marked = cache.mark_permanently_failed(max_attempts)?;
+        if marked > 0 {
+            info!(
+                "Marked {marked} NPIs permanently_failed after {max_attempts}+ failed lookups (--max-lifetime-attempts)."
+            );
+        }
+    }
+    let (resolved_before_bulk, _) = cache.classify_for_lookup(
+        &unique_npis,
+        skip_excluded,
+        args.strict_npi_validation,
+        args.max_lifetime_attempts,
+        npi_cache_stale_before(args.npi_cache_ttl_days),
+    )?;
     let mut monthly_loaded = 0usize;
     let mut weekly_loaded = 0usize;
     let mut used_monthly_file: Option<PathBuf> = None;
     let mut used_weekly_file: Option<PathBuf> = None;
-    let mut bulk_sources: Vec<NppesBulkFiles> = Vec::new();
 
-    if !args.skip_nppes_bulk {
-        let monthly_primary = select_latest_nppes_csv(nppes_monthly_dir)?;
-        let weekly_primary = select_latest_nppes_csv(nppes_weekly_dir)?;
-        if monthly_primary.is_none() && weekly_primary.is_none() {
-            println!(
+    let bulk_mode = NppesBulkMode::from_args(args);
+    if bulk_mode != NppesBulkMode::Skip {
+        let bulk_sources =
+            discover_nppes_bulk_sources(nppes_monthly_dir, nppes_weekly_dir, bulk_mode)?;
+        if bulk_sources.is_empty() {
+            info!(
                 "No local NPPES bulk files found under {} and {}. Falling back to cache/API.",
                 nppes_monthly_dir.display(),
                 nppes_weekly_dir.display()
             );
         } else {
-            if let Some(monthly) = monthly_primary.clone() {
-                used_monthly_file = Some(monthly.clone());
-                bulk_sources.push(NppesBulkFiles {
-                    label: "monthly",
-                    othername_csv: find_nppes_sibling_csv(&monthly, "othername_pfile_")?,
-                    pl_csv: find_nppes_sibling_csv(&monthly, "pl_pfile_")?,
-                    endpoint_csv: find_nppes_sibling_csv(&monthly, "endpoint_pfile_")?,
-                    npidata_csv: monthly,
-                });
-            }
-            if let Some(weekly) = weekly_primary.clone() {
-                used_weekly_file = Some(weekly.clone());
-                bulk_sources.push(NppesBulkFiles {
-                    label: "weekly",
-                    othername_csv: find_nppes_sibling_csv(&weekly, "othername_pfile_")?,
-                    pl_csv: find_nppes_sibling_csv(&weekly, "pl_pfile_")?,
-                    endpoint_csv: find_nppes_sibling_csv(&weekly, "endpoint_pfile_")?,
-                    npidata_csv: weekly,
-                });
+            // Sources are newest first, so the first of each label is the newest file.
+            used_weekly_file = bulk_sources
+                .iter()
+                .find(|s| s.label == "weekly")
+                .map(|s| s.npidata_csv.clone());
+            used_monthly_file = bulk_sources
+                .iter()
+                .find(|s| s.label == "monthly")
+                .map(|s| s.npidata_csv.clone());
+            if bulk_mode == NppesBulkMode::MergeAll {
+                info!(
+                    "Merging {} NPPES primary files (--nppes-merge-all); newer files win per NPI.",
+                    bulk_sources.len()
+                );
             }
 
-            println!("Loading local NPPES bulk files before API fallback...");
+            info!("Loading local NPPES bulk files before API fallback...");
             exporter.load_supplemental_records(&bulk_sources, &shutdown_requested)?;
-            if let Some(weekly_source) = bulk_sources.iter().find(|s| s.label == "weekly") {
-                weekly_loaded = exporter.write_bulk_from_primary(
-                    Some(&cache),
-                    weekly_source,
-                    &shutdown_requested,
-                )?;
-            }
-            if let Some(monthly_source) = bulk_sources.iter().find(|s| s.label == "monthly") {
-                monthly_loaded = exporter.write_bulk_from_primary(
-                    Some(&cache),
-                    monthly_source,
-                    &shutdown_requested,
-                )?;
+            for source in &bulk_sources {
+                let emitted =
+                    exporter.write_bulk_from_primary(Some(&cache), source, &shutdown_requested)?;
+                if source.label == "weekly" {
+                    weekly_loaded += emitted;
+                } else {
+                    monthly_loaded += emitted;
+                }
             }
         }
     } else {
-        println!("--skip-nppes-bulk set; skipping local NPPES bulk-file preload.");
+        info!("--skip-nppes-bulk set; skipping local NPPES bulk-file preload.");
     }
 
-    let (resolved_count, mut missing_npis) = cache.classify_for_lookup(&unique_npis)?;
+    let (resolved_count, mut missing_npis) = cache.classify_for_lookup(
+        &unique_npis,
+        skip_excluded,
+        args.strict_npi_validation,
+        args.max_lifetime_attempts,
+        npi_cache_stale_before(args.npi_cache_ttl_days),
+    )?;
     let unresolved_before_limit = missing_npis.len();
 
     if let Some(limit) = args.max_new_lookups {
         if missing_npis.len() > limit {
-            println!(
+            info!(
                 "Applying --max-new-lookups={} to NPI lookups (from {}).",
                 limit,
                 missing_npis.len()
@@ -433,7 +665,14 @@ pub async fn build_npi_mapping(
             missing_npis.truncate(limit);
         }
     }
-    let planned_api_lookups = if args.skip_api { 0 } else { missing_npis.len() };
+    // The combined budget is only taken as lookups are dispatched; this is an upper bound.
+    let planned_api_lookups = if args.skip_api {
+        0
+    } else {
+        lookup_budget
+            .remaining()
+            .map_or(missing_npis.len(), |left| left.min(missing_npis.len()))
+    };
     print_npi_download_plan_table(
         unique_npis.len(),
         resolved_before_bulk,
@@ -445,13 +684,41 @@ pub async fn build_npi_mapping(
         used_monthly_file.as_deref(),
         used_weekly_file.as_deref(),
     );
+    record_npi_plan(NpiPlanCounts {
+        unique_identifiers: unique_npis.len(),
+        resolved_before_bulk,
+        found_via_bulk: resolved_count.saturating_sub(resolved_before_bulk),
+        planned_api_lookups,
+    });
+    if args.npi_role_stats {
+        match npi_input {
+            NpiInput::Spending(input_path) => {
+                let roles = count_npi_roles(input_path)?;
+                info!(
+                    "NPI roles in source: billing-only={} servicing-only={} both={}",
+                    format_count(roles.billing_only),
+                    format_count(roles.servicing_only),
+                    format_count(roles.both)
+                );
+            }
+            NpiInput::List { .. } => {
+                info!(
+                    "--npi-role-stats ignored: an --npi-list input has no billing/servicing roles."
+                );
+            }
+        }
+    }
+    if args.dry_run {
+        exporter.abort()?;
+        return Ok(false);
+    }
 
     let mut interrupted = shutdown_requested.load(Ordering::SeqCst);
     let mut api_reference_rows: Vec<NpiApiReferenceRow> = Vec::new();
     if interrupted {
-        println!("Shutdown requested; skipping new NPI API lookups.");
+        info!("Shutdown requested; skipping new NPI API lookups.");
     } else if args.skip_api {
-        println!("--skip-api set; unresolved NPIs remain unresolved.");
+        info!("--skip-api set; unresolved NPIs remain unresolved.");
     } else if !missing_npis.is_empty() {
         let (api_interrupted, rows) = resolve_missing_npis(
             &cache,
@@ -459,6 +726,7 @@ pub async fn build_npi_mapping(
             client,
             args,
             api_run_id,
+            lookup_budget,
             progress_hub.clone(),
             Arc::clone(&shutdown_requested),
         )
@@ -469,46 +737,72 @@ pub async fn build_npi_mapping(
 
     cache.upsert_api_responses(&api_reference_rows)?;
     cache.export_mapping_csv(mapping_csv)?;
-    println!("Wrote NPI mapping CSV {}", mapping_csv.display());
-    exporter.write_remaining_from_api_responses(&cache, &shutdown_requested)?;
-    if shutdown_requested.load(Ordering::SeqCst) {
-        exporter.abort()?;
-        println!(
-            "Shutdown requested; aborted NPI resolved identifier parquet export (output not updated)."
-        );
-    } else {
-        exporter.finish()?;
-        println!(
-            "Wrote NPI resolved identifier dataset {}",
-            api_responses_parquet.display()
-        );
-    }
+    info!("Wrote NPI mapping CSV {}", mapping_csv.display());
+    let export = match exporter.write_remaining_from_api_responses(&cache, &shutdown_requested) {
+        Err(err) => {
+            let _ = exporter.abort();
+            Err(err)
+        }
+        Ok(()) if shutdown_requested.load(Ordering::SeqCst) => exporter.abort().map(|()| {
+            info!(
+                "Shutdown requested; aborted NPI resolved identifier parquet export (output not updated)."
+            );
+        }),
+        Ok(()) => exporter.finish().map(|()| {
+            info!(
+                "Wrote NPI resolved identifier dataset {}",
+                api_responses_parquet.display()
+            );
+        }),
+    };
+    export_failures.tolerate("NPI", export)?;
     Ok(interrupted || shutdown_requested.load(Ordering::SeqCst))
 }
 
+/// Cutoff for `--npi-cache-ttl-days`: `ok`/`not_found` rows fetched before it are stale.
+pub fn npi_cache_stale_before(ttl_days: Option<u64>) -> Option<i64> {
+    let ttl_secs = i64::try_from(ttl_days?.saturating_mul(86_400)).unwrap_or(i64::MAX);
+    Some(now_unix_seconds().saturating_sub(ttl_secs))
+}
+
+/// `cache_ttl_days` is `--npi-cache-ttl-days`: a stale `ok`/`not_found` row makes the dataset
+/// incomplete, matching what `classify_for_lookup` would re-query. Likewise `skipped` rows only
+/// count as complete while `--exclude-npi-types` is active (`skipped_is_resolved`).
 pub fn is_npi_dataset_complete(
-    input_path: &Path,
+    npi_input: NpiInput<'_>,
     cache_db: &Path,
     mapping_csv: &Path,
+    cache_ttl_days: Option<u64>,
+    skipped_is_resolved: bool,
 ) -> Result<bool> {
     if !cache_db.exists() || !mapping_csv.exists() {
         return Ok(false);
     }
 
-    let unique_npis = extract_unique_npis(input_path)?;
+    let unique_npis = extract_unique_npis(npi_input)?;
+    if unique_npis.is_empty() {
+        // Nothing extracted is never "complete"; let the build report it.
+        return Ok(false);
+    }
     let cache = NpiCache::open(cache_db)?;
     let mut stmt = cache
         .conn
         .prepare(
             "SELECT 1 FROM npi_cache
-             WHERE npi = ?1 AND status IN ('ok', 'not_found', 'error')
+             WHERE npi = ?1
+               AND status IN ('ok', 'not_found', 'error', 'skipped', 'permanently_failed', 'invalid')
+               AND NOT (status IN ('ok', 'not_found') AND fetched_at_unix < ?2)
+               AND (status <> 'skipped' OR ?3)
              LIMIT 1",
         )
         .context("Failed preparing NPI completeness query")?;
+    let stale_before = npi_cache_stale_before(cache_ttl_days).unwrap_or(i64::MIN);
 
     for npi in unique_npis {
         let exists: Option<i64> = stmt
-            .query_row([&npi], |row| row.get(0))
+            .query_row(params![npi, stale_before, skipped_is_resolved], |row| {
+                row.get(0)
+            })
             .optional()
             .with_context(|| format!("Failed checking NPI cache status for {npi}"))?;
         if exists.is_none() {
@@ -519,76 +813,71 @@ pub fn is_npi_dataset_complete(
 }
 
 pub fn export_npi_api_responses_parquet(
-    input_path: &Path,
+    npi_input: NpiInput<'_>,
     cache_db: &Path,
     output_path: &Path,
     api_run_id: &str,
     shutdown_requested: &Arc<AtomicBool>,
     nppes_monthly_dir: &Path,
     nppes_weekly_dir: &Path,
-    skip_nppes_bulk: bool,
+    bulk_mode: NppesBulkMode,
+    output: ParquetOutputOptions<'_>,
+    exclude_npi_types: &[String],
+    scan: BulkScanOptions,
 ) -> Result<()> {
-    println!(
+    info!(
         "Regenerating NPI resolved identifier parquet {} (bulk NPPES + cached API responses)...",
         output_path.display()
     );
 
-    let unique_npis = extract_unique_npis(input_path)?;
+    let unique_npis = extract_unique_npis(npi_input)?;
 
     let cache = NpiCache::open(cache_db)?;
-    let mut exporter = NpiResolvedParquetExporter::try_new(output_path, &unique_npis, api_run_id)?;
-    let mut bulk_sources: Vec<NppesBulkFiles> = Vec::new();
-
-    if !skip_nppes_bulk {
-        let monthly_primary = select_latest_nppes_csv(nppes_monthly_dir)?;
-        let weekly_primary = select_latest_nppes_csv(nppes_weekly_dir)?;
-        if let Some(monthly) = monthly_primary.clone() {
-            bulk_sources.push(NppesBulkFiles {
-                label: "monthly",
-                othername_csv: find_nppes_sibling_csv(&monthly, "othername_pfile_")?,
-                pl_csv: find_nppes_sibling_csv(&monthly, "pl_pfile_")?,
-                endpoint_csv: find_nppes_sibling_csv(&monthly, "endpoint_pfile_")?,
-                npidata_csv: monthly,
-            });
-        }
-        if let Some(weekly) = weekly_primary.clone() {
-            bulk_sources.push(NppesBulkFiles {
-                label: "weekly",
-                othername_csv: find_nppes_sibling_csv(&weekly, "othername_pfile_")?,
-                pl_csv: find_nppes_sibling_csv(&weekly, "pl_pfile_")?,
-                endpoint_csv: find_nppes_sibling_csv(&weekly, "endpoint_pfile_")?,
-                npidata_csv: weekly,
-            });
-        }
-
+    let mut exporter = NpiResolvedParquetExporter::try_new(
+        output_path,
+        &unique_npis,
+        api_run_id,
+        output,
+        exclude_npi_types,
+    )?;
+    exporter.scan = scan;
+
+    let settled = cache.npis_settled_without_bulk()?;
+    let needs_bulk = exporter.pending_without(&settled);
+    if bulk_mode != NppesBulkMode::Skip && needs_bulk == 0 {
+        info!(
+            "Cached API responses cover all {} NPIs; skipping the NPPES bulk scan.",
+            format_count(unique_npis.len())
+        );
+    } else if bulk_mode != NppesBulkMode::Skip {
+        let bulk_sources =
+            discover_nppes_bulk_sources(nppes_monthly_dir, nppes_weekly_dir, bulk_mode)?;
         if bulk_sources.is_empty() {
-            println!(
+            info!(
                 "No local NPPES bulk files found under {} and {}. Export will use cached API rows only.",
                 nppes_monthly_dir.display(),
                 nppes_weekly_dir.display()
             );
         } else {
             exporter.load_supplemental_records(&bulk_sources, shutdown_requested)?;
-            if let Some(weekly_source) = bulk_sources.iter().find(|s| s.label == "weekly") {
-                let _ =
-                    exporter.write_bulk_from_primary(None, weekly_source, shutdown_requested)?;
-            }
-            if let Some(monthly_source) = bulk_sources.iter().find(|s| s.label == "monthly") {
-                let _ =
-                    exporter.write_bulk_from_primary(None, monthly_source, shutdown_requested)?;
+            for source in &bulk_sources {
+                let _ = exporter.write_bulk_from_primary(None, source, shutdown_requested)?;
             }
         }
     } else {
-        println!("--skip-nppes-bulk set; exporting from cached API rows only.");
+        info!("--skip-nppes-bulk set; exporting from cached API rows only.");
     }
 
-    exporter.write_remaining_from_api_responses(&cache, shutdown_requested)?;
+    if let Err(err) = exporter.write_remaining_from_api_responses(&cache, shutdown_requested) {
+        let _ = exporter.abort();
+        return Err(err);
+    }
     if shutdown_requested.load(Ordering::SeqCst) {
         exporter.abort()?;
-        println!("Shutdown requested; aborted NPI parquet export (output not updated).");
+        info!("Shutdown requested; aborted NPI parquet export (output not updated).");
     } else {
         exporter.finish()?;
-        println!(
+        info!(
             "Wrote NPI resolved identifier dataset {}",
             output_path.display()
         );
@@ -596,6 +885,121 @@ pub fn export_npi_api_responses_parquet(
     Ok(())
 }
 
+/// Every NPI in the NPPES primary files (monthly and weekly), deduplicated and sorted.
+fn collect_nppes_primary_npis(
+    sources: &[NppesBulkFiles],
+    shutdown_requested: &Arc<AtomicBool>,
+) -> Result<Vec<String>> {
+    let mut npis = Vec::new();
+    for source in sources {
+        info!(
+            "Collecting NPIs from NPPES {} primary file {}",
+            source.label,
+            source.npidata_csv.display()
+        );
+        let mut reader = csv::ReaderBuilder::new().has_headers(true).from_reader(
+            open_maybe_gzip(&source.npidata_csv).with_context(|| {
+                format!("Failed opening NPPES CSV {}", source.npidata_csv.display())
+            })?,
+        );
+        let headers = reader
+            .headers()
+            .with_context(|| {
+                format!(
+                    "Failed reading NPPES headers from {}",
+                    source.npidata_csv.display()
+                )
+            })?
+            .clone();
+        let npi_idx = header_index(&headers, "NPI")?;
+        for (processed, row) in reader.records().enumerate() {
+            let row = row.with_context(|| {
+                format!("Failed reading record in {}", source.npidata_csv.display())
+            })?;
+            if processed % 100_000 == 0 && shutdown_requested.load(Ordering::SeqCst) {
+                return Ok(Vec::new());
+            }
+            let npi = normalize_npi(row.get(npi_idx).unwrap_or(""));
+            if !npi.is_empty() {
+                npis.push(npi.to_string());
+            }
+        }
+    }
+    npis.sort_unstable();
+    npis.dedup();
+    Ok(npis)
+}
+
+/// `--limit-bulk-to-dataset-npis=false`: writes every NPI in the local NPPES bulk files to
+/// `output_path` in the `npi.parquet` layout, independent of any spending dataset. No cache
+/// or API rows are involved.
+pub fn export_full_nppes_parquet(
+    args: &Args,
+    output_path: &Path,
+    api_run_id: &str,
+    shutdown_requested: &Arc<AtomicBool>,
+    nppes_monthly_dir: &Path,
+    nppes_weekly_dir: &Path,
+) -> Result<()> {
+    let bulk_sources = discover_nppes_bulk_sources(
+        nppes_monthly_dir,
+        nppes_weekly_dir,
+        NppesBulkMode::from_args(args),
+    )?;
+    if bulk_sources.is_empty() {
+        bail!(
+            "--limit-bulk-to-dataset-npis=false needs local NPPES bulk files under {} or {}",
+            nppes_monthly_dir.display(),
+            nppes_weekly_dir.display()
+        );
+    }
+
+    info!(
+        "Exporting the full NPPES registry to {} (not limited to dataset NPIs)...",
+        output_path.display()
+    );
+    let all_npis = collect_nppes_primary_npis(&bulk_sources, shutdown_requested)?;
+    if shutdown_requested.load(Ordering::SeqCst) {
+        info!("Shutdown requested; full NPPES export not written.");
+        return Ok(());
+    }
+    info!("Found {} distinct NPIs.", format_count(all_npis.len()));
+
+    let mut exporter = NpiResolvedParquetExporter::try_new(
+        output_path,
+        &all_npis,
+        api_run_id,
+        ParquetOutputOptions {
+            columns: args.npi_output_columns.as_deref(),
+            content_hash: args.content_hash,
+            sort_output: args.sort_output,
+            ndjson: args.emit_ndjson,
+            compression: args.parquet_compression,
+        },
+        &args.exclude_npi_types,
+    )?;
+    exporter.scan = BulkScanOptions::from_args(args);
+    exporter.load_supplemental_records(&bulk_sources, shutdown_requested)?;
+    // Newest first so its rows win for NPIs present in several files.
+    for source in &bulk_sources {
+        let _ = exporter.write_bulk_from_primary(None, source, shutdown_requested)?;
+    }
+
+    if shutdown_requested.load(Ordering::SeqCst) {
+        exporter.abort()?;
+        info!("Shutdown requested; aborted full NPPES export (output not updated).");
+    } else {
+        let written = exporter.resolved_rows;
+        exporter.finish()?;
+        info!(
+            "Wrote {} NPPES rows to {}",
+            format_count(written),
+            output_path.display()
+        );
+    }
+    Ok(())
+}
+
 pub fn backfill_npi_api_responses_from_legacy_parquet(
     cache_db: &Path,
     legacy_parquet: &Path,
@@ -618,6 +1022,22 @@ pub fn backfill_npi_api_responses_from_legacy_parquet(
     let conn = Connection::open_in_memory()
         .context("Failed opening DuckDB for NPI legacy parquet import")?;
     let legacy_escaped = sql_escape_path(legacy_parquet);
+    // Older legacy parquets predate the `http_status` column; backfill NULL for those.
+    let legacy_has_http_status: i64 = conn
+        .query_row(
+            &format!(
+                "SELECT COUNT(*) FROM (DESCRIBE SELECT * FROM read_parquet('{legacy_escaped}')) \
+                 WHERE column_name = 'http_status'"
+            ),
+            [],
+            |row| row.get(0),
+        )
+        .context("Failed describing NPI legacy parquet columns")?;
+    let http_status_expr = if legacy_has_http_status > 0 {
+        "TRY_CAST(http_status AS BIGINT)"
+    } else {
+        "CAST(NULL AS BIGINT)"
+    };
     let query = format!(
         "
         SELECT * EXCLUDE (rn) FROM (
@@ -637,6 +1057,7 @@ pub fn backfill_npi_api_responses_from_legacy_parquet(
                 request_params_json,
                 results_json,
                 response_json_raw,
+                {http_status_expr} AS http_status,
                 row_number() OVER (PARTITION BY npi ORDER BY requested_at_utc DESC) AS rn
             FROM read_parquet('{legacy_escaped}')
         ) WHERE rn = 1
@@ -678,6 +1099,7 @@ pub fn backfill_npi_api_responses_from_legacy_parquet(
         let results_json: Option<String> = row.get(13).context("Failed reading results_json")?;
         let response_json_raw: Option<String> =
             row.get(14).context("Failed reading response_json_raw")?;
+        let http_status: Option<i64> = row.get(15).context("Failed reading http_status")?;
 
         imported.push(NpiApiReferenceRow {
             npi,
@@ -689,11 +1111,13 @@ pub fn backfill_npi_api_responses_from_legacy_parquet(
             other_names_json,
             endpoints_json,
             request_url: request_url.unwrap_or_default(),
-            http_status: None,
+            http_status,
             error_message,
             api_run_id: api_run_id.unwrap_or_default(),
             requested_at_utc: requested_at_utc.unwrap_or_default(),
             request_params_json: request_params_json.unwrap_or_default(),
+            latency_ms: None,
+            duration_ms: None,
             results_json,
             response_json_raw,
         });
@@ -703,12 +1127,15 @@ pub fn backfill_npi_api_responses_from_legacy_parquet(
     Ok(imported.len())
 }
 
+/// `skipped_is_resolved` matches `classify_for_lookup`: without
+/// `--exclude-npi-types`, `skipped` rows are still pending and are reported.
 pub fn collect_unresolved_npis(
-    input_path: &Path,
+    npi_input: NpiInput<'_>,
     cache_db: &Path,
+    skipped_is_resolved: bool,
 ) -> Result<Vec<UnresolvedNpiEntry>> {
-    let unique_npis = extract_unique_npis(input_path)?;
-    let cache = NpiCache::open(cache_db)?;
+    let unique_npis = extract_unique_npis(npi_input)?;
+    let cache = NpiCache::open_read_only(cache_db)?;
     let mut stmt = cache
         .conn
         .prepare("SELECT status, error_message, fetched_at_unix FROM npi_cache WHERE npi = ?1")
@@ -722,7 +1149,8 @@ pub fn collect_unresolved_npis(
             .with_context(|| format!("Failed unresolved NPI lookup for {npi}"))?;
 
         match row {
-            Some((status, _error_message, _fetched_at_unix)) if status == "ok" => {}
+            Some((status, _error_message, _fetched_at_unix))
+                if status == "ok" || (skipped_is_resolved && status == "skipped") => {}
             Some((status, error_message, fetched_at_unix)) => unresolved.push(UnresolvedNpiEntry {
                 npi,
                 status,
@@ -752,7 +1180,96 @@ fn normalize_error_message(value: Option<String>) -> Option<String> {
     })
 }
 
-fn extract_unique_npis(input_path: &Path) -> Result<Vec<String>> {
+/// Where the NPI side reads its identifiers from.
+#[derive(Debug, Clone, Copy)]
+pub enum NpiInput<'a> {
+    /// Spending file: distinct billing + servicing NPIs.
+    Spending(&'a Path),
+    /// `--npi-list`: one NPI per line, or `column` of a CSV with a header row.
+    List {
+        path: &'a Path,
+        column: Option<&'a str>,
+    },
+}
+
+impl<'a> NpiInput<'a> {
+    pub fn from_args(args: &'a Args, input_path: &'a Path) -> Self {
+        match &args.npi_list {
+            Some(path) => NpiInput::List {
+                path,
+                column: args.npi_list_column.as_deref(),
+            },
+            None => NpiInput::Spending(input_path),
+        }
+    }
+
+    fn source_path(&self) -> &'a Path {
+        match self {
+            NpiInput::Spending(path) | NpiInput::List { path, .. } => path,
+        }
+    }
+
+    fn empty_hint(&self) -> &'static str {
+        match self {
+            NpiInput::Spending(_) => {
+                "check that the file has rows and BILLING_PROVIDER_NPI_NUM / SERVICING_PROVIDER_NPI_NUM columns"
+            }
+            NpiInput::List {
+                column: Some(_), ..
+            } => "check that the file has rows and that --npi-list-column names the NPI column",
+            NpiInput::List { column: None, .. } => "check that the file has one NPI per line",
+        }
+    }
+}
+
+fn extract_unique_npis(npi_input: NpiInput<'_>) -> Result<Vec<String>> {
+    match npi_input {
+        NpiInput::Spending(input_path) => extract_unique_spending_npis(input_path),
+        NpiInput::List { path, column } => read_npi_list(path, column),
+    }
+}
+
+/// Distinct non-blank NPIs from an `--npi-list` file, in first-seen order.
+fn read_npi_list(path: &Path, column: Option<&str>) -> Result<Vec<String>> {
+    let mut raw_values = Vec::new();
+    match column {
+        Some(column) => {
+            let mut reader = csv::Reader::from_path(path)
+                .with_context(|| format!("Failed opening NPI list CSV {}", path.display()))?;
+            let headers = reader
+                .headers()
+                .with_context(|| format!("Failed reading header of {}", path.display()))?;
+            let Some(idx) = headers.iter().position(|h| h.trim() == column) else {
+                bail!(
+                    "Column {column:?} not found in NPI list CSV {}",
+                    path.display()
+                );
+            };
+            for record in reader.records() {
+                let record = record
+                    .with_context(|| format!("Failed reading record in {}", path.display()))?;
+                raw_values.push(record.get(idx).unwrap_or("").to_string());
+            }
+        }
+        None => {
+            let text = fs::read_to_string(path)
+                .with_context(|| format!("Failed reading NPI list {}", path.display()))?;
+            raw_values.extend(text.lines().map(str::to_string));
+        }
+    }
+
+    let mut seen = HashSet::new();
+    let mut npis = Vec::new();
+    for raw in &raw_values {
+        let npi = normalize_npi(raw);
+        if !npi.is_empty() && seen.insert(npi.to_string()) {
+            npis.push(npi.to_string());
+        }
+    }
+    Ok(npis)
+}
+
+fn extract_unique_spending_npis(input_path: &Path) -> Result<Vec<String>> {
     let conn = Connection::open_in_memory().context("Failed opening DuckDB")?;
     let source = source_expr(input_path)?;
     let query = format!(
@@ -784,6 +1301,54 @@ fn extract_unique_npis(input_path: &Path) -> Result<Vec<String>> {
     Ok(npis)
 }
 
+struct NpiRoleCounts {
+    billing_only: usize,
+    servicing_only: usize,
+    both: usize,
+}
+
+fn count_npi_roles(input_path: &Path) -> Result<NpiRoleCounts> {
+    let conn = Connection::open_in_memory().context("Failed opening DuckDB")?;
+    let source = source_expr(input_path)?;
+    let query = format!(
+        "
+        WITH src AS (
+            SELECT * FROM {source}
+        ),
+        roles AS (
+            SELECT TRIM(CAST(BILLING_PROVIDER_NPI_NUM AS VARCHAR)) AS npi, 1 AS billing, 0 AS servicing FROM src
+            UNION ALL
+            SELECT TRIM(CAST(SERVICING_PROVIDER_NPI_NUM AS VARCHAR)) AS npi, 0 AS billing, 1 AS servicing FROM src
+        ),
+        per_npi AS (
+            SELECT npi, MAX(billing) AS billing, MAX(servicing) AS servicing
+            FROM roles
+            WHERE npi IS NOT NULL AND npi <> ''
+            GROUP BY npi
+        )
+        SELECT
+            COUNT(*) FILTER (WHERE billing = 1 AND servicing = 0),
+            COUNT(*) FILTER (WHERE billing = 0 AND servicing = 1),
+            COUNT(*) FILTER (WHERE billing = 1 AND servicing = 1)
+        FROM per_npi
+        "
+    );
+    let (billing_only, servicing_only, both) = conn
+        .query_row(&query, [], |row| {
+            Ok((
+                row.get::<usize, i64>(0)?,
+                row.get::<usize, i64>(1)?,
+                row.get::<usize, i64>(2)?,
+            ))
+        })
+        .context("Failed counting NPI billing/servicing roles")?;
+    Ok(NpiRoleCounts {
+        billing_only: billing_only.max(0) as usize,
+        servicing_only: servicing_only.max(0) as usize,
+        both: both.max(0) as usize,
+    })
+}
+
 fn format_count(value: usize) -> String {
     let digits = value.to_string();
     let mut out = String::with_capacity(digits.len() + digits.len() / 3);
@@ -832,30 +1397,30 @@ fn print_npi_download_plan_table(
     let border = "+--------------------------------------------+--------------------------+";
     let section = "| NPI API PRE-DOWNLOAD SUMMARY               |                          |";
 
-    println!();
-    println!("{bold}{cyan}{border}{reset}");
-    println!("{bold}{cyan}{section}{reset}");
-    println!("{bold}{cyan}{border}{reset}");
-    println!(
+    info!();
+    info!("{bold}{cyan}{border}{reset}");
+    info!("{bold}{cyan}{section}{reset}");
+    info!("{bold}{cyan}{border}{reset}");
+    info!(
         "| {:<42} | {:<24} |",
         "Unique NPIs in dataset",
         format_count(dataset_unique_npis)
     );
-    println!(
+    info!(
         "| {:<42} | {}{:<24}{} |",
         "Already saved in cache",
         green,
         format_count(resolved_before_bulk),
         reset
     );
-    println!(
+    info!(
         "| {:<42} | {}{:<24}{} |",
         "Found via NPPES bulk this run",
         green,
         format_count(found_via_bulk),
         reset
     );
-    println!(
+    info!(
         "| {:<42} | {}{:<24}{} |",
         "Total resolved before API",
         green,
@@ -866,39 +1431,39 @@ fn print_npi_download_plan_table(
         ),
         reset
     );
-    println!(
+    info!(
         "| {:<42} | {}{:<24}{} |",
         "Still unresolved",
         yellow,
         format_count(unresolved_before_limit),
         reset
     );
-    println!(
+    info!(
         "| {:<42} | {}{:<24}{} |",
         "Planned API downloads now",
         magenta,
         format_count(planned_api_lookups),
         reset
     );
-    println!(
+    info!(
         "| {:<42} | {:<24} |",
         "Bulk rows matched (monthly + weekly)",
         format_count(bulk_rows_matched)
     );
-    println!(
+    info!(
         "| {:<42} | {:<24} |",
         "Monthly source matched rows",
         format_count(monthly_loaded)
     );
-    println!(
+    info!(
         "| {:<42} | {:<24} |",
         "Weekly source matched rows",
         format_count(weekly_loaded)
     );
-    println!("{bold}{cyan}{border}{reset}");
-    println!("  monthly source: {}", monthly_src);
-    println!("  weekly source:  {}", weekly_src);
-    println!();
+    info!("{bold}{cyan}{border}{reset}");
+    info!("  monthly source: {}", monthly_src);
+    info!("  weekly source:  {}", weekly_src);
+    info!();
 }
 
 fn json_to_string_opt(value: Option<&Value>) -> Option<String> {
@@ -936,6 +1501,8 @@ fn build_npi_reference_row_from_value(
         api_run_id: api_run_id.to_string(),
         requested_at_utc: requested_at_utc.to_string(),
         request_params_json: request_params_json.to_string(),
+        latency_ms: None,
+        duration_ms: None,
         results_json: json_to_string_opt(response_value.get("results")),
         response_json_raw: serde_json::to_string(response_value).ok(),
     }
@@ -987,10 +1554,9 @@ fn collect_csv_paths_recursive(dir: &Path, out: &mut Vec<PathBuf>) -> Result<()>
         if path.is_dir() {
             collect_csv_paths_recursive(&path, out)?;
         } else if path
-            .extension()
-            .and_then(|ext| ext.to_str())
-            .map(|ext| ext.eq_ignore_ascii_case("csv"))
-            .unwrap_or(false)
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(has_csv_suffix)
         {
             out.push(path);
         }
@@ -998,11 +1564,16 @@ fn collect_csv_paths_recursive(dir: &Path, out: &mut Vec<PathBuf>) -> Result<()>
     Ok(())
 }
 
+/// `.csv`, or `.csv.gz` for gzipped NPPES extracts (case-insensitive).
+fn has_csv_suffix(name: &str) -> bool {
+    let lower = name.to_ascii_lowercase();
+    lower.ends_with(".csv") || lower.ends_with(".csv.gz")
+}
+
 fn is_nppes_primary_csv(path: &Path) -> Result<bool> {
-    let mut reader = csv::ReaderBuilder::new()
-        .has_headers(true)
-        .from_path(path)
-        .with_context(|| format!("Failed opening CSV {}", path.display()))?;
+    let mut reader = csv::ReaderBuilder::new().has_headers(true).from_reader(
+        open_maybe_gzip(path).with_context(|| format!("Failed opening CSV {}", path.display()))?,
+    );
     let headers = reader
         .headers()
         .with_context(|| format!("Failed reading headers from {}", path.display()))?;
@@ -1042,25 +1613,6 @@ fn opt_header_index(headers: &csv::StringRecord, name: &str) -> Option<usize> {
     headers.iter().position(|h| h.trim() == name)
 }
 
-fn normalize_postal_code(raw: &str) -> String {
-    let mut out = String::with_capacity(raw.len());
-    for ch in raw.chars() {
-        if ch.is_ascii_digit() {
-            out.push(ch);
-        }
-    }
-    out
-}
-
-fn normalize_country_code(raw: &str) -> String {
-    let trimmed = raw.trim();
-    if trimmed.is_empty() {
-        "US".to_string()
-    } else {
-        trimmed.to_string()
-    }
-}
-
 fn country_name_for_code(code: &str) -> Option<&'static str> {
     if code.eq_ignore_ascii_case("US") {
         Some("United States")
@@ -1069,6 +1621,21 @@ fn country_name_for_code(code: &str) -> Option<&'static str> {
     }
 }
 
+/// `Other Provider Identifier Type Code` values of the NPPES dissemination file, worded as the
+/// NPPES API's identifier `desc`.
+fn identifier_type_description(code: &str) -> Option<&'static str> {
+    match code {
+        "01" => Some("Other (non-Medicare)"),
+        "02" => Some("MEDICARE UPIN"),
+        "04" => Some("MEDICARE ID-Type Unspecified"),
+        "05" => Some("MEDICAID"),
+        "06" => Some("MEDICARE OSCAR/CERTIFICATION"),
+        "07" => Some("MEDICARE NSC"),
+        "08" => Some("MEDICARE PIN"),
+        _ => None,
+    }
+}
+
 fn address_type_for_country(code: &str) -> &'static str {
     if code.eq_ignore_ascii_case("US") {
         "DOM"
@@ -1150,6 +1717,78 @@ impl NppesBulkFiles {
     }
 }
 
+/// How the exporter scans NPPES primary files.
+#[derive(Debug, Clone, Copy)]
+pub struct BulkScanOptions {
+    /// `--progress-interval-seconds`; `None` reports bulk scans every 1M rows.
+    pub progress_interval: Option<Duration>,
+    /// `--bulk-scan-threads`; 1 scans on the calling thread.
+    pub threads: usize,
+}
+
+impl BulkScanOptions {
+    pub fn from_args(args: &Args) -> Self {
+        Self {
+            progress_interval: args.progress_interval_seconds.map(Duration::from_secs),
+            threads: args.bulk_scan_threads,
+        }
+    }
+}
+
+/// Which local NPPES primary files to preload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NppesBulkMode {
+    /// `--skip-nppes-bulk`: cache/API only.
+    Skip,
+    /// Newest primary file per directory (monthly, weekly).
+    Latest,
+    /// `--nppes-merge-all`: every primary file found under either directory.
+    MergeAll,
+}
+
+impl NppesBulkMode {
+    pub fn from_args(args: &Args) -> Self {
+        if args.skip_nppes_bulk {
+            Self::Skip
+        } else if args.nppes_merge_all {
+            Self::MergeAll
+        } else {
+            Self::Latest
+        }
+    }
+}
+
+/// Primary files (plus their supplemental siblings) in write priority: weekly before monthly,
+/// newest first within each directory, so the first source to emit an NPI is the newest.
+fn discover_nppes_bulk_sources(
+    nppes_monthly_dir: &Path,
+    nppes_weekly_dir: &Path,
+    mode: NppesBulkMode,
+) -> Result<Vec<NppesBulkFiles>> {
+    let mut sources = Vec::new();
+    for (label, dir) in [("weekly", nppes_weekly_dir), ("monthly", nppes_monthly_dir)] {
+        let primaries = match mode {
+            NppesBulkMode::Skip => Vec::new(),
+            NppesBulkMode::Latest => select_latest_nppes_csv(dir)?.into_iter().collect(),
+            NppesBulkMode::MergeAll => {
+                let mut all = collect_nppes_csvs(dir)?;
+                all.reverse();
+                all
+            }
+        };
+        for primary in primaries {
+            sources.push(NppesBulkFiles {
+                label,
+                othername_csv: find_nppes_sibling_csv(&primary, "othername_pfile_")?,
+                pl_csv: find_nppes_sibling_csv(&primary, "pl_pfile_")?,
+                endpoint_csv: find_nppes_sibling_csv(&primary, "endpoint_pfile_")?,
+                npidata_csv: primary,
+            });
+        }
+    }
+    Ok(sources)
+}
+
 fn find_nppes_sibling_csv(primary_csv: &Path, prefix: &str) -> Result<Option<PathBuf>> {
     let Some(dir) = primary_csv.parent() else {
         return Ok(None);
@@ -1165,10 +1804,11 @@ fn find_nppes_sibling_csv(primary_csv: &Path, prefix: &str) -> Result<Option<Pat
         let Some(name) = path.file_name().and_then(|x| x.to_str()) else {
             continue;
         };
-        if !name.to_ascii_lowercase().ends_with(".csv") {
+        if !has_csv_suffix(name) {
             continue;
         }
-        if name.to_ascii_lowercase().ends_with("_fileheader.csv") {
+        let lower = name.to_ascii_lowercase();
+        if lower.ends_with("_fileheader.csv") || lower.ends_with("_fileheader.csv.gz") {
             continue;
         }
         if name.starts_with(prefix) {
@@ -1192,11 +1832,12 @@ fn load_othername_records<'a>(
     out: &mut HashMap<&'a str, Vec<OtherNameRecord>>,
     shutdown_requested: &Arc<AtomicBool>,
 ) -> Result<usize> {
-    println!("Loading NPPES othername file {}", csv_path.display());
-    let mut reader = csv::ReaderBuilder::new()
-        .has_headers(true)
-        .from_path(csv_path)
-        .with_context(|| format!("Failed opening NPPES othername CSV {}", csv_path.display()))?;
+    info!("Loading NPPES othername file {}", csv_path.display());
+    let mut reader = csv::ReaderBuilder::new().has_headers(true).from_reader(
+        open_maybe_gzip(csv_path).with_context(|| {
+            format!("Failed opening NPPES othername CSV {}", csv_path.display())
+        })?,
+    );
     let headers = reader
         .headers()
         .with_context(|| format!("Failed reading headers from {}", csv_path.display()))?
@@ -1213,14 +1854,14 @@ fn load_othername_records<'a>(
             row.with_context(|| format!("Failed reading record in {}", csv_path.display()))?;
         processed += 1;
         if processed % 100_000 == 0 && shutdown_requested.load(Ordering::SeqCst) {
-            println!(
+            info!(
                 "Shutdown requested while reading {}. Stopping othername load early.",
                 csv_path.display()
             );
             break;
         }
 
-        let npi = row.get(npi_idx).unwrap_or("").trim();
+        let npi = normalize_npi(row.get(npi_idx).unwrap_or(""));
         let Some(key_ref) = target_npis.get(npi) else {
             continue;
         };
@@ -1237,7 +1878,7 @@ fn load_othername_records<'a>(
         loaded += 1;
     }
 
-    println!(
+    info!(
         "Loaded {} othername rows (scanned {}).",
         format_count(loaded),
         format_count(processed)
@@ -1251,14 +1892,14 @@ fn load_practice_location_records<'a>(
     out: &mut HashMap<&'a str, Vec<PracticeLocationRecord>>,
     shutdown_requested: &Arc<AtomicBool>,
 ) -> Result<usize> {
-    println!(
+    info!(
         "Loading NPPES secondary practice location file {}",
         csv_path.display()
     );
-    let mut reader = csv::ReaderBuilder::new()
-        .has_headers(true)
-        .from_path(csv_path)
-        .with_context(|| format!("Failed opening NPPES pl CSV {}", csv_path.display()))?;
+    let mut reader = csv::ReaderBuilder::new().has_headers(true).from_reader(
+        open_maybe_gzip(csv_path)
+            .with_context(|| format!("Failed opening NPPES pl CSV {}", csv_path.display()))?,
+    );
     let headers = reader
         .headers()
         .with_context(|| format!("Failed reading headers from {}", csv_path.display()))?
@@ -1306,14 +1947,14 @@ fn load_practice_location_records<'a>(
             row.with_context(|| format!("Failed reading record in {}", csv_path.display()))?;
         processed += 1;
         if processed % 100_000 == 0 && shutdown_requested.load(Ordering::SeqCst) {
-            println!(
+            info!(
                 "Shutdown requested while reading {}. Stopping practice location load early.",
                 csv_path.display()
             );
             break;
         }
 
-        let npi = row.get(npi_idx).unwrap_or("").trim();
+        let npi = normalize_npi(row.get(npi_idx).unwrap_or(""));
         let Some(key_ref) = target_npis.get(npi) else {
             continue;
         };
@@ -1353,7 +1994,7 @@ fn load_practice_location_records<'a>(
         loaded += 1;
     }
 
-    println!(
+    info!(
         "Loaded {} secondary practice location rows (scanned {}).",
         format_count(loaded),
         format_count(processed)
@@ -1367,11 +2008,12 @@ fn load_endpoint_records<'a>(
     out: &mut HashMap<&'a str, Vec<EndpointRecord>>,
     shutdown_requested: &Arc<AtomicBool>,
 ) -> Result<usize> {
-    println!("Loading NPPES endpoint file {}", csv_path.display());
+    info!("Loading NPPES endpoint file {}", csv_path.display());
     let mut reader = csv::ReaderBuilder::new()
         .has_headers(true)
-        .from_path(csv_path)
-        .with_context(|| format!("Failed opening NPPES endpoint CSV {}", csv_path.display()))?;
+        .from_reader(open_maybe_gzip(csv_path).with_context(|| {
+            format!("Failed opening NPPES endpoint CSV {}", csv_path.display())
+        })?);
     let headers = reader
         .headers()
         .with_context(|| format!("Failed reading headers from {}", csv_path.display()))?
@@ -1404,14 +2046,14 @@ fn load_endpoint_records<'a>(
             row.with_context(|| format!("Failed reading record in {}", csv_path.display()))?;
         processed += 1;
         if processed % 100_000 == 0 && shutdown_requested.load(Ordering::SeqCst) {
-            println!(
+            info!(
                 "Shutdown requested while reading {}. Stopping endpoint load early.",
                 csv_path.display()
             );
             break;
         }
 
-        let npi = row.get(npi_idx).unwrap_or("").trim();
+        let npi = normalize_npi(row.get(npi_idx).unwrap_or(""));
         let Some(key_ref) = target_npis.get(npi) else {
             continue;
         };
@@ -1461,7 +2103,7 @@ fn load_endpoint_records<'a>(
         loaded += 1;
     }
 
-    println!(
+    info!(
         "Loaded {} endpoint rows (scanned {}).",
         format_count(loaded),
         format_count(processed)
@@ -1517,6 +2159,7 @@ struct NppesPrimaryIndices {
     last_update_date: Option<usize>,
     deactivation_date: Option<usize>,
     reactivation_date: Option<usize>,
+    replacement_npi: Option<usize>,
     sex: Option<usize>,
     authorized_official_last: Option<usize>,
     authorized_official_first: Option<usize>,
@@ -1602,6 +2245,8 @@ impl NppesPrimaryIndices {
         let last_update_date = opt_header_index(headers, "Last Update Date");
         let deactivation_date = opt_header_index(headers, "NPI Deactivation Date");
         let reactivation_date = opt_header_index(headers, "NPI Reactivation Date");
+        // Only present in some NPPES extracts; absence simply leaves the field out of `basic`.
+        let replacement_npi = opt_header_index(headers, "Replacement NPI");
         let sex = opt_header_index(headers, "Provider Sex Code");
 
         let authorized_official_last = opt_header_index(headers, "Authorized Official Last Name");
@@ -1685,6 +2330,7 @@ impl NppesPrimaryIndices {
             last_update_date,
             deactivation_date,
             reactivation_date,
+            replacement_npi,
             sex,
             authorized_official_last,
             authorized_official_first,
@@ -1707,8 +2353,28 @@ fn row_value<'r>(row: &'r csv::StringRecord, idx: Option<usize>) -> &'r str {
     idx.and_then(|i| row.get(i)).unwrap_or("").trim()
 }
 
+/// Display name for an NPPES primary row: organization name, else `first last` (either half
+/// alone when the other is blank). Empty when the row carries no name at all.
+fn bulk_provider_name(row: &csv::StringRecord, idx: &NppesPrimaryIndices) -> String {
+    let org_name = row_value(row, idx.org_name);
+    let first_name = row_value(row, idx.first_name);
+    let last_name = row_value(row, idx.last_name);
+    if !org_name.is_empty() {
+        org_name.to_string()
+    } else if !first_name.is_empty() && !last_name.is_empty() {
+        format!("{first_name} {last_name}")
+    } else if !first_name.is_empty() {
+        first_name.to_string()
+    } else if !last_name.is_empty() {
+        last_name.to_string()
+    } else {
+        String::new()
+    }
+}
+
 #[derive(Debug, Clone)]
 struct BulkNpiJsonRow {
+    deactivated: bool,
     basic_json: String,
     addresses_json: String,
     practice_locations_json: String,
@@ -1720,6 +2386,67 @@ struct BulkNpiJsonRow {
     response_json: String,
 }
 
+fn entity_type_excluded(exclude_entity_types: &[String], entity_type_code: &str) -> bool {
+    !entity_type_code.is_empty()
+        && exclude_entity_types
+            .iter()
+            .any(|excluded| excluded == entity_type_code)
+}
+
+/// Request metadata shared by every row emitted from one NPPES primary file.
+struct BulkRowMeta {
+    url_sentinel: String,
+    api_run_id: String,
+    requested_at_utc: String,
+    request_params_json: String,
+}
+
+fn push_bulk_row(
+    writer: &mut StringParquetWriter,
+    npi: &str,
+    provider_name: &str,
+    json_row: &BulkNpiJsonRow,
+    meta: &BulkRowMeta,
+) -> Result<()> {
+    writer.push_row(&[
+        Some(npi),
+        Some(provider_name).filter(|name| !name.is_empty()),
+        Some(if json_row.deactivated {
+            "true"
+        } else {
+            "false"
+        }),
+        Some(json_row.basic_json.as_str()),
+        Some(json_row.addresses_json.as_str()),
+        Some(json_row.practice_locations_json.as_str()),
+        Some(json_row.taxonomies_json.as_str()),
+        Some(json_row.identifiers_json.as_str()),
+        Some(json_row.other_names_json.as_str()),
+        Some(json_row.endpoints_json.as_str()),
+        Some(meta.url_sentinel.as_str()),
+        None,
+        Some(meta.api_run_id.as_str()),
+        Some(meta.requested_at_utc.as_str()),
+        Some(meta.request_params_json.as_str()),
+        None,
+        None,
+        None,
+        Some(json_row.results_json.as_str()),
+        Some(json_row.response_json.as_str()),
+    ])
+}
+
+/// A dataset NPI found by a `--bulk-scan-threads` worker.
+enum BulkScanMatch {
+    /// Entity type excluded by `--exclude-npi-types`.
+    Excluded(String),
+    Row {
+        npi: String,
+        provider_name: String,
+        json_row: Box<BulkNpiJsonRow>,
+    },
+}
+
 fn build_bulk_npi_json_row(
     npi: &str,
     row: &csv::StringRecord,
@@ -1768,6 +2495,13 @@ fn build_bulk_npi_json_row(
             Value::String(certification_date.to_string()),
         );
     }
+    let replacement_npi = row_value(row, idx.replacement_npi);
+    if !replacement_npi.is_empty() {
+        basic.insert(
+            "replacement_npi".to_string(),
+            Value::String(replacement_npi.to_string()),
+        );
+    }
     let credential = row_value(row, idx.credential);
     if !credential.is_empty() {
         basic.insert(
@@ -2022,7 +2756,10 @@ fn build_bulk_npi_json_row(
 
         let mut obj = serde_json::Map::new();
         obj.insert("code".to_string(), Value::String(code.to_string()));
-        obj.insert("desc".to_string(), Value::Null);
+        obj.insert(
+            "desc".to_string(),
+            taxonomy_description(code).map_or(Value::Null, |desc| Value::String(desc.to_string())),
+        );
         obj.insert(
             "license".to_string(),
             if license_raw.is_empty() {
@@ -2067,7 +2804,11 @@ fn build_bulk_npi_json_row(
         if !code_raw.is_empty() {
             obj.insert("code".to_string(), Value::String(code_raw.to_string()));
         }
-        obj.insert("desc".to_string(), Value::Null);
+        obj.insert(
+            "desc".to_string(),
+            identifier_type_description(code_raw)
+                .map_or(Value::Null, |desc| Value::String(desc.to_string())),
+        );
         if !state_raw.is_empty() {
             obj.insert("state".to_string(), Value::String(state_raw.to_string()));
         }
@@ -2252,6 +2993,7 @@ fn build_bulk_npi_json_row(
     let response_json = format!("{{\"result_count\":1,\"results\":{results_json}}}");
 
     BulkNpiJsonRow {
+        deactivated: status == "D",
         basic_json,
         addresses_json,
         practice_locations_json,
@@ -2264,6 +3006,71 @@ fn build_bulk_npi_json_row(
     }
 }
 
+/// Column layout of `npi.parquet` (select a subset with `--npi-output-columns`).
+pub const NPI_PARQUET_COLUMNS: &[&str] = &[
+    "npi",
+    "provider_name",
+    "deactivated",
+    "basic",
+    "addresses",
+    "practice_locations",
+    "taxonomies",
+    "identifiers",
+    "other_names",
+    "endpoints",
+    "url",
+    "error_message",
+    "api_run_id",
+    "requested_at_utc",
+    "request_params",
+    "latency_ms",
+    "http_status",
+    "duration_ms",
+    "results",
+    "response_json",
+];
+
+/// Columns fed to `content_hash` (`--content-hash`): the NPI and its structured record.
+/// Request metadata (`url`, `api_run_id`, `requested_at_utc`, `request_params`, `latency_ms`,
+/// `http_status`, `duration_ms`), `error_message`, the derived `provider_name` and
+/// `deactivated` (both already in `basic`), and the raw `results`/`response_json` are excluded.
+pub const NPI_CONTENT_HASH_COLUMNS: &[&str] = &[
+    "npi",
+    "basic",
+    "addresses",
+    "practice_locations",
+    "taxonomies",
+    "identifiers",
+    "other_names",
+    "endpoints",
+];
+
+// Share of resolved NPIs with `basic.status = "D"` above which the exporter warns about a
+// likely period mismatch between the spending dataset and the NPPES release.
+const DEACTIVATED_SHARE_WARN_THRESHOLD: f64 = 0.05;
+
+/// NPPES entity type code (`1`/`2`) from an API `results` array (`enumeration_type` NPI-1/NPI-2).
+fn entity_type_code_from_results(results_json: Option<&str>) -> Option<&'static str> {
+    let results: Value = serde_json::from_str(results_json?).ok()?;
+    match results.get(0)?.get("enumeration_type")?.as_str()? {
+        "NPI-1" => Some("1"),
+        "NPI-2" => Some("2"),
+        _ => None,
+    }
+}
+
+fn basic_status_is_deactivated(basic_json: &str) -> bool {
+    serde_json::from_str::<Value>(basic_json)
+        .ok()
+        .and_then(|basic| {
+            basic
+                .get("status")
+                .and_then(Value::as_str)
+                .map(|status| status.eq_ignore_ascii_case("D"))
+        })
+        .unwrap_or(false)
+}
+
 struct NpiResolvedParquetExporter<'a> {
     unique_npis: &'a [String],
     remaining: HashSet<&'a str>,
@@ -2273,28 +3080,27 @@ struct NpiResolvedParquetExporter<'a> {
     writer: StringParquetWriter,
     requested_at_utc: String,
     api_run_id: String,
+    resolved_rows: usize,
+    deactivated_rows: usize,
+    exclude_entity_types: &'a [String],
+    scan: BulkScanOptions,
 }
 
 impl<'a> NpiResolvedParquetExporter<'a> {
-    fn try_new(output_path: &Path, unique_npis: &'a [String], api_run_id: &str) -> Result<Self> {
-        let columns = [
-            "npi",
-            "basic",
-            "addresses",
-            "practice_locations",
-            "taxonomies",
-            "identifiers",
-            "other_names",
-            "endpoints",
-            "url",
-            "error_message",
-            "api_run_id",
-            "requested_at_utc",
-            "request_params",
-            "results",
-            "response_json",
-        ];
-        let writer = StringParquetWriter::try_new(output_path, &columns, 10_000)?;
+    fn try_new(
+        output_path: &Path,
+        unique_npis: &'a [String],
+        api_run_id: &str,
+        output: ParquetOutputOptions<'_>,
+        exclude_entity_types: &'a [String],
+    ) -> Result<Self> {
+        let writer = StringParquetWriter::try_new(
+            output_path,
+            NPI_PARQUET_COLUMNS,
+            output,
+            NPI_CONTENT_HASH_COLUMNS,
+            10_000,
+        )?;
         let remaining: HashSet<&str> = unique_npis.iter().map(|s| s.as_str()).collect();
         Ok(Self {
             unique_npis,
@@ -2305,39 +3111,53 @@ impl<'a> NpiResolvedParquetExporter<'a> {
             writer,
             requested_at_utc: now_unix_seconds().to_string(),
             api_run_id: api_run_id.to_string(),
+            resolved_rows: 0,
+            deactivated_rows: 0,
+            exclude_entity_types,
+            scan: BulkScanOptions {
+                progress_interval: None,
+                threads: 1,
+            },
         })
     }
 
+    /// `sources` are in write priority (newest first). Files are loaded oldest first and each
+    /// file's records replace an NPI's earlier ones, so the newest file wins per NPI. A sibling
+    /// shared by several primaries in one directory is loaded once.
     fn load_supplemental_records(
         &mut self,
         sources: &[NppesBulkFiles],
         shutdown_requested: &Arc<AtomicBool>,
     ) -> Result<()> {
         let target_npis = &self.remaining;
-        for source in sources {
-            if let Some(path) = source.othername_csv.as_deref() {
-                let _ = load_othername_records(
-                    path,
-                    target_npis,
-                    &mut self.other_names,
-                    shutdown_requested,
-                )?;
+        let mut loaded_paths: HashSet<&Path> = HashSet::new();
+        for source in sources.iter().rev() {
+            if let Some(path) = source.othername_csv.as_deref()
+                && loaded_paths.insert(path)
+            {
+                let mut records = HashMap::new();
+                let _ =
+                    load_othername_records(path, target_npis, &mut records, shutdown_requested)?;
+                self.other_names.extend(records);
             }
-            if let Some(path) = source.pl_csv.as_deref() {
+            if let Some(path) = source.pl_csv.as_deref()
+                && loaded_paths.insert(path)
+            {
+                let mut records = HashMap::new();
                 let _ = load_practice_location_records(
                     path,
                     target_npis,
-                    &mut self.practice_locations,
+                    &mut records,
                     shutdown_requested,
                 )?;
+                self.practice_locations.extend(records);
             }
-            if let Some(path) = source.endpoint_csv.as_deref() {
-                let _ = load_endpoint_records(
-                    path,
-                    target_npis,
-                    &mut self.endpoints,
-                    shutdown_requested,
-                )?;
+            if let Some(path) = source.endpoint_csv.as_deref()
+                && loaded_paths.insert(path)
+            {
+                let mut records = HashMap::new();
+                let _ = load_endpoint_records(path, target_npis, &mut records, shutdown_requested)?;
+                self.endpoints.extend(records);
             }
         }
         Ok(())
@@ -2349,18 +3169,17 @@ impl<'a> NpiResolvedParquetExporter<'a> {
         source: &NppesBulkFiles,
         shutdown_requested: &Arc<AtomicBool>,
     ) -> Result<usize> {
-        println!(
+        info!(
             "Exporting bulk NPI rows from NPPES {} primary file {}",
             source.label,
             source.npidata_csv.display()
         );
 
-        let mut reader = csv::ReaderBuilder::new()
-            .has_headers(true)
-            .from_path(&source.npidata_csv)
-            .with_context(|| {
+        let mut reader = csv::ReaderBuilder::new().has_headers(true).from_reader(
+            open_maybe_gzip(&source.npidata_csv).with_context(|| {
                 format!("Failed opening NPPES CSV {}", source.npidata_csv.display())
-            })?;
+            })?,
+        );
         let headers = reader
             .headers()
             .with_context(|| {
@@ -2372,10 +3191,12 @@ impl<'a> NpiResolvedParquetExporter<'a> {
             .clone();
         let idx = NppesPrimaryIndices::from_headers(&headers)?;
 
-        let request_params_json = source.request_params_json();
-        let url_sentinel = source.url_sentinel();
-        let requested_at_utc = self.requested_at_utc.clone();
-        let api_run_id = self.api_run_id.clone();
+        let meta = BulkRowMeta {
+            url_sentinel: source.url_sentinel(),
+            api_run_id: self.api_run_id.clone(),
+            requested_at_utc: self.requested_at_utc.clone(),
+            request_params_json: source.request_params_json(),
+        };
 
         // Cache preload is optional (export-only runs should not mutate the cache).
         let mut stmt = if let Some(cache) = cache {
@@ -2387,13 +3208,14 @@ impl<'a> NpiResolvedParquetExporter<'a> {
                 cache.conn
                     .prepare(
                         "
-                        INSERT INTO npi_cache (npi, provider_name, status, error_message, fetched_at_unix)
-                        VALUES (?1, ?2, 'ok', NULL, strftime('%s', 'now'))
+                        INSERT INTO npi_cache (npi, provider_name, status, error_message, fetched_at_unix, deactivated)
+                        VALUES (?1, ?2, 'ok', NULL, strftime('%s', 'now'), ?3)
                         ON CONFLICT(npi) DO UPDATE SET
                             provider_name = excluded.provider_name,
                             status = excluded.status,
                             error_message = excluded.error_message,
-                            fetched_at_unix = excluded.fetched_at_unix
+                            fetched_at_unix = excluded.fetched_at_unix,
+                            deactivated = excluded.deactivated
                         ",
                     )
                     .context("Failed preparing NPPES cache upsert statement")?,
@@ -2402,102 +3224,114 @@ impl<'a> NpiResolvedParquetExporter<'a> {
             None
         };
 
-        let scan_result: Result<(usize, usize)> = (|| {
-            let mut processed = 0usize;
-            let mut emitted = 0usize;
-            for row in reader.records() {
-                let row = row.with_context(|| {
-                    format!("Failed reading record in {}", source.npidata_csv.display())
-                })?;
-                processed += 1;
-
-                if processed % 50_000 == 0 {
-                    if shutdown_requested.load(Ordering::SeqCst) {
-                        println!(
-                            "Shutdown requested while reading {}. Stopping bulk export early.",
-                            source.npidata_csv.display()
-                        );
-                        break;
-                    }
-                    if processed % 1_000_000 == 0 {
-                        println!(
-                            "Scanned {} rows from {} (emitted {} remaining {}).",
-                            format_count(processed),
-                            source.label,
-                            format_count(emitted),
-                            format_count(self.remaining.len())
-                        );
+        let mut scan_ticker = StatusTicker::new(self.scan.progress_interval);
+        // Byte-range splitting needs an uncompressed file.
+        let parallel = self.scan.threads > 1 && !is_gzip_path(&source.npidata_csv);
+        if self.scan.threads > 1 && !parallel {
+            info!(
+                "{} is gzipped; scanning it on one thread despite --bulk-scan-threads.",
+                source.npidata_csv.display()
+            );
+        }
+        let scan_result: Result<(usize, usize, usize)> = if parallel {
+            self.scan_primary_parallel(
+                source,
+                &idx,
+                stmt.as_mut(),
+                cache,
+                &meta,
+                shutdown_requested,
+            )
+        } else {
+            (|| {
+                let mut processed = 0usize;
+                let mut emitted = 0usize;
+                let mut skipped = 0usize;
+                for row in reader.records() {
+                    let row = row.with_context(|| {
+                        format!("Failed reading record in {}", source.npidata_csv.display())
+                    })?;
+                    processed += 1;
+
+                    if processed.is_multiple_of(50_000) {
+                        if shutdown_requested.load(Ordering::SeqCst) {
+                            info!(
+                                "Shutdown requested while reading {}. Stopping bulk export early.",
+                                source.npidata_csv.display()
+                            );
+                            break;
+                        }
+                        let report_scan = if scan_ticker.is_enabled() {
+                            scan_ticker.due()
+                        } else {
+                            processed.is_multiple_of(1_000_000)
+                        };
+                        if report_scan {
+                            info!(
+                                "Scanned {} rows from {} (emitted {} remaining {}).",
+                                format_count(processed),
+                                source.label,
+                                format_count(emitted),
+                                format_count(self.remaining.len())
+                            );
+                        }
                     }
-                }
 
-                let npi = row.get(idx.npi).unwrap_or("").trim();
-                if npi.is_empty() || !self.remaining.contains(npi) {
-                    continue;
-                }
-
-                if let Some(stmt) = stmt.as_mut() {
-                    let org_name = row_value(&row, idx.org_name);
-                    let first_name = row_value(&row, idx.first_name);
-                    let last_name = row_value(&row, idx.last_name);
-                    let provider_name = if !org_name.is_empty() {
-                        org_name.to_string()
-                    } else if !first_name.is_empty() && !last_name.is_empty() {
-                        format!("{first_name} {last_name}")
-                    } else if !first_name.is_empty() {
-                        first_name.to_string()
-                    } else if !last_name.is_empty() {
-                        last_name.to_string()
-                    } else {
-                        String::new()
-                    };
+                    let npi = normalize_npi(row.get(idx.npi).unwrap_or(""));
+                    if npi.is_empty() || !self.remaining.contains(npi) {
+                        continue;
+                    }
 
-                    stmt.execute(params![npi, provider_name])
-                        .with_context(|| format!("Failed upserting preloaded NPI {npi}"))?;
-                }
+                    if self.is_excluded_entity_type(row_value(&row, idx.entity_type)) {
+                        if let Some(cache) = cache {
+                            cache.upsert_skipped(npi)?;
+                        }
+                        self.remaining.remove(npi);
+                        skipped += 1;
+                        if self.remaining.is_empty() {
+                            break;
+                        }
+                        continue;
+                    }
 
-                let other_names = self.other_names.remove(npi).unwrap_or_default();
-                let practice_locations = self.practice_locations.remove(npi).unwrap_or_default();
-                let endpoints = self.endpoints.remove(npi).unwrap_or_default();
+                    let provider_name = bulk_provider_name(&row, &idx);
+                    let other_names = self.other_names.remove(npi).unwrap_or_default();
+                    let practice_locations =
+                        self.practice_locations.remove(npi).unwrap_or_default();
+                    let endpoints = self.endpoints.remove(npi).unwrap_or_default();
 
-                let json_row = build_bulk_npi_json_row(
-                    npi,
-                    &row,
-                    &idx,
-                    &other_names,
-                    &practice_locations,
-                    &endpoints,
-                );
+                    let json_row = build_bulk_npi_json_row(
+                        npi,
+                        &row,
+                        &idx,
+                        &other_names,
+                        &practice_locations,
+                        &endpoints,
+                    );
 
-                self.writer.push_row(&[
-                    Some(npi),
-                    Some(json_row.basic_json.as_str()),
-                    Some(json_row.addresses_json.as_str()),
-                    Some(json_row.practice_locations_json.as_str()),
-                    Some(json_row.taxonomies_json.as_str()),
-                    Some(json_row.identifiers_json.as_str()),
-                    Some(json_row.other_names_json.as_str()),
-                    Some(json_row.endpoints_json.as_str()),
-                    Some(url_sentinel.as_str()),
-                    None,
-                    Some(api_run_id.as_str()),
-                    Some(requested_at_utc.as_str()),
-                    Some(request_params_json.as_str()),
-                    Some(json_row.results_json.as_str()),
-                    Some(json_row.response_json.as_str()),
-                ])?;
+                    if let Some(stmt) = stmt.as_mut() {
+                        stmt.execute(params![npi, provider_name, json_row.deactivated])
+                            .with_context(|| format!("Failed upserting preloaded NPI {npi}"))?;
+                    }
+                    push_bulk_row(&mut self.writer, npi, &provider_name, &json_row, &meta)?;
 
-                self.remaining.remove(npi);
-                emitted += 1;
-                if self.remaining.is_empty() {
-                    break;
+                    self.resolved_rows += 1;
+                    if json_row.deactivated {
+                        self.deactivated_rows += 1;
+                    }
+                    self.remaining.remove(npi);
+                    emitted += 1;
+                    if self.remaining.is_empty() {
+                        break;
+                    }
                 }
-            }
-            Ok((processed, emitted))
-        })();
+                Ok((processed, emitted, skipped))
+            })()
+        };
 
         drop(stmt);
         match scan_result {
-            Ok((processed, emitted)) => {
+            Ok((processed, emitted, skipped)) => {
                 if let Some(cache) = cache {
                     cache
                         .conn
@@ -2505,11 +3339,12 @@ impl<'a> NpiResolvedParquetExporter<'a> {
                         .context("Failed committing NPPES bulk transaction")?;
                 }
 
-                println!(
-                    "Finished bulk export for {}: scanned {} emitted {} remaining {}",
+                info!(
+                    "Finished bulk export for {}: scanned {} emitted {} skipped {} remaining {}",
                     source.label,
                     format_count(processed),
                     format_count(emitted),
+                    format_count(skipped),
                     format_count(self.remaining.len())
                 );
                 Ok(emitted)
@@ -2523,6 +3358,189 @@ impl<'a> NpiResolvedParquetExporter<'a> {
         }
     }
 
+    /// `--bulk-scan-threads` > 1: workers scan byte ranges of the primary file and send dataset
+    /// NPIs back here, where the cache upserts (inside the caller's transaction) and parquet
+    /// writes stay on one thread. Rows land in arrival order; each NPI is emitted once.
+    fn scan_primary_parallel(
+        &mut self,
+        source: &NppesBulkFiles,
+        idx: &NppesPrimaryIndices,
+        mut stmt: Option<&mut rusqlite::Statement<'_>>,
+        cache: Option<&NpiCache>,
+        meta: &BulkRowMeta,
+        shutdown_requested: &Arc<AtomicBool>,
+    ) -> Result<(usize, usize, usize)> {
+        let path = source.npidata_csv.as_path();
+        let ranges = bulk_scan::split_csv_ranges(path, self.scan.threads)?;
+        info!(
+            "Scanning {} with {} threads (--bulk-scan-threads).",
+            path.display(),
+            ranges.len()
+        );
+
+        let remaining = &self.remaining;
+        let other_names = &self.other_names;
+        let practice_locations = &self.practice_locations;
+        let endpoints = &self.endpoints;
+        let exclude_entity_types = self.exclude_entity_types;
+        let writer = &mut self.writer;
+        let scanned = AtomicUsize::new(0);
+        let stop = AtomicBool::new(false);
+        let mut scan_ticker = StatusTicker::new(self.scan.progress_interval);
+        let mut seen: HashSet<String> = HashSet::new();
+        let (mut emitted, mut skipped, mut deactivated) = (0usize, 0usize, 0usize);
+
+        let (consumed, workers) = std::thread::scope(|scope| {
+            let (tx, rx) = sync_channel::<BulkScanMatch>(1_024);
+            let handles: Vec<_> = ranges
+                .iter()
+                .map(|range| {
+                    let tx = tx.clone();
+                    let (scanned, stop) = (&scanned, &stop);
+                    scope.spawn(move || -> Result<()> {
+                        let mut reader = bulk_scan::range_reader(path, range)?;
+                        let mut processed = 0usize;
+                        for row in reader.records() {
+                            let row = row.with_context(|| {
+                                format!("Failed reading record in {}", path.display())
+                            })?;
+                            processed += 1;
+                            if processed.is_multiple_of(50_000) {
+                                scanned.fetch_add(50_000, Ordering::Relaxed);
+                                if stop.load(Ordering::Relaxed)
+                                    || shutdown_requested.load(Ordering::SeqCst)
+                                {
+                                    break;
+                                }
+                            }
+
+                            let npi = normalize_npi(row.get(idx.npi).unwrap_or(""));
+                            if npi.is_empty() || !remaining.contains(npi) {
+                                continue;
+                            }
+                            let found = if entity_type_excluded(
+                                exclude_entity_types,
+                                row_value(&row, idx.entity_type),
+                            ) {
+                                BulkScanMatch::Excluded(npi.to_string())
+                            } else {
+                                BulkScanMatch::Row {
+                                    npi: npi.to_string(),
+                                    provider_name: bulk_provider_name(&row, idx),
+                                    json_row: Box::new(build_bulk_npi_json_row(
+                                        npi,
+                                        &row,
+                                        idx,
+                                        other_names.get(npi).map_or(&[], Vec::as_slice),
+                                        practice_locations.get(npi).map_or(&[], Vec::as_slice),
+                                        endpoints.get(npi).map_or(&[], Vec::as_slice),
+                                    )),
+                                }
+                            };
+                            // A closed channel means the writer thread stopped early.
+                            if tx.send(found).is_err() {
+                                break;
+                            }
+                        }
+                        scanned.fetch_add(processed % 50_000, Ordering::Relaxed);
+                        Ok(())
+                    })
+                })
+                .collect();
+            drop(tx);
+
+            let consumed = (|| -> Result<()> {
+                let mut reported_millions = 0usize;
+                loop {
+                    match rx.recv_timeout(Duration::from_secs(1)) {
+                        Ok(BulkScanMatch::Excluded(npi)) => {
+                            if seen.contains(&npi) {
+                                continue;
+                            }
+                            if let Some(cache) = cache {
+                                cache.upsert_skipped(&npi)?;
+                            }
+                            skipped += 1;
+                            seen.insert(npi);
+                        }
+                        Ok(BulkScanMatch::Row {
+                            npi,
+                            provider_name,
+                            json_row,
+                        }) => {
+                            if seen.contains(&npi) {
+                                continue;
+                            }
+                            if let Some(stmt) = stmt.as_mut() {
+                                stmt.execute(params![npi, provider_name, json_row.deactivated])
+                                    .with_context(|| {
+                                        format!("Failed upserting preloaded NPI {npi}")
+                                    })?;
+                            }
+                            push_bulk_row(writer, &npi, &provider_name, &json_row, meta)?;
+                            if json_row.deactivated {
+                                deactivated += 1;
+                            }
+                            emitted += 1;
+                            seen.insert(npi);
+                        }
+                        Err(RecvTimeoutError::Timeout) => {}
+                        Err(RecvTimeoutError::Disconnected) => break,
+                    }
+                    if seen.len() >= remaining.len() {
+                        stop.store(true, Ordering::Relaxed);
+                    }
+
+                    let processed = scanned.load(Ordering::Relaxed);
+                    let report_scan = if scan_ticker.is_enabled() {
+                        scan_ticker.due()
+                    } else if processed / 1_000_000 > reported_millions {
+                        reported_millions = processed / 1_000_000;
+                        true
+                    } else {
+                        false
+                    };
+                    if report_scan {
+                        info!(
+                            "Scanned {} rows from {} (emitted {} remaining {}).",
+                            format_count(processed),
+                            source.label,
+                            format_count(emitted),
+                            format_count(remaining.len().saturating_sub(seen.len()))
+                        );
+                    }
+                }
+                Ok(())
+            })();
+            // Dropping the receiver unblocks workers still sending after a write failure.
+            drop(rx);
+            let workers = handles.into_iter().try_for_each(|handle| {
+                handle
+                    .join()
+                    .unwrap_or_else(|_| Err(anyhow!("NPPES bulk scan worker panicked")))
+            });
+            (consumed, workers)
+        });
+        consumed?;
+        workers?;
+
+        if shutdown_requested.load(Ordering::SeqCst) {
+            info!(
+                "Shutdown requested while reading {}. Stopping bulk export early.",
+                path.display()
+            );
+        }
+        self.resolved_rows += emitted;
+        self.deactivated_rows += deactivated;
+        for npi in &seen {
+            self.remaining.remove(npi.as_str());
+            self.other_names.remove(npi.as_str());
+            self.practice_locations.remove(npi.as_str());
+            self.endpoints.remove(npi.as_str());
+        }
+        Ok((scanned.into_inner(), emitted, skipped))
+    }
+
     fn write_remaining_from_api_responses(
         &mut self,
         cache: &NpiCache,
@@ -2531,7 +3549,7 @@ impl<'a> NpiResolvedParquetExporter<'a> {
         if self.remaining.is_empty() {
             return Ok(());
         }
-        println!(
+        info!(
             "Appending {} NPIs from cached API responses / sentinels...",
             format_count(self.remaining.len())
         );
@@ -2542,22 +3560,27 @@ impl<'a> NpiResolvedParquetExporter<'a> {
             .prepare(
                 "
                 SELECT
-                    npi,
-                    basic_json,
-                    addresses_json,
-                    practice_locations_json,
-                    taxonomies_json,
-                    identifiers_json,
-                    other_names_json,
-                    endpoints_json,
-                    url,
-                    error_message,
-                    api_run_id,
-                    requested_at_utc,
-                    request_params_json,
-                    results_json,
-                    response_json_raw
-                FROM npi_api_responses
+                    r.npi,
+                    r.basic_json,
+                    r.addresses_json,
+                    r.practice_locations_json,
+                    r.taxonomies_json,
+                    r.identifiers_json,
+                    r.other_names_json,
+                    r.endpoints_json,
+                    r.url,
+                    r.error_message,
+                    r.api_run_id,
+                    r.requested_at_utc,
+                    r.request_params_json,
+                    r.results_json,
+                    r.response_json_raw,
+                    CAST(r.latency_ms AS TEXT),
+                    CAST(r.http_status AS TEXT),
+                    CAST(r.duration_ms AS TEXT),
+                    NULLIF(c.provider_name, '')
+                FROM npi_api_responses r
+                LEFT JOIN npi_cache c ON c.npi = r.npi
                 ",
             )
             .context("Failed preparing NPI API responses load query")?;
@@ -2583,6 +3606,10 @@ impl<'a> NpiResolvedParquetExporter<'a> {
                     row.get::<usize, Option<String>>(12)?,
                     row.get::<usize, Option<String>>(13)?,
                     row.get::<usize, Option<String>>(14)?,
+                    row.get::<usize, Option<String>>(15)?,
+                    row.get::<usize, Option<String>>(16)?,
+                    row.get::<usize, Option<String>>(17)?,
+                    row.get::<usize, Option<String>>(18)?,
                 ),
             );
         }
@@ -2599,7 +3626,7 @@ impl<'a> NpiResolvedParquetExporter<'a> {
             }
             processed += 1;
             if processed % 50_000 == 0 && shutdown_requested.load(Ordering::SeqCst) {
-                println!("Shutdown requested; stopping NPI remaining export early.");
+                info!("Shutdown requested; stopping NPI remaining export early.");
                 break;
             }
 
@@ -2618,10 +3645,35 @@ impl<'a> NpiResolvedParquetExporter<'a> {
                 request_params_json,
                 results_json,
                 response_json_raw,
+                latency_ms,
+                http_status,
+                duration_ms,
+                provider_name,
             )) = api_rows.get(key)
             {
+                if self.is_excluded_entity_type(
+                    entity_type_code_from_results(results_json.as_deref()).unwrap_or(""),
+                ) {
+                    self.remaining.remove(key);
+                    if self.remaining.is_empty() {
+                        break;
+                    }
+                    continue;
+                }
+                let deactivated = match (error_message, basic_json.as_deref()) {
+                    (None, Some(basic_json)) => Some(basic_status_is_deactivated(basic_json)),
+                    _ => None,
+                };
+                if let Some(deactivated) = deactivated {
+                    self.resolved_rows += 1;
+                    if deactivated {
+                        self.deactivated_rows += 1;
+                    }
+                }
                 self.writer.push_row(&[
                     Some(key),
+                    provider_name.as_deref(),
+                    deactivated.map(|d| if d { "true" } else { "false" }),
                     basic_json.as_deref(),
                     addresses_json.as_deref(),
                     practice_locations_json.as_deref(),
@@ -2634,6 +3686,9 @@ impl<'a> NpiResolvedParquetExporter<'a> {
                     api_run_id.as_deref(),
                     requested_at_utc.as_deref(),
                     request_params_json.as_deref(),
+                    latency_ms.as_deref(),
+                    http_status.as_deref(),
+                    duration_ms.as_deref(),
                     results_json.as_deref(),
                     response_json_raw.as_deref(),
                 ])?;
@@ -2647,11 +3702,16 @@ impl<'a> NpiResolvedParquetExporter<'a> {
                     None,
                     None,
                     None,
+                    None,
+                    None,
                     Some("missing_cache"),
                     Some("missing_cache"),
                     Some(self.api_run_id.as_str()),
                     Some(missing_requested_at.as_str()),
                     Some(missing_params.as_str()),
+                    None,
+                    None,
+                    None,
                     Some("[]"),
                     Some(missing_response_json.as_str()),
                 ])?;
@@ -2666,7 +3726,42 @@ impl<'a> NpiResolvedParquetExporter<'a> {
         Ok(())
     }
 
+    /// NPIs still waiting for a row that are not in `settled`.
+    fn pending_without(&self, settled: &HashSet<String>) -> usize {
+        self.remaining
+            .iter()
+            .filter(|npi| !settled.contains(**npi))
+            .count()
+    }
+
+    fn is_excluded_entity_type(&self, entity_type_code: &str) -> bool {
+        entity_type_excluded(self.exclude_entity_types, entity_type_code)
+    }
+
+    fn report_deactivated_share(&self) {
+        if self.resolved_rows == 0 {
+            return;
+        }
+        let share = self.deactivated_rows as f64 / self.resolved_rows as f64;
+        info!(
+            "Deactivated NPIs: {} of {} resolved ({:.2}%).",
+            format_count(self.deactivated_rows),
+            format_count(self.resolved_rows),
+            share * 100.0
+        );
+        if share > DEACTIVATED_SHARE_WARN_THRESHOLD {
+            eprintln!(
+                "Warning: {:.2}% of resolved NPIs are deactivated (threshold {:.0}%). The NPPES files \
+may be much newer than the spending data period; consider using an NPPES release closer to \
+the spending file's claim months.",
+                share * 100.0,
+                DEACTIVATED_SHARE_WARN_THRESHOLD * 100.0
+            );
+        }
+    }
+
     fn finish(self) -> Result<()> {
+        self.report_deactivated_share();
         self.writer.finish()
     }
 
@@ -2728,12 +3823,67 @@ async fn run_npi_retry_wait_countdown(
     shutdown_requested.load(Ordering::SeqCst)
 }
 
+/// Slowest pace `NpiRateLimits` backs off to, and the first step when `--requests-per-second`
+/// is 0 (unthrottled).
+const NPI_RATE_LIMIT_MAX_INTERVAL: Duration = Duration::from_secs(10);
+const NPI_RATE_LIMIT_FIRST_INTERVAL: Duration = Duration::from_millis(100);
+
+/// HTTP 429 responses seen by the NPI workers, and the request interval they all share. Every
+/// `--npi-rate-limit-threshold` 429s the interval doubles, so a run that outpaces NPPES slows
+/// itself down instead of burning retries.
+struct NpiRateLimits {
+    hits: AtomicUsize,
+    threshold: usize,
+    interval_micros: AtomicU64,
+}
+
+impl NpiRateLimits {
+    fn new(min_interval: Duration, threshold: usize) -> Self {
+        Self {
+            hits: AtomicUsize::new(0),
+            threshold,
+            interval_micros: AtomicU64::new(min_interval.as_micros() as u64),
+        }
+    }
+
+    fn record_429(&self) {
+        let hits = self.hits.fetch_add(1, Ordering::SeqCst) + 1;
+        if self.threshold == 0 || !hits.is_multiple_of(self.threshold) {
+            return;
+        }
+        let _ = self
+            .interval_micros
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |current| {
+                let slowed = match current {
+                    0 => NPI_RATE_LIMIT_FIRST_INTERVAL.as_micros() as u64,
+                    current => current.saturating_mul(2),
+                };
+                Some(slowed.min(NPI_RATE_LIMIT_MAX_INTERVAL.as_micros() as u64))
+            });
+    }
+
+    fn hits(&self) -> usize {
+        self.hits.load(Ordering::SeqCst)
+    }
+
+    fn min_interval(&self) -> Duration {
+        Duration::from_micros(self.interval_micros.load(Ordering::SeqCst))
+    }
+}
+
+/// Looks up `missing_npis` against the NPPES API, `--concurrency` requests at a time. Unlike
+/// `resolve_hcpcs_batch`, there is no batch request to fall back from: NPPES v2 accepts one
+/// `number` per query, so one request per NPI is the minimum.
+// `lookup_budget` is runtime state shared with the HCPCS build, so unlike the other knobs it
+// cannot ride along on `args`.
+#[allow(clippy::too_many_arguments)]
 async fn resolve_missing_npis(
     cache: &NpiCache,
     missing_npis: Vec<String>,
     client: &Client,
     args: &Args,
     api_run_id: &str,
+    lookup_budget: &LookupBudget,
     progress_hub: Option<Arc<MultiProgress>>,
     shutdown_requested: Arc<AtomicBool>,
 ) -> Result<(bool, Vec<NpiApiReferenceRow>)> {
@@ -2749,6 +3899,11 @@ async fn resolve_missing_npis(
         Duration::from_secs_f64(1.0 / args.requests_per_second as f64)
     };
     let next_slot = Arc::new(Mutex::new(Instant::now()));
+    let rate_limits = Arc::new(NpiRateLimits::new(
+        min_interval,
+        args.npi_rate_limit_threshold,
+    ));
+    let mut current_interval = min_interval;
 
     let progress = if let Some(hub) = &progress_hub {
         hub.add(ProgressBar::new(total as u64))
@@ -2771,6 +3926,10 @@ async fn resolve_missing_npis(
     let mut found = 0usize;
     let mut not_found = 0usize;
     let mut failed = 0usize;
+    let mut in_flight_ids = InFlightIds::new(|npi| normalize_npi(npi).to_string());
+    let mut budget_exhausted = false;
+    let mut status_ticker =
+        StatusTicker::new(args.progress_interval_seconds.map(Duration::from_secs));
 
     while !round_npis.is_empty() {
         if shutdown_requested.load(Ordering::SeqCst) {
@@ -2809,10 +3968,10 @@ async fn resolve_missing_npis(
             progress.set_message(format!(
                 "retry round {retry_round}/{max_retry_rounds} resumed pending={pending_retry}"
             ));
-            if !min_interval.is_zero() {
+            if !current_interval.is_zero() {
                 // Give the per_sec estimator one request-interval to stabilize before
                 // new completions arrive, so the first post-retry sample is not inflated.
-                sleep(min_interval).await;
+                sleep(current_interval).await;
             }
         }
 
@@ -2827,53 +3986,84 @@ async fn resolve_missing_npis(
                 interrupted = true;
                 break;
             }
-            if let Some(npi) = queue.next() {
+            if budget_exhausted {
+                break;
+            }
+            if let Some(npi) = in_flight_ids.next_claimed(&mut queue) {
+                if lookup_budget.take(1) == 0 {
+                    in_flight_ids.release(&npi);
+                    next_round_npis.push(npi);
+                    budget_exhausted = true;
+                    break;
+                }
                 in_flight.push(resolve_npi(
                     npi,
                     client.clone(),
                     args.api_base_url.clone(),
                     args.api_version.clone(),
                     api_run_id.to_string(),
-                    args.max_retries.max(1),
+                    RetryPolicy::from_args(args),
                     Arc::clone(&next_slot),
-                    min_interval,
+                    Arc::clone(&rate_limits),
                 ));
             }
         }
 
         let mut pending_current_round = in_flight.len() + queue.len();
         while let Some((npi, result)) = in_flight.next().await {
+            // Duplicates that were enqueued while this lookup was in flight settle with it.
+            let mut settled_npis = in_flight_ids.release(&npi);
+            settled_npis.insert(0, npi);
+            let settled_count = settled_npis.len();
             attempts += 1;
-            pending_current_round = pending_current_round.saturating_sub(1);
+            pending_current_round = pending_current_round.saturating_sub(settled_count);
 
             match result {
                 NpiResolveResult::Found {
                     provider_name,
                     reference_row,
                 } => {
-                    cache.upsert_ok(&npi, &provider_name)?;
+                    // Entity type is only known once the API responds for non-bulk NPIs.
+                    let excluded =
+                        entity_type_code_from_results(reference_row.results_json.as_deref())
+                            .is_some_and(|code| args.exclude_npi_types.iter().any(|t| t == code));
+                    let deactivated = reference_row
+                        .basic_json
+                        .as_deref()
+                        .is_some_and(basic_status_is_deactivated);
+                    for npi in &settled_npis {
+                        if excluded {
+                            cache.upsert_skipped(npi)?;
+                        } else {
+                            cache.upsert_ok(npi, &provider_name, deactivated)?;
+                        }
+                    }
                     reference_rows.push(reference_row);
-                    found += 1;
-                    progress.inc(1);
+                    found += settled_count;
+                    progress.inc(settled_count as u64);
                 }
                 NpiResolveResult::NotFound { reference_row } => {
-                    cache.upsert_not_found(&npi)?;
+                    for npi in &settled_npis {
+                        cache.upsert_not_found(npi)?;
+                    }
                     reference_rows.push(reference_row);
-                    not_found += 1;
-                    progress.inc(1);
+                    not_found += settled_count;
+                    progress.inc(settled_count as u64);
                 }
                 NpiResolveResult::Error {
                     error_message,
                     reference_row,
                 } => {
-                    cache.upsert_error(&npi, &error_message)?;
+                    for npi in &settled_npis {
+                        cache.upsert_error(npi, &error_message)?;
+                    }
                     reference_rows.push(reference_row);
                     if can_retry_errors_again && !shutdown_requested.load(Ordering::SeqCst) {
-                        next_round_npis.push(npi);
+                        next_round_npis.extend(settled_npis);
                         retry_failover_triggered = true;
                     } else {
-                        failed += 1;
-                        progress.inc(1);
+                        failed += settled_count;
+                        progress.inc(settled_count as u64);
                     }
                 }
             }
@@ -2897,78 +4087,164 @@ async fn resolve_missing_npis(
             progress.set_message(format!(
                 "mode={mode} ok={found} not_found={not_found} failed={failed} remaining={remaining_in_round} retry_queued={retry_queued}"
             ));
+            if rate_limits.min_interval() > current_interval {
+                current_interval = rate_limits.min_interval();
+                progress.suspend(|| {
+                    info!(
+                        "NPI API rate limited {} times (HTTP 429); slowing NPI lookups to {:.2} requests/s.",
+                        rate_limits.hits(),
+                        1.0 / current_interval.as_secs_f64()
+                    )
+                });
+            }
+            if status_ticker.due() {
+                let settled = found + not_found + failed;
+                progress.suspend(|| {
+                    info!(
+                        "NPI status: settled={settled}/{total} mode={mode} ok={found} not_found={not_found} failed={failed} remaining={remaining_in_round} retry_queued={retry_queued}"
+                    )
+                });
+            }
 
             if shutdown_requested.load(Ordering::SeqCst) {
                 interrupted = true;
-            } else if !retry_failover_triggered {
-                if let Some(next_npi) = queue.next() {
-                    in_flight.push(resolve_npi(
-                        next_npi,
-                        client.clone(),
-                        args.api_base_url.clone(),
-                        args.api_version.clone(),
-                        api_run_id.to_string(),
-                        args.max_retries.max(1),
-                        Arc::clone(&next_slot),
-                        min_interval,
-                    ));
+            } else if !retry_failover_triggered
+                && !budget_exhausted
+                && let Some(next_npi) = in_flight_ids.next_claimed(&mut queue)
+            {
+                if lookup_budget.take(1) == 0 {
+                    in_flight_ids.release(&next_npi);
+                    next_round_npis.push(next_npi);
+                    budget_exhausted = true;
+                    continue;
                 }
+                in_flight.push(resolve_npi(
+                    next_npi,
+                    client.clone(),
+                    args.api_base_url.clone(),
+                    args.api_version.clone(),
+                    api_run_id.to_string(),
+                    RetryPolicy::from_args(args),
+                    Arc::clone(&next_slot),
+                    Arc::clone(&rate_limits),
+                ));
             }
         }
 
         next_round_npis.extend(queue);
         round_npis = next_round_npis;
 
-        if round_npis.is_empty() || interrupted {
+        if round_npis.is_empty() || interrupted || budget_exhausted {
             break;
         }
         retry_round = retry_round.saturating_add(1);
     }
 
+    if budget_exhausted {
+        progress.suspend(|| {
+            info!(
+                "--max-new-lookups-total budget used up; {} NPIs left unresolved for a later run.",
+                round_npis.len()
+            )
+        });
+    }
     let settled = found + not_found + failed;
+    let latency = lookup_duration_summary(&reference_rows);
+    let rate_limited = rate_limits.hits();
     if interrupted {
         progress.abandon_with_message(format!(
-            "graceful stop: settled={settled}/{total} ok={found} not_found={not_found} failed={failed} pending_retry={} attempts={attempts}",
+            "graceful stop: settled={settled}/{total} ok={found} not_found={not_found} failed={failed} pending_retry={} attempts={attempts} rate_limited={rate_limited}{latency}",
             round_npis.len()
         ));
     } else {
         progress.finish_with_message(format!(
-            "done: settled={settled}/{total} ok={found} not_found={not_found} failed={failed} attempts={attempts}"
+            "done: settled={settled}/{total} ok={found} not_found={not_found} failed={failed} attempts={attempts} rate_limited={rate_limited}{latency}"
         ));
     }
+    record_npi_lookups(LookupTotals {
+        ok: found,
+        not_found,
+        error: failed,
+    });
+    if in_flight_ids.coalesced() > 0 {
+        info!(
+            "Coalesced {} duplicate NPI lookups that were already in flight.",
+            in_flight_ids.coalesced()
+        );
+    }
     Ok((interrupted, reference_rows))
 }
 
+/// ` p50=..ms p95=..ms` over the `duration_ms` of this run's lookups (nearest-rank), or empty
+/// when none completed.
+fn lookup_duration_summary(rows: &[NpiApiReferenceRow]) -> String {
+    let mut durations: Vec<i64> = rows.iter().filter_map(|row| row.duration_ms).collect();
+    if durations.is_empty() {
+        return String::new();
+    }
+    durations.sort_unstable();
+    let percentile = |pct: usize| durations[(durations.len() * pct).div_ceil(100).max(1) - 1];
+    format!(" p50={}ms p95={}ms", percentile(50), percentile(95))
+}
+
 async fn resolve_npi(
     npi: String,
     client: Client,
     api_base_url: String,
     api_version: String,
     api_run_id: String,
-    max_retries: u32,
+    retry: RetryPolicy,
     next_slot: Arc<Mutex<Instant>>,
-    min_interval: Duration,
+    rate_limits: Arc<NpiRateLimits>,
 ) -> (String, NpiResolveResult) {
-    wait_for_rate_slot(&next_slot, min_interval).await;
+    wait_for_rate_slot(&next_slot, rate_limits.min_interval()).await;
     let result = fetch_npi_name(
         &client,
         &api_base_url,
         &api_version,
         &npi,
         &api_run_id,
-        max_retries,
+        retry,
+        &rate_limits,
     )
     .await;
     (npi, result)
 }
 
+/// Resolves one NPI, stamping `duration_ms` with the wall-clock time of the whole lookup
+/// (every attempt plus retry waits), as opposed to `latency_ms` of the last attempt only.
 async fn fetch_npi_name(
     client: &Client,
     api_base_url: &str,
     api_version: &str,
     npi: &str,
     api_run_id: &str,
-    max_retries: u32,
+    retry: RetryPolicy,
+    rate_limits: &NpiRateLimits,
+) -> NpiResolveResult {
+    let started = Instant::now();
+    let mut result = fetch_npi_name_attempts(
+        client,
+        api_base_url,
+        api_version,
+        npi,
+        api_run_id,
+        retry,
+        rate_limits,
+    )
+    .await;
+    result.reference_row_mut().duration_ms = Some(elapsed_millis(started));
+    result
+}
+
+async fn fetch_npi_name_attempts(
+    client: &Client,
+    api_base_url: &str,
+    api_version: &str,
+    npi: &str,
+    api_run_id: &str,
+    retry: RetryPolicy,
+    rate_limits: &NpiRateLimits,
 ) -> NpiResolveResult {
     let request_params_json = json!({
         "version": api_version,
@@ -2980,6 +4256,8 @@ async fn fetch_npi_name(
             .map(|url| url.to_string())
             .unwrap_or_else(|_| format!("{api_base_url}?version={api_version}&number={npi}"));
     let requested_at_utc = now_unix_seconds().to_string();
+    // Round-trip time of the most recent attempt (send + body read), recorded on every row.
+    let attempt_latency_ms: Cell<Option<i64>> = Cell::new(None);
 
     let make_base_row = || NpiApiReferenceRow {
         npi: npi.to_string(),
@@ -2996,17 +4274,32 @@ async fn fetch_npi_name(
         api_run_id: api_run_id.to_string(),
         requested_at_utc: requested_at_utc.clone(),
         request_params_json: request_params_json.clone(),
+        latency_ms: attempt_latency_ms.get(),
+        duration_ms: None,
         results_json: None,
         response_json_raw: None,
     };
+    let dump = |http_status: Option<u16>, error_message: &str, body: &str| {
+        dump_failed_body(&FailedBody {
+            kind: "npi",
+            identifier: npi,
+            api_run_id,
+            request_url: &request_url,
+            http_status,
+            error_message,
+            body,
+        })
+    };
 
-    let attempts = max_retries.max(1);
-    let mut backoff = Duration::from_secs(1);
+    let attempts = retry.attempts();
+    let mut backoff = retry.initial_backoff();
 
     for attempt in 1..=attempts {
+        let started = Instant::now();
         let response = client
             .get(api_base_url)
             .query(&[("version", api_version), ("number", npi)])
+            .timeout(retry.request_timeout)
             .send()
             .await;
 
@@ -3014,7 +4307,9 @@ async fn fetch_npi_name(
             Ok(resp) => {
                 let status = resp.status();
                 if status.is_success() {
-                    let body_text = match resp.text().await {
+                    let body_text = resp.text().await;
+                    attempt_latency_ms.set(Some(elapsed_millis(started)));
+                    let body_text = match body_text {
                         Ok(text) => text,
                         Err(err) => {
                             let mut row = make_base_row();
@@ -3036,6 +4331,11 @@ async fn fetch_npi_name(
                             row.http_status = Some(status.as_u16() as i64);
                             row.error_message =
                                 Some(format!("Invalid NPI API JSON for {npi}: {err}"));
+                            dump(
+                                Some(status.as_u16()),
+                                row.error_message.as_deref().unwrap_or_default(),
+                                &body_text,
+                            );
                             return NpiResolveResult::Error {
                                 error_message: row.error_message.clone().unwrap_or_default(),
                                 reference_row: row,
@@ -3052,12 +4352,18 @@ async fn fetch_npi_name(
                         &requested_at_utc,
                         &request_params_json,
                     );
+                    row.latency_ms = attempt_latency_ms.get();
 
                     let parsed: NpiApiResponse = match serde_json::from_value(body_value) {
                         Ok(parsed) => parsed,
                         Err(err) => {
                             row.error_message =
                                 Some(format!("Failed decoding NPI API response for {npi}: {err}"));
+                            dump(
+                                Some(status.as_u16()),
+                                row.error_message.as_deref().unwrap_or_default(),
+                                &body_text,
+                            );
                             return NpiResolveResult::Error {
                                 error_message: row.error_message.clone().unwrap_or_default(),
                                 reference_row: row,
@@ -3076,6 +4382,10 @@ async fn fetch_npi_name(
 
                 let retry_after = parse_retry_after(resp.headers().get(RETRY_AFTER));
                 let body = resp.text().await.unwrap_or_default();
+                attempt_latency_ms.set(Some(elapsed_millis(started)));
+                if status == StatusCode::TOO_MANY_REQUESTS {
+                    rate_limits.record_429();
+                }
                 if is_retryable_status(status) {
                     if attempt == attempts {
                         let mut row = make_base_row();
@@ -3087,6 +4397,7 @@ async fn fetch_npi_name(
                             attempts,
                             truncate_for_log(&body)
                         );
+                        dump(Some(status.as_u16()), &message, &body);
                         row.error_message = Some(message.clone());
                         return NpiResolveResult::Error {
                             error_message: message,
@@ -3094,7 +4405,7 @@ async fn fetch_npi_name(
                         };
                     }
                     tokio::time::sleep(retry_after.unwrap_or(backoff)).await;
-                    backoff = (backoff + backoff).min(Duration::from_secs(60));
+                    backoff = retry.next_backoff(backoff);
                     continue;
                 }
 
@@ -3106,6 +4417,7 @@ async fn fetch_npi_name(
                     npi,
                     truncate_for_log(&body)
                 );
+                dump(Some(status.as_u16()), &message, &body);
                 row.error_message = Some(message.clone());
                 return NpiResolveResult::Error {
                     error_message: message,
@@ -3113,6 +4425,7 @@ async fn fetch_npi_name(
                 };
             }
             Err(err) => {
+                attempt_latency_ms.set(Some(elapsed_millis(started)));
                 if attempt == attempts {
                     let mut row = make_base_row();
                     let message = format!("NPI API request failed for {npi}: {err}");
@@ -3123,7 +4436,7 @@ async fn fetch_npi_name(
                     };
                 }
                 tokio::time::sleep(backoff).await;
-                backoff = (backoff + backoff).min(Duration::from_secs(60));
+                backoff = retry.next_backoff(backoff);
             }
         }
     }
@@ -3169,3 +4482,278 @@ fn extract_name_from_response(response: &NpiApiResponse) -> Option<String> {
         (None, None) => None,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_api::{
+        MALFORMED_JSON, MockApi, MockResponse, fast_retry, npi_found, npi_not_found,
+    };
+    use std::path::PathBuf;
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("build_datasets_npi_{name}_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    const NPI: &str = "1234567893";
+
+    async fn lookup(mock: &MockApi, retry: RetryPolicy) -> (NpiResolveResult, NpiRateLimits) {
+        let rate_limits = NpiRateLimits::new(Duration::ZERO, 0);
+        let result = fetch_npi_name(
+            &Client::new(),
+            &mock.url("/npi"),
+            "2.1",
+            NPI,
+            "test-run",
+            retry,
+            &rate_limits,
+        )
+        .await;
+        (result, rate_limits)
+    }
+
+    #[tokio::test]
+    async fn found_npi_returns_provider_name_and_request_params() {
+        let mock =
+            MockApi::scripted(vec![MockResponse::json(npi_found(NPI, "ADA", "LOVELACE"))]).await;
+        let (result, _) = lookup(&mock, fast_retry(3)).await;
+        let NpiResolveResult::Found {
+            provider_name,
+            reference_row,
+        } = result
+        else {
+            panic!("expected Found");
+        };
+        assert_eq!(provider_name, "ADA LOVELACE");
+        assert_eq!(reference_row.http_status, Some(200));
+        assert!(reference_row.duration_ms.is_some());
+        let requests = mock.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].param("number"), NPI);
+        assert_eq!(requests[0].param("version"), "2.1");
+    }
+
+    #[tokio::test]
+    async fn empty_result_is_not_found() {
+        let mock = MockApi::scripted(vec![MockResponse::json(npi_not_found())]).await;
+        let (result, _) = lookup(&mock, fast_retry(3)).await;
+        assert!(matches!(result, NpiResolveResult::NotFound { .. }));
+    }
+
+    #[tokio::test]
+    async fn server_error_is_retried_until_it_succeeds() {
+        let mock = MockApi::scripted(vec![
+            MockResponse::status(503),
+            MockResponse::status(502),
+            MockResponse::json(npi_found(NPI, "ADA", "LOVELACE")),
+        ])
+        .await;
+        let (result, _) = lookup(&mock, fast_retry(3)).await;
+        assert!(matches!(result, NpiResolveResult::Found { .. }));
+        assert_eq!(mock.requests().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn retries_give_up_after_the_last_attempt() {
+        let mock = MockApi::scripted(vec![MockResponse::status(503)]).await;
+        let (result, _) = lookup(&mock, fast_retry(2)).await;
+        let NpiResolveResult::Error {
+            error_message,
+            reference_row,
+        } = result
+        else {
+            panic!("expected Error");
+        };
+        assert!(
+            error_message.contains("after 2 attempts"),
+            "{error_message}"
+        );
+        assert_eq!(reference_row.http_status, Some(503));
+        assert_eq!(mock.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn rate_limit_waits_for_retry_after() {
+        let mock = MockApi::scripted(vec![
+            MockResponse::status(429).retry_after(1),
+            MockResponse::json(npi_found(NPI, "ADA", "LOVELACE")),
+        ])
+        .await;
+        let (result, rate_limits) = lookup(&mock, fast_retry(3)).await;
+        assert!(matches!(result, NpiResolveResult::Found { .. }));
+        assert_eq!(rate_limits.hits(), 1);
+        // The 10ms backoff would retry immediately; only Retry-After explains a 1s gap.
+        assert!(mock.first_retry_gap().unwrap() >= Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn malformed_json_is_an_error_without_retrying() {
+        let mock = MockApi::scripted(vec![MockResponse::raw(200, MALFORMED_JSON)]).await;
+        let (result, _) = lookup(&mock, fast_retry(3)).await;
+        let NpiResolveResult::Error { error_message, .. } = result else {
+            panic!("expected Error");
+        };
+        assert!(
+            error_message.contains("Invalid NPI API JSON"),
+            "{error_message}"
+        );
+        assert_eq!(mock.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn client_error_is_not_retried() {
+        let mock = MockApi::scripted(vec![MockResponse::status(400)]).await;
+        let (result, _) = lookup(&mock, fast_retry(3)).await;
+        let NpiResolveResult::Error { error_message, .. } = result else {
+            panic!("expected Error");
+        };
+        assert!(error_message.contains("non-retryable"), "{error_message}");
+        assert_eq!(mock.requests().len(), 1);
+    }
+
+    #[test]
+    fn empty_parquet_fails_extraction_unless_empty_input_is_allowed() {
+        let dir = scratch_dir("empty_input");
+        let input = dir.join("spending.parquet");
+        Connection::open_in_memory()
+            .unwrap()
+            .execute_batch(&format!(
+                "COPY (SELECT '' AS BILLING_PROVIDER_NPI_NUM, '' AS SERVICING_PROVIDER_NPI_NUM,
+                              '' AS HCPCS_CODE WHERE false)
+                 TO '{}' (FORMAT PARQUET)",
+                sql_escape_path(&input)
+            ))
+            .unwrap();
+        let npi_input = NpiInput::Spending(&input);
+        let npis = extract_unique_npis(npi_input).unwrap();
+        assert!(npis.is_empty());
+
+        let check = |allow_empty_input| {
+            ensure_identifiers_extracted(
+                npis.len(),
+                "NPIs",
+                npi_input.source_path(),
+                npi_input.empty_hint(),
+                allow_empty_input,
+                usize::MAX,
+            )
+        };
+        let err = check(false).unwrap_err().to_string();
+        assert!(err.contains("No NPIs extracted from"), "{err}");
+        assert!(err.contains("BILLING_PROVIDER_NPI_NUM"), "{err}");
+        assert!(err.contains("--allow-empty-input"), "{err}");
+        assert!(check(true).is_ok());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn ttl_requeues_ok_and_not_found_rows_fetched_before_the_cutoff() {
+        let dir = scratch_dir("ttl");
+        let cache = NpiCache::open(&dir.join("npi_cache.sqlite")).unwrap();
+        let now = now_unix_seconds();
+        let ten_days_ago = now - 10 * 86_400;
+        for (npi, status, fetched_at) in [
+            ("1000000001", "ok", ten_days_ago),
+            ("1000000002", "not_found", ten_days_ago),
+            ("1000000003", "ok", now),
+            ("1000000004", "error", ten_days_ago),
+        ] {
+            cache
+                .conn
+                .execute(
+                    "INSERT INTO npi_cache (npi, status, fetched_at_unix) VALUES (?1, ?2, ?3)",
+                    params![npi, status, fetched_at],
+                )
+                .unwrap();
+        }
+        let npis: Vec<String> = ["1000000001", "1000000002", "1000000003", "1000000004"]
+            .map(String::from)
+            .to_vec();
+
+        let classify = |ttl_days| {
+            cache
+                .classify_for_lookup(&npis, false, false, None, npi_cache_stale_before(ttl_days))
+                .unwrap()
+        };
+        assert_eq!(
+            classify(Some(7)),
+            (
+                1,
+                vec![
+                    "1000000001".to_string(),
+                    "1000000002".to_string(),
+                    "1000000004".to_string()
+                ]
+            )
+        );
+        assert_eq!(classify(Some(30)), (3, vec!["1000000004".to_string()]));
+        assert_eq!(classify(None), (3, vec!["1000000004".to_string()]));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn bulk_basic_carries_replacement_npi_only_when_the_column_has_one() {
+        let basic = |headers: &[&str], values: &[&str]| -> Value {
+            let idx = NppesPrimaryIndices::from_headers(&csv::StringRecord::from(headers.to_vec()))
+                .unwrap();
+            let row = csv::StringRecord::from(values.to_vec());
+            let json_row = build_bulk_npi_json_row(NPI, &row, &idx, &[], &[], &[]);
+            serde_json::from_str(&json_row.basic_json).unwrap()
+        };
+        let headers = [
+            "NPI",
+            "Entity Type Code",
+            "NPI Deactivation Date",
+            "Replacement NPI",
+        ];
+
+        let replaced = basic(&headers, &[NPI, "1", "01/02/2020", "1245319599"]);
+        assert_eq!(replaced["replacement_npi"], "1245319599");
+        assert_eq!(replaced["status"], "D");
+
+        let blank = basic(&headers, &[NPI, "1", "01/02/2020", ""]);
+        assert!(blank.get("replacement_npi").is_none());
+
+        let no_column = basic(&headers[..3], &[NPI, "1", "01/02/2020"]);
+        assert!(no_column.get("replacement_npi").is_none());
+    }
+
+    #[test]
+    fn skipped_npis_are_unresolved_unless_exclusions_are_active() {
+        let dir = scratch_dir("unresolved_skipped");
+        let list = dir.join("npis.txt");
+        fs::write(&list, "1000000001\n1000000002\n").unwrap();
+        let cache_db = dir.join("npi_cache.sqlite");
+        let cache = NpiCache::open(&cache_db).unwrap();
+        for (npi, status) in [("1000000001", "ok"), ("1000000002", "skipped")] {
+            cache
+                .conn
+                .execute(
+                    "INSERT INTO npi_cache (npi, status, fetched_at_unix) VALUES (?1, ?2, 0)",
+                    params![npi, status],
+                )
+                .unwrap();
+        }
+        let input = NpiInput::List {
+            path: &list,
+            column: None,
+        };
+
+        let pending = collect_unresolved_npis(input, &cache_db, false).unwrap();
+        let pending: Vec<_> = pending
+            .iter()
+            .map(|e| (e.npi.as_str(), e.status.as_str()))
+            .collect();
+        assert_eq!(pending, [("1000000002", "skipped")]);
+        assert!(
+            collect_unresolved_npis(input, &cache_db, true)
+                .unwrap()
+                .is_empty()
+        );
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
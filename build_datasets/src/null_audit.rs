@@ -7,6 +7,7 @@ use std::{
 
 use crate::common::now_unix_seconds;
 use crate::common::{project_root, sql_escape_path};
+use crate::output::info;
 
 #[derive(Debug, Clone)]
 struct ColumnAuditRow {
@@ -318,7 +319,7 @@ pub fn generate_and_update_hf_docs(npi_parquet: &Path, hcpcs_parquet: &Path) ->
     };
     update_hf_readme_with_audit(&hf_readme_path, &readme_block)?;
 
-    println!(
+    info!(
         "Wrote parquet null audit {} and updated HF dataset card {}",
         audit_md_path.display(),
         hf_readme_path.display()
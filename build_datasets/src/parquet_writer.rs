@@ -4,14 +4,64 @@ use arrow::{
     datatypes::{DataType, Field, Schema},
     record_batch::RecordBatch,
 };
+use duckdb::Connection;
 use parquet::arrow::arrow_writer::ArrowWriter;
-use parquet::{basic::Compression, file::properties::WriterProperties};
+use parquet::{
+    basic::{Compression, GzipLevel, ZstdLevel},
+    file::properties::WriterProperties,
+};
 use std::{
     fs::{self, File},
+    io::{BufWriter, Write},
     path::{Path, PathBuf},
     sync::Arc,
+    time::Instant,
 };
 
+use crate::{args::ParquetCompression, common::sql_escape_path, output::info};
+
+/// Column selection and `content_hash` settings for the resolved-identifier parquets.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ParquetOutputOptions<'a> {
+    /// `--npi-output-columns` / `--hcpcs-output-columns`; `None` keeps every column.
+    pub columns: Option<&'a [String]>,
+    /// `--content-hash`: append a `content_hash` column.
+    pub content_hash: bool,
+    /// `--sort-output`: rewrite the finished file ordered by its first (key) column.
+    pub sort_output: bool,
+    /// `--emit-ndjson`: also write a `.ndjson` copy of the key, `api_run_id`, and `response_json`.
+    pub ndjson: bool,
+    /// `--parquet-compression`, also applied to the `--sort-output` rewrite.
+    pub compression: ParquetCompression,
+}
+
+/// Output column holding [`content_hash`] of a row's hashed columns.
+pub const CONTENT_HASH_COLUMN: &str = "content_hash";
+
+/// Stable 64-bit FNV-1a over `values` as 16 hex chars. Each value is length-prefixed and
+/// nulls are distinct from empty strings, so shifting text between columns changes the hash.
+pub fn content_hash(values: &[Option<&str>]) -> String {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+    let mut hash = OFFSET_BASIS;
+    let mut feed = |bytes: &[u8]| {
+        for &byte in bytes {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(PRIME);
+        }
+    };
+    for value in values {
+        match value {
+            Some(v) => {
+                feed(&(v.len() as u64).to_le_bytes());
+                feed(v.as_bytes());
+            }
+            None => feed(&u64::MAX.to_le_bytes()),
+        }
+    }
+    format!("{hash:016x}")
+}
+
 /// Streaming Parquet writer for "all-string" datasets.
 ///
 /// We use this for large exports (e.g. NPPES-derived NPI rows) to avoid writing
@@ -22,12 +72,52 @@ pub struct StringParquetWriter {
     schema: Arc<Schema>,
     writer: ArrowWriter<File>,
     builders: Vec<StringBuilder>,
+    input_columns: usize,
+    projection: Vec<usize>,
     rows_in_batch: usize,
     batch_size: usize,
+    /// Input-column indices fed to [`content_hash`], plus the builder for that column.
+    content_hash: Option<(Vec<usize>, StringBuilder)>,
+    /// Column to order the finished file by (`--sort-output`).
+    sort_key: Option<String>,
+    compression: ParquetCompression,
+    ndjson: Option<NdjsonSidecar>,
 }
 
 impl StringParquetWriter {
-    pub fn try_new(output_path: &Path, columns: &[&str], batch_size: usize) -> Result<Self> {
+    /// Create a writer for `columns`, optionally restricted to a `selected` subset.
+    ///
+    /// Callers always push full rows in `columns` order; unselected values are dropped.
+    /// With `options.content_hash`, a trailing `content_hash` column is computed from
+    /// `hashed_columns` of the full row (independent of the selected subset).
+    pub fn try_new(
+        output_path: &Path,
+        columns: &[&str],
+        options: ParquetOutputOptions<'_>,
+        hashed_columns: &[&str],
+        batch_size: usize,
+    ) -> Result<Self> {
+        let projection = resolve_column_projection(columns, options.columns)?;
+        let compression = options.compression;
+        let sort_key = columns
+            .first()
+            .filter(|_| options.sort_output)
+            .map(|key| key.to_string());
+        let content_hash = if options.content_hash {
+            let indices = hashed_columns
+                .iter()
+                .map(|name| {
+                    columns
+                        .iter()
+                        .position(|c| c == name)
+                        .with_context(|| format!("Unknown content hash column {name:?}"))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            Some((indices, StringBuilder::new()))
+        } else {
+            None
+        };
+
         if let Some(parent) = output_path.parent() {
             fs::create_dir_all(parent)
                 .with_context(|| format!("Failed creating {}", parent.display()))?;
@@ -39,14 +129,17 @@ impl StringParquetWriter {
             .unwrap_or("output.parquet");
         let tmp_path = output_path.with_file_name(format!("{file_name}.tmp"));
 
-        let fields: Vec<Field> = columns
+        let mut fields: Vec<Field> = projection
             .iter()
-            .map(|name| Field::new(*name, DataType::Utf8, true))
+            .map(|&idx| Field::new(columns[idx], DataType::Utf8, true))
             .collect();
+        if content_hash.is_some() {
+            fields.push(Field::new(CONTENT_HASH_COLUMN, DataType::Utf8, true));
+        }
         let schema = Arc::new(Schema::new(fields));
 
         let props = WriterProperties::builder()
-            .set_compression(Compression::SNAPPY)
+            .set_compression(parquet_codec(compression)?)
             .build();
 
         let file = File::create(&tmp_path)
@@ -54,7 +147,14 @@ impl StringParquetWriter {
         let writer = ArrowWriter::try_new(file, Arc::clone(&schema), Some(props))
             .context("Failed creating Parquet ArrowWriter")?;
 
-        let builders = (0..columns.len()).map(|_| StringBuilder::new()).collect();
+        let builders = (0..projection.len())
+            .map(|_| StringBuilder::new())
+            .collect();
+        let ndjson = if options.ndjson {
+            Some(NdjsonSidecar::try_new(output_path, columns)?)
+        } else {
+            None
+        };
 
         Ok(Self {
             output_path: output_path.to_path_buf(),
@@ -62,25 +162,38 @@ impl StringParquetWriter {
             schema,
             writer,
             builders,
+            input_columns: columns.len(),
+            projection,
             rows_in_batch: 0,
             batch_size: batch_size.max(1),
+            content_hash,
+            sort_key,
+            compression,
+            ndjson,
         })
     }
 
     pub fn push_row(&mut self, values: &[Option<&str>]) -> Result<()> {
         anyhow::ensure!(
-            values.len() == self.builders.len(),
+            values.len() == self.input_columns,
             "push_row expected {} columns, got {}",
-            self.builders.len(),
+            self.input_columns,
             values.len()
         );
 
-        for (idx, value) in values.iter().enumerate() {
-            match value {
-                Some(v) => self.builders[idx].append_value(v),
-                None => self.builders[idx].append_null(),
+        for (builder, &idx) in self.builders.iter_mut().zip(&self.projection) {
+            match values[idx] {
+                Some(v) => builder.append_value(v),
+                None => builder.append_null(),
             }
         }
+        if let Some((indices, builder)) = &mut self.content_hash {
+            let hashed: Vec<Option<&str>> = indices.iter().map(|&idx| values[idx]).collect();
+            builder.append_value(content_hash(&hashed));
+        }
+        if let Some(ndjson) = &mut self.ndjson {
+            ndjson.push_row(values)?;
+        }
         self.rows_in_batch += 1;
         if self.rows_in_batch >= self.batch_size {
             self.flush_batch()?;
@@ -93,6 +206,23 @@ impl StringParquetWriter {
         self.writer
             .close()
             .context("Failed closing Parquet writer")?;
+        if let Some(key) = &self.sort_key {
+            let started = Instant::now();
+            let sorted_path = self.tmp_path.with_extension("sorted");
+            if let Err(err) = sort_parquet_file(&self.tmp_path, &sorted_path, key, self.compression)
+            {
+                let _ = fs::remove_file(&sorted_path);
+                return Err(err);
+            }
+            info!(
+                "Sorted {} by {key} in {:.1}s (--sort-output)",
+                self.output_path.display(),
+                started.elapsed().as_secs_f64()
+            );
+            fs::remove_file(&self.tmp_path)
+                .with_context(|| format!("Failed removing {}", self.tmp_path.display()))?;
+            self.tmp_path = sorted_path;
+        }
         fs::rename(&self.tmp_path, &self.output_path).with_context(|| {
             format!(
                 "Failed moving temp parquet {} to {}",
@@ -100,6 +230,9 @@ impl StringParquetWriter {
                 self.output_path.display()
             )
         })?;
+        if let Some(ndjson) = self.ndjson {
+            ndjson.finish()?;
+        }
         Ok(())
     }
 
@@ -107,6 +240,9 @@ impl StringParquetWriter {
         // Best-effort cleanup: don't replace the output parquet with a partial tmp file.
         let _ = self.writer.close();
         let _ = fs::remove_file(&self.tmp_path);
+        if let Some(ndjson) = self.ndjson {
+            let _ = fs::remove_file(&ndjson.tmp_path);
+        }
         Ok(())
     }
 
@@ -115,11 +251,14 @@ impl StringParquetWriter {
             return Ok(());
         }
 
-        let arrays: Vec<ArrayRef> = self
+        let mut arrays: Vec<ArrayRef> = self
             .builders
             .iter_mut()
             .map(|b| Arc::new(b.finish()) as ArrayRef)
             .collect();
+        if let Some((_, builder)) = &mut self.content_hash {
+            arrays.push(Arc::new(builder.finish()) as ArrayRef);
+        }
         let batch = RecordBatch::try_new(Arc::clone(&self.schema), arrays)
             .context("Failed creating RecordBatch for Parquet write")?;
         self.writer
@@ -129,3 +268,230 @@ impl StringParquetWriter {
         Ok(())
     }
 }
+
+/// `--emit-ndjson` copy of a parquet output (`npi.parquet` -> `npi.ndjson`), fed the same rows
+/// as the parquet so nothing is read back. Each line holds the key column, `api_run_id`, and
+/// `response_json`, embedded as JSON when it parses and as a string otherwise. Rows are in
+/// emit order even under `--sort-output`. The file is renamed into place after the parquet.
+struct NdjsonSidecar {
+    output_path: PathBuf,
+    tmp_path: PathBuf,
+    writer: BufWriter<File>,
+    /// JSON-encoded name of the key (first) column, e.g. `"npi"`.
+    key: String,
+    api_run_id: usize,
+    response_json: usize,
+}
+
+impl NdjsonSidecar {
+    fn try_new(parquet_path: &Path, columns: &[&str]) -> Result<Self> {
+        let position = |name: &str| {
+            columns
+                .iter()
+                .position(|c| *c == name)
+                .with_context(|| format!("--emit-ndjson needs a {name:?} column"))
+        };
+        let key = columns
+            .first()
+            .context("--emit-ndjson needs at least one column")?;
+        let output_path = parquet_path.with_extension("ndjson");
+        let file_name = output_path
+            .file_name()
+            .and_then(|x| x.to_str())
+            .unwrap_or("output.ndjson");
+        let tmp_path = output_path.with_file_name(format!("{file_name}.tmp"));
+        let file = File::create(&tmp_path)
+            .with_context(|| format!("Failed creating {}", tmp_path.display()))?;
+        Ok(Self {
+            key: serde_json::to_string(key)?,
+            api_run_id: position("api_run_id")?,
+            response_json: position("response_json")?,
+            output_path,
+            tmp_path,
+            writer: BufWriter::new(file),
+        })
+    }
+
+    fn push_row(&mut self, values: &[Option<&str>]) -> Result<()> {
+        let response = match values[self.response_json] {
+            // Raw newlines in valid JSON are whitespace, so folding them keeps one row per line.
+            Some(raw) if serde_json::from_str::<serde::de::IgnoredAny>(raw).is_ok() => {
+                raw.replace(['\r', '\n'], " ")
+            }
+            other => serde_json::to_string(&other)?,
+        };
+        writeln!(
+            self.writer,
+            "{{{}:{},\"api_run_id\":{},\"response_json\":{}}}",
+            self.key,
+            serde_json::to_string(&values[0])?,
+            serde_json::to_string(&values[self.api_run_id])?,
+            response
+        )
+        .with_context(|| format!("Failed writing {}", self.tmp_path.display()))
+    }
+
+    fn finish(self) -> Result<()> {
+        self.writer
+            .into_inner()
+            .with_context(|| format!("Failed writing {}", self.tmp_path.display()))?;
+        fs::rename(&self.tmp_path, &self.output_path).with_context(|| {
+            format!(
+                "Failed moving temp NDJSON {} to {}",
+                self.tmp_path.display(),
+                self.output_path.display()
+            )
+        })?;
+        info!("Wrote {} (--emit-ndjson)", self.output_path.display());
+        Ok(())
+    }
+}
+
+fn parquet_codec(compression: ParquetCompression) -> Result<Compression> {
+    Ok(match compression {
+        ParquetCompression::Uncompressed => Compression::UNCOMPRESSED,
+        ParquetCompression::Snappy => Compression::SNAPPY,
+        ParquetCompression::Gzip => Compression::GZIP(GzipLevel::default()),
+        ParquetCompression::Zstd { level } => Compression::ZSTD(
+            ZstdLevel::try_new(level).with_context(|| format!("Invalid zstd level {level}"))?,
+        ),
+    })
+}
+
+/// DuckDB `COPY ... (FORMAT PARQUET, ...)` options for the same codec.
+fn duckdb_codec_options(compression: ParquetCompression) -> String {
+    match compression {
+        ParquetCompression::Uncompressed => "COMPRESSION UNCOMPRESSED".to_string(),
+        ParquetCompression::Snappy => "COMPRESSION SNAPPY".to_string(),
+        ParquetCompression::Gzip => "COMPRESSION GZIP".to_string(),
+        ParquetCompression::Zstd { level } => {
+            format!("COMPRESSION ZSTD, COMPRESSION_LEVEL {level}")
+        }
+    }
+}
+
+/// Copies `input` to `output` ordered by `key` (DuckDB external sort, so it spills to disk
+/// instead of holding the file in memory).
+fn sort_parquet_file(
+    input: &Path,
+    output: &Path,
+    key: &str,
+    compression: ParquetCompression,
+) -> Result<()> {
+    let conn = Connection::open_in_memory().context("Failed opening DuckDB for --sort-output")?;
+    let sql = format!(
+        "COPY (SELECT * FROM read_parquet('{}') ORDER BY \"{}\") TO '{}' (FORMAT PARQUET, {})",
+        sql_escape_path(input),
+        key.replace('"', "\"\""),
+        sql_escape_path(output),
+        duckdb_codec_options(compression)
+    );
+    conn.execute_batch(&sql)
+        .with_context(|| format!("Failed sorting {} by {key}", input.display()))?;
+    Ok(())
+}
+
+/// Map a user-selected column list onto indices of `columns`.
+///
+/// `None` keeps every column. Selected columns are written in `columns` order (not request
+/// order) so projected outputs keep the same human-readable layout as full exports. The key
+/// column (`columns[0]`) is required: completeness checks and `--sort-output` read it back.
+pub fn resolve_column_projection(
+    columns: &[&str],
+    selected: Option<&[String]>,
+) -> Result<Vec<usize>> {
+    let Some(selected) = selected else {
+        return Ok((0..columns.len()).collect());
+    };
+
+    let mut wanted: Vec<usize> = Vec::with_capacity(selected.len());
+    for name in selected {
+        let name = name.trim();
+        let Some(idx) = columns.iter().position(|c| *c == name) else {
+            anyhow::bail!(
+                "Unknown output column {name:?}; expected one of: {}",
+                columns.join(", ")
+            );
+        };
+        if wanted.contains(&idx) {
+            anyhow::bail!("Output column {name:?} was listed more than once");
+        }
+        wanted.push(idx);
+    }
+    anyhow::ensure!(!wanted.is_empty(), "Output column list must not be empty");
+    anyhow::ensure!(
+        wanted.contains(&0),
+        "Output columns must include the key column {:?}",
+        columns[0]
+    );
+    wanted.sort_unstable();
+    Ok(wanted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+
+    const COLUMNS: &[&str] = &["npi", "basic", "taxonomies", "api_run_id"];
+
+    fn selection(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn projection_keeps_column_order_and_rejects_bad_lists() {
+        let selected = selection(&["taxonomies", "npi"]);
+        assert_eq!(
+            resolve_column_projection(COLUMNS, Some(&selected)).unwrap(),
+            vec![0, 2]
+        );
+        assert_eq!(
+            resolve_column_projection(COLUMNS, None).unwrap(),
+            vec![0, 1, 2, 3]
+        );
+        for bad in [
+            selection(&["basic", "taxonomies"]),
+            selection(&["npi", "npi"]),
+            selection(&["npi", "nope"]),
+            selection(&[]),
+        ] {
+            assert!(
+                resolve_column_projection(COLUMNS, Some(&bad)).is_err(),
+                "{bad:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn projected_parquet_has_exactly_the_requested_columns() {
+        let dir = std::env::temp_dir().join(format!(
+            "build_datasets_projection_test_{}",
+            std::process::id()
+        ));
+        let output = dir.join("npi.parquet");
+        let selected = selection(&["api_run_id", "npi"]);
+        let mut writer = StringParquetWriter::try_new(
+            &output,
+            COLUMNS,
+            ParquetOutputOptions {
+                columns: Some(&selected),
+                ..Default::default()
+            },
+            &[],
+            10,
+        )
+        .unwrap();
+        writer
+            .push_row(&[Some("1234567893"), Some("{}"), None, Some("run-1")])
+            .unwrap();
+        writer.finish().unwrap();
+
+        let reader = SerializedFileReader::new(File::open(&output).unwrap()).unwrap();
+        let schema = reader.metadata().file_metadata().schema_descr();
+        let names: Vec<&str> = schema.columns().iter().map(|c| c.name()).collect();
+        assert_eq!(names, ["npi", "api_run_id"]);
+        assert_eq!(reader.metadata().file_metadata().num_rows(), 1);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,51 @@
+use anyhow::{Context, Result};
+use serde::{Serialize, Serializer};
+use std::{fs, path::Path};
+
+use crate::{args::Args, failed_bodies::redact_url, output::info};
+
+/// Contents of `run_config.json`: the fully resolved flags (defaults included) of one run.
+#[derive(Serialize)]
+struct RunConfig<'a> {
+    api_run_id: &'a str,
+    version: &'static str,
+    args: &'a Args,
+}
+
+/// Writes the effective configuration to `path` before any build work starts, so an output
+/// can be traced back to the exact flags it was built with. Secrets are redacted.
+pub fn write_run_config(args: &Args, api_run_id: &str, path: &Path) -> Result<()> {
+    let config = RunConfig {
+        api_run_id,
+        version: env!("CARGO_PKG_VERSION"),
+        args,
+    };
+    let json = serde_json::to_string_pretty(&config).context("Failed serializing run config")?;
+    fs::write(path, json).with_context(|| format!("Failed writing {}", path.display()))?;
+    info!("Wrote run config {}", path.display());
+    Ok(())
+}
+
+pub fn serialize_secret<S: Serializer>(value: &Option<String>, s: S) -> Result<S::Ok, S::Error> {
+    match value {
+        Some(_) => s.serialize_str("REDACTED"),
+        None => s.serialize_none(),
+    }
+}
+
+/// Strips URL credentials and key/token-like query values.
+pub fn serialize_url<S: Serializer>(value: &str, s: S) -> Result<S::Ok, S::Error> {
+    s.serialize_str(&redact_url(value))
+}
+
+pub fn serialize_tls_version<S: Serializer>(
+    value: &reqwest::tls::Version,
+    s: S,
+) -> Result<S::Ok, S::Error> {
+    let label = if *value == reqwest::tls::Version::TLS_1_3 {
+        "1.3"
+    } else {
+        "1.2"
+    };
+    s.serialize_str(label)
+}
@@ -0,0 +1,114 @@
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::{fs, path::Path, sync::Mutex};
+
+use crate::{common::now_unix_seconds, output::info};
+
+/// Counts from the NPI pre-download summary table.
+#[derive(Clone, Copy, Default, Serialize)]
+pub struct NpiPlanCounts {
+    pub unique_identifiers: usize,
+    pub resolved_before_bulk: usize,
+    pub found_via_bulk: usize,
+    pub planned_api_lookups: usize,
+}
+
+/// Counts from the HCPCS pre-download summary table.
+#[derive(Clone, Copy, Default, Serialize)]
+pub struct HcpcsPlanCounts {
+    pub unique_identifiers: usize,
+    pub resolved_before_api: usize,
+    pub planned_api_lookups: usize,
+    pub fallback_seeded: usize,
+}
+
+/// Settled API lookups of this run, as shown on the progress bar's final message.
+#[derive(Clone, Copy, Default, Serialize)]
+pub struct LookupTotals {
+    pub ok: usize,
+    pub not_found: usize,
+    pub error: usize,
+}
+
+#[derive(Clone, Copy, Default, Serialize)]
+struct DatasetSummary<P> {
+    plan: Option<P>,
+    lookups: LookupTotals,
+}
+
+#[derive(Default)]
+struct Recorded {
+    npi: Option<DatasetSummary<NpiPlanCounts>>,
+    hcpcs: Option<DatasetSummary<HcpcsPlanCounts>>,
+}
+
+/// Filled in by the NPI and HCPCS builds as they run; datasets skipped this run stay `None`.
+static RECORDED: Mutex<Recorded> = Mutex::new(Recorded {
+    npi: None,
+    hcpcs: None,
+});
+
+fn recorded() -> std::sync::MutexGuard<'static, Recorded> {
+    RECORDED
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+pub fn record_npi_plan(plan: NpiPlanCounts) {
+    recorded().npi.get_or_insert_default().plan = Some(plan);
+}
+
+pub fn record_npi_lookups(lookups: LookupTotals) {
+    recorded().npi.get_or_insert_default().lookups = lookups;
+}
+
+pub fn record_hcpcs_plan(plan: HcpcsPlanCounts) {
+    recorded().hcpcs.get_or_insert_default().plan = Some(plan);
+}
+
+pub fn record_hcpcs_lookups(lookups: LookupTotals) {
+    recorded().hcpcs.get_or_insert_default().lookups = lookups;
+}
+
+/// Contents of `run_summary.json`.
+#[derive(Serialize)]
+struct RunSummary<'a> {
+    api_run_id: &'a str,
+    started_at_unix: i64,
+    finished_at_unix: i64,
+    interrupted: bool,
+    npi: Option<DatasetSummary<NpiPlanCounts>>,
+    hcpcs: Option<DatasetSummary<HcpcsPlanCounts>>,
+}
+
+/// Writes the plan counts and lookup totals recorded so far. Also called on graceful shutdown,
+/// so a partial run leaves the same audit trail as a complete one.
+pub fn write_run_summary(
+    path: &Path,
+    api_run_id: &str,
+    started_at_unix: i64,
+    interrupted: bool,
+) -> Result<()> {
+    let (npi, hcpcs) = {
+        let recorded = recorded();
+        (recorded.npi, recorded.hcpcs)
+    };
+    let summary = RunSummary {
+        api_run_id,
+        started_at_unix,
+        finished_at_unix: now_unix_seconds(),
+        interrupted,
+        npi,
+        hcpcs,
+    };
+    let json = serde_json::to_string_pretty(&summary).context("Failed serializing run summary")?;
+    if let Some(parent) = path.parent()
+        && !parent.as_os_str().is_empty()
+    {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed creating {}", parent.display()))?;
+    }
+    fs::write(path, json).with_context(|| format!("Failed writing {}", path.display()))?;
+    info!("Wrote run summary {}", path.display());
+    Ok(())
+}
@@ -0,0 +1,57 @@
+use anyhow::{Context, Result, bail};
+use arrow::datatypes::DataType;
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use std::{fs::File, path::Path};
+
+use crate::output::info;
+use crate::spending_schema::{EXPECTED_SPENDING_SCHEMA, spending_schema_drift};
+
+fn type_family(data_type: &DataType) -> String {
+    match data_type {
+        DataType::Utf8 | DataType::LargeUtf8 | DataType::Utf8View => "string".to_string(),
+        DataType::Int64 => "int64".to_string(),
+        DataType::Float64 => "float64".to_string(),
+        other => format!("{other:?}").to_ascii_lowercase(),
+    }
+}
+
+/// Fails fast with an expected-vs-actual diff when the spending parquet's columns or types
+/// drift from [`EXPECTED_SPENDING_SCHEMA`]. Reads only the parquet footer.
+pub fn check_spending_schema(input_path: &Path) -> Result<()> {
+    let is_parquet = input_path
+        .extension()
+        .and_then(|x| x.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("parquet"));
+    if !is_parquet {
+        info!(
+            "--strict-schema only checks parquet inputs; skipping {}",
+            input_path.display()
+        );
+        return Ok(());
+    }
+
+    let file = File::open(input_path)
+        .with_context(|| format!("Failed opening {}", input_path.display()))?;
+    let builder = ParquetRecordBatchReaderBuilder::try_new(file)
+        .with_context(|| format!("Failed reading parquet schema of {}", input_path.display()))?;
+    let actual: Vec<(String, String)> = builder
+        .schema()
+        .fields()
+        .iter()
+        .map(|field| (field.name().clone(), type_family(field.data_type())))
+        .collect();
+
+    let diff = spending_schema_drift(&actual);
+    if !diff.is_empty() {
+        bail!(
+            "Spending parquet schema drifted from the expected layout in {}:\n{}",
+            input_path.display(),
+            diff.join("\n")
+        );
+    }
+    info!(
+        "Spending parquet schema matches the expected {} columns.",
+        EXPECTED_SPENDING_SCHEMA.len()
+    );
+    Ok(())
+}
@@ -0,0 +1,479 @@
+use anyhow::{Context, Result, bail};
+use clap::Parser;
+use parquet::file::reader::{FileReader, SerializedFileReader};
+use reqwest::Client;
+use serde_json::{Value, json};
+use std::{
+    collections::{BTreeSet, HashMap},
+    fs::{self, File},
+    path::Path,
+    sync::{Arc, Mutex, atomic::AtomicBool},
+};
+
+use crate::{
+    args::Args,
+    common::LookupBudget,
+    export_errors::ExportFailures,
+    hcpcs::build_hcpcs_mapping,
+    mock_api::{MALFORMED_JSON, MockApi, MockRequest, MockResponse},
+    npi::{NpiInput, build_npi_mapping, collect_unresolved_npis, is_npi_dataset_complete},
+};
+
+/// NPIs 1234567893 and 1245319599 are known to the mock NPI API, 1003000126 is not; J1234 is
+/// known to the mock HCPCS API, 99213 is not; J1234 is also billed with the JW modifier. The
+/// fourth and fifth rows carry the fault scenarios below, the last one the paged HCPCS batch.
+const SELFTEST_SPENDING_CSV: &str = "\
+BILLING_PROVIDER_NPI_NUM,SERVICING_PROVIDER_NPI_NUM,HCPCS_CODE,CLAIM_FROM_MONTH,TOTAL_UNIQUE_BENEFICIARIES,TOTAL_CLAIMS,TOTAL_PAID
+1234567893,1245319599,J1234,2024-01,12,20,1500.00
+1234567893,1234567890,99213,2024-01,30,45,2250.50
+1003000126,1234567893,J1234-JW,2024-02,5,7,310.25
+1000000004,1100000003,A0428,2024-02,3,4,880.00
+1200000002,,J1234,2024-03,2,2,95.10
+1234567893,1245319599,J9035,2024-03,1,1,410.00
+";
+
+const KNOWN_NPIS: [&str; 4] = ["1234567893", DEACTIVATED_NPI, FLAKY_NPI, THROTTLED_NPI];
+const KNOWN_HCPCS: [&str; 3] = ["J1234", THROTTLED_HCPCS, PAGED_HCPCS];
+
+/// Resolves with `basic.status = "D"`.
+const DEACTIVATED_NPI: &str = "1245319599";
+/// Answers its first request with 503, then resolves normally.
+const FLAKY_NPI: &str = "1000000004";
+/// Answers its first request with 429 + `Retry-After: 1`, then resolves normally.
+const THROTTLED_NPI: &str = "1100000003";
+/// Always answers 200 with a body that is not JSON.
+const MALFORMED_NPI: &str = "1200000002";
+/// Fails the NPI check digit, so `--strict-npi-validation` must never send it.
+const INVALID_NPI: &str = "1234567890";
+/// The first HCPCS batch containing this code gets 429 + `Retry-After: 1`.
+const THROTTLED_HCPCS: &str = "A0428";
+/// Has [`PAGED_HCPCS_REVISIONS`] description revisions, so its batch overflows
+/// `--hcpcs-batch-count=2` and must be paged in.
+const PAGED_HCPCS: &str = "J9035";
+const PAGED_HCPCS_REVISIONS: usize = 3;
+const RETRY_AFTER_SECS: u64 = 1;
+
+/// Records an attempt for a fault-scenario `key` (`npi:<npi>` / `hcpcs:<code>`) and returns
+/// how many it had seen before this one.
+fn record_attempt(attempts: &Mutex<HashMap<String, usize>>, key: String) -> usize {
+    let mut attempts = attempts
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let seen = attempts.entry(key).or_default();
+    *seen += 1;
+    *seen - 1
+}
+
+/// Requests the mock API received for one NPI (`/npi`) or HCPCS code (`/hcpcs`), in order.
+fn scenario_hits(mock: &MockApi, path: &str, id: &str) -> Vec<MockRequest> {
+    mock.requests()
+        .into_iter()
+        .filter(|request| request.path == path)
+        .filter(|request| match path {
+            "/npi" => request.param("number") == id,
+            _ => request.param("q").contains(id),
+        })
+        .collect()
+}
+
+/// Milliseconds between the first and second request, or 0 if it was not retried.
+fn retry_gap_ms(hits: &[MockRequest]) -> i64 {
+    match hits {
+        [first, second, ..] => second
+            .received_at
+            .duration_since(first.received_at)
+            .as_millis() as i64,
+        _ => 0,
+    }
+}
+
+fn mock_npi_response(npi: &str) -> Value {
+    if !KNOWN_NPIS.contains(&npi) {
+        return json!({ "result_count": 0, "results": [] });
+    }
+    json!({
+        "result_count": 1,
+        "results": [{
+            "number": npi,
+            "enumeration_type": "NPI-1",
+            "basic": {
+                "first_name": "SELFTEST",
+                "last_name": format!("PROVIDER {npi}"),
+                "status": if npi == DEACTIVATED_NPI { "D" } else { "A" }
+            },
+            "addresses": [],
+            "practice_locations": [],
+            "taxonomies": [],
+            "identifiers": [],
+            "other_names": [],
+            "endpoints": []
+        }]
+    })
+}
+
+/// Clinical Tables shape: `[total, codes, extra_fields, display]`, filtered to the codes in
+/// the request's `q=code:X` / `q=code:(X OR Y)` filter and paged by `offset`/`count`.
+fn mock_hcpcs_response(code_filter: &str, offset: usize, count: usize) -> Value {
+    let requested = code_filter
+        .trim_start_matches("code:")
+        .trim_matches(|c| c == '(' || c == ')');
+    let matched: Vec<(&str, String)> = requested
+        .split(" OR ")
+        .map(str::trim)
+        .filter_map(|code| {
+            KNOWN_HCPCS
+                .into_iter()
+                .find(|known| code.eq_ignore_ascii_case(known))
+        })
+        .flat_map(|code| {
+            let revisions = if code == PAGED_HCPCS {
+                PAGED_HCPCS_REVISIONS
+            } else {
+                1
+            };
+            (0..revisions).map(move |rev| (code, format!("20{:02}0101", 20 + rev)))
+        })
+        .collect();
+    let total = matched.len();
+    let page: Vec<(&str, String)> = matched.into_iter().skip(offset).take(count).collect();
+    let codes: Vec<&str> = page.iter().map(|(code, _)| *code).collect();
+    let dates: Vec<&str> = page.iter().map(|(_, date)| date.as_str()).collect();
+    let column = |value: &'static str| vec![value; page.len()];
+    json!([
+        total,
+        codes,
+        {
+            "short_desc": column("Selftest injection"),
+            "long_desc": column("Selftest injection, long description"),
+            "add_dt": dates,
+            "act_eff_dt": dates,
+            "term_dt": column(""),
+            "obsolete": column("false"),
+            "is_noc": column("false")
+        },
+        codes
+            .iter()
+            .map(|code| json!([code, "Selftest injection"]))
+            .collect::<Vec<_>>()
+    ])
+}
+
+/// The response to one request, including the scripted faults.
+fn mock_response(request: &MockRequest, attempts: &Mutex<HashMap<String, usize>>) -> MockResponse {
+    let body = match request.path.as_str() {
+        "/npi" => {
+            let npi = request.param("number");
+            if [FLAKY_NPI, THROTTLED_NPI, MALFORMED_NPI, INVALID_NPI].contains(&npi) {
+                let previous = record_attempt(attempts, format!("npi:{npi}"));
+                match npi {
+                    FLAKY_NPI if previous == 0 => {
+                        return MockResponse::raw(503, "{\"error\":\"unavailable\"}");
+                    }
+                    THROTTLED_NPI if previous == 0 => {
+                        return MockResponse::status(429).retry_after(RETRY_AFTER_SECS);
+                    }
+                    MALFORMED_NPI => return MockResponse::raw(200, MALFORMED_JSON),
+                    _ => {}
+                }
+            }
+            mock_npi_response(npi)
+        }
+        "/hcpcs" => {
+            let filter = request.param("q");
+            if filter.contains(THROTTLED_HCPCS)
+                && record_attempt(attempts, format!("hcpcs:{THROTTLED_HCPCS}")) == 0
+            {
+                return MockResponse::raw(429, "[]").retry_after(RETRY_AFTER_SECS);
+            }
+            let number = |name: &str| request.param(name).parse().ok();
+            mock_hcpcs_response(
+                filter,
+                number("offset").unwrap_or(0),
+                number("count").unwrap_or(usize::MAX),
+            )
+        }
+        _ => return MockResponse::status(404),
+    };
+    MockResponse::json(body)
+}
+
+/// Distinct identifiers per `status` in a mapping CSV.
+fn mapping_status_counts(path: &Path, id_column: &str) -> Result<HashMap<String, usize>> {
+    let mut reader = csv::Reader::from_path(path)
+        .with_context(|| format!("Failed opening mapping CSV {}", path.display()))?;
+    let headers = reader.headers()?.clone();
+    let id_idx = headers
+        .iter()
+        .position(|h| h == id_column)
+        .with_context(|| format!("Missing {id_column} column in {}", path.display()))?;
+    let status_idx = headers
+        .iter()
+        .position(|h| h == "status")
+        .with_context(|| format!("Missing status column in {}", path.display()))?;
+    let mut seen: BTreeSet<(String, String)> = BTreeSet::new();
+    for record in reader.records() {
+        let record = record?;
+        seen.insert((
+            record.get(status_idx).unwrap_or("").to_string(),
+            record.get(id_idx).unwrap_or("").to_string(),
+        ));
+    }
+    let mut counts = HashMap::new();
+    for (status, _) in seen {
+        *counts.entry(status).or_insert(0) += 1;
+    }
+    Ok(counts)
+}
+
+/// Rows of a mapping CSV whose `column` equals `value`.
+fn mapping_value_count(path: &Path, column: &str, value: &str) -> Result<usize> {
+    let mut reader = csv::Reader::from_path(path)
+        .with_context(|| format!("Failed opening mapping CSV {}", path.display()))?;
+    let idx = reader
+        .headers()?
+        .iter()
+        .position(|h| h == column)
+        .with_context(|| format!("Missing {column} column in {}", path.display()))?;
+    let mut count = 0usize;
+    for record in reader.records() {
+        if record?.get(idx) == Some(value) {
+            count += 1;
+        }
+    }
+    Ok(count)
+}
+
+/// An HCPCS cache from before `code_key`/`modifier`, holding a stale lowercase `j1234` error
+/// row. The build must migrate it and let the `J1234` lookup replace that row exactly.
+fn seed_legacy_hcpcs_cache(path: &Path) -> Result<()> {
+    rusqlite::Connection::open(path)?
+        .execute_batch(
+            "
+            CREATE TABLE hcpcs_cache (
+                hcpcs_code TEXT NOT NULL,
+                short_desc TEXT NOT NULL DEFAULT '',
+                long_desc TEXT NOT NULL DEFAULT '',
+                add_dt TEXT NOT NULL DEFAULT '',
+                act_eff_dt TEXT NOT NULL DEFAULT '',
+                term_dt TEXT NOT NULL DEFAULT '',
+                obsolete TEXT NOT NULL DEFAULT '',
+                is_noc TEXT NOT NULL DEFAULT '',
+                status TEXT NOT NULL,
+                error_message TEXT NOT NULL DEFAULT '',
+                fetched_at_unix INTEGER NOT NULL,
+                attempt_count INTEGER NOT NULL DEFAULT 0,
+                PRIMARY KEY (
+                    hcpcs_code, short_desc, long_desc, add_dt, act_eff_dt, term_dt, obsolete,
+                    is_noc, status
+                )
+            );
+            INSERT INTO hcpcs_cache (hcpcs_code, status, error_message, fetched_at_unix, attempt_count)
+            VALUES ('j1234', 'error', 'http_status_503', 0, 1);
+            ",
+        )
+        .with_context(|| format!("Failed seeding legacy HCPCS cache {}", path.display()))
+}
+
+fn parquet_row_count(path: &Path) -> Result<i64> {
+    let file = File::open(path).with_context(|| format!("Failed opening {}", path.display()))?;
+    let reader = SerializedFileReader::new(file)
+        .with_context(|| format!("Failed reading parquet {}", path.display()))?;
+    Ok(reader.metadata().file_metadata().num_rows())
+}
+
+/// Runs extraction, classification, mocked API resolution, and export on the bundled fixture
+/// in a temp dir, then checks the output row counts. Never touches the network or `data/`.
+pub async fn run_selftest() -> Result<()> {
+    let dir = std::env::temp_dir().join(format!("build_datasets_selftest_{}", std::process::id()));
+    if dir.exists() {
+        fs::remove_dir_all(&dir).with_context(|| format!("Failed clearing {}", dir.display()))?;
+    }
+    fs::create_dir_all(&dir).with_context(|| format!("Failed creating {}", dir.display()))?;
+    let input_path = dir.join("spending.csv");
+    fs::write(&input_path, SELFTEST_SPENDING_CSV)
+        .with_context(|| format!("Failed writing {}", input_path.display()))?;
+
+    let attempts = Mutex::new(HashMap::new());
+    let mock = MockApi::start(move |request, _| mock_response(request, &attempts)).await;
+
+    let path = |name: &str| dir.join(name).to_string_lossy().into_owned();
+    let args = Args::try_parse_from([
+        "build_datasets".to_string(),
+        "--skip-nppes-bulk".to_string(),
+        "--requests-per-second=0".to_string(),
+        "--failure-retry-rounds=0".to_string(),
+        "--max-retries=2".to_string(),
+        "--retry-backoff-base-ms=10".to_string(),
+        "--strict-npi-validation".to_string(),
+        "--hcpcs-batch-size=2".to_string(),
+        "--hcpcs-batch-count=2".to_string(),
+        format!("--api-base-url={}", mock.url("/npi")),
+        format!("--hcpcs-api-base-url={}", mock.url("/hcpcs")),
+        format!("--hcpcs-fallback-csv={}", path("no_fallback.csv")),
+    ])
+    .context("Failed building selftest arguments")?;
+
+    let client = Client::builder()
+        .user_agent("medicaid-provider-spending-mappings-selftest")
+        .build()
+        .context("Failed building HTTP client")?;
+    let shutdown_requested = Arc::new(AtomicBool::new(false));
+    let export_failures = ExportFailures::new(args.continue_on_export_error);
+    let lookup_budget = LookupBudget::new(args.max_new_lookups_total);
+    let npi_mapping_csv = dir.join("npi_provider_mapping.csv");
+    let npi_parquet = dir.join("npi.parquet");
+    let hcpcs_mapping_csv = dir.join("hcpcs_code_mapping.csv");
+    let hcpcs_parquet = dir.join("hcpcs.parquet");
+
+    build_npi_mapping(
+        &args,
+        &client,
+        NpiInput::Spending(&input_path),
+        &dir.join("npi_cache.sqlite"),
+        &npi_mapping_csv,
+        &npi_parquet,
+        "selftest",
+        &export_failures,
+        &lookup_budget,
+        None,
+        Arc::clone(&shutdown_requested),
+        &dir.join("nppes_monthly"),
+        &dir.join("nppes_weekly"),
+    )
+    .await?;
+    let hcpcs_cache_db = dir.join("hcpcs_cache.sqlite");
+    seed_legacy_hcpcs_cache(&hcpcs_cache_db)?;
+    build_hcpcs_mapping(
+        &args,
+        &client,
+        &input_path,
+        &hcpcs_cache_db,
+        &hcpcs_mapping_csv,
+        &hcpcs_parquet,
+        &dir.join("no_fallback.csv"),
+        "selftest",
+        &export_failures,
+        &lookup_budget,
+        None,
+        Arc::clone(&shutdown_requested),
+    )
+    .await?;
+
+    // Age one `ok` row past a 30-day TTL: only a TTL-aware completeness check re-queues it.
+    let npi_cache_db = dir.join("npi_cache.sqlite");
+    rusqlite::Connection::open(&npi_cache_db)?
+        .execute(
+            "UPDATE npi_cache SET fetched_at_unix = 0 WHERE npi = ?1",
+            [KNOWN_NPIS[0]],
+        )
+        .context("Failed aging selftest NPI cache row")?;
+    let npi_input = NpiInput::Spending(&input_path);
+    let invalid_unresolved = collect_unresolved_npis(npi_input, &npi_cache_db, false)?
+        .iter()
+        .filter(|entry| entry.status == "invalid")
+        .count();
+    let complete_without_ttl =
+        is_npi_dataset_complete(npi_input, &npi_cache_db, &npi_mapping_csv, None, false)?;
+    let complete_with_ttl =
+        is_npi_dataset_complete(npi_input, &npi_cache_db, &npi_mapping_csv, Some(30), false)?;
+    let stale_mixed_case_rows: i64 = rusqlite::Connection::open(&hcpcs_cache_db)?.query_row(
+        "SELECT COUNT(*) FROM hcpcs_cache WHERE code_key = 'J1234' AND status <> 'ok'",
+        [],
+        |row| row.get(0),
+    )?;
+
+    let npi_counts = mapping_status_counts(&npi_mapping_csv, "npi")?;
+    let hcpcs_counts = mapping_status_counts(&hcpcs_mapping_csv, "hcpcs_code")?;
+    let status = |counts: &HashMap<String, usize>, key: &str| counts.get(key).copied().unwrap_or(0);
+    // The HCPCS mapping CSV only carries `ok` rows; not_found codes show up in the parquet.
+    // Likewise the malformed-JSON NPI is an error row in npi.parquet but absent from the CSV.
+    let retry_after_ms = (RETRY_AFTER_SECS * 1_000) as i64;
+    let checks: [(&str, i64, i64); 18] = [
+        ("NPI mapping ok", status(&npi_counts, "ok") as i64, 4),
+        (
+            "NPI mapping not_found",
+            status(&npi_counts, "not_found") as i64,
+            1,
+        ),
+        ("NPI mapping error", status(&npi_counts, "error") as i64, 0),
+        (
+            "NPI mapping deactivated",
+            mapping_value_count(&npi_mapping_csv, "deactivated", "true")? as i64,
+            1,
+        ),
+        ("HCPCS mapping ok", status(&hcpcs_counts, "ok") as i64, 3),
+        (
+            "HCPCS paged batch revisions",
+            mapping_value_count(&hcpcs_mapping_csv, "hcpcs_code", PAGED_HCPCS)? as i64,
+            PAGED_HCPCS_REVISIONS as i64,
+        ),
+        (
+            "HCPCS mapping modifier",
+            mapping_value_count(&hcpcs_mapping_csv, "modifier", "JW")? as i64,
+            1,
+        ),
+        (
+            "HCPCS legacy mixed-case row replaced",
+            stale_mixed_case_rows,
+            0,
+        ),
+        ("npi.parquet rows", parquet_row_count(&npi_parquet)?, 7),
+        ("hcpcs.parquet rows", parquet_row_count(&hcpcs_parquet)?, 4),
+        (
+            "NPI 503 retried",
+            scenario_hits(&mock, "/npi", FLAKY_NPI).len() as i64,
+            2,
+        ),
+        (
+            "NPI malformed JSON not retried",
+            scenario_hits(&mock, "/npi", MALFORMED_NPI).len() as i64,
+            1,
+        ),
+        (
+            "NPI invalid check digit not requested",
+            scenario_hits(&mock, "/npi", INVALID_NPI).len() as i64,
+            0,
+        ),
+        ("NPI unresolved invalid", invalid_unresolved as i64, 1),
+        (
+            "NPI 429 waited Retry-After",
+            i64::from(retry_gap_ms(&scenario_hits(&mock, "/npi", THROTTLED_NPI)) >= retry_after_ms),
+            1,
+        ),
+        (
+            "HCPCS 429 waited Retry-After",
+            i64::from(
+                retry_gap_ms(&scenario_hits(&mock, "/hcpcs", THROTTLED_HCPCS)) >= retry_after_ms,
+            ),
+            1,
+        ),
+        (
+            "NPI dataset complete (no TTL)",
+            i64::from(complete_without_ttl),
+            1,
+        ),
+        (
+            "NPI dataset complete (stale row, 30-day TTL)",
+            i64::from(complete_with_ttl),
+            0,
+        ),
+    ];
+
+    let mut failed = 0usize;
+    for (label, actual, expected) in checks {
+        let verdict = if actual == expected { "ok" } else { "FAIL" };
+        if actual != expected {
+            failed += 1;
+        }
+        println!("selftest {verdict}: {label} = {actual} (expected {expected})");
+    }
+    if failed > 0 {
+        bail!(
+            "selftest failed {failed} check(s); outputs kept in {}",
+            dir.display()
+        );
+    }
+    fs::remove_dir_all(&dir).with_context(|| format!("Failed removing {}", dir.display()))?;
+    println!("selftest passed");
+    Ok(())
+}
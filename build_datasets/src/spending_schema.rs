@@ -0,0 +1,84 @@
+//! Expected spending parquet layout, shared with the site backend (`site-backend build
+//! --strict-schema`) so both tools reject the same drift. Keep this file free of crate imports.
+
+/// Expected spending columns and type families, matching the HF `spending` split.
+pub const EXPECTED_SPENDING_SCHEMA: &[(&str, &str)] = &[
+    ("BILLING_PROVIDER_NPI_NUM", "string"),
+    ("SERVICING_PROVIDER_NPI_NUM", "string"),
+    ("HCPCS_CODE", "string"),
+    ("CLAIM_FROM_MONTH", "string"),
+    ("TOTAL_UNIQUE_BENEFICIARIES", "int64"),
+    ("TOTAL_CLAIMS", "int64"),
+    ("TOTAL_PAID", "float64"),
+];
+
+/// Columns some spending extracts carry and the pipeline reads when present.
+pub const OPTIONAL_SPENDING_COLUMNS: &[(&str, &str)] = &[("HCPCS_MODIFIER", "string")];
+
+/// One line per missing, unexpected, or retyped column in `actual` (`(name, type_family)`
+/// pairs); empty when the layout matches.
+pub fn spending_schema_drift(actual: &[(String, String)]) -> Vec<String> {
+    let mut diff = Vec::new();
+    for (name, expected_type) in EXPECTED_SPENDING_SCHEMA {
+        match actual.iter().find(|(actual_name, _)| actual_name == name) {
+            None => diff.push(format!("  missing column {name} ({expected_type})")),
+            Some((_, actual_type)) if actual_type != expected_type => diff.push(format!(
+                "  {name}: expected {expected_type}, found {actual_type}"
+            )),
+            Some(_) => {}
+        }
+    }
+    for (name, actual_type) in actual {
+        if EXPECTED_SPENDING_SCHEMA.iter().any(|(n, _)| n == name) {
+            continue;
+        }
+        match OPTIONAL_SPENDING_COLUMNS.iter().find(|(n, _)| n == name) {
+            None => diff.push(format!("  unexpected column {name} ({actual_type})")),
+            Some((_, expected_type)) if actual_type != expected_type => diff.push(format!(
+                "  {name}: expected {expected_type}, found {actual_type}"
+            )),
+            Some(_) => {}
+        }
+    }
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expected() -> Vec<(String, String)> {
+        EXPECTED_SPENDING_SCHEMA
+            .iter()
+            .map(|(name, ty)| (name.to_string(), ty.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn expected_layout_with_optional_modifier_has_no_drift() {
+        let mut actual = expected();
+        assert!(spending_schema_drift(&actual).is_empty());
+        actual.push(("HCPCS_MODIFIER".to_string(), "string".to_string()));
+        assert!(spending_schema_drift(&actual).is_empty());
+    }
+
+    #[test]
+    fn drift_lists_missing_retyped_and_unexpected_columns() {
+        let mut actual = expected();
+        actual.retain(|(name, _)| name != "CLAIM_FROM_MONTH");
+        actual
+            .iter_mut()
+            .find(|(name, _)| name == "TOTAL_PAID")
+            .unwrap()
+            .1 = "string".to_string();
+        actual.push(("EXTRA".to_string(), "int64".to_string()));
+        assert_eq!(
+            spending_schema_drift(&actual),
+            [
+                "  missing column CLAIM_FROM_MONTH (string)",
+                "  TOTAL_PAID: expected float64, found string",
+                "  unexpected column EXTRA (int64)",
+            ]
+        );
+    }
+}
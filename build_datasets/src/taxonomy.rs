@@ -0,0 +1,65 @@
+use anyhow::{Context, Result};
+use std::{collections::HashMap, fs::File, path::Path, sync::OnceLock};
+
+use crate::output::info;
+
+/// NUCC taxonomy code -> description, set once from the reference CSV. While unset (or when
+/// the CSV is absent) bulk-sourced taxonomies keep a null `desc`.
+static TAXONOMY_DESCRIPTIONS: OnceLock<HashMap<String, String>> = OnceLock::new();
+
+/// Loads the NUCC taxonomy CSV (`Code`, `Classification`, `Specialization`, ... as published
+/// on nucc.org). A missing file is not an error: descriptions are only a convenience for
+/// bulk-sourced rows, which otherwise carry codes alone.
+pub fn load_taxonomy_reference(path: &Path) -> Result<()> {
+    if !path.exists() {
+        info!(
+            "NUCC taxonomy reference {} not found; bulk NPI taxonomies keep a null desc.",
+            path.display()
+        );
+        return Ok(());
+    }
+    let file = File::open(path).with_context(|| format!("Failed opening {}", path.display()))?;
+    let mut reader = csv::ReaderBuilder::new().flexible(true).from_reader(file);
+    let headers = reader
+        .headers()
+        .with_context(|| format!("Failed reading header of {}", path.display()))?
+        .clone();
+    // The published CSV starts with a UTF-8 BOM.
+    let column = |name: &str| {
+        headers
+            .iter()
+            .position(|h| h.trim_start_matches('\u{feff}').trim() == name)
+            .with_context(|| format!("{} missing required header '{name}'", path.display()))
+    };
+    let code_idx = column("Code")?;
+    let classification_idx = column("Classification")?;
+    let specialization_idx = column("Specialization")?;
+
+    let mut descriptions = HashMap::new();
+    for record in reader.records() {
+        let record = record.with_context(|| format!("Failed reading {}", path.display()))?;
+        let field = |idx: usize| record.get(idx).unwrap_or("").trim();
+        let code = field(code_idx);
+        let classification = field(classification_idx);
+        if code.is_empty() || classification.is_empty() {
+            continue;
+        }
+        // Same shape as the NPPES API `desc`: "Classification" or "Classification, Specialization".
+        let desc = match field(specialization_idx) {
+            "" => classification.to_string(),
+            specialization => format!("{classification}, {specialization}"),
+        };
+        descriptions.insert(code.to_string(), desc);
+    }
+    info!(
+        "Loaded {} NUCC taxonomy descriptions from {}",
+        descriptions.len(),
+        path.display()
+    );
+    let _ = TAXONOMY_DESCRIPTIONS.set(descriptions);
+    Ok(())
+}
+
+pub fn taxonomy_description(code: &str) -> Option<&'static str> {
+    TAXONOMY_DESCRIPTIONS.get()?.get(code).map(String::as_str)
+}
@@ -3,6 +3,8 @@ use csv::Writer;
 use serde::Deserialize;
 use std::{collections::HashMap, fs, path::Path};
 
+use crate::normalize::{normalize_code_key, npi_luhn_valid};
+
 #[derive(Debug, Deserialize)]
 struct UnresolvedRow {
     identifier_type: String,
@@ -33,10 +35,8 @@ pub struct IdentifierTriageSummary {
     pub hcpcs_needs_review_rows: usize,
     pub npi_rows: usize,
     pub npi_needs_review_rows: usize,
-}
-
-fn normalize_identifier(raw: &str) -> String {
-    raw.trim().to_ascii_uppercase()
+    pub permanently_failed_rows: usize,
+    pub npi_invalid_rows: usize,
 }
 
 fn is_placeholder(u: &str) -> bool {
@@ -213,37 +213,6 @@ fn classify_hcpcs_identifier(raw: &str) -> (String, Option<String>, Option<Strin
     ("unknown".to_string(), Some(u), None)
 }
 
-fn luhn_mod10(digits: &[u32]) -> u32 {
-    let mut sum: u32 = 0;
-    let mut double = false;
-    for d in digits.iter().rev() {
-        let mut v = *d;
-        if double {
-            v *= 2;
-            if v > 9 {
-                v -= 9;
-            }
-        }
-        sum += v;
-        double = !double;
-    }
-    sum % 10
-}
-
-fn npi_luhn_valid(npi_digits: &str) -> bool {
-    if npi_digits.len() != 10 || !npi_digits.as_bytes().iter().all(|b| b.is_ascii_digit()) {
-        return false;
-    }
-
-    // NPI validation uses Luhn with the fixed "80840" prefix applied to the full 10 digits.
-    let mut digits: [u32; 15] = [0; 15];
-    digits[..5].copy_from_slice(&[8, 0, 8, 4, 0]);
-    for (idx, b) in npi_digits.as_bytes().iter().enumerate() {
-        digits[5 + idx] = (b - b'0') as u32;
-    }
-    luhn_mod10(&digits) == 0
-}
-
 fn classify_npi_identifier(raw: &str) -> (String, Option<String>, Option<String>) {
     let s = raw.trim();
     let u = s.to_ascii_uppercase();
@@ -346,7 +315,7 @@ pub fn write_unresolved_identifier_triage(
         let id_type = row.identifier_type.trim();
         if id_type.eq_ignore_ascii_case("hcpcs") {
             let (inferred, base, suffix) = classify_hcpcs_identifier(&row.identifier);
-            let identifier_norm = normalize_identifier(&row.identifier);
+            let identifier_norm = normalize_code_key(&row.identifier);
             hcpcs_rows.push(TriageRow {
                 identifier_type: row.identifier_type,
                 identifier: row.identifier,
@@ -360,7 +329,7 @@ pub fn write_unresolved_identifier_triage(
             });
         } else if id_type.eq_ignore_ascii_case("npi") {
             let (inferred, base, suffix) = classify_npi_identifier(&row.identifier);
-            let identifier_norm = normalize_identifier(&row.identifier);
+            let identifier_norm = normalize_code_key(&row.identifier);
             npi_rows.push(TriageRow {
                 identifier_type: row.identifier_type,
                 identifier: row.identifier,
@@ -575,10 +544,32 @@ pub fn write_unresolved_identifier_triage(
         writer.flush().context("Failed flushing writer")?;
     }
 
+    // --- Identifiers past --max-lifetime-attempts (both types) ---
+    let permanently_failed: Vec<TriageRow> = hcpcs_rows
+        .iter()
+        .chain(npi_rows.iter())
+        .filter(|r| r.status == "permanently_failed")
+        .cloned()
+        .collect();
+    write_triage_rows(
+        &out_dir.join("permanently_failed_identifiers.csv"),
+        &permanently_failed,
+    )?;
+
+    // --- NPIs flagged `invalid` by --strict-npi-validation (never sent to the API) ---
+    let npi_invalid: Vec<TriageRow> = npi_rows
+        .iter()
+        .filter(|r| r.status == "invalid")
+        .cloned()
+        .collect();
+    write_triage_rows(&out_dir.join("npi_invalid_identifiers.csv"), &npi_invalid)?;
+
     Ok(IdentifierTriageSummary {
         hcpcs_rows: hcpcs_rows.len(),
         hcpcs_needs_review_rows: hcpcs_unmapped.len(),
         npi_rows: npi_rows.len(),
         npi_needs_review_rows: npi_unmapped.len(),
+        permanently_failed_rows: permanently_failed.len(),
+        npi_invalid_rows: npi_invalid.len(),
     })
 }
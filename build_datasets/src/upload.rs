@@ -2,6 +2,7 @@ use anyhow::{Context, Result, bail};
 use std::{path::Path, process::Command};
 
 use crate::args::Args;
+use crate::output::info;
 
 pub fn maybe_upload_outputs(
     args: &Args,
@@ -10,6 +11,19 @@ pub fn maybe_upload_outputs(
     npi_api_responses_parquet: &Path,
     hcpcs_api_responses_parquet: &Path,
 ) -> Result<()> {
+    if let Some(destination) = args.upload_s3.as_deref() {
+        upload_outputs_to_s3(
+            destination,
+            args.s3_endpoint.as_deref(),
+            &[
+                npi_mapping_csv,
+                hcpcs_mapping_csv,
+                npi_api_responses_parquet,
+                hcpcs_api_responses_parquet,
+            ],
+        )?;
+    }
+
     if !args.hf_upload_mapping
         && !args.hf_upload_hcpcs_mapping
         && !args.hf_upload_npi
@@ -117,6 +131,84 @@ fn file_name_for_repo(path: &Path) -> Result<String> {
         .context("Could not derive filename for repo path")
 }
 
+/// Uploads each existing file to `<destination>/<file name>`. Files this run did not write
+/// (e.g. the HCPCS outputs under `--npi-only`) are skipped rather than failing the upload.
+fn upload_outputs_to_s3(destination: &str, endpoint: Option<&str>, files: &[&Path]) -> Result<()> {
+    let (bucket, prefix) = split_s3_url(destination)?;
+    for local_file in files {
+        if !local_file.exists() {
+            info!(
+                "Skipping S3 upload of {}: file does not exist",
+                local_file.display()
+            );
+            continue;
+        }
+        let file_name = file_name_for_repo(local_file)?;
+        let key = match prefix {
+            "" => file_name,
+            prefix => format!("{prefix}/{file_name}"),
+        };
+        upload_file_to_s3(local_file, bucket, &key, endpoint)?;
+    }
+    Ok(())
+}
+
+/// `s3://bucket/some/prefix` -> (`bucket`, `some/prefix`); the prefix may be empty.
+fn split_s3_url(url: &str) -> Result<(&str, &str)> {
+    let rest = url
+        .strip_prefix("s3://")
+        .with_context(|| format!("Invalid S3 URL {url:?}: expected s3://<bucket>[/<prefix>]"))?;
+    let (bucket, prefix) = rest.split_once('/').unwrap_or((rest, ""));
+    if bucket.is_empty() {
+        bail!("Invalid S3 URL {url:?}: missing bucket name");
+    }
+    Ok((bucket, prefix.trim_matches('/')))
+}
+
+fn s3_content_type(path: &Path) -> &'static str {
+    match path.extension().and_then(|x| x.to_str()) {
+        Some("csv") => "text/csv",
+        Some("parquet") => "application/vnd.apache.parquet",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Boto3 upload with the skip-if-unchanged sidecar; see the script's docstring.
+const S3_UPLOAD_SCRIPT: &str = include_str!("../scripts/s3_upload.py");
+
+/// The SHA-256 of the last successful upload is kept in `<file>.s3.sha256` together with the
+/// destination, so a rerun that left the file unchanged does not upload it again.
+fn upload_file_to_s3(
+    local_file: &Path,
+    bucket: &str,
+    key: &str,
+    endpoint: Option<&str>,
+) -> Result<()> {
+    info!(
+        "Uploading {} -> s3://{}/{}{}",
+        local_file.display(),
+        bucket,
+        key,
+        endpoint.map(|x| format!(" ({x})")).unwrap_or_default()
+    );
+
+    let status = Command::new("python3")
+        .arg("-c")
+        .arg(S3_UPLOAD_SCRIPT)
+        .env("S3_LOCAL_FILE", local_file.to_string_lossy().to_string())
+        .env("S3_BUCKET", bucket)
+        .env("S3_KEY", key)
+        .env("S3_ENDPOINT", endpoint.unwrap_or_default())
+        .env("S3_CONTENT_TYPE", s3_content_type(local_file))
+        .status()
+        .context("Failed starting python3 for S3 upload")?;
+
+    if !status.success() {
+        bail!("S3 upload failed for {}", local_file.display());
+    }
+    Ok(())
+}
+
 fn upload_file_to_hf(
     local_file: &Path,
     path_in_repo: &str,
@@ -124,7 +216,7 @@ fn upload_file_to_hf(
     repo_type: &str,
     token: &str,
 ) -> Result<()> {
-    println!(
+    info!(
         "Uploading {} -> hf://{}/{} ({})",
         local_file.display(),
         repo_id,
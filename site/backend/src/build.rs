@@ -6,11 +6,14 @@ use serde::Serialize;
 
 use crate::cli::BuildArgs;
 use crate::download;
+use crate::duplicates;
 use crate::geo;
 use crate::hcpcs;
 use crate::index;
 use crate::npi;
+use crate::spending_schema::{EXPECTED_SPENDING_SCHEMA, spending_schema_drift};
 use crate::storage::StoragePaths;
+use crate::views;
 
 #[derive(Debug, Serialize)]
 struct BuildMeta {
@@ -63,8 +66,18 @@ pub async fn run(opts: BuildArgs) -> anyhow::Result<()> {
     // Basic performance tuning; keep conservative defaults.
     let _ = conn.execute("PRAGMA threads=4", []);
 
-    create_or_replace_views(&mut conn, &sources.spending, &sources.npi, &sources.hcpcs)
+    if opts.strict_schema {
+        let source = format!("read_parquet('{}')", sql_quote_path(&sources.spending));
+        check_spending_schema(&mut conn, &source)
+            .with_context(|| format!("check schema of {}", sources.spending.display()))?;
+    }
+
+    views::create_or_replace_views(&mut conn, &sources.spending, &sources.npi, &sources.hcpcs)
         .context("create views")?;
+    let amounts = AmountColumns::resolve(&mut conn, &opts)?;
+    if opts.report_duplicates.is_some() || opts.dedupe_spending {
+        apply_duplicate_options(&mut conn, &sources.spending, &opts, &amounts)?;
+    }
     tracing::info!(
         "DuckDB ready in {:.1}s: {}",
         t1.elapsed().as_secs_f64(),
@@ -72,14 +85,22 @@ pub async fn run(opts: BuildArgs) -> anyhow::Result<()> {
     );
 
     tracing::info!("Step 3/6: build rollups (provider_totals + hcpcs_totals)");
-    if opts.rebuild || !table_exists(&mut conn, "provider_totals")? {
-        rebuild_provider_totals(&mut conn).context("build provider_totals")?;
+    let provider_totals_exists = table_exists(&mut conn, "provider_totals")?;
+    let provider_totals_stale = provider_totals_exists
+        && !opts.rebuild
+        && has_missing_columns(&mut conn, "provider_totals", &["distinct_codes"])?;
+    if opts.rebuild || !provider_totals_exists || provider_totals_stale {
+        rebuild_provider_totals(&mut conn, &amounts).context("build provider_totals")?;
     } else {
         tracing::info!("DuckDB table provider_totals already exists; skipping");
     }
 
-    if opts.rebuild || !table_exists(&mut conn, "hcpcs_totals")? {
-        rebuild_hcpcs_totals(&mut conn).context("build hcpcs_totals")?;
+    let hcpcs_totals_exists = table_exists(&mut conn, "hcpcs_totals")?;
+    let hcpcs_totals_stale = hcpcs_totals_exists
+        && !opts.rebuild
+        && has_missing_columns(&mut conn, "hcpcs_totals", HCPCS_ADDED_COLUMNS)?;
+    if opts.rebuild || !hcpcs_totals_exists || hcpcs_totals_stale {
+        rebuild_hcpcs_totals(&mut conn, &amounts).context("build hcpcs_totals")?;
     } else {
         tracing::info!("DuckDB table hcpcs_totals already exists; skipping");
     }
@@ -87,13 +108,20 @@ pub async fn run(opts: BuildArgs) -> anyhow::Result<()> {
     tracing::info!(
         "Step 4/6: build geo + metadata tables (zip_centroids + provider_info + hcpcs_info)"
     );
-    if opts.rebuild || !table_exists(&mut conn, "zip_centroids")? {
+    if opts.rebuild
+        || !table_exists(&mut conn, "zip_centroids")?
+        || !table_exists(&mut conn, "zip3_centroids")?
+    {
         rebuild_zip_centroids(&mut conn, &geonames_txt).context("build zip_centroids")?;
     } else {
         tracing::info!("DuckDB table zip_centroids already exists; skipping");
     }
 
-    if opts.rebuild || !table_exists(&mut conn, "provider_info")? {
+    let provider_info_exists = table_exists(&mut conn, "provider_info")?;
+    let provider_info_stale = provider_info_exists
+        && !opts.rebuild
+        && has_missing_columns(&mut conn, "provider_info", PROVIDER_ADDED_COLUMNS)?;
+    if opts.rebuild || !provider_info_exists || provider_info_stale {
         rebuild_provider_info(&mut conn).context("build provider_info")?;
     } else {
         tracing::info!("DuckDB table provider_info already exists; skipping");
@@ -122,7 +150,21 @@ pub async fn run(opts: BuildArgs) -> anyhow::Result<()> {
         false
     };
 
-    if opts.rebuild || !provider_search_exists || provider_search_bad {
+    let provider_search_stale = provider_search_exists
+        && !opts.rebuild
+        && (has_missing_columns(&mut conn, "provider_search", PROVIDER_ADDED_COLUMNS)?
+            || has_missing_columns(
+                &mut conn,
+                "provider_search",
+                &[
+                    "geo_source",
+                    "distinct_codes",
+                    "state_rank_paid",
+                    "state_provider_count",
+                ],
+            )?);
+
+    if opts.rebuild || !provider_search_exists || provider_search_bad || provider_search_stale {
         rebuild_provider_search(&mut conn).context("build provider_search")?;
     } else {
         tracing::info!("DuckDB table provider_search already exists; skipping");
@@ -144,7 +186,11 @@ pub async fn run(opts: BuildArgs) -> anyhow::Result<()> {
         false
     };
 
-    if opts.rebuild || !hcpcs_search_exists || hcpcs_search_bad {
+    let hcpcs_search_stale = hcpcs_search_exists
+        && !opts.rebuild
+        && has_missing_columns(&mut conn, "hcpcs_search", HCPCS_ADDED_COLUMNS)?;
+
+    if opts.rebuild || !hcpcs_search_exists || hcpcs_search_bad || hcpcs_search_stale {
         rebuild_hcpcs_search(&mut conn).context("build hcpcs_search")?;
     } else {
         tracing::info!("DuckDB table hcpcs_search already exists; skipping");
@@ -156,6 +202,23 @@ pub async fn run(opts: BuildArgs) -> anyhow::Result<()> {
     index::hcpcs::build_hcpcs_index(&conn, &paths.hcpcs_index_dir, opts.rebuild)
         .context("build hcpcs tantivy index")?;
 
+    if let Some(top_n) = opts.leaderboard_top_n {
+        tracing::info!(
+            "Writing leaderboard (top {top_n}) to {}",
+            paths.leaderboard_path.display()
+        );
+        crate::leaderboard::write_leaderboard(&mut conn, &paths.leaderboard_path, top_n)
+            .context("write leaderboard.parquet")?;
+    } else if paths.leaderboard_path.exists() {
+        // A leaderboard from an earlier build would be served against the new rollups.
+        tracing::info!(
+            "Removing stale leaderboard {} (no --leaderboard-top-n)",
+            paths.leaderboard_path.display()
+        );
+        std::fs::remove_file(&paths.leaderboard_path)
+            .context("remove stale leaderboard.parquet")?;
+    }
+
     let provider_count: u64 = one_u64(&mut conn, "SELECT COUNT(*) FROM provider_search")?;
     let hcpcs_count: u64 = one_u64(&mut conn, "SELECT COUNT(*) FROM hcpcs_search")?;
 
@@ -179,45 +242,135 @@ pub async fn run(opts: BuildArgs) -> anyhow::Result<()> {
     Ok(())
 }
 
-fn create_or_replace_views(
+/// Quoted spending columns behind the paid/claims/bene rollups (`--paid-col` etc.).
+struct AmountColumns {
+    paid: String,
+    claims: String,
+    bene: String,
+}
+
+impl AmountColumns {
+    /// Checks each configured column against `spending_raw` so a typo fails before any scan.
+    fn resolve(conn: &mut Connection, opts: &BuildArgs) -> anyhow::Result<Self> {
+        let mut stmt = conn.prepare("DESCRIBE SELECT * FROM spending_raw")?;
+        let available = stmt
+            .query_map([], |row| row.get::<usize, String>(0))?
+            .collect::<Result<Vec<_>, _>>()
+            .context("describe spending_raw")?;
+        let column = |flag: &str, name: &str| {
+            if available.iter().any(|c| c == name) {
+                Ok(format!("\"{}\"", name.replace('"', "\"\"")))
+            } else {
+                Err(anyhow!(
+                    "{flag} column {name:?} is not in the spending parquet (columns: {})",
+                    available.join(", ")
+                ))
+            }
+        };
+        Ok(Self {
+            paid: column("--paid-col", &opts.paid_col)?,
+            claims: column("--claims-col", &opts.claims_col)?,
+            bene: column("--bene-col", &opts.bene_col)?,
+        })
+    }
+}
+
+/// `--strict-schema`: compares `source`'s columns with [`EXPECTED_SPENDING_SCHEMA`] and fails
+/// with an expected-vs-actual diff on drift.
+fn check_spending_schema(conn: &mut Connection, source: &str) -> anyhow::Result<()> {
+    let mut stmt = conn.prepare(&format!("DESCRIBE SELECT * FROM {source}"))?;
+    let actual = stmt
+        .query_map([], |row| {
+            Ok((row.get::<usize, String>(0)?, row.get::<usize, String>(1)?))
+        })?
+        .collect::<Result<Vec<_>, _>>()
+        .context("describe spending parquet")?
+        .into_iter()
+        .map(|(name, duckdb_type)| {
+            let family = match duckdb_type.as_str() {
+                "VARCHAR" => "string".to_string(),
+                "BIGINT" => "int64".to_string(),
+                "DOUBLE" => "float64".to_string(),
+                other => other.to_ascii_lowercase(),
+            };
+            (name, family)
+        })
+        .collect::<Vec<_>>();
+
+    let diff = spending_schema_drift(&actual);
+    if !diff.is_empty() {
+        return Err(anyhow!(
+            "spending parquet schema drifted from the expected layout:\n{}",
+            diff.join("\n")
+        ));
+    }
+    tracing::info!(
+        "Spending parquet schema matches the expected {} columns",
+        EXPECTED_SPENDING_SCHEMA.len()
+    );
+    Ok(())
+}
+
+fn apply_duplicate_options(
     conn: &mut Connection,
     spending: &Path,
-    npi: &Path,
-    hcpcs: &Path,
+    opts: &BuildArgs,
+    amounts: &AmountColumns,
 ) -> anyhow::Result<()> {
-    let spending = sql_quote_path(spending);
-    let npi = sql_quote_path(npi);
-    let hcpcs = sql_quote_path(hcpcs);
+    let source = format!("read_parquet('{}')", sql_quote_path(spending));
+    let key_columns = duplicates::resolve_key_columns(conn, &source, &opts.duplicate_key)?;
+
+    if let Some(report_path) = &opts.report_duplicates {
+        tracing::info!(
+            "Counting duplicate spending rows over ({})...",
+            key_columns.join(", ")
+        );
+        let report =
+            duplicates::duplicate_report(conn, spending, &source, &key_columns, &amounts.paid)?;
+        tracing::info!(
+            "Duplicate spending rows: {} of {} in {} groups ({} {:.2})",
+            report.duplicate_rows,
+            report.total_rows,
+            report.duplicate_groups,
+            opts.paid_col,
+            report.duplicate_paid
+        );
+        write_json(Path::new(report_path), &report).context("write duplicate report")?;
+        tracing::info!("Wrote duplicate report {}", report_path);
+    }
 
-    conn.execute(
-        &format!("CREATE OR REPLACE VIEW spending_raw AS SELECT * FROM read_parquet('{spending}')"),
-        [],
-    )?;
-    conn.execute(
-        &format!("CREATE OR REPLACE VIEW npi_api_raw AS SELECT * FROM read_parquet('{npi}')"),
-        [],
-    )?;
-    conn.execute(
-        &format!("CREATE OR REPLACE VIEW hcpcs_api_raw AS SELECT * FROM read_parquet('{hcpcs}')"),
-        [],
-    )?;
+    if opts.dedupe_spending {
+        tracing::info!(
+            "dedupe_spending=true (one row per {} for rollups)",
+            key_columns.join(", ")
+        );
+        conn.execute(
+            &format!(
+                "CREATE OR REPLACE VIEW spending_raw AS {}",
+                duplicates::dedupe_select(&source, &key_columns)
+            ),
+            [],
+        )?;
+    }
     Ok(())
 }
 
-fn rebuild_provider_totals(conn: &mut Connection) -> anyhow::Result<()> {
+fn rebuild_provider_totals(conn: &mut Connection, amounts: &AmountColumns) -> anyhow::Result<()> {
     tracing::info!(
         "Building provider_totals (this will scan the spending parquet; can take a while)..."
     );
     conn.execute("DROP TABLE IF EXISTS provider_totals", [])?;
 
-    let sql = r#"
+    let AmountColumns { paid, claims, bene } = amounts;
+    let sql = format!(
+        r#"
         CREATE TABLE provider_totals AS
         WITH billing AS (
           SELECT
             NULLIF(TRIM(BILLING_PROVIDER_NPI_NUM), '') AS npi,
-            SUM(TOTAL_PAID) AS paid_billing,
-            SUM(TOTAL_CLAIMS) AS claims_billing,
-            SUM(TOTAL_UNIQUE_BENEFICIARIES) AS bene_billing
+            SUM({paid}) AS paid_billing,
+            SUM({claims}) AS claims_billing,
+            SUM({bene}) AS bene_billing
           FROM spending_raw
           WHERE BILLING_PROVIDER_NPI_NUM IS NOT NULL AND TRIM(BILLING_PROVIDER_NPI_NUM) <> ''
           GROUP BY 1
@@ -225,12 +378,26 @@ fn rebuild_provider_totals(conn: &mut Connection) -> anyhow::Result<()> {
         servicing AS (
           SELECT
             NULLIF(TRIM(SERVICING_PROVIDER_NPI_NUM), '') AS npi,
-            SUM(TOTAL_PAID) AS paid_servicing,
-            SUM(TOTAL_CLAIMS) AS claims_servicing,
-            SUM(TOTAL_UNIQUE_BENEFICIARIES) AS bene_servicing
+            SUM({paid}) AS paid_servicing,
+            SUM({claims}) AS claims_servicing,
+            SUM({bene}) AS bene_servicing
           FROM spending_raw
           WHERE SERVICING_PROVIDER_NPI_NUM IS NOT NULL AND TRIM(SERVICING_PROVIDER_NPI_NUM) <> ''
           GROUP BY 1
+        ),
+        codes AS (
+          -- Distinct HCPCS codes billed or serviced, counted once across both roles.
+          SELECT npi, COUNT(DISTINCT hcpcs_code) AS distinct_codes
+          FROM (
+            SELECT NULLIF(TRIM(BILLING_PROVIDER_NPI_NUM), '') AS npi,
+                   NULLIF(TRIM(HCPCS_CODE), '') AS hcpcs_code
+            FROM spending_raw
+            UNION ALL
+            SELECT NULLIF(TRIM(SERVICING_PROVIDER_NPI_NUM), ''), NULLIF(TRIM(HCPCS_CODE), '')
+            FROM spending_raw
+          )
+          WHERE npi IS NOT NULL
+          GROUP BY 1
         )
         SELECT
           COALESCE(billing.npi, servicing.npi) AS npi,
@@ -242,44 +409,71 @@ fn rebuild_provider_totals(conn: &mut Connection) -> anyhow::Result<()> {
           COALESCE(bene_servicing, 0) AS bene_servicing,
           COALESCE(paid_billing, 0) + COALESCE(paid_servicing, 0) AS paid_total,
           COALESCE(claims_billing, 0) + COALESCE(claims_servicing, 0) AS claims_total,
-          COALESCE(bene_billing, 0) + COALESCE(bene_servicing, 0) AS bene_total
+          COALESCE(bene_billing, 0) + COALESCE(bene_servicing, 0) AS bene_total,
+          COALESCE(codes.distinct_codes, 0) AS distinct_codes
         FROM billing
         FULL OUTER JOIN servicing ON billing.npi = servicing.npi
+        LEFT JOIN codes ON codes.npi = COALESCE(billing.npi, servicing.npi)
         WHERE COALESCE(billing.npi, servicing.npi) IS NOT NULL
-    "#;
-    conn.execute(sql, [])?;
+    "#
+    );
+    conn.execute(&sql, [])?;
     Ok(())
 }
 
-fn rebuild_hcpcs_totals(conn: &mut Connection) -> anyhow::Result<()> {
+fn rebuild_hcpcs_totals(conn: &mut Connection, amounts: &AmountColumns) -> anyhow::Result<()> {
     tracing::info!(
         "Building hcpcs_totals (this will scan the spending parquet; can take a while)..."
     );
     conn.execute("DROP TABLE IF EXISTS hcpcs_totals", [])?;
-    let sql = r#"
+    let AmountColumns { paid, claims, bene } = amounts;
+    let sql = format!(
+        r#"
         CREATE TABLE hcpcs_totals AS
         SELECT
           NULLIF(TRIM(HCPCS_CODE), '') AS hcpcs_code,
-          SUM(TOTAL_PAID) AS paid_total,
-          SUM(TOTAL_CLAIMS) AS claims_total,
-          SUM(TOTAL_UNIQUE_BENEFICIARIES) AS bene_total
-        FROM spending_raw
+          SUM({paid}) AS paid_total,
+          SUM({claims}) AS claims_total,
+          SUM({bene}) AS bene_total,
+          SUM({paid}) FILTER (WHERE has_billing) AS paid_billing,
+          SUM({claims}) FILTER (WHERE has_billing) AS claims_billing,
+          SUM({bene}) FILTER (WHERE has_billing) AS bene_billing,
+          SUM({paid}) FILTER (WHERE has_servicing) AS paid_servicing,
+          SUM({claims}) FILTER (WHERE has_servicing) AS claims_servicing,
+          SUM({bene}) FILTER (WHERE has_servicing) AS bene_servicing
+        FROM (
+          SELECT
+            *,
+            BILLING_PROVIDER_NPI_NUM IS NOT NULL AND TRIM(BILLING_PROVIDER_NPI_NUM) <> '' AS has_billing,
+            SERVICING_PROVIDER_NPI_NUM IS NOT NULL AND TRIM(SERVICING_PROVIDER_NPI_NUM) <> '' AS has_servicing
+          FROM spending_raw
+        )
         WHERE HCPCS_CODE IS NOT NULL AND TRIM(HCPCS_CODE) <> ''
         GROUP BY 1
-    "#;
-    conn.execute(sql, [])?;
+    "#
+    );
+    conn.execute(&sql, [])?;
     Ok(())
 }
 
 fn rebuild_zip_centroids(conn: &mut Connection, geonames_txt: &Path) -> anyhow::Result<()> {
-    tracing::info!("Building zip_centroids from {}...", geonames_txt.display());
+    tracing::info!(
+        "Building zip_centroids + zip3_centroids from {}...",
+        geonames_txt.display()
+    );
     conn.execute("DROP TABLE IF EXISTS zip_centroids", [])?;
     conn.execute(
         "CREATE TABLE zip_centroids (zip5 TEXT PRIMARY KEY, lat DOUBLE, lon DOUBLE)",
         [],
     )?;
+    conn.execute("DROP TABLE IF EXISTS zip3_centroids", [])?;
+    conn.execute(
+        "CREATE TABLE zip3_centroids (zip3 TEXT PRIMARY KEY, lat DOUBLE, lon DOUBLE)",
+        [],
+    )?;
 
     let centroids = geo::parse_geonames_us_txt(geonames_txt)?;
+    let zip3 = geo::zip3_centroids(&centroids);
 
     let tx = conn.transaction().context("begin tx")?;
     {
@@ -289,6 +483,12 @@ fn rebuild_zip_centroids(conn: &mut Connection, geonames_txt: &Path) -> anyhow::
         for c in centroids {
             stmt.execute(params![c.zip5, c.lat, c.lon])?;
         }
+        let mut stmt = tx
+            .prepare("INSERT OR REPLACE INTO zip3_centroids (zip3, lat, lon) VALUES (?, ?, ?)")
+            .context("prepare insert zip3_centroids")?;
+        for c in zip3 {
+            stmt.execute(params![c.zip3, c.lat, c.lon])?;
+        }
     }
     tx.commit().context("commit zip_centroids")?;
     Ok(())
@@ -307,7 +507,14 @@ fn rebuild_provider_info(conn: &mut Connection) -> anyhow::Result<()> {
           primary_taxonomy_desc TEXT,
           state TEXT,
           city TEXT,
-          zip5 TEXT
+          zip5 TEXT,
+          practice_address TEXT,
+          replacement_npi TEXT,
+          sex TEXT,
+          sole_proprietor TEXT,
+          state_count INTEGER,
+          enumeration_date DATE,
+          last_updated DATE
         )
     "#,
         [],
@@ -322,8 +529,8 @@ fn rebuild_provider_info(conn: &mut Connection) -> anyhow::Result<()> {
         let mut ins = tx.prepare(
             r#"
             INSERT OR REPLACE INTO provider_info
-              (npi, display_name, enumeration_type, primary_taxonomy_code, primary_taxonomy_desc, state, city, zip5)
-            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+              (npi, display_name, enumeration_type, primary_taxonomy_code, primary_taxonomy_desc, state, city, zip5, practice_address, replacement_npi, sex, sole_proprietor, state_count, enumeration_date, last_updated)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, TRY_CAST(? AS DATE), TRY_CAST(? AS DATE))
         "#,
         )?;
 
@@ -339,7 +546,14 @@ fn rebuild_provider_info(conn: &mut Connection) -> anyhow::Result<()> {
                 ex.primary_taxonomy_desc,
                 ex.state,
                 ex.city,
-                ex.zip5
+                ex.zip5,
+                ex.practice_address,
+                ex.replacement_npi,
+                ex.sex,
+                ex.sole_proprietor,
+                ex.state_count,
+                ex.enumeration_date,
+                ex.last_updated
             ])?;
         }
     }
@@ -419,6 +633,13 @@ fn rebuild_provider_search(conn: &mut Connection) -> anyhow::Result<()> {
             pi.state,
             pi.city,
             pi.zip5,
+            pi.practice_address,
+            pi.replacement_npi,
+            pi.sex,
+            pi.sole_proprietor,
+            pi.state_count,
+            pi.enumeration_date,
+            pi.last_updated,
             COALESCE(pt.paid_billing, 0) AS paid_billing,
             COALESCE(pt.claims_billing, 0) AS claims_billing,
             COALESCE(pt.bene_billing, 0) AS bene_billing,
@@ -427,16 +648,29 @@ fn rebuild_provider_search(conn: &mut Connection) -> anyhow::Result<()> {
             COALESCE(pt.bene_servicing, 0) AS bene_servicing,
             COALESCE(pt.paid_total, 0) AS paid_total,
             COALESCE(pt.claims_total, 0) AS claims_total,
-            COALESCE(pt.bene_total, 0) AS bene_total
+            COALESCE(pt.bene_total, 0) AS bene_total,
+            COALESCE(pt.distinct_codes, 0) AS distinct_codes
           FROM provider_totals pt
           FULL OUTER JOIN provider_info pi ON pi.npi = pt.npi
         )
         SELECT
           joined.*,
-          z.lat,
-          z.lon
+          COALESCE(z.lat, z3.lat) AS lat,
+          COALESCE(z.lon, z3.lon) AS lon,
+          CASE
+            WHEN z.lat IS NOT NULL THEN 'zip5'
+            WHEN z3.lat IS NOT NULL THEN 'zip3'
+          END AS geo_source,
+          -- Rank within state by paid_total; NULL for providers without a state.
+          CASE WHEN NULLIF(TRIM(joined.state), '') IS NOT NULL THEN
+            RANK() OVER (PARTITION BY NULLIF(TRIM(joined.state), '') ORDER BY joined.paid_total DESC)
+          END AS state_rank_paid,
+          CASE WHEN NULLIF(TRIM(joined.state), '') IS NOT NULL THEN
+            COUNT(*) OVER (PARTITION BY NULLIF(TRIM(joined.state), ''))
+          END AS state_provider_count
         FROM joined
         LEFT JOIN zip_centroids z ON z.zip5 = joined.zip5
+        LEFT JOIN zip3_centroids z3 ON z3.zip3 = LEFT(joined.zip5, 3)
         WHERE joined.npi IS NOT NULL AND TRIM(joined.npi) <> ''
     "#;
     conn.execute(sql, [])?;
@@ -461,7 +695,13 @@ fn rebuild_hcpcs_search(conn: &mut Connection) -> anyhow::Result<()> {
             hi.is_noc,
             COALESCE(ht.paid_total, 0) AS paid_total,
             COALESCE(ht.claims_total, 0) AS claims_total,
-            COALESCE(ht.bene_total, 0) AS bene_total
+            COALESCE(ht.bene_total, 0) AS bene_total,
+            ht.paid_billing,
+            ht.claims_billing,
+            ht.bene_billing,
+            ht.paid_servicing,
+            ht.claims_servicing,
+            ht.bene_servicing
           FROM hcpcs_totals ht
           FULL OUTER JOIN hcpcs_info hi ON hi.hcpcs_code = ht.hcpcs_code
         )
@@ -484,6 +724,50 @@ fn table_exists(conn: &mut Connection, name: &str) -> anyhow::Result<bool> {
     Ok(count > 0)
 }
 
+/// Columns added to provider_info/provider_search after their first release; tables built
+/// before a column existed are rebuilt rather than queried for a missing column.
+const PROVIDER_ADDED_COLUMNS: &[&str] = &[
+    "replacement_npi",
+    "practice_address",
+    "sex",
+    "sole_proprietor",
+    "state_count",
+    "enumeration_date",
+    "last_updated",
+];
+
+/// Same as [`PROVIDER_ADDED_COLUMNS`] for hcpcs_totals/hcpcs_search.
+const HCPCS_ADDED_COLUMNS: &[&str] = &[
+    "paid_billing",
+    "claims_billing",
+    "bene_billing",
+    "paid_servicing",
+    "claims_servicing",
+    "bene_servicing",
+];
+
+fn has_missing_columns(
+    conn: &mut Connection,
+    table: &str,
+    columns: &[&str],
+) -> anyhow::Result<bool> {
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT COUNT(*)::BIGINT
+        FROM information_schema.columns
+        WHERE table_schema = 'main' AND table_name = ? AND column_name = ?
+    "#,
+    )?;
+    for col in columns {
+        let count: i64 = stmt.query_row(params![table, col], |row| row.get(0))?;
+        if count == 0 {
+            tracing::info!("DuckDB table {table} is missing column {col}; rebuilding");
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
 fn count_bad_keys(conn: &mut Connection, table: &str, col: &str) -> anyhow::Result<i64> {
     // table/col are compile-time constants in this crate; keep this helper private.
     let sql =
@@ -529,7 +813,44 @@ fn now_utc_rfc3339() -> String {
     format!("{}s_since_epoch", dur.as_secs())
 }
 
-fn sql_quote_path(path: &Path) -> String {
+pub(crate) fn sql_quote_path(path: &Path) -> String {
     // DuckDB expects single-quoted string literals; escape embedded single quotes.
     path.display().to_string().replace('\'', "''")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strict_schema_rejects_drifted_spending_columns() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE spending (
+               BILLING_PROVIDER_NPI_NUM VARCHAR, SERVICING_PROVIDER_NPI_NUM VARCHAR,
+               HCPCS_CODE VARCHAR, CLAIM_FROM_MONTH VARCHAR,
+               TOTAL_UNIQUE_BENEFICIARIES BIGINT, TOTAL_CLAIMS BIGINT, TOTAL_PAID DOUBLE,
+               HCPCS_MODIFIER VARCHAR
+             );
+             CREATE TABLE drifted (
+               BILLING_PROVIDER_NPI_NUM VARCHAR, SERVICING_PROVIDER_NPI_NUM VARCHAR,
+               HCPCS_CODE VARCHAR, TOTAL_UNIQUE_BENEFICIARIES BIGINT,
+               TOTAL_CLAIMS INTEGER, TOTAL_PAID DOUBLE
+             );",
+        )
+        .unwrap();
+        check_spending_schema(&mut conn, "spending").unwrap();
+
+        let err = check_spending_schema(&mut conn, "drifted")
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.contains("missing column CLAIM_FROM_MONTH (string)"),
+            "{err}"
+        );
+        assert!(
+            err.contains("TOTAL_CLAIMS: expected int64, found integer"),
+            "{err}"
+        );
+    }
+}
@@ -13,7 +13,7 @@ pub struct Args {
 #[derive(Subcommand, Debug)]
 pub enum Command {
     /// Download inputs (if missing), build DuckDB rollups, build Tantivy indices.
-    Build(BuildArgs),
+    Build(Box<BuildArgs>),
     /// Serve the HTTP API (requires a completed build).
     Serve(ServeArgs),
 }
@@ -47,6 +47,72 @@ pub struct BuildArgs {
     /// Rebuild DuckDB tables and Tantivy indices even if they already exist.
     #[arg(long)]
     pub rebuild: bool,
+
+    /// Extra PEM root certificate(s) to trust for downloads (e.g. a private CA for mirrors).
+    #[arg(long)]
+    pub ca_cert: Option<String>,
+
+    /// Disable TLS certificate verification for downloads (development only).
+    #[arg(long)]
+    pub danger_accept_invalid_certs: bool,
+
+    /// Max idle pooled connections kept per download host (reqwest default: unlimited).
+    #[arg(long)]
+    pub pool_max_idle_per_host: Option<usize>,
+
+    /// TCP keepalive interval in seconds for download connections (default: OS setting).
+    #[arg(long)]
+    pub tcp_keepalive_secs: Option<u64>,
+
+    /// Minimum TLS version for downloads: `1.2` or `1.3` (rustls cannot negotiate older).
+    #[arg(long, default_value = "1.2", value_parser = parse_min_tls_version)]
+    pub min_tls_version: reqwest::tls::Version,
+
+    /// Write a JSON report of duplicate spending rows (count, groups, inflated TOTAL_PAID).
+    #[arg(long)]
+    pub report_duplicates: Option<String>,
+
+    /// Keep one row per `--duplicate-key` group when building rollups. Needs `--rebuild` so
+    /// existing totals and everything derived from them are recomputed.
+    #[arg(long, requires = "rebuild")]
+    pub dedupe_spending: bool,
+
+    /// Comma-separated spending columns defining a duplicate (default: every column).
+    #[arg(long, value_delimiter = ',')]
+    pub duplicate_key: Vec<String>,
+
+    /// Spending column summed into the paid totals (state extracts may name it differently).
+    #[arg(long, default_value = "TOTAL_PAID")]
+    pub paid_col: String,
+
+    /// Spending column summed into the claim totals.
+    #[arg(long, default_value = "TOTAL_CLAIMS")]
+    pub claims_col: String,
+
+    /// Spending column summed into the beneficiary totals.
+    #[arg(long, default_value = "TOTAL_UNIQUE_BENEFICIARIES")]
+    pub bene_col: String,
+
+    /// Also write `leaderboard.parquet`: the top N providers by paid total (overall and per
+    /// state) and the top N HCPCS codes, served from memory at `/api/leaderboard`.
+    #[arg(long, value_parser = clap::value_parser!(u64).range(1..=100_000))]
+    pub leaderboard_top_n: Option<u64>,
+
+    /// Fail before building if the spending parquet's columns or types drift from the expected
+    /// layout (the same check as `build_datasets --strict-schema`).
+    #[arg(long)]
+    pub strict_schema: bool,
+}
+
+fn parse_min_tls_version(value: &str) -> Result<reqwest::tls::Version, String> {
+    match value.trim() {
+        "1.2" => Ok(reqwest::tls::Version::TLS_1_2),
+        "1.3" => Ok(reqwest::tls::Version::TLS_1_3),
+        "1.0" | "1.1" => Err(format!(
+            "TLS {value} cannot be enforced: the rustls backend only speaks TLS 1.2 and 1.3"
+        )),
+        _ => Err(format!("expected 1.2 or 1.3 (got {value:?})")),
+    }
 }
 
 #[derive(clap::Args, Debug, Clone)]
@@ -60,4 +126,9 @@ pub struct ServeArgs {
 
     #[arg(long, default_value_t = 8787)]
     pub port: u16,
+
+    /// Narrow `npi_api_raw`/`hcpcs_api_raw` to the key and JSON columns the detail endpoints
+    /// read, so DuckDB never materializes the other raw-response columns.
+    #[arg(long)]
+    pub slim_raw_views: bool,
 }
@@ -16,12 +16,45 @@ pub async fn ensure_inputs(
     tracing::info!("Ensuring input datasets (HF downloads with local reuse when available)...");
     paths.ensure_dirs().context("create data directories")?;
 
-    let sources = ensure_hf_parquets(paths, opts).await?;
-    let geonames_txt = ensure_geonames_zip_centroids(paths, opts).await?;
+    let client = http_client(opts)?;
+    let sources = ensure_hf_parquets(&client, paths, opts).await?;
+    let geonames_txt = ensure_geonames_zip_centroids(&client, paths, opts).await?;
     Ok((sources, geonames_txt))
 }
 
-async fn ensure_hf_parquets(paths: &StoragePaths, opts: &BuildArgs) -> anyhow::Result<SourceFiles> {
+fn http_client(opts: &BuildArgs) -> anyhow::Result<reqwest::Client> {
+    let mut builder = reqwest::Client::builder().min_tls_version(opts.min_tls_version);
+    if let Some(path) = opts.ca_cert.as_deref() {
+        let pem = std::fs::read(path).with_context(|| format!("read --ca-cert {path}"))?;
+        let certs = reqwest::Certificate::from_pem_bundle(&pem)
+            .with_context(|| format!("parse PEM certificates from {path}"))?;
+        if certs.is_empty() {
+            return Err(anyhow!("No PEM certificates found in {path}"));
+        }
+        for cert in certs {
+            builder = builder.add_root_certificate(cert);
+        }
+    }
+    if let Some(max_idle) = opts.pool_max_idle_per_host {
+        builder = builder.pool_max_idle_per_host(max_idle);
+    }
+    if let Some(secs) = opts.tcp_keepalive_secs {
+        builder = builder.tcp_keepalive(std::time::Duration::from_secs(secs));
+    }
+    if opts.danger_accept_invalid_certs {
+        tracing::warn!(
+            "--danger-accept-invalid-certs disables TLS certificate verification; do not use outside development"
+        );
+        builder = builder.danger_accept_invalid_certs(true);
+    }
+    builder.build().context("create HTTP client")
+}
+
+async fn ensure_hf_parquets(
+    client: &reqwest::Client,
+    paths: &StoragePaths,
+    opts: &BuildArgs,
+) -> anyhow::Result<SourceFiles> {
     let sources = paths.source_files();
     tracing::info!("Inputs will be stored under {}", paths.source_dir.display());
 
@@ -29,14 +62,15 @@ async fn ensure_hf_parquets(paths: &StoragePaths, opts: &BuildArgs) -> anyhow::R
     let npi_url = hf_resolve_url(&opts.hf_repo, &opts.hf_revision, "data/npi.parquet");
     let hcpcs_url = hf_resolve_url(&opts.hf_repo, &opts.hf_revision, "data/hcpcs.parquet");
 
-    ensure_one_file(&spending_url, &sources.spending, opts).await?;
-    ensure_one_file(&npi_url, &sources.npi, opts).await?;
-    ensure_one_file(&hcpcs_url, &sources.hcpcs, opts).await?;
+    ensure_one_file(client, &spending_url, &sources.spending, opts).await?;
+    ensure_one_file(client, &npi_url, &sources.npi, opts).await?;
+    ensure_one_file(client, &hcpcs_url, &sources.hcpcs, opts).await?;
 
     Ok(sources)
 }
 
 async fn ensure_geonames_zip_centroids(
+    client: &reqwest::Client,
     paths: &StoragePaths,
     opts: &BuildArgs,
 ) -> anyhow::Result<PathBuf> {
@@ -57,12 +91,17 @@ async fn ensure_geonames_zip_centroids(
     }
 
     let zip_path = paths.geonames_us_zip();
-    ensure_download(GEONAMES_US_ZIP_URL, &zip_path, opts.force_download).await?;
+    ensure_download(client, GEONAMES_US_ZIP_URL, &zip_path, opts.force_download).await?;
     extract_first_txt_from_zip(&zip_path, &out_txt).context("extract US.zip")?;
     Ok(out_txt)
 }
 
-async fn ensure_one_file(url: &str, dest: &Path, opts: &BuildArgs) -> anyhow::Result<()> {
+async fn ensure_one_file(
+    client: &reqwest::Client,
+    url: &str,
+    dest: &Path,
+    opts: &BuildArgs,
+) -> anyhow::Result<()> {
     if !opts.force_download && file_present_nonempty(dest) {
         return Ok(());
     }
@@ -82,7 +121,7 @@ async fn ensure_one_file(url: &str, dest: &Path, opts: &BuildArgs) -> anyhow::Re
             url
         ));
     }
-    ensure_download(url, dest, opts.force_download).await
+    ensure_download(client, url, dest, opts.force_download).await
 }
 
 fn try_seed_from_repo_data(dest: &Path) -> anyhow::Result<bool> {
@@ -163,7 +202,12 @@ fn hf_resolve_url(repo: &str, rev: &str, path_in_repo: &str) -> String {
     format!("{HF_BASE}/{repo}/resolve/{rev}/{path_in_repo}")
 }
 
-async fn ensure_download(url: &str, dest: &Path, force: bool) -> anyhow::Result<()> {
+async fn ensure_download(
+    client: &reqwest::Client,
+    url: &str,
+    dest: &Path,
+    force: bool,
+) -> anyhow::Result<()> {
     if !force && file_present_nonempty(dest) {
         return Ok(());
     }
@@ -175,7 +219,6 @@ async fn ensure_download(url: &str, dest: &Path, force: bool) -> anyhow::Result<
 
     tracing::info!("Downloading {} -> {}", url, dest.display());
 
-    let client = reqwest::Client::new();
     let resp = client
         .get(url)
         .send()
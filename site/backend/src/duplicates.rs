@@ -0,0 +1,180 @@
+use std::path::Path;
+
+use anyhow::{Context, anyhow};
+use duckdb::Connection;
+use serde::Serialize;
+
+#[derive(Debug, Serialize)]
+pub struct DuplicateReport {
+    pub spending: String,
+    /// Columns that define a duplicate (every spending column unless `--duplicate-key` is set).
+    pub key_columns: Vec<String>,
+    pub total_rows: u64,
+    /// Rows beyond the first in each key group; what `--dedupe-spending` drops.
+    pub duplicate_rows: u64,
+    /// Key groups with more than one row.
+    pub duplicate_groups: u64,
+    /// `--paid-col` sum of the dropped rows, i.e. how much duplicates inflate totals.
+    pub duplicate_paid: f64,
+}
+
+fn quote_ident(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// Spending columns of `source` (a `read_parquet(...)` expression) that make up the duplicate
+/// key: `requested` (validated against the parquet) or every column when empty.
+pub fn resolve_key_columns(
+    conn: &mut Connection,
+    source: &str,
+    requested: &[String],
+) -> anyhow::Result<Vec<String>> {
+    let available = source_columns(conn, source)?;
+    if requested.is_empty() {
+        return Ok(available);
+    }
+    for col in requested {
+        if !available.iter().any(|c| c == col) {
+            return Err(anyhow!(
+                "--duplicate-key column {col:?} is not in the spending parquet (columns: {})",
+                available.join(", ")
+            ));
+        }
+    }
+    Ok(requested.to_vec())
+}
+
+fn source_columns(conn: &mut Connection, source: &str) -> anyhow::Result<Vec<String>> {
+    let mut stmt = conn.prepare(&format!("DESCRIBE SELECT * FROM {source}"))?;
+    let columns = stmt
+        .query_map([], |row| row.get::<usize, String>(0))?
+        .collect::<Result<Vec<_>, _>>()
+        .context("describe spending parquet")?;
+    Ok(columns)
+}
+
+/// `SELECT` over `source` keeping one row per key (all columns when `key_columns` covers them).
+/// The kept row is the first in full-row order (`ORDER BY ALL`), so it is the same on every run
+/// and matches the row [`duplicate_report`] keeps.
+pub fn dedupe_select(source: &str, key_columns: &[String]) -> String {
+    let key = key_columns
+        .iter()
+        .map(|c| quote_ident(c))
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!("SELECT DISTINCT ON ({key}) * FROM {source} ORDER BY ALL")
+}
+
+/// `paid_column` is a quoted identifier (see `AmountColumns` in build.rs).
+pub fn duplicate_report(
+    conn: &mut Connection,
+    spending: &Path,
+    source: &str,
+    key_columns: &[String],
+    paid_column: &str,
+) -> anyhow::Result<DuplicateReport> {
+    let key = key_columns
+        .iter()
+        .map(|c| quote_ident(c))
+        .collect::<Vec<_>>()
+        .join(", ");
+    // Same full-row order as `ORDER BY ALL` in `dedupe_select`, so the rows counted here are
+    // exactly the ones `--dedupe-spending` drops.
+    let order = source_columns(conn, source)?
+        .iter()
+        .map(|c| quote_ident(c))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let sql = format!(
+        r#"
+        WITH ranked AS (
+          SELECT
+            {paid_column} AS paid,
+            ROW_NUMBER() OVER (PARTITION BY {key} ORDER BY {order}) AS rn
+          FROM {source}
+        )
+        SELECT
+          COUNT(*),
+          COUNT(*) FILTER (WHERE rn > 1),
+          COUNT(*) FILTER (WHERE rn = 2),
+          CAST(COALESCE(SUM(paid) FILTER (WHERE rn > 1), 0) AS DOUBLE)
+        FROM ranked
+    "#
+    );
+    let (total_rows, duplicate_rows, duplicate_groups, duplicate_paid) = conn
+        .query_row(&sql, [], |row| {
+            Ok((
+                row.get::<usize, i64>(0)?,
+                row.get::<usize, i64>(1)?,
+                row.get::<usize, i64>(2)?,
+                row.get::<usize, f64>(3)?,
+            ))
+        })
+        .context("count duplicate spending rows")?;
+    Ok(DuplicateReport {
+        spending: spending.display().to_string(),
+        key_columns: key_columns.to_vec(),
+        total_rows: total_rows.max(0) as u64,
+        duplicate_rows: duplicate_rows.max(0) as u64,
+        duplicate_groups: duplicate_groups.max(0) as u64,
+        duplicate_paid,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spending_with_near_duplicates() -> Connection {
+        let conn = Connection::open_in_memory().expect("open duckdb");
+        conn.execute_batch(
+            r#"
+            CREATE TABLE spending AS
+            SELECT * FROM (VALUES
+              ('1234567893', 'J1885', '2024-01', 30.0),
+              ('1234567893', 'J1885', '2024-01', 10.0),
+              ('1234567893', 'J1885', '2024-01', 20.0),
+              ('1234567893', '99213', '2024-01', 5.0)
+            ) AS t("BILLING_PROVIDER_NPI_NUM", "HCPCS_CODE", "CLAIM_FROM_MONTH", "TOTAL_PAID");
+            "#,
+        )
+        .expect("create spending");
+        conn
+    }
+
+    fn narrowed_key() -> Vec<String> {
+        ["BILLING_PROVIDER_NPI_NUM", "HCPCS_CODE", "CLAIM_FROM_MONTH"]
+            .map(String::from)
+            .to_vec()
+    }
+
+    #[test]
+    fn narrowed_key_keeps_the_first_row_in_full_row_order() {
+        let conn = spending_with_near_duplicates();
+        let sql = format!(
+            "SELECT \"TOTAL_PAID\" FROM ({}) WHERE \"HCPCS_CODE\" = 'J1885'",
+            dedupe_select("spending", &narrowed_key())
+        );
+        for _ in 0..5 {
+            let kept: f64 = conn.query_row(&sql, [], |row| row.get(0)).expect("dedupe");
+            assert_eq!(kept, 10.0);
+        }
+    }
+
+    #[test]
+    fn report_counts_exactly_the_rows_dedupe_drops() {
+        let mut conn = spending_with_near_duplicates();
+        let report = duplicate_report(
+            &mut conn,
+            Path::new("spending.parquet"),
+            "spending",
+            &narrowed_key(),
+            "\"TOTAL_PAID\"",
+        )
+        .expect("report");
+        assert_eq!(report.total_rows, 4);
+        assert_eq!(report.duplicate_rows, 2);
+        assert_eq!(report.duplicate_groups, 1);
+        assert_eq!(report.duplicate_paid, 50.0);
+    }
+}
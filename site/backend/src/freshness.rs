@@ -0,0 +1,108 @@
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+use duckdb::Connection;
+use serde::Serialize;
+
+use crate::build::sql_quote_path;
+use crate::storage::SourceFiles;
+
+/// When one source parquet was generated, as far as the file itself can tell.
+#[derive(Debug, Clone, Serialize)]
+pub struct SourceFreshness {
+    pub source: &'static str,
+    pub path: String,
+    pub size_bytes: Option<u64>,
+    pub modified_unix: Option<u64>,
+    /// `modified_unix` as `YYYY-MM-DDTHH:MM:SSZ`.
+    pub modified_at_utc: Option<String>,
+    /// `schema_version` key from the parquet footer metadata, if the writer set one.
+    pub schema_version: Option<String>,
+    /// Footer `api_run_id` if present, else the latest `api_run_id` column value.
+    pub api_run_id: Option<String>,
+}
+
+/// Reads file times and embedded metadata for `spending`, `npi`, and `hcpcs`. Missing files
+/// and unreadable metadata become `None` fields rather than errors.
+pub fn collect_freshness(conn: &mut Connection, sources: &SourceFiles) -> Vec<SourceFreshness> {
+    [
+        ("spending", &sources.spending),
+        ("npi", &sources.npi),
+        ("hcpcs", &sources.hcpcs),
+    ]
+    .into_iter()
+    .map(|(source, path)| source_freshness(conn, source, path))
+    .collect()
+}
+
+fn source_freshness(conn: &mut Connection, source: &'static str, path: &Path) -> SourceFreshness {
+    let metadata = std::fs::metadata(path).ok();
+    let modified_unix = metadata
+        .as_ref()
+        .and_then(|m| m.modified().ok())
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs());
+    let (schema_version, mut api_run_id) = if metadata.is_some() {
+        (
+            kv_metadata(conn, path, "schema_version"),
+            kv_metadata(conn, path, "api_run_id"),
+        )
+    } else {
+        (None, None)
+    };
+    if api_run_id.is_none() && metadata.is_some() && source != "spending" {
+        api_run_id = latest_api_run_id(conn, path);
+    }
+    SourceFreshness {
+        source,
+        path: path.display().to_string(),
+        size_bytes: metadata.as_ref().map(|m| m.len()),
+        modified_unix,
+        modified_at_utc: modified_unix.map(unix_to_rfc3339),
+        schema_version,
+        api_run_id,
+    }
+}
+
+fn kv_metadata(conn: &mut Connection, path: &Path, key: &str) -> Option<String> {
+    let sql = format!(
+        "SELECT decode(value) FROM parquet_kv_metadata('{}') WHERE decode(key) = ? LIMIT 1",
+        sql_quote_path(path)
+    );
+    let mut stmt = conn.prepare(&sql).ok()?;
+    stmt.query_row([key], |row| row.get::<usize, Option<String>>(0))
+        .ok()
+        .flatten()
+}
+
+/// Generated run ids (`api-run-<unix_millis>`) sort chronologically, so `MAX` is the newest.
+fn latest_api_run_id(conn: &mut Connection, path: &Path) -> Option<String> {
+    let sql = format!(
+        "SELECT MAX(api_run_id) FROM read_parquet('{}')",
+        sql_quote_path(path)
+    );
+    conn.query_row(&sql, [], |row| row.get::<usize, Option<String>>(0))
+        .ok()
+        .flatten()
+}
+
+fn unix_to_rfc3339(secs: u64) -> String {
+    // Civil-from-days (proleptic Gregorian), so no date crate is needed for one timestamp.
+    let days = (secs / 86_400) as i64;
+    let rem = secs % 86_400;
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        rem / 3_600,
+        (rem % 3_600) / 60,
+        rem % 60
+    )
+}
@@ -9,6 +9,60 @@ pub struct ZipCentroid {
     pub lon: f64,
 }
 
+/// Mean of the ZIP centroids sharing a 3-digit prefix (roughly a ZCTA3 centroid); the
+/// fallback location for a 5-digit ZIP that GeoNames does not list.
+#[derive(Debug, Clone)]
+pub struct Zip3Centroid {
+    pub zip3: String,
+    pub lat: f64,
+    pub lon: f64,
+}
+
+pub fn zip3_centroids(centroids: &[ZipCentroid]) -> Vec<Zip3Centroid> {
+    let mut sums: std::collections::BTreeMap<&str, (f64, f64, u32)> =
+        std::collections::BTreeMap::new();
+    for c in centroids {
+        let entry = sums.entry(&c.zip5[..3]).or_default();
+        entry.0 += c.lat;
+        entry.1 += c.lon;
+        entry.2 += 1;
+    }
+    sums.into_iter()
+        .map(|(zip3, (lat, lon, n))| Zip3Centroid {
+            zip3: zip3.to_string(),
+            lat: lat / f64::from(n),
+            lon: lon / f64::from(n),
+        })
+        .collect()
+}
+
+pub const EARTH_RADIUS_MILES: f64 = 3958.8;
+
+/// DuckDB expression for the great-circle (haversine) distance in miles between the
+/// `lat`/`lon` columns and a fixed point.
+pub fn haversine_miles_sql(lat: f64, lon: f64) -> String {
+    format!(
+        "2 * {EARTH_RADIUS_MILES} * ASIN(SQRT(\
+         POW(SIN(RADIANS(lat - {lat}) / 2), 2) + \
+         COS(RADIANS({lat})) * COS(RADIANS(lat)) * POW(SIN(RADIANS(lon - {lon}) / 2), 2)))"
+    )
+}
+
+/// `(min_lat, max_lat, min_lon, max_lon)` enclosing a `radius_miles` circle, used to prune
+/// rows before the exact haversine check.
+pub fn radius_bbox(lat: f64, lon: f64, radius_miles: f64) -> (f64, f64, f64, f64) {
+    const MILES_PER_DEGREE: f64 = 69.0;
+    let dlat = radius_miles / MILES_PER_DEGREE;
+    // Near the poles a degree of longitude shrinks to nothing; fall back to all longitudes.
+    let cos_lat = lat.to_radians().cos();
+    let dlon = if cos_lat > 0.01 {
+        radius_miles / (MILES_PER_DEGREE * cos_lat)
+    } else {
+        180.0
+    };
+    (lat - dlat, lat + dlat, lon - dlon, lon + dlon)
+}
+
 pub fn normalize_zip5(s: &str) -> Option<String> {
     let mut digits = String::with_capacity(5);
     for ch in s.chars() {
@@ -54,3 +108,41 @@ pub fn parse_geonames_us_txt(path: &Path) -> anyhow::Result<Vec<ZipCentroid>> {
     }
     Ok(out)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn haversine_sql_matches_known_distances() {
+        let conn = duckdb::Connection::open_in_memory().unwrap();
+        // One degree of latitude, and New York to Los Angeles (about 2,445 miles).
+        let distance = |(lat, lon): (f64, f64), from: (f64, f64)| -> f64 {
+            conn.query_row(
+                &format!(
+                    "SELECT {} FROM (SELECT {lat} AS lat, {lon} AS lon)",
+                    haversine_miles_sql(from.0, from.1)
+                ),
+                [],
+                |row| row.get(0),
+            )
+            .unwrap()
+        };
+        assert!((distance((41.0, -100.0), (40.0, -100.0)) - 69.09).abs() < 0.05);
+        assert!((distance((34.0522, -118.2437), (40.7128, -74.0060)) - 2445.0).abs() < 5.0);
+        assert_eq!(distance((40.0, -100.0), (40.0, -100.0)), 0.0);
+    }
+
+    #[test]
+    fn radius_bbox_widens_longitude_away_from_the_equator() {
+        let (min_lat, max_lat, min_lon, max_lon) = radius_bbox(0.0, 10.0, 69.0);
+        assert_eq!((min_lat, max_lat), (-1.0, 1.0));
+        assert!((min_lon - 9.0).abs() < 1e-9 && (max_lon - 11.0).abs() < 1e-9);
+
+        let (_, _, min_lon, max_lon) = radius_bbox(60.0, 10.0, 69.0);
+        assert!((min_lon - 8.0).abs() < 1e-9 && (max_lon - 12.0).abs() < 1e-9);
+
+        let (_, _, min_lon, max_lon) = radius_bbox(90.0, 10.0, 69.0);
+        assert_eq!((min_lon, max_lon), (-170.0, 190.0));
+    }
+}
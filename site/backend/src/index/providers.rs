@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use std::ops::Bound;
 use std::path::Path;
 
@@ -11,7 +12,9 @@ use tantivy::schema::{
     Facet, FacetOptions, Field, IndexRecordOption, NumericOptions, STORED, STRING, Schema, TEXT,
     Value,
 };
-use tantivy::{DocAddress, Index, IndexReader, Order, Score, TantivyDocument, Term};
+use tantivy::{
+    DocAddress, Index, IndexReader, Order, Score, SnippetGenerator, TantivyDocument, Term,
+};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Role {
@@ -28,6 +31,12 @@ pub enum Sort {
     ClaimsDesc,
     ClaimsAsc,
     NameAsc,
+    /// `paid / claims` for the selected role; providers with zero claims sort last.
+    PaidPerClaimDesc,
+    /// Distinct HCPCS codes across both roles (precomputed fast field).
+    DistinctCodesDesc,
+    /// `bene / claims` for the selected role; providers with zero claims sort last.
+    BenePerClaimDesc,
 }
 
 #[derive(Debug, Clone)]
@@ -36,14 +45,22 @@ pub struct ProviderSearchQuery {
     pub states: Vec<String>,
     pub taxonomies: Vec<String>,
     pub entity: Option<String>,
+    /// `M`/`F`; organizations (no sex) never match.
+    pub sex: Option<String>,
+    /// Only providers practicing in more than one state (`state_count > 1`).
+    pub multi_state: bool,
     pub role: Role,
     pub paid_min: Option<f64>,
     pub paid_max: Option<f64>,
     pub claims_min: Option<i64>,
     pub claims_max: Option<i64>,
+    pub bene_min: Option<i64>,
+    pub bene_max: Option<i64>,
     pub sort: Sort,
     pub page: usize,
     pub page_size: usize,
+    /// Attach `highlights` (matched name/city/taxonomy snippets) to each hit.
+    pub highlight: bool,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -55,6 +72,19 @@ pub struct ProviderHit {
     pub enumeration_type: Option<String>,
     pub primary_taxonomy_code: Option<String>,
     pub primary_taxonomy_desc: Option<String>,
+    /// Only populated when the search request opts in with `include_address=1`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub practice_address: Option<String>,
+    /// Only populated for `paid_per_claim_desc` searches (role-aware; `None` for zero claims).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub paid_per_claim: Option<f64>,
+    /// Only populated for `bene_per_claim_desc` searches (role-aware; `None` for zero claims).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bene_per_claim: Option<f64>,
+    /// Field name -> HTML snippet with matched terms in `<b>`; only with `highlight=1` and a
+    /// text query, and only for fields that matched.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub highlights: Option<BTreeMap<&'static str, String>>,
 
     pub paid_billing: f64,
     pub claims_billing: i64,
@@ -65,6 +95,8 @@ pub struct ProviderHit {
     pub paid_total: f64,
     pub claims_total: i64,
     pub bene_total: i64,
+    /// Distinct HCPCS codes the provider billed or serviced.
+    pub distinct_codes: i64,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -89,6 +121,8 @@ struct ProviderFields {
     enumeration_type: Field,
     primary_taxonomy_code: Field,
     primary_taxonomy_desc: Field,
+    sex: Field,
+    state_count: Field,
 
     state_facet: Field,
     entity_facet: Field,
@@ -103,8 +137,24 @@ struct ProviderFields {
     paid_total: Field,
     claims_total: Field,
     bene_total: Field,
+    distinct_codes: Field,
+
+    paid_per_claim_billing: Field,
+    bene_per_claim_billing: Field,
+    paid_per_claim_servicing: Field,
+    bene_per_claim_servicing: Field,
+    paid_per_claim_total: Field,
+    bene_per_claim_total: Field,
 }
 
+/// Free-text field weights (city stays at 1.0).
+const NAME_BOOST: Score = 3.0;
+const TAXONOMY_BOOST: Score = 0.5;
+
+/// Per-claim ratio indexed for providers with no claims, so they sort after every real ratio
+/// (including negative ones from net-negative paid totals).
+const NO_CLAIMS_RATIO: f64 = f64::MIN;
+
 impl ProviderEngine {
     pub fn open(index_dir: &Path) -> anyhow::Result<Self> {
         let dir = MmapDirectory::open(index_dir)
@@ -114,7 +164,7 @@ impl ProviderEngine {
         let fields = provider_fields(&schema)?;
 
         let reader = index.reader().context("create index reader")?;
-        let query_parser = QueryParser::for_index(
+        let mut query_parser = QueryParser::for_index(
             &index,
             vec![
                 fields.display_name,
@@ -122,6 +172,10 @@ impl ProviderEngine {
                 fields.primary_taxonomy_desc,
             ],
         );
+        // Terms are OR'd, so "pediatric smith" ranks a pediatric Smith (both fields) first;
+        // the weights keep a name-only match above a taxonomy-only one.
+        query_parser.set_field_boost(fields.display_name, NAME_BOOST);
+        query_parser.set_field_boost(fields.primary_taxonomy_desc, TAXONOMY_BOOST);
 
         Ok(Self {
             reader,
@@ -142,7 +196,7 @@ impl ProviderEngine {
         let page_size = q.page_size.clamp(1, 200);
         let offset = q.page.saturating_mul(page_size);
 
-        let hits: Vec<ProviderHit> = match q.sort {
+        let mut hits: Vec<ProviderHit> = match q.sort {
             Sort::Relevance => {
                 let top_docs: Vec<(Score, DocAddress)> = searcher
                     .search(&query, &TopDocs::with_limit(page_size).and_offset(offset))
@@ -219,11 +273,99 @@ impl ProviderEngine {
                 });
                 docs.into_iter().skip(offset).take(page_size).collect()
             }
+            Sort::DistinctCodesDesc => {
+                let top_docs: Vec<(i64, DocAddress)> = searcher.search(
+                    &query,
+                    &TopDocs::with_limit(page_size)
+                        .and_offset(offset)
+                        .order_by_fast_field::<i64>("distinct_codes", Order::Desc),
+                )?;
+                top_docs
+                    .into_iter()
+                    .map(|(_, addr)| self.doc_to_hit(&searcher, addr))
+                    .collect::<anyhow::Result<Vec<_>>>()?
+            }
+            Sort::PaidPerClaimDesc | Sort::BenePerClaimDesc => {
+                let bene = q.sort == Sort::BenePerClaimDesc;
+                let (paid_ratio_name, bene_ratio_name) = role_per_claim_field_names(q.role);
+                let ratio_name = if bene {
+                    bene_ratio_name
+                } else {
+                    paid_ratio_name
+                };
+                let top_docs: Vec<(f64, DocAddress)> = searcher.search(
+                    &query,
+                    &TopDocs::with_limit(page_size)
+                        .and_offset(offset)
+                        .order_by_fast_field::<f64>(ratio_name, Order::Desc),
+                )?;
+                top_docs
+                    .into_iter()
+                    .map(|(_, addr)| {
+                        let mut hit = self.doc_to_hit(&searcher, addr)?;
+                        if bene {
+                            hit.bene_per_claim = bene_per_claim(&hit, q.role);
+                        } else {
+                            hit.paid_per_claim = paid_per_claim(&hit, q.role);
+                        }
+                        Ok(hit)
+                    })
+                    .collect::<anyhow::Result<Vec<_>>>()?
+            }
         };
 
+        if q.highlight
+            && let Some(text) = q.q.as_deref().map(str::trim).filter(|s| !s.is_empty())
+        {
+            self.attach_highlights(&searcher, text, &mut hits)?;
+        }
+
         Ok(ProviderSearchResponse { total_hits, hits })
     }
 
+    /// Snippets come from the free-text query alone, so facet and range filters never count
+    /// as a match.
+    fn attach_highlights(
+        &self,
+        searcher: &tantivy::Searcher,
+        text: &str,
+        hits: &mut [ProviderHit],
+    ) -> anyhow::Result<()> {
+        let query = self.build_simple_query(text)?;
+        let generators = [
+            ("display_name", self.fields.display_name),
+            ("city", self.fields.city),
+            ("primary_taxonomy_desc", self.fields.primary_taxonomy_desc),
+        ]
+        .into_iter()
+        .map(|(name, field)| {
+            SnippetGenerator::create(searcher, &*query, field)
+                .map(|generator| (name, generator))
+                .context("create snippet generator")
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+        for hit in hits {
+            let mut highlights = BTreeMap::new();
+            for (name, generator) in &generators {
+                let value = match *name {
+                    "display_name" => hit.display_name.as_deref(),
+                    "city" => hit.city.as_deref(),
+                    _ => hit.primary_taxonomy_desc.as_deref(),
+                };
+                let Some(value) = value else {
+                    continue;
+                };
+                let snippet = generator.snippet(value);
+                if !snippet.highlighted().is_empty() {
+                    highlights.insert(*name, snippet.to_html());
+                }
+            }
+            hit.highlights = Some(highlights);
+        }
+        Ok(())
+    }
+
     pub fn search_simple(&self, q: &str, limit: usize) -> anyhow::Result<Vec<ProviderHit>> {
         let searcher = self.reader.searcher();
         let query = self.build_simple_query(q)?;
@@ -277,6 +419,25 @@ impl ProviderEngine {
             ));
         }
 
+        if let Some(sex) = q.sex.as_deref().map(str::trim).filter(|s| !s.is_empty()) {
+            let term = Term::from_field_text(self.fields.sex, &sex.to_ascii_uppercase());
+            clauses.push((
+                tantivy::query::Occur::Must,
+                Box::new(TermQuery::new(term, IndexRecordOption::Basic)),
+            ));
+        }
+
+        if q.multi_state {
+            clauses.push((
+                tantivy::query::Occur::Must,
+                Box::new(tantivy::query::RangeQuery::new_i64_bounds(
+                    "state_count".to_string(),
+                    Bound::Included(2),
+                    Bound::Unbounded,
+                )),
+            ));
+        }
+
         // numeric range filters (role-aware)
         let (paid_name, claims_name) = role_field_names(q.role);
 
@@ -312,6 +473,19 @@ impl ProviderEngine {
             ));
         }
 
+        if q.bene_min.is_some() || q.bene_max.is_some() {
+            let lb = q.bene_min.map(Bound::Included).unwrap_or(Bound::Unbounded);
+            let ub = q.bene_max.map(Bound::Included).unwrap_or(Bound::Unbounded);
+            clauses.push((
+                tantivy::query::Occur::Must,
+                Box::new(tantivy::query::RangeQuery::new_i64_bounds(
+                    role_bene_field_name(q.role).to_string(),
+                    lb,
+                    ub,
+                )),
+            ));
+        }
+
         Ok(Box::new(BooleanQuery::new(clauses)))
     }
 
@@ -354,6 +528,10 @@ impl ProviderEngine {
                 .get_first(self.fields.primary_taxonomy_desc)
                 .and_then(|v| v.as_str())
                 .map(|s| s.to_string()),
+            practice_address: None,
+            paid_per_claim: None,
+            bene_per_claim: None,
+            highlights: None,
 
             paid_billing: doc
                 .get_first(self.fields.paid_billing)
@@ -391,6 +569,10 @@ impl ProviderEngine {
                 .get_first(self.fields.bene_total)
                 .and_then(|v| v.as_i64())
                 .unwrap_or(0),
+            distinct_codes: doc
+                .get_first(self.fields.distinct_codes)
+                .and_then(|v| v.as_i64())
+                .unwrap_or(0),
         })
     }
 }
@@ -402,7 +584,7 @@ pub fn build_provider_index(
 ) -> anyhow::Result<()> {
     let success_marker = index_dir.join("_SUCCESS");
     if index_dir.exists() && !rebuild {
-        if success_marker.exists() {
+        if success_marker.exists() && index_has_current_schema(index_dir) {
             tracing::info!(
                 "Provider index already exists at {}; skipping",
                 index_dir.display()
@@ -410,8 +592,8 @@ pub fn build_provider_index(
             return Ok(());
         }
         tracing::info!(
-            "Provider index dir exists but is missing {} (previous build likely failed); rebuilding",
-            success_marker.display()
+            "Provider index at {} is incomplete or predates the current schema; rebuilding",
+            index_dir.display()
         );
         std::fs::remove_dir_all(index_dir)
             .with_context(|| format!("remove {}", index_dir.display()))?;
@@ -447,7 +629,10 @@ pub fn build_provider_index(
           bene_servicing,
           paid_total,
           claims_total,
-          bene_total
+          bene_total,
+          sex,
+          state_count,
+          distinct_codes
         FROM provider_search
     "#;
 
@@ -470,6 +655,9 @@ pub fn build_provider_index(
             row.get::<usize, Option<f64>>(13)?,
             row.get::<usize, Option<i64>>(14)?,
             row.get::<usize, Option<i64>>(15)?,
+            row.get::<usize, Option<String>>(16)?,
+            row.get::<usize, Option<i64>>(17)?,
+            row.get::<usize, Option<i64>>(18)?,
         ))
     })?;
 
@@ -493,6 +681,9 @@ pub fn build_provider_index(
             paid_total,
             claims_total,
             bene_total,
+            sex,
+            state_count,
+            distinct_codes,
         ) = r?;
 
         let Some(npi) = npi.as_deref().map(str::trim).filter(|s| !s.is_empty()) else {
@@ -531,6 +722,10 @@ pub fn build_provider_index(
         if let Some(v) = tax_desc.as_deref().filter(|s| !s.trim().is_empty()) {
             doc.add_text(fields.primary_taxonomy_desc, v);
         }
+        if let Some(v) = sex.as_deref().filter(|s| !s.trim().is_empty()) {
+            doc.add_text(fields.sex, v.trim());
+        }
+        doc.add_i64(fields.state_count, state_count.unwrap_or(0));
 
         doc.add_f64(fields.paid_billing, paid_billing.unwrap_or(0.0));
         doc.add_i64(fields.claims_billing, claims_billing.unwrap_or(0));
@@ -541,6 +736,42 @@ pub fn build_provider_index(
         doc.add_f64(fields.paid_total, paid_total.unwrap_or(0.0));
         doc.add_i64(fields.claims_total, claims_total.unwrap_or(0));
         doc.add_i64(fields.bene_total, bene_total.unwrap_or(0));
+        doc.add_i64(fields.distinct_codes, distinct_codes.unwrap_or(0));
+
+        for (paid_ratio, bene_ratio, paid, claims, bene) in [
+            (
+                fields.paid_per_claim_billing,
+                fields.bene_per_claim_billing,
+                paid_billing,
+                claims_billing,
+                bene_billing,
+            ),
+            (
+                fields.paid_per_claim_servicing,
+                fields.bene_per_claim_servicing,
+                paid_servicing,
+                claims_servicing,
+                bene_servicing,
+            ),
+            (
+                fields.paid_per_claim_total,
+                fields.bene_per_claim_total,
+                paid_total,
+                claims_total,
+                bene_total,
+            ),
+        ] {
+            let claims = claims.unwrap_or(0);
+            let per_claim = |v: f64| {
+                if claims == 0 {
+                    NO_CLAIMS_RATIO
+                } else {
+                    v / claims as f64
+                }
+            };
+            doc.add_f64(paid_ratio, per_claim(paid.unwrap_or(0.0)));
+            doc.add_f64(bene_ratio, per_claim(bene.unwrap_or(0) as f64));
+        }
 
         writer.add_document(doc)?;
         count += 1;
@@ -559,6 +790,17 @@ pub fn build_provider_index(
     Ok(())
 }
 
+/// Whether an existing index has every field of [`provider_schema`] (fields get added over time).
+fn index_has_current_schema(index_dir: &Path) -> bool {
+    let Ok(index) = Index::open_in_dir(index_dir) else {
+        return false;
+    };
+    let existing = index.schema();
+    provider_schema()
+        .fields()
+        .all(|(_, entry)| existing.get_field(entry.name()).is_ok())
+}
+
 fn provider_schema() -> Schema {
     let mut b = Schema::builder();
 
@@ -566,6 +808,8 @@ fn provider_schema() -> Schema {
     b.add_text_field("display_name", TEXT | STORED);
     b.add_text_field("city", TEXT | STORED);
     b.add_text_field("state", STRING | STORED);
+    b.add_text_field("sex", STRING);
+    b.add_i64_field("state_count", NumericOptions::default().set_indexed());
     b.add_text_field("enumeration_type", STRING | STORED);
     b.add_text_field("primary_taxonomy_code", STRING | STORED);
     b.add_text_field("primary_taxonomy_desc", TEXT | STORED);
@@ -592,6 +836,16 @@ fn provider_schema() -> Schema {
     b.add_f64_field("paid_total", f64o.clone());
     b.add_i64_field("claims_total", i64o.clone());
     b.add_i64_field("bene_total", i64o.clone());
+    b.add_i64_field("distinct_codes", i64o.clone());
+
+    // Sort keys only; hits recompute the ratios from the stored totals.
+    let ratio = NumericOptions::default().set_fast();
+    b.add_f64_field("paid_per_claim_billing", ratio.clone());
+    b.add_f64_field("bene_per_claim_billing", ratio.clone());
+    b.add_f64_field("paid_per_claim_servicing", ratio.clone());
+    b.add_f64_field("bene_per_claim_servicing", ratio.clone());
+    b.add_f64_field("paid_per_claim_total", ratio.clone());
+    b.add_f64_field("bene_per_claim_total", ratio);
 
     b.build()
 }
@@ -605,6 +859,8 @@ fn provider_fields(schema: &Schema) -> anyhow::Result<ProviderFields> {
         enumeration_type: schema.get_field("enumeration_type")?,
         primary_taxonomy_code: schema.get_field("primary_taxonomy_code")?,
         primary_taxonomy_desc: schema.get_field("primary_taxonomy_desc")?,
+        sex: schema.get_field("sex")?,
+        state_count: schema.get_field("state_count")?,
 
         state_facet: schema.get_field("state_facet")?,
         entity_facet: schema.get_field("entity_facet")?,
@@ -619,6 +875,14 @@ fn provider_fields(schema: &Schema) -> anyhow::Result<ProviderFields> {
         paid_total: schema.get_field("paid_total")?,
         claims_total: schema.get_field("claims_total")?,
         bene_total: schema.get_field("bene_total")?,
+        distinct_codes: schema.get_field("distinct_codes")?,
+
+        paid_per_claim_billing: schema.get_field("paid_per_claim_billing")?,
+        bene_per_claim_billing: schema.get_field("bene_per_claim_billing")?,
+        paid_per_claim_servicing: schema.get_field("paid_per_claim_servicing")?,
+        bene_per_claim_servicing: schema.get_field("bene_per_claim_servicing")?,
+        paid_per_claim_total: schema.get_field("paid_per_claim_total")?,
+        bene_per_claim_total: schema.get_field("bene_per_claim_total")?,
     })
 }
 
@@ -644,6 +908,32 @@ fn looks_like_npi(q: &str) -> bool {
     s.len() == 10 && s.chars().all(|c| c.is_ascii_digit())
 }
 
+fn paid_per_claim(hit: &ProviderHit, role: Role) -> Option<f64> {
+    let (paid, claims) = match role {
+        Role::Billing => (hit.paid_billing, hit.claims_billing),
+        Role::Servicing => (hit.paid_servicing, hit.claims_servicing),
+        Role::Total => (hit.paid_total, hit.claims_total),
+    };
+    if claims == 0 {
+        None
+    } else {
+        Some(paid / claims as f64)
+    }
+}
+
+fn bene_per_claim(hit: &ProviderHit, role: Role) -> Option<f64> {
+    let (bene, claims) = match role {
+        Role::Billing => (hit.bene_billing, hit.claims_billing),
+        Role::Servicing => (hit.bene_servicing, hit.claims_servicing),
+        Role::Total => (hit.bene_total, hit.claims_total),
+    };
+    if claims == 0 {
+        None
+    } else {
+        Some(bene as f64 / claims as f64)
+    }
+}
+
 fn role_field_names(role: Role) -> (&'static str, &'static str) {
     match role {
         Role::Billing => ("paid_billing", "claims_billing"),
@@ -651,3 +941,446 @@ fn role_field_names(role: Role) -> (&'static str, &'static str) {
         Role::Total => ("paid_total", "claims_total"),
     }
 }
+
+/// `(paid_per_claim, bene_per_claim)` sort fields for `role`.
+fn role_per_claim_field_names(role: Role) -> (&'static str, &'static str) {
+    match role {
+        Role::Billing => ("paid_per_claim_billing", "bene_per_claim_billing"),
+        Role::Servicing => ("paid_per_claim_servicing", "bene_per_claim_servicing"),
+        Role::Total => ("paid_per_claim_total", "bene_per_claim_total"),
+    }
+}
+
+pub fn role_bene_field_name(role: Role) -> &'static str {
+    match role {
+        Role::Billing => "bene_billing",
+        Role::Servicing => "bene_servicing",
+        Role::Total => "bene_total",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// One `provider_search` row; roles split as billing = total, servicing = 0.
+    #[derive(Default)]
+    struct Row {
+        npi: &'static str,
+        name: &'static str,
+        city: &'static str,
+        state: &'static str,
+        entity: &'static str,
+        tax_desc: &'static str,
+        sex: &'static str,
+        state_count: i64,
+        paid: f64,
+        claims: i64,
+        bene: i64,
+        distinct_codes: i64,
+    }
+
+    fn row(npi: &'static str, name: &'static str) -> Row {
+        Row {
+            npi,
+            name,
+            entity: "NPI-1",
+            state_count: 1,
+            ..Row::default()
+        }
+    }
+
+    fn engine(name: &str, rows: &[Row]) -> ProviderEngine {
+        let conn = Connection::open_in_memory().expect("open duckdb");
+        conn.execute_batch(
+            r#"
+            CREATE TABLE provider_search (
+              npi VARCHAR, display_name VARCHAR, enumeration_type VARCHAR,
+              primary_taxonomy_code VARCHAR, primary_taxonomy_desc VARCHAR,
+              state VARCHAR, city VARCHAR,
+              paid_billing DOUBLE, claims_billing BIGINT, bene_billing BIGINT,
+              paid_servicing DOUBLE, claims_servicing BIGINT, bene_servicing BIGINT,
+              paid_total DOUBLE, claims_total BIGINT, bene_total BIGINT,
+              sex VARCHAR, state_count BIGINT, distinct_codes BIGINT
+            );
+            "#,
+        )
+        .expect("create provider_search");
+        for r in rows {
+            conn.execute(
+                "INSERT INTO provider_search VALUES \
+                 (?, ?, ?, NULL, ?, ?, ?, ?, ?, ?, 0, 0, 0, ?, ?, ?, ?, ?, ?)",
+                duckdb::params![
+                    r.npi,
+                    r.name,
+                    r.entity,
+                    r.tax_desc,
+                    r.state,
+                    r.city,
+                    r.paid,
+                    r.claims,
+                    r.bene,
+                    r.paid,
+                    r.claims,
+                    r.bene,
+                    r.sex,
+                    r.state_count,
+                    r.distinct_codes,
+                ],
+            )
+            .expect("insert provider");
+        }
+
+        let dir = std::env::temp_dir().join(format!(
+            "site_backend_providers_{name}_{}",
+            std::process::id()
+        ));
+        build_provider_index(&conn, &dir, true).expect("build provider index");
+        ProviderEngine::open(&dir).expect("open provider index")
+    }
+
+    fn query() -> ProviderSearchQuery {
+        ProviderSearchQuery {
+            q: None,
+            states: Vec::new(),
+            taxonomies: Vec::new(),
+            entity: None,
+            sex: None,
+            multi_state: false,
+            role: Role::Total,
+            paid_min: None,
+            paid_max: None,
+            claims_min: None,
+            claims_max: None,
+            bene_min: None,
+            bene_max: None,
+            sort: Sort::PaidDesc,
+            page: 0,
+            page_size: 50,
+            highlight: false,
+        }
+    }
+
+    fn npis(engine: &ProviderEngine, q: ProviderSearchQuery) -> Vec<String> {
+        let res = engine.search(q).expect("search");
+        assert_eq!(res.total_hits, res.hits.len());
+        res.hits.into_iter().map(|h| h.npi).collect()
+    }
+
+    #[test]
+    fn bene_range_filters_on_the_role_bene_field() {
+        let engine = engine(
+            "bene_range",
+            &[
+                Row {
+                    bene: 5,
+                    paid: 3.0,
+                    ..row("1000000001", "Few")
+                },
+                Row {
+                    bene: 50,
+                    paid: 2.0,
+                    ..row("1000000002", "Some")
+                },
+                Row {
+                    bene: 500,
+                    paid: 1.0,
+                    ..row("1000000003", "Many")
+                },
+            ],
+        );
+
+        let between = ProviderSearchQuery {
+            bene_min: Some(10),
+            bene_max: Some(100),
+            ..query()
+        };
+        assert_eq!(npis(&engine, between), ["1000000002"]);
+
+        let at_least = ProviderSearchQuery {
+            bene_min: Some(50),
+            ..query()
+        };
+        assert_eq!(npis(&engine, at_least), ["1000000002", "1000000003"]);
+
+        let at_most = ProviderSearchQuery {
+            bene_max: Some(50),
+            ..query()
+        };
+        assert_eq!(npis(&engine, at_most), ["1000000001", "1000000002"]);
+
+        // Servicing bene is 0 for every fixture row, so a billing-only range matches nobody there.
+        let servicing = ProviderSearchQuery {
+            role: Role::Servicing,
+            bene_min: Some(1),
+            ..query()
+        };
+        assert!(npis(&engine, servicing).is_empty());
+    }
+
+    #[test]
+    fn sex_filter_matches_individuals_only() {
+        let engine = engine(
+            "sex",
+            &[
+                Row {
+                    sex: "F",
+                    paid: 3.0,
+                    ..row("1000000001", "Ann")
+                },
+                Row {
+                    sex: "M",
+                    paid: 2.0,
+                    ..row("1000000002", "Bob")
+                },
+                Row {
+                    entity: "NPI-2",
+                    paid: 1.0,
+                    ..row("1000000003", "Clinic")
+                },
+            ],
+        );
+        let female = ProviderSearchQuery {
+            sex: Some("f".to_string()),
+            ..query()
+        };
+        assert_eq!(npis(&engine, female), ["1000000001"]);
+        let male = ProviderSearchQuery {
+            sex: Some("M".to_string()),
+            ..query()
+        };
+        assert_eq!(npis(&engine, male), ["1000000002"]);
+        assert_eq!(npis(&engine, query()).len(), 3);
+    }
+
+    #[test]
+    fn multi_state_keeps_providers_in_more_than_one_state() {
+        let engine = engine(
+            "multi_state",
+            &[
+                Row {
+                    state_count: 3,
+                    paid: 3.0,
+                    ..row("1000000001", "Telehealth")
+                },
+                Row {
+                    state_count: 1,
+                    paid: 2.0,
+                    ..row("1000000002", "Local")
+                },
+                Row {
+                    state_count: 0,
+                    paid: 1.0,
+                    ..row("1000000003", "Unknown")
+                },
+            ],
+        );
+        let multi = ProviderSearchQuery {
+            multi_state: true,
+            ..query()
+        };
+        assert_eq!(npis(&engine, multi), ["1000000001"]);
+    }
+
+    #[test]
+    fn paid_per_claim_sorts_descending_with_zero_claims_last() {
+        let engine = engine(
+            "paid_per_claim",
+            &[
+                Row {
+                    paid: 100.0,
+                    claims: 10,
+                    ..row("1000000001", "Ten each")
+                },
+                Row {
+                    paid: 50.0,
+                    claims: 0,
+                    ..row("1000000002", "No claims")
+                },
+                Row {
+                    paid: 90.0,
+                    claims: 3,
+                    ..row("1000000003", "Thirty each")
+                },
+            ],
+        );
+        let res = engine
+            .search(ProviderSearchQuery {
+                sort: Sort::PaidPerClaimDesc,
+                ..query()
+            })
+            .unwrap();
+        let ranked: Vec<_> = res
+            .hits
+            .iter()
+            .map(|h| (h.npi.as_str(), h.paid_per_claim))
+            .collect();
+        assert_eq!(
+            ranked,
+            [
+                ("1000000003", Some(30.0)),
+                ("1000000001", Some(10.0)),
+                ("1000000002", None),
+            ]
+        );
+    }
+
+    #[test]
+    fn paid_per_claim_ranks_the_whole_index_across_pages() {
+        // The top ratio belongs to the last-indexed provider with the smallest totals, so it
+        // only ranks first if the sort covers every document rather than a leading window.
+        let mut rows: Vec<Row> = (0..30)
+            .map(|i| Row {
+                paid: 1_000.0 + i as f64,
+                claims: 100,
+                ..row(format!("11{i:08}").leak(), "Bulk")
+            })
+            .collect();
+        rows.push(Row {
+            paid: 50.0,
+            claims: 1,
+            ..row("1999999999", "Small but pricey")
+        });
+        let engine = engine("paid_per_claim_pages", &rows);
+        let page = |page| {
+            let res = engine
+                .search(ProviderSearchQuery {
+                    sort: Sort::PaidPerClaimDesc,
+                    page,
+                    page_size: 1,
+                    ..query()
+                })
+                .unwrap();
+            assert_eq!(res.total_hits, 31);
+            res.hits.into_iter().map(|h| h.npi).collect::<Vec<_>>()
+        };
+        assert_eq!(page(0), ["1999999999"]);
+        assert_eq!(page(1), ["1100000029"]);
+    }
+
+    #[test]
+    fn name_and_taxonomy_match_outranks_either_alone() {
+        let engine = engine(
+            "name_taxonomy",
+            &[
+                Row {
+                    tax_desc: "Pediatrics",
+                    paid: 3.0,
+                    ..row("1000000001", "Jane Doe")
+                },
+                Row {
+                    tax_desc: "Dermatology",
+                    paid: 2.0,
+                    ..row("1000000002", "John Smith")
+                },
+                Row {
+                    tax_desc: "Pediatrics",
+                    paid: 1.0,
+                    ..row("1000000003", "Mary Smith")
+                },
+            ],
+        );
+        let ranked = npis(
+            &engine,
+            ProviderSearchQuery {
+                q: Some("pediatrics smith".to_string()),
+                sort: Sort::Relevance,
+                ..query()
+            },
+        );
+        // Both fields, then name only (boosted), then taxonomy only.
+        assert_eq!(ranked, ["1000000003", "1000000002", "1000000001"]);
+    }
+
+    #[test]
+    fn highlights_mark_only_the_fields_that_matched() {
+        let engine = engine(
+            "highlights",
+            &[Row {
+                city: "Springfield",
+                tax_desc: "Pediatrics",
+                ..row("1000000001", "Mary Smith")
+            }],
+        );
+        let text_query = ProviderSearchQuery {
+            q: Some("smith".to_string()),
+            highlight: true,
+            ..query()
+        };
+        let hits = engine.search(text_query.clone()).unwrap().hits;
+        let highlights = hits[0].highlights.as_ref().expect("highlights");
+        assert_eq!(
+            highlights.get("display_name").map(String::as_str),
+            Some("Mary <b>Smith</b>")
+        );
+        assert!(!highlights.contains_key("city"));
+        assert!(!highlights.contains_key("primary_taxonomy_desc"));
+
+        let off = ProviderSearchQuery {
+            highlight: false,
+            ..text_query
+        };
+        assert!(engine.search(off).unwrap().hits[0].highlights.is_none());
+
+        let no_text = ProviderSearchQuery {
+            highlight: true,
+            ..query()
+        };
+        assert!(engine.search(no_text).unwrap().hits[0].highlights.is_none());
+    }
+
+    #[test]
+    fn distinct_codes_and_bene_per_claim_sorts() {
+        let engine = engine(
+            "distinct_codes",
+            &[
+                Row {
+                    distinct_codes: 2,
+                    bene: 5,
+                    claims: 10,
+                    ..row("1000000001", "A")
+                },
+                Row {
+                    distinct_codes: 9,
+                    bene: 9,
+                    claims: 0,
+                    ..row("1000000002", "B")
+                },
+                Row {
+                    distinct_codes: 4,
+                    bene: 8,
+                    claims: 10,
+                    ..row("1000000003", "C")
+                },
+            ],
+        );
+        let by_codes = npis(
+            &engine,
+            ProviderSearchQuery {
+                sort: Sort::DistinctCodesDesc,
+                ..query()
+            },
+        );
+        assert_eq!(by_codes, ["1000000002", "1000000003", "1000000001"]);
+
+        let res = engine
+            .search(ProviderSearchQuery {
+                sort: Sort::BenePerClaimDesc,
+                ..query()
+            })
+            .unwrap();
+        let ranked: Vec<_> = res
+            .hits
+            .iter()
+            .map(|h| (h.npi.as_str(), h.bene_per_claim))
+            .collect();
+        assert_eq!(
+            ranked,
+            [
+                ("1000000003", Some(0.8)),
+                ("1000000001", Some(0.5)),
+                ("1000000002", None),
+            ]
+        );
+    }
+}
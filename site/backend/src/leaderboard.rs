@@ -0,0 +1,118 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use anyhow::Context;
+use duckdb::Connection;
+use serde::Serialize;
+
+use crate::build::sql_quote_path;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LeaderboardEntry {
+    pub rank: u64,
+    /// NPI for provider entries, HCPCS code for code entries.
+    pub id: String,
+    /// Provider display name / HCPCS short description.
+    pub name: Option<String>,
+    pub state: Option<String>,
+    pub paid_total: f64,
+    pub claims_total: i64,
+}
+
+/// `leaderboard.parquet` held in memory by `serve`.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Leaderboard {
+    pub providers: Vec<LeaderboardEntry>,
+    pub providers_by_state: BTreeMap<String, Vec<LeaderboardEntry>>,
+    pub hcpcs: Vec<LeaderboardEntry>,
+}
+
+/// Writes the top `top_n` providers by `paid_total` (overall and per state) and the top
+/// `top_n` HCPCS codes from the serving tables. Rows are `(kind, scope, rank, ...)`, where
+/// `scope` is `''` for overall lists and the state code for per-state provider lists.
+pub fn write_leaderboard(conn: &mut Connection, path: &Path, top_n: u64) -> anyhow::Result<()> {
+    let path = sql_quote_path(path);
+    let sql = format!(
+        r#"
+        COPY (
+          WITH providers AS (
+            SELECT
+              npi,
+              display_name,
+              NULLIF(TRIM(state), '') AS state,
+              CAST(paid_total AS DOUBLE) AS paid_total,
+              CAST(claims_total AS BIGINT) AS claims_total,
+              ROW_NUMBER() OVER (ORDER BY paid_total DESC, npi) AS overall_rank,
+              ROW_NUMBER() OVER (
+                PARTITION BY NULLIF(TRIM(state), '') ORDER BY paid_total DESC, npi
+              ) AS state_rank
+            FROM provider_search
+            WHERE paid_total > 0
+          ),
+          codes AS (
+            SELECT
+              hcpcs_code,
+              short_desc,
+              CAST(paid_total AS DOUBLE) AS paid_total,
+              CAST(claims_total AS BIGINT) AS claims_total,
+              ROW_NUMBER() OVER (ORDER BY paid_total DESC, hcpcs_code) AS overall_rank
+            FROM hcpcs_search
+            WHERE paid_total > 0
+          )
+          SELECT 'provider' AS kind, '' AS scope, overall_rank AS rank, npi AS id,
+                 display_name AS name, state, paid_total, claims_total
+          FROM providers WHERE overall_rank <= {top_n}
+          UNION ALL
+          SELECT 'provider', state, state_rank, npi, display_name, state, paid_total, claims_total
+          FROM providers WHERE state IS NOT NULL AND state_rank <= {top_n}
+          UNION ALL
+          SELECT 'hcpcs', '', overall_rank, hcpcs_code, short_desc, NULL, paid_total, claims_total
+          FROM codes WHERE overall_rank <= {top_n}
+        ) TO '{path}' (FORMAT PARQUET)
+    "#
+    );
+    conn.execute(&sql, [])?;
+    Ok(())
+}
+
+pub fn load_leaderboard(conn: &mut Connection, path: &Path) -> anyhow::Result<Leaderboard> {
+    let sql = format!(
+        r#"
+        SELECT kind, scope, rank, id, name, state, paid_total, claims_total
+        FROM read_parquet('{}')
+        ORDER BY kind, scope, rank
+    "#,
+        sql_quote_path(path)
+    );
+    let mut stmt = conn.prepare(&sql)?;
+    let rows = stmt.query_map([], |row| {
+        Ok((
+            row.get::<usize, String>(0)?,
+            row.get::<usize, String>(1)?,
+            LeaderboardEntry {
+                rank: row.get::<usize, i64>(2)?.max(0) as u64,
+                id: row.get(3)?,
+                name: row.get(4)?,
+                state: row.get(5)?,
+                paid_total: row.get::<usize, Option<f64>>(6)?.unwrap_or(0.0),
+                claims_total: row.get::<usize, Option<i64>>(7)?.unwrap_or(0),
+            },
+        ))
+    })?;
+
+    let mut out = Leaderboard::default();
+    for r in rows {
+        let (kind, scope, entry) = r.context("read leaderboard row")?;
+        match (kind.as_str(), scope.as_str()) {
+            ("provider", "") => out.providers.push(entry),
+            ("provider", state) => out
+                .providers_by_state
+                .entry(state.to_string())
+                .or_default()
+                .push(entry),
+            ("hcpcs", _) => out.hcpcs.push(entry),
+            _ => {}
+        }
+    }
+    Ok(out)
+}
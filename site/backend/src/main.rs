@@ -1,12 +1,23 @@
 mod build;
 mod cli;
 mod download;
+mod duplicates;
+mod freshness;
 mod geo;
 mod hcpcs;
 mod index;
+mod leaderboard;
+// Identifier rules live in `build_datasets` so the pipeline and the site never disagree on what
+// an NPI or HCPCS code is; the site only uses part of that module.
+#[allow(dead_code)]
+#[path = "../../../build_datasets/src/normalize.rs"]
+mod normalize;
 mod npi;
 mod server;
+#[path = "../../../build_datasets/src/spending_schema.rs"]
+mod spending_schema;
 mod storage;
+mod views;
 
 use anyhow::Context;
 use clap::Parser;
@@ -20,7 +31,7 @@ async fn main() -> anyhow::Result<()> {
     let args = cli::Args::parse();
 
     match args.cmd {
-        cli::Command::Build(cmd) => build::run(cmd).await.context("build failed"),
+        cli::Command::Build(cmd) => build::run(*cmd).await.context("build failed"),
         cli::Command::Serve(cmd) => server::run(cmd).await.context("serve failed"),
     }
 }
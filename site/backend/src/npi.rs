@@ -1,6 +1,8 @@
+use serde::Serialize;
 use serde_json::Value;
 
 use crate::geo::normalize_zip5;
+use crate::normalize;
 
 #[derive(Debug, Clone)]
 pub struct NpiExtract {
@@ -12,6 +14,25 @@ pub struct NpiExtract {
     pub state: Option<String>,
     pub city: Option<String>,
     pub zip5: Option<String>,
+    /// `line1, city, ST zip5` from the chosen practice address; `None` when any part is blank.
+    pub practice_address: Option<String>,
+    pub replacement_npi: Option<String>,
+    /// `M`/`F` from `basic.sex` (older payloads: `basic.gender`); always `None` for NPI-2.
+    pub sex: Option<String>,
+    /// `YES`/`NO` from `basic.sole_proprietor`; `X` (not answered) and blanks are `None`.
+    pub sole_proprietor: Option<String>,
+    /// Distinct states across the primary (`LOCATION`) address and any `practiceLocations`.
+    pub state_count: i64,
+    /// `basic.enumeration_date` as `YYYY-MM-DD`; blank or unparseable dates are `None`.
+    pub enumeration_date: Option<String>,
+    /// `basic.last_updated` as `YYYY-MM-DD`, same rules as `enumeration_date`.
+    pub last_updated: Option<String>,
+}
+
+/// Canonical NPI (see [`normalize::normalize_npi`]) when it is exactly 10 digits.
+pub fn normalize_npi(raw: &str) -> Option<String> {
+    let npi = normalize::normalize_npi(raw);
+    (npi.len() == 10 && npi.bytes().all(|b| b.is_ascii_digit())).then(|| npi.to_string())
 }
 
 pub fn extract_provider_fields(npi: &str, response_json: Option<&str>) -> NpiExtract {
@@ -24,6 +45,13 @@ pub fn extract_provider_fields(npi: &str, response_json: Option<&str>) -> NpiExt
         state: None,
         city: None,
         zip5: None,
+        practice_address: None,
+        replacement_npi: None,
+        sex: None,
+        sole_proprietor: None,
+        state_count: 0,
+        enumeration_date: None,
+        last_updated: None,
     };
 
     let Some(s) = response_json else {
@@ -50,6 +78,35 @@ pub fn extract_provider_fields(npi: &str, response_json: Option<&str>) -> NpiExt
     // display_name from basic
     let basic = r0.get("basic").and_then(|x| x.as_object());
     if let Some(basic) = basic {
+        // Only NPPES bulk rows for deactivated NPIs carry this (and only some extracts at that).
+        out.replacement_npi = basic
+            .get("replacement_npi")
+            .and_then(|x| x.as_str())
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(|s| s.to_string());
+
+        let basic_upper = |key: &str| {
+            basic
+                .get(key)
+                .and_then(|x| x.as_str())
+                .map(|s| s.trim().to_ascii_uppercase())
+        };
+        if out.enumeration_type.as_deref() != Some("NPI-2") {
+            out.sex = basic_upper("sex")
+                .or_else(|| basic_upper("gender"))
+                .filter(|s| s == "M" || s == "F");
+        }
+        out.sole_proprietor = basic_upper("sole_proprietor").filter(|s| s == "YES" || s == "NO");
+        let basic_date = |key: &str| {
+            basic
+                .get(key)
+                .and_then(|x| x.as_str())
+                .and_then(normalize_nppes_date)
+        };
+        out.enumeration_date = basic_date("enumeration_date");
+        out.last_updated = basic_date("last_updated");
+
         if let Some(org) = basic
             .get("organization_name")
             .and_then(|x| x.as_str())
@@ -120,9 +177,53 @@ pub fn extract_provider_fields(npi: &str, response_json: Option<&str>) -> NpiExt
                 .get("postal_code")
                 .and_then(|x| x.as_str())
                 .and_then(normalize_zip5);
+            let line1 = a
+                .get("address_1")
+                .and_then(|x| x.as_str())
+                .map(str::trim)
+                .filter(|s| !s.is_empty());
+            out.practice_address = format_practice_address(
+                line1,
+                out.city.as_deref(),
+                out.state.as_deref(),
+                out.zip5.as_deref(),
+            );
         }
     }
 
+    // every practice state: LOCATION addresses plus secondary practice locations
+    let mut practice_states = std::collections::BTreeSet::new();
+    let location_addresses = addresses.into_iter().flatten().filter(|a| {
+        a.get("address_purpose")
+            .and_then(|x| x.as_str())
+            .is_some_and(|s| s.eq_ignore_ascii_case("LOCATION"))
+    });
+    let secondary = r0
+        .get("practiceLocations")
+        .and_then(|x| x.as_array())
+        .into_iter()
+        .flatten();
+    for a in location_addresses.chain(secondary) {
+        if let Some(st) = a
+            .get("state")
+            .and_then(|x| x.as_str())
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+        {
+            practice_states.insert(st.to_ascii_uppercase());
+        }
+    }
+    if practice_states.is_empty()
+        && let Some(st) = out
+            .state
+            .as_deref()
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+    {
+        practice_states.insert(st.to_ascii_uppercase());
+    }
+    out.state_count = practice_states.len() as i64;
+
     // primary taxonomy
     let taxonomies = r0.get("taxonomies").and_then(|x| x.as_array());
     if let Some(taxes) = taxonomies {
@@ -154,3 +255,195 @@ pub fn extract_provider_fields(npi: &str, response_json: Option<&str>) -> NpiExt
 
     out
 }
+
+/// NPPES dates arrive as `YYYY-MM-DD` from the API and `MM/DD/YYYY` from the bulk CSV;
+/// both become `YYYY-MM-DD` so DuckDB can store them as `DATE`.
+fn normalize_nppes_date(raw: &str) -> Option<String> {
+    let raw = raw.trim();
+    let (y, m, d) = if let Some((m, rest)) = raw.split_once('/') {
+        let (d, y) = rest.split_once('/')?;
+        (y, m, d)
+    } else {
+        let mut parts = raw.splitn(3, '-');
+        (parts.next()?, parts.next()?, parts.next()?)
+    };
+    let num = |s: &str, len: usize| {
+        (s.len() <= len && !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit()))
+            .then(|| s.parse::<u32>().ok())
+            .flatten()
+    };
+    let (y, m, d) = (num(y, 4)?, num(m, 2)?, num(d, 2)?);
+    if y < 1000 || !(1..=12).contains(&m) || !(1..=31).contains(&d) {
+        return None;
+    }
+    Some(format!("{y:04}-{m:02}-{d:02}"))
+}
+
+fn format_practice_address(
+    line1: Option<&str>,
+    city: Option<&str>,
+    state: Option<&str>,
+    zip5: Option<&str>,
+) -> Option<String> {
+    let line1 = line1?;
+    let city = city.map(str::trim).filter(|s| !s.is_empty())?;
+    let state = state.map(str::trim).filter(|s| !s.is_empty())?;
+    let zip5 = zip5?;
+    Some(format!("{line1}, {city}, {state} {zip5}"))
+}
+
+/// One entry of the NPI API `identifiers` array (Medicare/Medicaid and other provider IDs).
+#[derive(Debug, Clone, Serialize)]
+pub struct OtherIdentifier {
+    pub identifier: String,
+    pub code: Option<String>,
+    pub desc: Option<String>,
+    pub state: Option<String>,
+    pub issuer: Option<String>,
+}
+
+pub fn extract_other_identifiers(response_json: Option<&str>) -> Vec<OtherIdentifier> {
+    let Some(s) = response_json else {
+        return Vec::new();
+    };
+    let Ok(v) = serde_json::from_str::<Value>(s) else {
+        return Vec::new();
+    };
+    let Some(ids) = v
+        .get("results")
+        .and_then(|x| x.as_array())
+        .and_then(|r| r.first())
+        .and_then(|r0| r0.get("identifiers"))
+        .and_then(|x| x.as_array())
+    else {
+        return Vec::new();
+    };
+
+    let text = |item: &Value, key: &str| {
+        item.get(key)
+            .and_then(|x| x.as_str())
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(|s| s.to_string())
+    };
+    ids.iter()
+        .filter_map(|item| {
+            Some(OtherIdentifier {
+                identifier: text(item, "identifier")?,
+                code: text(item, "code"),
+                desc: text(item, "desc"),
+                state: text(item, "state"),
+                issuer: text(item, "issuer"),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn route_npi_must_be_ten_digits_after_trimming() {
+        assert_eq!(normalize_npi(" 1234567893 ").as_deref(), Some("1234567893"));
+        assert_eq!(normalize_npi("123456789"), None);
+        assert_eq!(normalize_npi("12345678901"), None);
+        assert_eq!(normalize_npi("12345 6789"), None);
+        assert_eq!(normalize_npi("123456789x"), None);
+    }
+
+    fn nppes(result: &str) -> String {
+        format!(r#"{{"result_count":1,"results":[{result}]}}"#)
+    }
+
+    #[test]
+    fn sex_and_sole_proprietor_come_from_basic() {
+        let individual = nppes(
+            r#"{"enumeration_type":"NPI-1","basic":{"first_name":"Ann","last_name":"Lee","sex":"f","sole_proprietor":"YES"}}"#,
+        );
+        let out = extract_provider_fields("1234567893", Some(&individual));
+        assert_eq!(out.sex.as_deref(), Some("F"));
+        assert_eq!(out.sole_proprietor.as_deref(), Some("YES"));
+
+        // Bulk rows spell it `gender`; `X` means the question was not answered.
+        let bulk =
+            nppes(r#"{"enumeration_type":"NPI-1","basic":{"gender":"M","sole_proprietor":"X"}}"#);
+        let out = extract_provider_fields("1234567893", Some(&bulk));
+        assert_eq!(out.sex.as_deref(), Some("M"));
+        assert_eq!(out.sole_proprietor, None);
+
+        let organization = nppes(
+            r#"{"enumeration_type":"NPI-2","basic":{"organization_name":"Clinic","sex":"F","sole_proprietor":"NO"}}"#,
+        );
+        let out = extract_provider_fields("1245319599", Some(&organization));
+        assert_eq!(out.sex, None);
+        assert_eq!(out.sole_proprietor.as_deref(), Some("NO"));
+    }
+
+    #[test]
+    fn state_count_unions_location_and_practice_location_states() {
+        let multi = nppes(
+            r#"{"basic":{},"addresses":[
+                {"address_purpose":"MAILING","state":"NV"},
+                {"address_purpose":"LOCATION","state":"CA"}
+            ],"practiceLocations":[{"state":"or"},{"state":"CA"},{"state":" "}]}"#,
+        );
+        let out = extract_provider_fields("1234567893", Some(&multi));
+        assert_eq!(out.state.as_deref(), Some("CA"));
+        assert_eq!(out.state_count, 2);
+
+        // Without a LOCATION address the chosen (first) address still counts.
+        let mailing_only =
+            nppes(r#"{"basic":{},"addresses":[{"address_purpose":"MAILING","state":"NV"}]}"#);
+        assert_eq!(
+            extract_provider_fields("1234567893", Some(&mailing_only)).state_count,
+            1
+        );
+        assert_eq!(extract_provider_fields("1234567893", None).state_count, 0);
+    }
+
+    #[test]
+    fn replacement_npi_is_read_when_present() {
+        let deactivated = nppes(
+            r#"{"enumeration_type":"NPI-1","basic":{"status":"D","replacement_npi":" 1245319599 "}}"#,
+        );
+        let out = extract_provider_fields("1234567893", Some(&deactivated));
+        assert_eq!(out.replacement_npi.as_deref(), Some("1245319599"));
+
+        for basic in [
+            r#"{"status":"A"}"#,
+            r#"{"status":"D","replacement_npi":""}"#,
+        ] {
+            let json = nppes(&format!(
+                r#"{{"enumeration_type":"NPI-1","basic":{basic}}}"#
+            ));
+            assert_eq!(
+                extract_provider_fields("1234567893", Some(&json)).replacement_npi,
+                None
+            );
+        }
+    }
+
+    #[test]
+    fn practice_address_is_built_from_the_location_address() {
+        let json = nppes(
+            r#"{"basic":{},"addresses":[
+                {"address_purpose":"MAILING","address_1":"PO BOX 1","city":"RENO","state":"NV","postal_code":"89501"},
+                {"address_purpose":"LOCATION","address_1":" 1 MAIN ST ","city":"FRESNO","state":"CA","postal_code":"937011234"}
+            ]}"#,
+        );
+        let out = extract_provider_fields("1234567893", Some(&json));
+        assert_eq!(
+            out.practice_address.as_deref(),
+            Some("1 MAIN ST, FRESNO, CA 93701")
+        );
+
+        let no_street = nppes(
+            r#"{"basic":{},"addresses":[{"address_purpose":"LOCATION","city":"FRESNO","state":"CA","postal_code":"93701"}]}"#,
+        );
+        assert_eq!(
+            extract_provider_fields("1234567893", Some(&no_street)).practice_address,
+            None
+        );
+    }
+}
@@ -1,10 +1,11 @@
+use std::collections::BTreeMap;
 use std::net::SocketAddr;
 use std::path::Path;
 use std::sync::Arc;
 
 use anyhow::{Context, anyhow};
 use axum::extract::{Path as AxumPath, Query, State};
-use axum::http::StatusCode;
+use axum::http::{StatusCode, header};
 use axum::response::IntoResponse;
 use axum::routing::get;
 use axum::{Json, Router};
@@ -14,8 +15,14 @@ use tokio::sync::Mutex;
 use tower_http::cors::{Any, CorsLayer};
 
 use crate::cli::ServeArgs;
+use crate::freshness::{SourceFreshness, collect_freshness};
 use crate::index::hcpcs::{HcpcsEngine, HcpcsSearchQuery, Sort as HcpcsSort};
-use crate::index::providers::{ProviderEngine, ProviderSearchQuery, Role, Sort as ProviderSort};
+use crate::index::providers::{
+    ProviderEngine, ProviderSearchQuery, Role, Sort as ProviderSort, role_bene_field_name,
+};
+use crate::leaderboard::{Leaderboard, LeaderboardEntry, load_leaderboard};
+use crate::normalize::normalize_hcpcs_code;
+use crate::npi::normalize_npi;
 use crate::storage::{StoragePaths, file_present_nonempty};
 
 #[derive(Clone)]
@@ -25,7 +32,14 @@ struct AppState {
     hcpcs: Arc<HcpcsEngine>,
     npi_json_col: String,
     hcpcs_json_col: String,
+    /// Whether hcpcs_search carries the billing/servicing split (DBs built before it did not).
+    hcpcs_role_split: bool,
     meta: Option<serde_json::Value>,
+    state_counts: Arc<BTreeMap<String, u64>>,
+    /// `leaderboard.parquet` from `build --leaderboard-top-n`, if one was written.
+    leaderboard: Option<Arc<Leaderboard>>,
+    /// File times and embedded run metadata of the source parquets, read at startup.
+    freshness: Arc<Vec<SourceFreshness>>,
 }
 
 pub async fn run(opts: ServeArgs) -> anyhow::Result<()> {
@@ -54,11 +68,53 @@ pub async fn run(opts: ServeArgs) -> anyhow::Result<()> {
 
     // Ensure parquet-backed views exist (for detail endpoints).
     let sources = paths.source_files();
-    create_or_replace_views(&mut conn, &sources.spending, &sources.npi, &sources.hcpcs)
-        .context("create views")?;
+    crate::views::create_or_replace_views(
+        &mut conn,
+        &sources.spending,
+        &sources.npi,
+        &sources.hcpcs,
+    )
+    .context("create views")?;
+    // Before any slim views: the full parquet files are read directly.
+    let freshness = collect_freshness(&mut conn, &sources);
 
     let npi_json_col = detect_json_col(&mut conn, "npi_api_raw")?;
     let hcpcs_json_col = detect_json_col(&mut conn, "hcpcs_api_raw")?;
+    if opts.slim_raw_views {
+        create_slim_raw_views(
+            &mut conn,
+            &sources.npi,
+            &sources.hcpcs,
+            &npi_json_col,
+            &hcpcs_json_col,
+        )
+        .context("create slim raw views")?;
+    }
+    let hcpcs_role_split = conn
+        .prepare("SELECT paid_billing FROM hcpcs_search LIMIT 1")
+        .is_ok();
+    if !hcpcs_role_split {
+        tracing::warn!(
+            "hcpcs_search has no billing/servicing columns; HCPCS detail will return nulls (rerun site-backend build)"
+        );
+    }
+
+    let state_counts = query_state_counts(&mut conn).context("count providers per state")?;
+
+    let leaderboard = if file_present_nonempty(&paths.leaderboard_path) {
+        match load_leaderboard(&mut conn, &paths.leaderboard_path) {
+            Ok(lb) => Some(Arc::new(lb)),
+            Err(e) => {
+                tracing::warn!(
+                    "failed loading {}; /api/leaderboard disabled: {e:#}",
+                    paths.leaderboard_path.display()
+                );
+                None
+            }
+        }
+    } else {
+        None
+    };
 
     let providers =
         ProviderEngine::open(&paths.provider_index_dir).context("open provider index")?;
@@ -80,7 +136,11 @@ pub async fn run(opts: ServeArgs) -> anyhow::Result<()> {
         hcpcs: Arc::new(hcpcs),
         npi_json_col,
         hcpcs_json_col,
+        hcpcs_role_split,
         meta,
+        state_counts: Arc::new(state_counts),
+        leaderboard,
+        freshness: Arc::new(freshness),
     };
 
     let cors = CorsLayer::new()
@@ -90,10 +150,15 @@ pub async fn run(opts: ServeArgs) -> anyhow::Result<()> {
 
     let app = Router::new()
         .route("/api/stats", get(api_stats))
+        .route("/api/meta/freshness", get(api_meta_freshness))
         .route("/api/search", get(api_global_search))
         .route("/api/filters/providers", get(api_provider_filters))
+        .route("/api/taxonomies/spend", get(api_taxonomy_spend))
+        .route("/api/leaderboard", get(api_leaderboard))
         .route("/api/providers/search", get(api_provider_search))
+        .route("/api/providers/compare", get(api_provider_compare))
         .route("/api/providers/:npi", get(api_provider_detail))
+        .route("/api/providers/:npi/nearby", get(api_provider_nearby))
         .route("/api/hcpcs/search", get(api_hcpcs_search))
         .route("/api/hcpcs/:code", get(api_hcpcs_detail))
         .route("/api/map/zips", get(api_map_zips))
@@ -119,6 +184,17 @@ async fn api_stats(State(st): State<AppState>) -> impl IntoResponse {
     Json(StatsResponse { meta: st.meta })
 }
 
+#[derive(Debug, Serialize)]
+struct FreshnessResponse {
+    sources: Vec<SourceFreshness>,
+}
+
+async fn api_meta_freshness(State(st): State<AppState>) -> impl IntoResponse {
+    Json(FreshnessResponse {
+        sources: (*st.freshness).clone(),
+    })
+}
+
 #[derive(Debug, Deserialize)]
 struct GlobalSearchParams {
     q: String,
@@ -150,6 +226,7 @@ async fn api_global_search(
 #[derive(Debug, Serialize)]
 struct ProviderFiltersResponse {
     states: Vec<String>,
+    state_counts: BTreeMap<String, u64>,
     entities: Vec<String>,
     taxonomies: Vec<TaxonomyOpt>,
 }
@@ -176,6 +253,7 @@ async fn api_provider_filters(State(st): State<AppState>) -> impl IntoResponse {
 
     Json(ProviderFiltersResponse {
         states,
+        state_counts: (*st.state_counts).clone(),
         entities,
         taxonomies,
     })
@@ -191,6 +269,154 @@ fn query_string_list(db: &mut Connection, sql: &str) -> anyhow::Result<Vec<Strin
     Ok(out)
 }
 
+/// Provider count per state, computed once at startup (provider_search is static while serving).
+fn query_state_counts(db: &mut Connection) -> anyhow::Result<BTreeMap<String, u64>> {
+    let sql = r#"
+        SELECT state, COUNT(*) AS provider_count
+        FROM provider_search
+        WHERE state IS NOT NULL
+        GROUP BY state
+    "#;
+    let mut stmt = db.prepare(sql)?;
+    let rows = stmt.query_map([], |row| {
+        Ok((
+            row.get::<usize, String>(0)?,
+            row.get::<usize, i64>(1)?.max(0) as u64,
+        ))
+    })?;
+    let mut out = BTreeMap::new();
+    for r in rows {
+        let (state, count) = r?;
+        out.insert(state, count);
+    }
+    Ok(out)
+}
+
+#[derive(Debug, Serialize)]
+struct TaxonomySpend {
+    code: String,
+    desc: Option<String>,
+    provider_count: u64,
+    paid_total: f64,
+}
+
+#[derive(Debug, Deserialize)]
+struct TaxonomySpendParams {
+    state: Option<Vec<String>>,
+    limit: Option<usize>,
+}
+
+async fn api_taxonomy_spend(
+    State(st): State<AppState>,
+    Query(p): Query<TaxonomySpendParams>,
+) -> impl IntoResponse {
+    let limit = p.limit.unwrap_or(50).clamp(1, 1000);
+    let states = flatten_list(p.state)
+        .into_iter()
+        .filter(|s| s.len() == 2 && s.chars().all(|c| c.is_ascii_alphabetic()))
+        .map(|s| s.to_uppercase())
+        .collect::<Vec<_>>();
+
+    let mut db = st.db.lock().await;
+    match query_taxonomy_spend(&mut db, &states, limit) {
+        Ok(v) => Json(v).into_response(),
+        Err(e) => (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct LeaderboardParams {
+    state: Option<String>,
+    limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+struct LeaderboardResponse<'a> {
+    state: Option<String>,
+    providers: &'a [LeaderboardEntry],
+    hcpcs: &'a [LeaderboardEntry],
+}
+
+async fn api_leaderboard(
+    State(st): State<AppState>,
+    Query(p): Query<LeaderboardParams>,
+) -> impl IntoResponse {
+    let Some(lb) = st.leaderboard.as_deref() else {
+        return (
+            StatusCode::NOT_FOUND,
+            "leaderboard not built (run: site-backend build --leaderboard-top-n N)",
+        )
+            .into_response();
+    };
+    let state = p
+        .state
+        .map(|s| s.trim().to_uppercase())
+        .filter(|s| !s.is_empty());
+    let providers = match &state {
+        Some(s) => lb
+            .providers_by_state
+            .get(s)
+            .map(Vec::as_slice)
+            .unwrap_or(&[]),
+        None => &lb.providers,
+    };
+    let limit = p.limit.unwrap_or(usize::MAX);
+    Json(LeaderboardResponse {
+        state,
+        providers: &providers[..providers.len().min(limit)],
+        hcpcs: &lb.hcpcs[..lb.hcpcs.len().min(limit)],
+    })
+    .into_response()
+}
+
+/// Total paid per primary taxonomy, highest first (optionally restricted to `states`).
+fn query_taxonomy_spend(
+    db: &mut Connection,
+    states: &[String],
+    limit: usize,
+) -> anyhow::Result<Vec<TaxonomySpend>> {
+    let mut where_sql = String::from("WHERE primary_taxonomy_code IS NOT NULL");
+    if !states.is_empty() {
+        where_sql.push_str(" AND state IN (");
+        where_sql.push_str(
+            &states
+                .iter()
+                .map(|s| format!("'{s}'"))
+                .collect::<Vec<_>>()
+                .join(","),
+        );
+        where_sql.push(')');
+    }
+    let sql = format!(
+        r#"
+        SELECT
+          primary_taxonomy_code,
+          ANY_VALUE(primary_taxonomy_desc) AS primary_taxonomy_desc,
+          COUNT(*) AS provider_count,
+          COALESCE(SUM(paid_total), 0) AS paid_total
+        FROM provider_search
+        {where_sql}
+        GROUP BY primary_taxonomy_code
+        ORDER BY paid_total DESC, primary_taxonomy_code ASC
+        LIMIT {limit}
+        "#
+    );
+    let mut stmt = db.prepare(&sql)?;
+    let rows = stmt.query_map([], |row| {
+        Ok(TaxonomySpend {
+            code: row.get::<usize, String>(0)?,
+            desc: row.get::<usize, Option<String>>(1)?,
+            provider_count: row.get::<usize, i64>(2)?.max(0) as u64,
+            paid_total: row.get::<usize, f64>(3)?,
+        })
+    })?;
+    let mut out = Vec::new();
+    for r in rows {
+        out.push(r?);
+    }
+    Ok(out)
+}
+
 fn query_taxonomy_list(db: &mut Connection) -> anyhow::Result<Vec<TaxonomyOpt>> {
     let sql = r#"
         SELECT
@@ -224,14 +450,25 @@ struct ProviderSearchParams {
     state: Option<Vec<String>>,
     taxonomy: Option<Vec<String>>,
     entity: Option<String>,
+    /// `M` or `F` (NPI-1 only; organizations never match).
+    sex: Option<String>,
+    /// `1` keeps only providers practicing in more than one state.
+    multi_state: Option<u8>,
     role: Option<String>,
     paid_min: Option<f64>,
     paid_max: Option<f64>,
     claims_min: Option<i64>,
     claims_max: Option<i64>,
+    /// Unique-beneficiary bounds for the selected role; must be non-negative.
+    bene_min: Option<i64>,
+    bene_max: Option<i64>,
     sort: Option<String>,
     page: Option<usize>,
     page_size: Option<usize>,
+    /// `1` adds `practice_address` to each hit.
+    include_address: Option<u8>,
+    /// `1` adds `highlights` (matched snippets per field) to each hit of a text search.
+    highlight: Option<u8>,
 }
 
 async fn api_provider_search(
@@ -240,11 +477,34 @@ async fn api_provider_search(
 ) -> impl IntoResponse {
     let role = parse_role(p.role.as_deref());
     let sort = parse_provider_sort(p.sort.as_deref());
+    let include_address = p.include_address == Some(1);
+    if p.bene_min.is_some_and(|v| v < 0) || p.bene_max.is_some_and(|v| v < 0) {
+        return (
+            StatusCode::BAD_REQUEST,
+            "bene_min and bene_max must be non-negative",
+        )
+            .into_response();
+    }
+    let sex = p
+        .sex
+        .as_deref()
+        .map(|s| s.trim().to_ascii_uppercase())
+        .filter(|s| !s.is_empty());
+    if sex.as_deref().is_some_and(|s| s != "M" && s != "F") {
+        return (StatusCode::BAD_REQUEST, "sex must be M or F").into_response();
+    }
 
     // For fully alphabetical browsing, use DuckDB directly when q is empty.
     let q_empty = p.q.as_deref().map(str::trim).unwrap_or("").is_empty();
-    if q_empty && sort == ProviderSort::NameAsc {
-        return match duckdb_provider_search(&st, &p, role).await {
+    if q_empty
+        && matches!(
+            sort,
+            ProviderSort::NameAsc | ProviderSort::PaidPerClaimDesc | ProviderSort::BenePerClaimDesc
+        )
+    {
+        return match duckdb_provider_search(&st, &p, role, sort, sex.as_deref(), include_address)
+            .await
+        {
             Ok(r) => Json(r).into_response(),
             Err(e) => (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
         };
@@ -255,26 +515,73 @@ async fn api_provider_search(
         states: flatten_list(p.state),
         taxonomies: flatten_list(p.taxonomy),
         entity: p.entity.clone(),
+        sex,
+        multi_state: p.multi_state == Some(1),
         role,
         paid_min: p.paid_min,
         paid_max: p.paid_max,
         claims_min: p.claims_min,
         claims_max: p.claims_max,
+        bene_min: p.bene_min,
+        bene_max: p.bene_max,
         sort,
         page: p.page.unwrap_or(0),
         page_size: p.page_size.unwrap_or(50),
+        highlight: p.highlight == Some(1),
     };
 
-    match st.providers.search(query) {
-        Ok(r) => Json(r).into_response(),
-        Err(e) => (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+    let mut resp = match st.providers.search(query) {
+        Ok(r) => r,
+        Err(e) => return (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+    };
+    if include_address {
+        // The Tantivy index doesn't carry addresses; fill them from provider_search.
+        let mut db = st.db.lock().await;
+        if let Err(e) = attach_practice_addresses(&mut db, &mut resp.hits) {
+            return (StatusCode::BAD_REQUEST, e.to_string()).into_response();
+        }
+    }
+    Json(resp).into_response()
+}
+
+fn attach_practice_addresses(
+    db: &mut Connection,
+    hits: &mut [crate::index::providers::ProviderHit],
+) -> anyhow::Result<()> {
+    if hits.is_empty() {
+        return Ok(());
     }
+    let placeholders = vec!["?"; hits.len()].join(", ");
+    let sql =
+        format!("SELECT npi, practice_address FROM provider_search WHERE npi IN ({placeholders})");
+    let mut stmt = db.prepare(&sql)?;
+    let rows = stmt.query_map(
+        duckdb::params_from_iter(hits.iter().map(|h| &h.npi)),
+        |row| {
+            Ok((
+                row.get::<usize, String>(0)?,
+                row.get::<usize, Option<String>>(1)?,
+            ))
+        },
+    )?;
+    let mut by_npi = BTreeMap::new();
+    for r in rows {
+        let (npi, addr) = r?;
+        by_npi.insert(npi, addr);
+    }
+    for hit in hits {
+        hit.practice_address = by_npi.remove(&hit.npi).flatten();
+    }
+    Ok(())
 }
 
 async fn duckdb_provider_search(
     st: &AppState,
     p: &ProviderSearchParams,
     role: Role,
+    sort: ProviderSort,
+    sex: Option<&str>,
+    include_address: bool,
 ) -> anyhow::Result<crate::index::providers::ProviderSearchResponse> {
     let page_size = p.page_size.unwrap_or(50).clamp(1, 200);
     let offset = p.page.unwrap_or(0).saturating_mul(page_size);
@@ -304,6 +611,13 @@ async fn duckdb_provider_search(
             where_sql.push_str(&format!(" AND enumeration_type = '{entity}'"));
         }
     }
+    if let Some(sex) = sex {
+        // Validated to `M`/`F` by the handler.
+        where_sql.push_str(&format!(" AND sex = '{sex}'"));
+    }
+    if p.multi_state == Some(1) {
+        where_sql.push_str(" AND state_count > 1");
+    }
     if let Some(taxes) = &p.taxonomy {
         let taxes = flatten_list(Some(taxes.clone()));
         let taxes = taxes
@@ -341,6 +655,22 @@ async fn duckdb_provider_search(
     if let Some(max) = p.claims_max {
         where_sql.push_str(&format!(" AND {claims_col} <= {max}"));
     }
+    let bene_col = role_bene_field_name(role);
+    if let Some(min) = p.bene_min {
+        where_sql.push_str(&format!(" AND {bene_col} >= {min}"));
+    }
+    if let Some(max) = p.bene_max {
+        where_sql.push_str(&format!(" AND {bene_col} <= {max}"));
+    }
+
+    let ratio_sql = format!("{paid_col} / NULLIF({claims_col}, 0)");
+    let bene_ratio_sql = format!("CAST({bene_col} AS DOUBLE) / NULLIF({claims_col}, 0)");
+    let order_sql = match sort {
+        ProviderSort::PaidPerClaimDesc => format!("{ratio_sql} DESC NULLS LAST, npi ASC"),
+        ProviderSort::BenePerClaimDesc => format!("{bene_ratio_sql} DESC NULLS LAST, npi ASC"),
+        ProviderSort::DistinctCodesDesc => "distinct_codes DESC, npi ASC".to_string(),
+        _ => "display_name ASC NULLS LAST, npi ASC".to_string(),
+    };
 
     let count_sql = format!("SELECT COUNT(*) FROM provider_search {where_sql}");
     let data_sql = format!(
@@ -361,10 +691,14 @@ async fn duckdb_provider_search(
           bene_servicing,
           paid_total,
           claims_total,
-          bene_total
+          bene_total,
+          practice_address,
+          {ratio_sql} AS paid_per_claim,
+          distinct_codes,
+          {bene_ratio_sql} AS bene_per_claim
         FROM provider_search
         {where_sql}
-        ORDER BY display_name ASC NULLS LAST, npi ASC
+        ORDER BY {order_sql}
         LIMIT {page_size} OFFSET {offset}
     "#
     );
@@ -382,6 +716,23 @@ async fn duckdb_provider_search(
             enumeration_type: row.get::<usize, Option<String>>(4)?,
             primary_taxonomy_code: row.get::<usize, Option<String>>(5)?,
             primary_taxonomy_desc: row.get::<usize, Option<String>>(6)?,
+            practice_address: if include_address {
+                row.get::<usize, Option<String>>(16)?
+            } else {
+                None
+            },
+            paid_per_claim: if sort == ProviderSort::PaidPerClaimDesc {
+                row.get::<usize, Option<f64>>(17)?
+            } else {
+                None
+            },
+            bene_per_claim: if sort == ProviderSort::BenePerClaimDesc {
+                row.get::<usize, Option<f64>>(19)?
+            } else {
+                None
+            },
+            // Only reached without a text query, so there is nothing to highlight.
+            highlights: None,
             paid_billing: row.get::<usize, Option<f64>>(7)?.unwrap_or(0.0),
             claims_billing: row.get::<usize, Option<i64>>(8)?.unwrap_or(0),
             bene_billing: row.get::<usize, Option<i64>>(9)?.unwrap_or(0),
@@ -391,6 +742,7 @@ async fn duckdb_provider_search(
             paid_total: row.get::<usize, Option<f64>>(13)?.unwrap_or(0.0),
             claims_total: row.get::<usize, Option<i64>>(14)?.unwrap_or(0),
             bene_total: row.get::<usize, Option<i64>>(15)?.unwrap_or(0),
+            distinct_codes: row.get::<usize, Option<i64>>(18)?.unwrap_or(0),
         })
     })?;
     let mut hits = Vec::new();
@@ -421,6 +773,9 @@ fn parse_provider_sort(s: Option<&str>) -> ProviderSort {
         "claims_desc" => ProviderSort::ClaimsDesc,
         "claims_asc" => ProviderSort::ClaimsAsc,
         "name_asc" => ProviderSort::NameAsc,
+        "paid_per_claim_desc" => ProviderSort::PaidPerClaimDesc,
+        "distinct_codes_desc" => ProviderSort::DistinctCodesDesc,
+        "bene_per_claim_desc" => ProviderSort::BenePerClaimDesc,
         "relevance" => ProviderSort::Relevance,
         _ => ProviderSort::PaidDesc,
     }
@@ -484,9 +839,11 @@ async fn api_hcpcs_search(
 struct ProviderDetailResponse {
     provider: Option<ProviderRow>,
     npi_api: Option<String>,
+    /// Parsed from `npi_api` (`results[0].identifiers`); empty when absent.
+    identifiers: Vec<crate::npi::OtherIdentifier>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 struct ProviderRow {
     npi: String,
     display_name: Option<String>,
@@ -498,6 +855,20 @@ struct ProviderRow {
     zip5: Option<String>,
     lat: Option<f64>,
     lon: Option<f64>,
+    /// `zip5`, `zip3` (prefix fallback), or `null` when `lat`/`lon` are unknown.
+    geo_source: Option<String>,
+    replacement_npi: Option<String>,
+    sex: Option<String>,
+    sole_proprietor: Option<String>,
+    /// Distinct practice states (primary location plus secondary practice locations).
+    state_count: Option<i64>,
+    /// NPPES `YYYY-MM-DD` dates; `null` when blank.
+    enumeration_date: Option<String>,
+    last_updated: Option<String>,
+    /// Rank by `paid_total` among providers in `state` (ties share a rank), out of
+    /// `state_provider_count`; both `null` when the state is unknown.
+    state_rank_paid: Option<i64>,
+    state_provider_count: Option<i64>,
 
     paid_billing: f64,
     claims_billing: i64,
@@ -510,24 +881,7 @@ struct ProviderRow {
     bene_total: i64,
 }
 
-async fn api_provider_detail(
-    State(st): State<AppState>,
-    AxumPath(npi): AxumPath<String>,
-) -> impl IntoResponse {
-    let mut db = st.db.lock().await;
-    match provider_detail(&mut db, &st.npi_json_col, &npi) {
-        Ok(v) => Json(v).into_response(),
-        Err(e) => (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
-    }
-}
-
-fn provider_detail(
-    db: &mut Connection,
-    npi_json_col: &str,
-    npi: &str,
-) -> anyhow::Result<ProviderDetailResponse> {
-    let provider_sql = r#"
-        SELECT
+const PROVIDER_ROW_COLUMNS: &str = r#"
           npi,
           display_name,
           city,
@@ -546,37 +900,131 @@ fn provider_detail(
           bene_servicing,
           paid_total,
           claims_total,
-          bene_total
-        FROM provider_search
-        WHERE npi = ?
-        LIMIT 1
-    "#;
+          bene_total,
+          replacement_npi,
+          sex,
+          sole_proprietor,
+          state_count,
+          CAST(enumeration_date AS VARCHAR) AS enumeration_date,
+          CAST(last_updated AS VARCHAR) AS last_updated,
+          geo_source,
+          state_rank_paid,
+          state_provider_count
+"#;
+
+fn provider_row_from_sql(row: &duckdb::Row<'_>) -> duckdb::Result<ProviderRow> {
+    Ok(ProviderRow {
+        npi: row.get(0)?,
+        display_name: row.get(1)?,
+        city: row.get(2)?,
+        state: row.get(3)?,
+        enumeration_type: row.get(4)?,
+        primary_taxonomy_code: row.get(5)?,
+        primary_taxonomy_desc: row.get(6)?,
+        zip5: row.get(7)?,
+        lat: row.get(8)?,
+        lon: row.get(9)?,
+        paid_billing: row.get::<usize, Option<f64>>(10)?.unwrap_or(0.0),
+        claims_billing: row.get::<usize, Option<i64>>(11)?.unwrap_or(0),
+        bene_billing: row.get::<usize, Option<i64>>(12)?.unwrap_or(0),
+        paid_servicing: row.get::<usize, Option<f64>>(13)?.unwrap_or(0.0),
+        claims_servicing: row.get::<usize, Option<i64>>(14)?.unwrap_or(0),
+        bene_servicing: row.get::<usize, Option<i64>>(15)?.unwrap_or(0),
+        paid_total: row.get::<usize, Option<f64>>(16)?.unwrap_or(0.0),
+        claims_total: row.get::<usize, Option<i64>>(17)?.unwrap_or(0),
+        bene_total: row.get::<usize, Option<i64>>(18)?.unwrap_or(0),
+        replacement_npi: row.get(19)?,
+        sex: row.get(20)?,
+        sole_proprietor: row.get(21)?,
+        state_count: row.get(22)?,
+        enumeration_date: row.get(23)?,
+        last_updated: row.get(24)?,
+        geo_source: row.get(25)?,
+        state_rank_paid: row.get(26)?,
+        state_provider_count: row.get(27)?,
+    })
+}
+
+const MAX_COMPARE_NPIS: usize = 4;
+
+#[derive(Debug, Deserialize)]
+struct ProviderCompareParams {
+    npis: Option<String>,
+}
+
+async fn api_provider_compare(
+    State(st): State<AppState>,
+    Query(p): Query<ProviderCompareParams>,
+) -> impl IntoResponse {
+    let npis = flatten_list(p.npis.map(|v| vec![v]));
+    if npis.is_empty() {
+        return (StatusCode::BAD_REQUEST, "npis is required".to_string()).into_response();
+    }
+    if npis.len() > MAX_COMPARE_NPIS {
+        return (
+            StatusCode::BAD_REQUEST,
+            format!(
+                "at most {MAX_COMPARE_NPIS} npis can be compared (got {})",
+                npis.len()
+            ),
+        )
+            .into_response();
+    }
+
+    let mut db = st.db.lock().await;
+    match provider_compare(&mut db, &npis) {
+        Ok(v) => Json(v).into_response(),
+        Err(e) => (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+    }
+}
+
+/// One entry per requested NPI, in request order (`None` when the NPI is not in the dataset).
+fn provider_compare(
+    db: &mut Connection,
+    npis: &[String],
+) -> anyhow::Result<Vec<Option<ProviderRow>>> {
+    let placeholders = vec!["?"; npis.len()].join(", ");
+    let sql =
+        format!("SELECT {PROVIDER_ROW_COLUMNS} FROM provider_search WHERE npi IN ({placeholders})");
+    let mut stmt = db.prepare(&sql)?;
+    let rows = stmt.query_map(duckdb::params_from_iter(npis.iter()), provider_row_from_sql)?;
+
+    let mut by_npi = BTreeMap::new();
+    for row in rows {
+        let row = row?;
+        by_npi.insert(row.npi.clone(), row);
+    }
+    Ok(npis.iter().map(|npi| by_npi.get(npi).cloned()).collect())
+}
+
+async fn api_provider_detail(
+    State(st): State<AppState>,
+    AxumPath(npi): AxumPath<String>,
+) -> impl IntoResponse {
+    let Some(npi) = normalize_npi(&npi) else {
+        return (
+            StatusCode::NOT_FOUND,
+            format!("not a 10-digit NPI: {npi:?}"),
+        )
+            .into_response();
+    };
+    let mut db = st.db.lock().await;
+    match provider_detail(&mut db, &st.npi_json_col, &npi) {
+        Ok(v) => Json(v).into_response(),
+        Err(e) => (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+    }
+}
+
+fn provider_detail(
+    db: &mut Connection,
+    npi_json_col: &str,
+    npi: &str,
+) -> anyhow::Result<ProviderDetailResponse> {
+    let provider_sql =
+        format!("SELECT {PROVIDER_ROW_COLUMNS} FROM provider_search WHERE npi = ? LIMIT 1");
     let provider: Option<ProviderRow> = {
-        let mut stmt = db.prepare(provider_sql)?;
-        stmt.query_row([npi], |row| {
-            Ok(ProviderRow {
-                npi: row.get(0)?,
-                display_name: row.get(1)?,
-                city: row.get(2)?,
-                state: row.get(3)?,
-                enumeration_type: row.get(4)?,
-                primary_taxonomy_code: row.get(5)?,
-                primary_taxonomy_desc: row.get(6)?,
-                zip5: row.get(7)?,
-                lat: row.get(8)?,
-                lon: row.get(9)?,
-                paid_billing: row.get::<usize, Option<f64>>(10)?.unwrap_or(0.0),
-                claims_billing: row.get::<usize, Option<i64>>(11)?.unwrap_or(0),
-                bene_billing: row.get::<usize, Option<i64>>(12)?.unwrap_or(0),
-                paid_servicing: row.get::<usize, Option<f64>>(13)?.unwrap_or(0.0),
-                claims_servicing: row.get::<usize, Option<i64>>(14)?.unwrap_or(0),
-                bene_servicing: row.get::<usize, Option<i64>>(15)?.unwrap_or(0),
-                paid_total: row.get::<usize, Option<f64>>(16)?.unwrap_or(0.0),
-                claims_total: row.get::<usize, Option<i64>>(17)?.unwrap_or(0),
-                bene_total: row.get::<usize, Option<i64>>(18)?.unwrap_or(0),
-            })
-        })
-        .optional()?
+        let mut stmt = db.prepare(&provider_sql)?;
+        stmt.query_row([npi], provider_row_from_sql).optional()?
     };
 
     let npi_sql = format!("SELECT {npi_json_col} FROM npi_api_raw WHERE npi = ? LIMIT 1");
@@ -587,7 +1035,145 @@ fn provider_detail(
             .flatten()
     };
 
-    Ok(ProviderDetailResponse { provider, npi_api })
+    let identifiers = crate::npi::extract_other_identifiers(npi_api.as_deref());
+    Ok(ProviderDetailResponse {
+        provider,
+        npi_api,
+        identifiers,
+    })
+}
+
+const DEFAULT_NEARBY_RADIUS_MILES: f64 = 25.0;
+const MAX_NEARBY_RADIUS_MILES: f64 = 250.0;
+
+#[derive(Debug, Deserialize)]
+struct ProviderNearbyParams {
+    radius_miles: Option<f64>,
+    limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+struct ProviderNearbyResponse {
+    npi: String,
+    primary_taxonomy_code: Option<String>,
+    lat: Option<f64>,
+    lon: Option<f64>,
+    radius_miles: f64,
+    /// Same primary taxonomy within `radius_miles`, nearest first; empty when the target has
+    /// no coordinates or taxonomy.
+    providers: Vec<NearbyProvider>,
+}
+
+#[derive(Debug, Serialize)]
+struct NearbyProvider {
+    npi: String,
+    display_name: Option<String>,
+    city: Option<String>,
+    state: Option<String>,
+    geo_source: Option<String>,
+    distance_miles: f64,
+    paid_total: f64,
+    claims_total: i64,
+    bene_total: i64,
+}
+
+async fn api_provider_nearby(
+    State(st): State<AppState>,
+    AxumPath(npi): AxumPath<String>,
+    Query(p): Query<ProviderNearbyParams>,
+) -> impl IntoResponse {
+    let Some(npi) = normalize_npi(&npi) else {
+        return (
+            StatusCode::NOT_FOUND,
+            format!("not a 10-digit NPI: {npi:?}"),
+        )
+            .into_response();
+    };
+    let radius_miles = p.radius_miles.unwrap_or(DEFAULT_NEARBY_RADIUS_MILES);
+    if !(radius_miles > 0.0 && radius_miles <= MAX_NEARBY_RADIUS_MILES) {
+        return (
+            StatusCode::BAD_REQUEST,
+            format!("radius_miles must be in (0, {MAX_NEARBY_RADIUS_MILES}]"),
+        )
+            .into_response();
+    }
+    let limit = p.limit.unwrap_or(20).clamp(1, 100);
+    let mut db = st.db.lock().await;
+    match provider_nearby(&mut db, &npi, radius_miles, limit) {
+        Ok(v) => Json(v).into_response(),
+        Err(e) => (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+    }
+}
+
+fn provider_nearby(
+    db: &mut Connection,
+    npi: &str,
+    radius_miles: f64,
+    limit: usize,
+) -> anyhow::Result<ProviderNearbyResponse> {
+    let target = {
+        let mut stmt = db.prepare(
+            "SELECT lat, lon, primary_taxonomy_code FROM provider_search WHERE npi = ? LIMIT 1",
+        )?;
+        stmt.query_row([npi], |row| {
+            Ok((
+                row.get::<usize, Option<f64>>(0)?,
+                row.get::<usize, Option<f64>>(1)?,
+                row.get::<usize, Option<String>>(2)?,
+            ))
+        })
+        .optional()?
+    };
+    let (lat, lon, taxonomy) = target.unwrap_or((None, None, None));
+    let mut resp = ProviderNearbyResponse {
+        npi: npi.to_string(),
+        primary_taxonomy_code: taxonomy.clone(),
+        lat,
+        lon,
+        radius_miles,
+        providers: Vec::new(),
+    };
+    let (Some(lat), Some(lon), Some(taxonomy)) = (lat, lon, taxonomy) else {
+        return Ok(resp);
+    };
+
+    let distance_sql = crate::geo::haversine_miles_sql(lat, lon);
+    let (min_lat, max_lat, min_lon, max_lon) = crate::geo::radius_bbox(lat, lon, radius_miles);
+    let sql = format!(
+        r#"
+        SELECT npi, display_name, city, state, geo_source, distance_miles,
+               paid_total, claims_total, bene_total
+        FROM (
+          SELECT *, {distance_sql} AS distance_miles
+          FROM provider_search
+          WHERE primary_taxonomy_code = ?
+            AND npi <> ?
+            AND lat BETWEEN {min_lat} AND {max_lat}
+            AND lon BETWEEN {min_lon} AND {max_lon}
+        )
+        WHERE distance_miles <= {radius_miles}
+        ORDER BY distance_miles ASC, paid_total DESC, npi ASC
+        LIMIT {limit}
+    "#
+    );
+    let mut stmt = db.prepare(&sql)?;
+    let rows = stmt.query_map([taxonomy.as_str(), npi], |row| {
+        Ok(NearbyProvider {
+            npi: row.get(0)?,
+            display_name: row.get(1)?,
+            city: row.get(2)?,
+            state: row.get(3)?,
+            geo_source: row.get(4)?,
+            distance_miles: row.get(5)?,
+            paid_total: row.get::<usize, Option<f64>>(6)?.unwrap_or(0.0),
+            claims_total: row.get::<usize, Option<i64>>(7)?.unwrap_or(0),
+            bene_total: row.get::<usize, Option<i64>>(8)?.unwrap_or(0),
+        })
+    })?;
+    for r in rows {
+        resp.providers.push(r?);
+    }
+    Ok(resp)
 }
 
 #[derive(Debug, Serialize)]
@@ -609,14 +1195,27 @@ struct HcpcsRow {
     paid_total: f64,
     claims_total: i64,
     bene_total: i64,
+    paid_billing: Option<f64>,
+    claims_billing: Option<i64>,
+    bene_billing: Option<i64>,
+    paid_servicing: Option<f64>,
+    claims_servicing: Option<i64>,
+    bene_servicing: Option<i64>,
 }
 
 async fn api_hcpcs_detail(
     State(st): State<AppState>,
     AxumPath(code): AxumPath<String>,
 ) -> impl IntoResponse {
+    let Some(code) = normalize_hcpcs_code(&code) else {
+        return (
+            StatusCode::NOT_FOUND,
+            format!("not a 5-character HCPCS/CPT code: {code:?}"),
+        )
+            .into_response();
+    };
     let mut db = st.db.lock().await;
-    match hcpcs_detail(&mut db, &st.hcpcs_json_col, &code) {
+    match hcpcs_detail(&mut db, &st.hcpcs_json_col, st.hcpcs_role_split, &code) {
         Ok(v) => Json(v).into_response(),
         Err(e) => (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
     }
@@ -625,9 +1224,16 @@ async fn api_hcpcs_detail(
 fn hcpcs_detail(
     db: &mut Connection,
     hcpcs_json_col: &str,
+    role_split: bool,
     code: &str,
 ) -> anyhow::Result<HcpcsDetailResponse> {
-    let hcpcs_sql = r#"
+    let role_cols = if role_split {
+        "paid_billing, claims_billing, bene_billing, paid_servicing, claims_servicing, bene_servicing"
+    } else {
+        "NULL::DOUBLE, NULL::BIGINT, NULL::BIGINT, NULL::DOUBLE, NULL::BIGINT, NULL::BIGINT"
+    };
+    let hcpcs_sql = format!(
+        r#"
         SELECT
           hcpcs_code,
           short_desc,
@@ -639,13 +1245,15 @@ fn hcpcs_detail(
           is_noc,
           paid_total,
           claims_total,
-          bene_total
+          bene_total,
+          {role_cols}
         FROM hcpcs_search
         WHERE hcpcs_code = ?
         LIMIT 1
-    "#;
+    "#
+    );
     let hcpcs: Option<HcpcsRow> = {
-        let mut stmt = db.prepare(hcpcs_sql)?;
+        let mut stmt = db.prepare(&hcpcs_sql)?;
         stmt.query_row([code], |row| {
             Ok(HcpcsRow {
                 hcpcs_code: row.get(0)?,
@@ -659,6 +1267,12 @@ fn hcpcs_detail(
                 paid_total: row.get::<usize, Option<f64>>(8)?.unwrap_or(0.0),
                 claims_total: row.get::<usize, Option<i64>>(9)?.unwrap_or(0),
                 bene_total: row.get::<usize, Option<i64>>(10)?.unwrap_or(0),
+                paid_billing: row.get(11)?,
+                claims_billing: row.get(12)?,
+                bene_billing: row.get(13)?,
+                paid_servicing: row.get(14)?,
+                claims_servicing: row.get(15)?,
+                bene_servicing: row.get(16)?,
             })
         })
         .optional()?
@@ -684,6 +1298,9 @@ struct MapZipsParams {
     entity: Option<String>,
     role: Option<String>,
     metric: Option<String>,
+    format: Option<String>,
+    /// `1` drops providers placed by the 3-digit ZIP prefix fallback (`geo_source = 'zip3'`).
+    strict_geo: Option<u8>,
 }
 
 #[derive(Debug, Serialize)]
@@ -691,6 +1308,8 @@ struct MapZipPoint {
     zip5: String,
     lat: f64,
     lon: f64,
+    /// `zip5` (exact ZIP centroid) or `zip3` (3-digit prefix fallback).
+    geo_source: Option<String>,
     provider_count: u64,
     metric_total: f64,
 }
@@ -734,6 +1353,9 @@ async fn api_map_zips(
         "WHERE lat IS NOT NULL AND lon IS NOT NULL AND lat BETWEEN {} AND {} AND lon BETWEEN {} AND {}",
         bbox.min_lat, bbox.max_lat, bbox.min_lon, bbox.max_lon
     );
+    if p.strict_geo == Some(1) {
+        where_sql.push_str(" AND geo_source = 'zip5'");
+    }
 
     if let Some(states) = &p.state {
         let states = flatten_list(Some(states.clone()));
@@ -785,10 +1407,11 @@ async fn api_map_zips(
           lat,
           lon,
           COUNT(*) AS provider_count,
-          SUM(CAST({metric_col} AS {metric_cast})) AS metric_total
+          SUM(CAST({metric_col} AS {metric_cast})) AS metric_total,
+          geo_source
         FROM provider_search
         {where_sql}
-        GROUP BY zip5, lat, lon
+        GROUP BY zip5, lat, lon, geo_source
         ORDER BY metric_total DESC
         LIMIT 20000
     "#
@@ -807,6 +1430,7 @@ async fn api_map_zips(
             lon: row.get::<usize, f64>(2)?,
             provider_count: row.get::<usize, i64>(3)?.max(0) as u64,
             metric_total: row.get::<usize, Option<f64>>(4)?.unwrap_or(0.0),
+            geo_source: row.get::<usize, Option<String>>(5)?,
         })
     }) {
         Ok(r) => r,
@@ -821,9 +1445,48 @@ async fn api_map_zips(
         }
     }
 
+    let geojson = p
+        .format
+        .as_deref()
+        .map(|f| f.eq_ignore_ascii_case("geojson"))
+        .unwrap_or(false);
+    if geojson {
+        return (
+            [(header::CONTENT_TYPE, "application/geo+json")],
+            map_zips_feature_collection(&out).to_string(),
+        )
+            .into_response();
+    }
+
     Json(out).into_response()
 }
 
+fn map_zips_feature_collection(points: &[MapZipPoint]) -> serde_json::Value {
+    let features = points
+        .iter()
+        .map(|p| {
+            serde_json::json!({
+                "type": "Feature",
+                "geometry": {
+                    "type": "Point",
+                    // GeoJSON positions are [longitude, latitude] (RFC 7946).
+                    "coordinates": [p.lon, p.lat],
+                },
+                "properties": {
+                    "zip5": p.zip5,
+                    "geo_source": p.geo_source,
+                    "provider_count": p.provider_count,
+                    "metric_total": p.metric_total,
+                },
+            })
+        })
+        .collect::<Vec<_>>();
+    serde_json::json!({
+        "type": "FeatureCollection",
+        "features": features,
+    })
+}
+
 #[derive(Debug, Clone, Copy)]
 struct Bbox {
     min_lon: f64,
@@ -849,26 +1512,27 @@ fn parse_bbox(s: &str) -> anyhow::Result<Bbox> {
     })
 }
 
-fn create_or_replace_views(
+/// Replace the raw API views with projections of just the columns the server queries.
+fn create_slim_raw_views(
     conn: &mut Connection,
-    spending: &Path,
     npi: &Path,
     hcpcs: &Path,
+    npi_json_col: &str,
+    hcpcs_json_col: &str,
 ) -> anyhow::Result<()> {
-    let spending = sql_quote_path(spending);
     let npi = sql_quote_path(npi);
     let hcpcs = sql_quote_path(hcpcs);
 
     conn.execute(
-        &format!("CREATE OR REPLACE VIEW spending_raw AS SELECT * FROM read_parquet('{spending}')"),
-        [],
-    )?;
-    conn.execute(
-        &format!("CREATE OR REPLACE VIEW npi_api_raw AS SELECT * FROM read_parquet('{npi}')"),
+        &format!(
+            "CREATE OR REPLACE VIEW npi_api_raw AS SELECT npi, {npi_json_col} FROM read_parquet('{npi}')"
+        ),
         [],
     )?;
     conn.execute(
-        &format!("CREATE OR REPLACE VIEW hcpcs_api_raw AS SELECT * FROM read_parquet('{hcpcs}')"),
+        &format!(
+            "CREATE OR REPLACE VIEW hcpcs_api_raw AS SELECT hcpcs_code, {hcpcs_json_col} FROM read_parquet('{hcpcs}')"
+        ),
         [],
     )?;
     Ok(())
@@ -889,3 +1553,122 @@ fn detect_json_col(conn: &mut Connection, view: &str) -> anyhow::Result<String>
 fn sql_quote_path(path: &Path) -> String {
     path.display().to_string().replace('\'', "''")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index::providers::ProviderHit;
+
+    fn hit(npi: &str) -> ProviderHit {
+        ProviderHit {
+            npi: npi.to_string(),
+            display_name: None,
+            city: None,
+            state: None,
+            enumeration_type: None,
+            primary_taxonomy_code: None,
+            primary_taxonomy_desc: None,
+            practice_address: None,
+            paid_per_claim: None,
+            bene_per_claim: None,
+            highlights: None,
+            paid_billing: 0.0,
+            claims_billing: 0,
+            bene_billing: 0,
+            paid_servicing: 0.0,
+            claims_servicing: 0,
+            bene_servicing: 0,
+            paid_total: 0.0,
+            claims_total: 0,
+            bene_total: 0,
+            distinct_codes: 0,
+        }
+    }
+
+    #[test]
+    fn practice_addresses_are_filled_from_provider_search() {
+        let mut db = Connection::open_in_memory().unwrap();
+        db.execute_batch(
+            "CREATE TABLE provider_search (npi VARCHAR, practice_address VARCHAR);
+             INSERT INTO provider_search VALUES
+               ('1234567893', '1 MAIN ST, FRESNO, CA 93701'),
+               ('1245319599', NULL);",
+        )
+        .unwrap();
+        let mut hits = [hit("1234567893"), hit("1245319599"), hit("1003000126")];
+        attach_practice_addresses(&mut db, &mut hits).unwrap();
+        assert_eq!(
+            hits[0].practice_address.as_deref(),
+            Some("1 MAIN ST, FRESNO, CA 93701")
+        );
+        assert_eq!(hits[1].practice_address, None);
+        assert_eq!(hits[2].practice_address, None);
+
+        // Off by default: the hit does not serialize the field at all.
+        let json = serde_json::to_value(hit("1234567893")).unwrap();
+        assert!(json.get("practice_address").is_none());
+    }
+
+    #[test]
+    fn nearby_lists_same_taxonomy_peers_within_the_radius_nearest_first() {
+        let mut db = Connection::open_in_memory().unwrap();
+        // 0.1 degrees of latitude is about 6.9 miles.
+        db.execute_batch(
+            "CREATE TABLE provider_search (
+               npi VARCHAR, display_name VARCHAR, city VARCHAR, state VARCHAR,
+               geo_source VARCHAR, lat DOUBLE, lon DOUBLE, primary_taxonomy_code VARCHAR,
+               paid_total DOUBLE, claims_total BIGINT, bene_total BIGINT
+             );
+             INSERT INTO provider_search VALUES
+               ('1234567893', 'Target', 'FRESNO', 'CA', 'zip5', 36.7, -119.8, '207Q00000X', 10, 1, 1),
+               ('1000000001', 'Far', NULL, 'CA', 'zip5', 36.9, -119.8, '207Q00000X', 5, 1, 1),
+               ('1000000002', 'Near', NULL, 'CA', 'zip5', 36.8, -119.8, '207Q00000X', 1, 1, 1),
+               ('1000000003', 'Outside', NULL, 'CA', 'zip5', 37.2, -119.8, '207Q00000X', 9, 1, 1),
+               ('1000000004', 'Other taxonomy', NULL, 'CA', 'zip5', 36.7, -119.8, '390200000X', 9, 1, 1),
+               ('1000000005', 'No coordinates', NULL, 'CA', NULL, NULL, NULL, '207Q00000X', 9, 1, 1);",
+        )
+        .unwrap();
+
+        let resp = provider_nearby(&mut db, "1234567893", 25.0, 20).unwrap();
+        let peers: Vec<_> = resp.providers.iter().map(|p| p.npi.as_str()).collect();
+        assert_eq!(peers, ["1000000002", "1000000001"]);
+        assert!((resp.providers[0].distance_miles - 6.9).abs() < 0.1);
+        assert!((resp.providers[1].distance_miles - 13.8).abs() < 0.1);
+
+        let limited = provider_nearby(&mut db, "1234567893", 25.0, 1).unwrap();
+        assert_eq!(limited.providers.len(), 1);
+
+        let no_coords = provider_nearby(&mut db, "1000000005", 250.0, 20).unwrap();
+        assert_eq!(
+            no_coords.primary_taxonomy_code.as_deref(),
+            Some("207Q00000X")
+        );
+        assert!(no_coords.providers.is_empty());
+
+        let unknown = provider_nearby(&mut db, "1003000126", 25.0, 20).unwrap();
+        assert!(unknown.providers.is_empty());
+    }
+
+    #[test]
+    fn provider_sort_names_parse_case_insensitively() {
+        assert_eq!(
+            parse_provider_sort(Some("Paid_Per_Claim_Desc")),
+            ProviderSort::PaidPerClaimDesc
+        );
+        assert_eq!(parse_provider_sort(Some("name_asc")), ProviderSort::NameAsc);
+        assert_eq!(parse_provider_sort(Some("bogus")), ProviderSort::PaidDesc);
+        assert_eq!(parse_provider_sort(None), ProviderSort::PaidDesc);
+    }
+
+    #[test]
+    fn distinct_codes_and_bene_per_claim_sort_names_parse() {
+        assert_eq!(
+            parse_provider_sort(Some("distinct_codes_desc")),
+            ProviderSort::DistinctCodesDesc
+        );
+        assert_eq!(
+            parse_provider_sort(Some("bene_per_claim_desc")),
+            ProviderSort::BenePerClaimDesc
+        );
+    }
+}
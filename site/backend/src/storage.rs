@@ -9,6 +9,7 @@ pub struct StoragePaths {
     pub provider_index_dir: PathBuf,
     pub hcpcs_index_dir: PathBuf,
     pub meta_path: PathBuf,
+    pub leaderboard_path: PathBuf,
 }
 
 #[derive(Debug, Clone)]
@@ -28,6 +29,7 @@ impl StoragePaths {
         let provider_index_dir = index_dir.join("providers");
         let hcpcs_index_dir = index_dir.join("hcpcs");
         let meta_path = data_dir.join("meta.json");
+        let leaderboard_path = data_dir.join("leaderboard.parquet");
 
         Self {
             source_dir,
@@ -37,6 +39,7 @@ impl StoragePaths {
             provider_index_dir,
             hcpcs_index_dir,
             meta_path,
+            leaderboard_path,
         }
     }
 
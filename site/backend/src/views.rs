@@ -0,0 +1,140 @@
+use std::path::Path;
+
+use anyhow::{Context, anyhow};
+use duckdb::{Connection, params};
+
+use crate::build::sql_quote_path;
+
+/// `npi.parquet` layout written by `build_datasets` (every column is a string). Older files may
+/// predate some of these, and `--npi-output-columns` can drop them.
+const NPI_EXPECTED_COLUMNS: &[&str] = &[
+    "npi",
+    "provider_name",
+    "deactivated",
+    "basic",
+    "addresses",
+    "practice_locations",
+    "taxonomies",
+    "identifiers",
+    "other_names",
+    "endpoints",
+    "url",
+    "error_message",
+    "api_run_id",
+    "requested_at_utc",
+    "request_params",
+    "latency_ms",
+    "http_status",
+    "duration_ms",
+    "results",
+    "response_json",
+];
+
+/// Same as [`NPI_EXPECTED_COLUMNS`] for `hcpcs.parquet`.
+const HCPCS_EXPECTED_COLUMNS: &[&str] = &[
+    "hcpcs_code",
+    "modifier",
+    "ef_short_desc",
+    "ef_long_desc",
+    "ef_add_dt",
+    "ef_act_eff_dt",
+    "ef_term_dt",
+    "ef_obsolete",
+    "ef_is_noc",
+    "response_codes",
+    "response_display",
+    "response_extra_fields",
+    "url",
+    "error_message",
+    "api_run_id",
+    "requested_at_utc",
+    "request_params",
+    "latency_ms",
+    "response_json",
+];
+
+pub fn create_or_replace_views(
+    conn: &mut Connection,
+    spending: &Path,
+    npi: &Path,
+    hcpcs: &Path,
+) -> anyhow::Result<()> {
+    let spending = sql_quote_path(spending);
+    conn.execute(
+        &format!("CREATE OR REPLACE VIEW spending_raw AS SELECT * FROM read_parquet('{spending}')"),
+        [],
+    )?;
+    create_padded_view(conn, "npi_api_raw", npi, "npi", NPI_EXPECTED_COLUMNS)?;
+    create_padded_view(
+        conn,
+        "hcpcs_api_raw",
+        hcpcs,
+        "hcpcs_code",
+        HCPCS_EXPECTED_COLUMNS,
+    )?;
+    Ok(())
+}
+
+/// `SELECT *` over the parquet, plus a `NULL::VARCHAR` for each expected column it lacks so
+/// queries written against the current layout still bind. The key column is required.
+fn create_padded_view(
+    conn: &mut Connection,
+    view: &str,
+    parquet: &Path,
+    key: &str,
+    expected: &[&str],
+) -> anyhow::Result<()> {
+    let source = format!("read_parquet('{}')", sql_quote_path(parquet));
+    conn.execute(
+        &format!("CREATE OR REPLACE VIEW {view} AS SELECT * FROM {source}"),
+        [],
+    )?;
+
+    let existing = view_columns(conn, view)?;
+    if !existing.iter().any(|c| c == key) {
+        return Err(anyhow!(
+            "{} has no {key} column; cannot serve {view}",
+            parquet.display()
+        ));
+    }
+    let missing: Vec<&str> = expected
+        .iter()
+        .copied()
+        .filter(|col| !existing.iter().any(|c| c == col))
+        // Legacy files keep the raw payload in `response`, which detect_json_col falls back to.
+        .filter(|col| !(*col == "response_json" && existing.iter().any(|c| c == "response")))
+        .collect();
+    if missing.is_empty() {
+        return Ok(());
+    }
+
+    tracing::warn!(
+        "{} lacks {} expected column(s) ({}); {view} serves them as NULL",
+        parquet.display(),
+        missing.len(),
+        missing.join(", ")
+    );
+    let fill = missing
+        .iter()
+        .map(|col| format!("CAST(NULL AS VARCHAR) AS \"{col}\""))
+        .collect::<Vec<_>>()
+        .join(", ");
+    conn.execute(
+        &format!("CREATE OR REPLACE VIEW {view} AS SELECT *, {fill} FROM {source}"),
+        [],
+    )?;
+    Ok(())
+}
+
+fn view_columns(conn: &mut Connection, view: &str) -> anyhow::Result<Vec<String>> {
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT column_name
+        FROM information_schema.columns
+        WHERE table_schema = 'main' AND table_name = ?
+    "#,
+    )?;
+    stmt.query_map(params![view], |row| row.get::<usize, String>(0))?
+        .collect::<Result<Vec<_>, _>>()
+        .with_context(|| format!("list columns of {view}"))
+}
@@ -56,6 +56,18 @@ export async function apiProviderDetail(npi: string): Promise<any> {
   return await fetchJson(`${API_BASE_URL}/api/providers/${encodeURIComponent(npi)}`);
 }
 
+export async function apiProviderNearby(
+  npi: string,
+  params: { radius_miles?: number; limit?: number } = {}
+): Promise<any> {
+  const u = new URL(`${API_BASE_URL}/api/providers/${encodeURIComponent(npi)}/nearby`);
+  for (const [k, v] of Object.entries(params)) {
+    if (v === undefined) continue;
+    u.searchParams.set(k, String(v));
+  }
+  return await fetchJson(u.toString());
+}
+
 export async function apiHcpcsSearch(params: Record<string, string | number | undefined>): Promise<any> {
   const u = new URL(`${API_BASE_URL}/api/hcpcs/search`);
   for (const [k, v] of Object.entries(params)) {